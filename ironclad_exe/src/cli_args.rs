@@ -0,0 +1,129 @@
+//! Command line arguments for the Ironclad executable
+
+use libironclad_erlang::project::module::module_doc::DocFormat;
+use std::path::PathBuf;
+
+/// The action requested from the command line
+#[derive(Debug, PartialEq, Eq)]
+pub enum CliCommand {
+  /// Parse all project modules (the default)
+  Build,
+  /// Write each module after preprocessing as `<module>.P`, like `erlc -P` does
+  Preprocess {
+    /// Where to write the `.P` files
+    output_dir: PathBuf,
+  },
+  /// Write the documentation from `-doc` and `-moduledoc` attributes for each module
+  Docs {
+    /// Where to write the documentation files
+    output_dir: PathBuf,
+    /// Markdown or HTML
+    format: DocFormat,
+  },
+  /// Write each parsed module as `<module>.json`, for external tools
+  ExportJson {
+    /// Where to write the JSON files
+    output_dir: PathBuf,
+  },
+  /// Print the compiler options for a file, and the `[[overrides]]` which affected them
+  ShowOptions {
+    /// The file to check
+    file: PathBuf,
+  },
+}
+
+/// Parsed command line
+#[derive(Debug)]
+pub struct CliArgs {
+  /// Path to `ironclad.toml`
+  pub project_file: String,
+  /// Preprocessor defines given as `-DNAME` or `-DNAME=VALUE`, added to the project defines
+  pub defines: Vec<String>,
+  /// What to do
+  pub command: CliCommand,
+}
+
+impl CliArgs {
+  /// Project file used when `--project` is not given
+  pub const DEFAULT_PROJECT_FILE: &'static str = "test_project/ironclad.toml";
+
+  /// Printed when the command line can't be parsed
+  pub const USAGE: &'static str = "Usage: ironclad [--project <ironclad.toml>] [options] [command]
+Options:
+  -D<name>, -D<name>=<value>     Define a macro, also -D <name>=<value> or -D'<name>(<args>)=<value>'
+Commands:
+  build                          Parse all project modules (default)
+  preprocess [-o <dir>]          Write preprocessed modules as <module>.P (also: -P)
+  docs [--format markdown|html] [-o <dir>]
+                                 Write module documentation, Markdown by default
+  export-json [-o <dir>]         Write parsed modules as <module>.json, schema version 1
+  show-options <file>            Print compiler options for the file, and the overrides applied";
+
+  /// Parse command line arguments, not including the program name
+  pub fn parse<Iter: Iterator<Item = String>>(mut args: Iter) -> Result<Self, String> {
+    let mut project_file = Self::DEFAULT_PROJECT_FILE.to_string();
+    let mut command = CliCommand::Build;
+    let mut defines = Vec::new();
+
+    while let Some(arg) = args.next() {
+      match arg.as_str() {
+        "--project" => project_file = Self::option_value(&arg, &mut args)?,
+        "-D" => defines.push(Self::option_value(&arg, &mut args)?),
+        define if define.starts_with("-D") => defines.push(define[2..].to_string()),
+        "build" => command = CliCommand::Build,
+        "preprocess" | "-P" => {
+          command = CliCommand::Preprocess { output_dir: PathBuf::from(".") };
+        }
+        "docs" => {
+          command = CliCommand::Docs {
+            output_dir: PathBuf::from("doc"),
+            format: DocFormat::Markdown,
+          };
+        }
+        "export-json" => {
+          command = CliCommand::ExportJson { output_dir: PathBuf::from(".") };
+        }
+        "show-options" => {
+          command = CliCommand::ShowOptions {
+            file: PathBuf::from(Self::option_value(&arg, &mut args)?),
+          };
+        }
+        "-o" => match &mut command {
+          CliCommand::Preprocess { output_dir }
+          | CliCommand::Docs { output_dir, .. }
+          | CliCommand::ExportJson { output_dir } => {
+            *output_dir = PathBuf::from(Self::option_value(&arg, &mut args)?)
+          }
+          _ => {
+            return Err(
+              "Option -o is only valid for the preprocess, docs and export-json commands"
+                .to_string(),
+            )
+          }
+        },
+        "--format" => match &mut command {
+          CliCommand::Docs { format, .. } => {
+            *format = match Self::option_value(&arg, &mut args)?.as_str() {
+              "markdown" | "md" => DocFormat::Markdown,
+              "html" => DocFormat::Html,
+              other => return Err(format!("Unknown documentation format: {}", other)),
+            }
+          }
+          _ => return Err("Option --format is only valid for the docs command".to_string()),
+        },
+        other => return Err(format!("Unexpected command line argument: {}", other)),
+      }
+    }
+    Ok(Self { project_file, defines, command })
+  }
+
+  /// Take the value following an option, or report that it is missing
+  fn option_value<Iter: Iterator<Item = String>>(
+    option: &str,
+    args: &mut Iter,
+  ) -> Result<String, String> {
+    args
+      .next()
+      .ok_or_else(|| format!("Option {} requires a value", option))
+  }
+}
@@ -7,33 +7,60 @@ extern crate libironclad_erlang;
 
 use std::process::exit;
 
+use cli_args::{CliArgs, CliCommand};
 use libironclad_erlang::error::ic_error::IroncladResult;
-use libironclad_erlang::exit_codes::erl_fatal_icerror;
+use libironclad_erlang::exit_codes::{erl_fatal_icerror, EXIT_FATAL};
 use libironclad_erlang::project::conf::ProjectConf;
 use libironclad_erlang::project::project_impl::ErlProjectImpl;
 use libironclad_erlang::project::ErlProject;
+use stage::stage_docs::ErlDocsStage;
+use stage::stage_json::ErlJsonStage;
 use stage::stage_parse::ErlParseStage;
+use stage::stage_preprocess::ErlPreprocessStage;
 
+pub mod cli_args;
 pub mod stage;
 
-fn main_do() -> IroncladResult<()> {
-  let project: ErlProject = match ProjectConf::from_project_file("test_project/ironclad.toml") {
-    Ok(erlp) => ErlProjectImpl::from(erlp).into(),
-    Err(e) => return Err(e),
-  };
+fn main_do(args: CliArgs) -> IroncladResult<()> {
+  let mut conf = ProjectConf::from_project_file(&args.project_file)?;
+  conf.add_defines(&args.defines);
+  let project: ErlProject = ErlProjectImpl::try_from(conf)?.into();
   println!("{}", project);
 
   project.build_file_list()?;
 
-  // Parse all ERL files and their included includes
-  if let Err(e) = ErlParseStage::run_parse_stage(&project) {
+  let stage_result = match &args.command {
+    // Parse all ERL files and their included includes
+    CliCommand::Build => ErlParseStage::run_parse_stage(&project),
+    CliCommand::Preprocess { output_dir } => {
+      ErlPreprocessStage::run_preprocess_stage(&project, output_dir)
+    }
+    CliCommand::Docs { output_dir, format } => {
+      ErlDocsStage::run_docs_stage(&project, output_dir, *format)
+    }
+    CliCommand::ExportJson { output_dir } => ErlJsonStage::run_json_stage(&project, output_dir),
+    CliCommand::ShowOptions { file } => {
+      // Input paths are absolute, check the same path the build would use
+      let path = file.canonicalize().unwrap_or_else(|_| file.clone());
+      println!("{}", project.describe_compiler_options_for(&path));
+      Ok(())
+    }
+  };
+  if let Err(e) = stage_result {
     erl_fatal_icerror(e);
   }
   Ok(())
 }
 
 fn main() {
-  match main_do() {
+  let args = match CliArgs::parse(std::env::args().skip(1)) {
+    Ok(args) => args,
+    Err(msg) => {
+      println!("{}\n{}", msg, CliArgs::USAGE);
+      exit(EXIT_FATAL);
+    }
+  };
+  match main_do(args) {
     Ok(_) => {
       println!("Ironclad finished.");
       exit(0);
@@ -2,4 +2,7 @@
 //! A stage takes project, and some input, and maybe some context data like defined macros.
 //! A stage outputs something usable by the following stage.
 
+pub mod stage_docs;
+pub mod stage_json;
 pub mod stage_parse;
+pub mod stage_preprocess;
@@ -0,0 +1,64 @@
+//! Writes module documentation collected from `-doc` and `-moduledoc` attributes
+
+use libironclad_erlang::error::ic_error::{IroncladError, IroncladResult};
+use libironclad_erlang::project::module::module_doc::{DocFormat, ModuleDocs};
+use libironclad_erlang::project::module::module_impl::ErlModuleImpl;
+use libironclad_erlang::project::ErlProject;
+use libironclad_util::stats::time_stats::TimeStatsImpl;
+use std::path::Path;
+
+/// Handles the documentation mode, renders the documentation for each module
+pub struct ErlDocsStage {}
+
+impl ErlDocsStage {
+  /// Docs stage
+  /// * Parse loaded ERL files and collect their documentation.
+  /// * Write each module not hidden with `-moduledoc false.` as `<output_dir>/<module>.<ext>`, and
+  ///   an index page `<output_dir>/index.<ext>`.
+  pub fn run_docs_stage(
+    project: &ErlProject,
+    output_dir: &Path,
+    format: DocFormat,
+  ) -> IroncladResult<()> {
+    let mut stage_time = TimeStatsImpl::default();
+    let inputs = project.project_inputs.input_paths.clone_contents();
+    std::fs::create_dir_all(output_dir).map_err(IroncladError::from)?;
+
+    let mut documented = Vec::new();
+    for path in inputs.iter() {
+      if path.extension().unwrap_or_default() != "erl" {
+        continue;
+      }
+      let compiler_opts = project.get_compiler_options_for(path);
+      let source_file = project.get_source_file(path)?;
+      let module =
+        ErlModuleImpl::from_module_source(project, &source_file, Some(compiler_opts.clone()))?;
+      project.register_new_module(&module);
+
+      if module.has_errors() {
+        module.print_errors()
+      }
+
+      let docs = module.documentation();
+      if docs.is_hidden() {
+        continue;
+      }
+      let out_path = output_dir
+        .join(&docs.name)
+        .with_extension(format.extension());
+      std::fs::write(&out_path, docs.render(format)).map_err(IroncladError::from)?;
+      println!("DOCS {} -> {}", path.to_string_lossy(), out_path.to_string_lossy());
+      documented.push(docs);
+    }
+
+    documented.sort_by(|a, b| a.name.cmp(&b.name));
+    let index_path = output_dir.join("index").with_extension(format.extension());
+    std::fs::write(&index_path, ModuleDocs::render_index(&documented, format))
+      .map_err(IroncladError::from)?;
+
+    stage_time.stop_timer();
+    println!("DOCS stage: {}", stage_time);
+
+    Ok(())
+  }
+}
@@ -0,0 +1,47 @@
+//! Writes parsed modules as JSON for external tools
+
+use libironclad_erlang::error::ic_error::{IroncladError, IroncladResult};
+use libironclad_erlang::project::module::module_impl::ErlModuleImpl;
+use libironclad_erlang::project::ErlProject;
+use libironclad_util::stats::time_stats::TimeStatsImpl;
+use std::path::Path;
+
+/// Handles the JSON export mode, writes the AST and the module scope of each module
+pub struct ErlJsonStage {}
+
+impl ErlJsonStage {
+  /// JSON export stage
+  /// * Parse loaded ERL files as Erlang.
+  /// * Write each module as `<output_dir>/<module>.json`, see `module_json` for the schema.
+  pub fn run_json_stage(project: &ErlProject, output_dir: &Path) -> IroncladResult<()> {
+    let mut stage_time = TimeStatsImpl::default();
+    let inputs = project.project_inputs.input_paths.clone_contents();
+    std::fs::create_dir_all(output_dir).map_err(IroncladError::from)?;
+
+    for path in inputs.iter() {
+      if path.extension().unwrap_or_default() != "erl" {
+        continue;
+      }
+      let compiler_opts = project.get_compiler_options_for(path);
+      let source_file = project.get_source_file(path)?;
+      let module =
+        ErlModuleImpl::from_module_source(project, &source_file, Some(compiler_opts.clone()))?;
+      project.register_new_module(&module);
+
+      if module.has_errors() {
+        module.print_errors()
+      }
+
+      let module_name = module.name.read().unwrap().clone();
+      let out_path = output_dir.join(module_name).with_extension("json");
+      std::fs::write(&out_path, module.to_json().to_pretty_string())
+        .map_err(IroncladError::from)?;
+      println!("JSON {} -> {}", path.to_string_lossy(), out_path.to_string_lossy());
+    }
+
+    stage_time.stop_timer();
+    println!("JSON stage: {}", stage_time);
+
+    Ok(())
+  }
+}
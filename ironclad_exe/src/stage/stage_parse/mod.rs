@@ -39,6 +39,9 @@ impl ErlParseStage {
 
         // Check for possible errors, like expressions containing wrong types of nodes
         module.verify_parsed_integrity()?;
+        module.verify_variables();
+        module.verify_unused();
+        module.fold_constants();
 
         operation_timer.stop_timer();
         println!("FILE {} - {}", operation_timer, source_file.file_name.to_string_lossy());
@@ -49,6 +52,11 @@ impl ErlParseStage {
       }
     }
 
+    // Behaviours can be defined in the project, check the implementers when all modules are known
+    project.verify_behaviours();
+    // Opaque types can be defined in any project module
+    project.verify_opaque_patterns();
+
     stage_time.stop_timer();
     println!("PARSE stage: {}", stage_time);
 
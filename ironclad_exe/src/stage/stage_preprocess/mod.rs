@@ -0,0 +1,47 @@
+//! Writes preprocessed Erlang source for each module
+
+use libironclad_erlang::error::ic_error::{IroncladError, IroncladResult};
+use libironclad_erlang::project::module::module_impl::ErlModuleImpl;
+use libironclad_erlang::project::ErlProject;
+use libironclad_util::stats::time_stats::TimeStatsImpl;
+use std::path::Path;
+
+/// Handles the preprocess-only mode, an equivalent of `erlc -P`
+pub struct ErlPreprocessStage {}
+
+impl ErlPreprocessStage {
+  /// Preprocess stage
+  /// * Run the preprocessor on loaded ERL files: paste includes, resolve conditions, expand macros.
+  /// * Write the result for each module as `<output_dir>/<module>.P`.
+  pub fn run_preprocess_stage(project: &ErlProject, output_dir: &Path) -> IroncladResult<()> {
+    let mut stage_time = TimeStatsImpl::default();
+    let inputs = project.project_inputs.input_paths.clone_contents();
+    std::fs::create_dir_all(output_dir).map_err(IroncladError::from)?;
+
+    for path in inputs.iter() {
+      if path.extension().unwrap_or_default() != "erl" {
+        continue;
+      }
+      let compiler_opts = project.get_compiler_options_for(path);
+      let source_file = project.get_source_file(path)?;
+
+      let (module, text) =
+        ErlModuleImpl::preprocess_to_source(project, &source_file, Some(compiler_opts.clone()))?;
+
+      let out_path = output_dir
+        .join(path.file_stem().unwrap_or_default())
+        .with_extension("P");
+      std::fs::write(&out_path, text).map_err(IroncladError::from)?;
+      println!("PREPROCESSED {} -> {}", path.to_string_lossy(), out_path.to_string_lossy());
+
+      if module.has_errors() {
+        module.print_errors()
+      }
+    }
+
+    stage_time.stop_timer();
+    println!("PREPROCESS stage: {}", stage_time);
+
+    Ok(())
+  }
+}
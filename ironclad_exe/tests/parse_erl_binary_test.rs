@@ -100,3 +100,15 @@ fn parse_fun_with_binary_constructor() -> IroncladResult<()> {
   let _module = test_util::parse_module(function_name!(), input);
   Ok(())
 }
+
+#[named]
+#[test]
+fn parse_binary_sigil() {
+  test_util::start(function_name!(), "Parse a binary string sigil as a binary literal");
+
+  let ast1 = test_util::parse_expr(function_name!(), "~\"text\"");
+  assert!(ast1.is_binary(), "Expected a binary literal, got {}", ast1);
+
+  let ast2 = test_util::parse_expr(function_name!(), "~s\"text\"");
+  assert!(!ast2.is_binary(), "Expected a string literal, got {}", ast2);
+}
@@ -3,19 +3,31 @@ extern crate function_name;
 extern crate libironclad_erlang;
 
 use std::ops::Deref;
+use std::path::PathBuf;
+use std::sync::Arc;
 
 use ::function_name::named;
+use libironclad_erlang::erl_syntax::erl_ast::ast_folder::{fold_node, Folder};
 use libironclad_erlang::erl_syntax::erl_ast::ast_iter::IterableAstNodeT;
+use libironclad_erlang::erl_syntax::erl_ast::ast_visitor::{walk_node, AstScope, Visitor};
+use libironclad_erlang::erl_syntax::erl_ast::node_impl::AstNodeImpl;
 use libironclad_erlang::erl_syntax::erl_ast::node_impl::AstNodeType::{
-  Apply, BinaryOp, ListComprehension, Lit,
+  Apply, BinaryOp, FnRef, FnRefDynamic, ListComprehension, Lit,
 };
+use libironclad_erlang::erl_syntax::erl_ast::AstNode;
+use libironclad_erlang::erl_syntax::node::erl_var::ErlVar;
 use libironclad_erlang::erl_syntax::parsers::misc::panicking_parser_error_reporter;
+use libironclad_erlang::erl_syntax::parsers::parse_expr::parse_expr;
 use libironclad_erlang::erl_syntax::parsers::parse_expr::parse_expr_list::parse_list_comprehension;
+use libironclad_erlang::erl_syntax::parsers::parser_error::ErlParserErrorKind;
 use libironclad_erlang::erl_syntax::parsers::parser_input::ParserInput;
 use libironclad_erlang::erl_syntax::preprocessor::parsers::parse_record::parse_record_def;
 use libironclad_erlang::error::ic_error::IroncladResult;
 use libironclad_erlang::literal::Literal;
+use libironclad_erlang::project::module::module_doc::{DocFormat, DocTarget};
 use libironclad_erlang::project::module::module_impl::ErlModuleImpl;
+use libironclad_erlang::project::project_impl::ErlProjectImpl;
+use libironclad_erlang::project::ErlProject;
 use libironclad_util::mfarity::MFArity;
 use nom::Finish;
 
@@ -276,6 +288,51 @@ fn parse_expr_lc2() -> IroncladResult<()> {
   Ok(())
 }
 
+/// Try parse map comprehensions and map generators
+#[named]
+#[test]
+fn parse_expr_map_comprehension() -> IroncladResult<()> {
+  test_util::start(function_name!(), "Parse map comprehensions and map generators");
+  let expr = test_util::parse_expr(function_name!(), "#{K => V * 2 || K := V <- M, V > 0}");
+  assert!(expr.is_map_comprehension());
+  assert_eq!(format!("{}", expr), "#{K => (V * 2) || K := V <- M, (V > 0)}");
+
+  let expr = test_util::parse_expr(function_name!(), "[{K, V} || K := V <- M]");
+  assert!(matches!(&expr.content, ListComprehension { .. }));
+  assert_eq!(format!("{}", expr), "[{K, V} || K := V <- M]");
+
+  let source = "invert(M) -> #{V => K || K := V <- M}.";
+  let module = test_util::parse_module(function_name!(), source);
+  assert!(!module.has_errors());
+  assert!(module
+    .root_scope
+    .fn_defs
+    .contains(&MFArity::new_local("invert", 1)));
+  Ok(())
+}
+
+/// Try parse strict generators and zip generators in comprehensions
+#[named]
+#[test]
+fn parse_expr_strict_and_zip_generators() -> IroncladResult<()> {
+  test_util::start(function_name!(), "Parse strict generators and zip generators");
+  for src in [
+    "[X || X <:- L]",
+    "[{X, Y} || X <- Xs && Y <:- Ys, (X > Y)]",
+    "#{K => V || K := V <:- M && X <- L}",
+  ] {
+    let expr = test_util::parse_expr(function_name!(), src);
+    assert_eq!(format!("{}", expr), src);
+  }
+  let bin = test_util::parse_expr(function_name!(), "<< <<B>> || <<B>> <:= Bin && <<C>> <= D >>");
+  assert!(bin.is_binary_comprehension());
+
+  let source = "pairs(Xs, Ys) -> [{X, Y} || X <:- Xs && Y <:- Ys].";
+  let module = test_util::parse_module(function_name!(), source);
+  assert!(!module.has_errors());
+  Ok(())
+}
+
 /// Try parse a comma expression with some simpler nested exprs
 #[named]
 #[test]
@@ -567,6 +624,76 @@ coalesce_consecutive_labels([], Replace, Acc) ->
   Ok(())
 }
 
+#[named]
+#[test]
+fn parse_named_lambda() -> IroncladResult<()> {
+  test_util::start(function_name!(), "Parse a named recursive lambda");
+  let expr = test_util::parse_expr(
+    function_name!(),
+    "fun Loop(0) -> ok; Loop(N) when N > 0 -> Loop(N - 1) end",
+  );
+  let fndef = expr.as_fn_def();
+  assert_eq!(fndef.funarity, MFArity::new_local("Loop", 1));
+  assert_eq!(fndef.clauses.len(), 2);
+  assert!(fndef
+    .clauses
+    .iter()
+    .all(|fnc| fnc.name == Some("Loop".to_string())));
+
+  let anonymous = test_util::parse_expr(function_name!(), "fun(X) -> X end");
+  assert_eq!(anonymous.as_fn_def().clauses[0].name, None);
+  Ok(())
+}
+
+#[named]
+#[test]
+fn parse_named_lambda_different_names() {
+  test_util::start(function_name!(), "Named fun clauses with different names must be an error");
+  let input = "fun A() -> ok; B() -> ok end";
+  let tokens = test_util::tokenize(input);
+  let module = ErlModuleImpl::new_default();
+  let p_input = ParserInput::new_slice(module, &tokens);
+  let err = parse_expr(p_input)
+    .finish()
+    .expect_err("Named fun with different names must fail");
+  assert!(
+    err.errors.iter().any(|(_, kind)| matches!(
+      kind,
+      ErlParserErrorKind::Context("all clauses of a named fun must have the same name")
+    )),
+    "Unexpected parser error: {:?}",
+    err.errors.iter().map(|(_, kind)| kind).collect::<Vec<_>>()
+  );
+}
+
+#[named]
+#[test]
+fn parse_fn_reference_forms() -> IroncladResult<()> {
+  test_util::start(function_name!(), "Parse fun references with variable parts");
+  let local = test_util::parse_expr(function_name!(), "fun foo/2");
+  assert!(matches!(&local.content, FnRef { mfa } if *mfa == MFArity::new_local("foo", 2)));
+
+  let remote = test_util::parse_expr(function_name!(), "fun lists:map/2");
+  assert!(matches!(&remote.content, FnRef { mfa } if *mfa == MFArity::new("lists", "map", 2)));
+
+  for src in [
+    "fun M:'foo'/2",
+    "fun 'lists':F/2",
+    "fun 'lists':'map'/A",
+    "fun M:F/A",
+  ] {
+    let expr = test_util::parse_expr(function_name!(), src);
+    assert!(
+      matches!(&expr.content, FnRefDynamic { .. }),
+      "Expected a dynamic fun reference from {}, got {}",
+      src,
+      expr
+    );
+    assert_eq!(format!("{}", expr), src);
+  }
+  Ok(())
+}
+
 #[named]
 #[test]
 fn parse_fun_guard() -> IroncladResult<()> {
@@ -942,3 +1069,635 @@ fn parse_record_construction_with_base_value() {
   let input_expr = r#"St0#st{lmap=D,lc=New+1}"#;
   let _e = test_util::parse_expr(function_name!(), input_expr);
 }
+
+#[named]
+#[test]
+fn parse_callback_attrs() {
+  test_util::start(function_name!(), "Parse -callback, -optional_callbacks and -behaviour");
+  let module = test_util::parse_module(
+    function_name!(),
+    "-behaviour(gen_server).
+-behavior(application).
+-callback handle_thing(Thing :: atom()) -> ok | {error, term()}.
+-callback describe() -> string().
+-optional_callbacks([describe/0]).",
+  );
+  let scope = &module.root_scope;
+  assert!(scope.behaviours.contains(&"gen_server".to_string()));
+  assert!(scope.behaviours.contains(&"application".to_string()));
+  assert!(scope
+    .callbacks
+    .contains(&MFArity::new_local("handle_thing", 1)));
+  assert!(scope.callbacks.contains(&MFArity::new_local("describe", 0)));
+  assert!(scope
+    .optional_callbacks
+    .contains(&MFArity::new_local("describe", 0)));
+  assert!(scope.fn_specs.is_empty(), "Callbacks must not be stored as function specs");
+}
+
+#[named]
+#[test]
+fn verify_builtin_behaviour() {
+  test_util::start(function_name!(), "Check the implementers of gen_server");
+  let project: ErlProject = ErlProjectImpl::default().into();
+  let complete = test_util::parse_module_in_project(
+    &project,
+    "complete_server",
+    "-behaviour(gen_server).
+-export([init/1, handle_call/3, handle_cast/2]).
+-spec init(list()) -> {ok, list()}.
+init(Args) -> {ok, Args}.
+handle_call(_Request, _From, State) -> {reply, ok, State}.
+handle_cast(_Request, State) -> {noreply, State}.",
+  );
+  assert!(complete.verify_behaviours(), "All required gen_server callbacks are exported");
+
+  let incomplete = test_util::parse_module_in_project(
+    &project,
+    "incomplete_server",
+    "-behaviour(gen_server).
+-export([init/1, handle_call/3]).
+init(Args) -> {ok, Args}.
+handle_call(_Request, _From, State) -> {reply, ok, State}.",
+  );
+  assert!(!incomplete.verify_behaviours(), "handle_cast/2 is missing");
+  assert!(incomplete.errors.is_empty(), "A missing callback is not an error");
+  assert_eq!(incomplete.warnings.len(), 1);
+  assert_eq!(
+    incomplete.warnings.data.read().unwrap()[0]
+      .get_location()
+      .start(),
+    incomplete
+      .root_scope
+      .behaviours
+      .get(&"gen_server".to_string())
+      .unwrap()
+      .start(),
+    "The missing callback is reported at the -behaviour attribute"
+  );
+
+  for name in ["gen_server", "supervisor", "gen_statem", "application"] {
+    let behaviour = project.find_behaviour(name).unwrap();
+    assert!(!behaviour.root_scope.callbacks.is_empty(), "No callbacks for {}", name);
+    assert!(
+      Arc::ptr_eq(&behaviour, &project.find_behaviour(name).unwrap()),
+      "Built-in behaviour {} must be parsed once",
+      name
+    );
+  }
+}
+
+#[named]
+#[test]
+fn verify_project_behaviour() {
+  test_util::start(function_name!(), "Check the implementers of a behaviour from the project");
+  let project: ErlProject = ErlProjectImpl::default().into();
+  test_util::parse_module_in_project(
+    &project,
+    "shape",
+    "-callback area(Shape :: tuple()) -> number().
+-callback name() -> atom().
+-optional_callbacks([name/0]).",
+  );
+
+  let square = test_util::parse_module_in_project(
+    &project,
+    "square",
+    "-behaviour(shape).
+-export([area/1]).
+-spec area({square, number()}) -> number().
+area({square, Side}) -> Side * Side.",
+  );
+  assert!(square.verify_behaviours(), "Optional name/0 may be missing");
+
+  let circle = test_util::parse_module_in_project(
+    &project,
+    "circle",
+    "-behaviour(shape).
+-export([area/1]).
+-spec area({circle, number()}) -> atom().
+area({circle, _R}) -> unknown.",
+  );
+  assert!(!circle.verify_behaviours(), "area/1 spec returns atom() instead of number()");
+  let spec_location = circle
+    .root_scope
+    .fn_spec_locations
+    .get(&MFArity::new_local("area", 1))
+    .unwrap();
+  assert!(spec_location.start().is_some());
+  assert_eq!(
+    circle.errors.data.read().unwrap()[0].get_location().start(),
+    spec_location.start(),
+    "The incompatible spec is reported at the -spec attribute"
+  );
+
+  let unknown = test_util::parse_module_in_project(&project, "unknown", "-behaviour(no_such).");
+  assert!(!unknown.verify_behaviours(), "Behaviour no_such does not exist");
+  assert!(unknown.errors.is_empty(), "An unknown behaviour is not an error");
+  assert!(unknown.warnings.data.read().unwrap()[0]
+    .get_location()
+    .start()
+    .is_some());
+
+  let strict = test_util::parse_module_in_project(
+    &project,
+    "strict",
+    "-compile(warnings_as_errors).\n-behaviour(no_such).",
+  );
+  assert!(!strict.verify_behaviours());
+  assert_eq!(strict.errors.len(), 1, "warnings_as_errors applies to the behaviour warnings");
+
+  let quiet = test_util::parse_module_in_project(
+    &project,
+    "quiet",
+    "-compile(nowarn_undefined_behaviour).\n-behaviour(no_such).",
+  );
+  assert!(quiet.verify_behaviours());
+  assert!(quiet.warnings.is_empty(), "nowarn_undefined_behaviour disables the warning");
+}
+
+#[named]
+#[test]
+fn parse_doc_attrs() {
+  test_util::start(function_name!(), "Attach -doc attributes to functions, types and callbacks");
+  let module = test_util::parse_module(
+    function_name!(),
+    "-moduledoc \"Shapes and areas.\".
+-moduledoc #{since => \"1.0\"}.
+-export([area/1, secret/0]).
+-export_type([shape/0]).
+-doc \"A shape.\".
+-type shape() :: {square, integer()}.
+-doc \"Called to draw.\".
+-callback draw(shape()) -> ok.
+-doc \"Area of a shape.\".
+-spec area(shape()) -> integer().
+area({square, Side}) -> Side * Side.
+-doc false.
+secret() -> 42.",
+  );
+  let docs = &module.root_scope.docs;
+  let text_of = |target: DocTarget| docs.get(&target).and_then(|d| d.text.clone());
+
+  let module_doc = docs.get(&DocTarget::Module).unwrap();
+  assert_eq!(module_doc.text.as_deref(), Some("Shapes and areas."));
+  assert_eq!(module_doc.metadata, vec![("since".to_string(), "1.0".to_string())]);
+  assert_eq!(
+    text_of(DocTarget::Type(MFArity::new_local("shape", 0))).as_deref(),
+    Some("A shape.")
+  );
+  assert_eq!(
+    text_of(DocTarget::Callback(MFArity::new_local("draw", 1))).as_deref(),
+    Some("Called to draw.")
+  );
+  assert_eq!(
+    text_of(DocTarget::Function(MFArity::new_local("area", 1))).as_deref(),
+    Some("Area of a shape.")
+  );
+  assert!(
+    docs
+      .get(&DocTarget::Function(MFArity::new_local("secret", 0)))
+      .unwrap()
+      .hidden
+  );
+
+  let documentation = module.documentation();
+  assert_eq!(documentation.functions.len(), 1, "Hidden functions are not documented");
+  assert_eq!(documentation.types.len(), 1);
+  assert_eq!(documentation.callbacks.len(), 1);
+
+  let markdown = documentation.render(DocFormat::Markdown);
+  assert!(markdown.contains("Shapes and areas."));
+  assert!(markdown.contains("Area of a shape."));
+  assert!(markdown.contains("area("), "The -spec is rendered: {}", markdown);
+  assert!(!markdown.contains("secret"));
+  let html = documentation.render(DocFormat::Html);
+  assert!(html.contains("<h1>Module parse_doc_attrs</h1>"), "{}", html);
+}
+
+#[named]
+#[test]
+fn parse_doc_attrs_placement() {
+  test_util::start(function_name!(), "Attach -doc to the following form, warn on a dangling one");
+  let module = test_util::parse_module(
+    function_name!(),
+    "-doc \"Documents f/0 through its spec.\".
+-spec f() -> ok.
+g() -> ok.
+f() -> ok.
+-doc \"Dangling before a record.\".
+-record(r, {a}).
+h() -> ok.
+-doc \"Dangling at the end.\".",
+  );
+  let docs = &module.root_scope.docs;
+  let text_of = |name: &str| {
+    docs
+      .get(&DocTarget::Function(MFArity::new_local(name, 0)))
+      .and_then(|d| d.text.clone())
+  };
+  assert_eq!(text_of("f").as_deref(), Some("Documents f/0 through its spec."));
+  assert_eq!(text_of("g"), None, "The -doc belongs to the spec which follows it");
+  assert_eq!(
+    text_of("h"),
+    None,
+    "The -doc before a record is not attached to the next function"
+  );
+
+  let warnings: Vec<String> = module
+    .warnings
+    .data
+    .read()
+    .unwrap()
+    .iter()
+    .filter(|w| w.get_message().starts_with("-doc is not followed"))
+    .map(|w| {
+      module
+        .format_location(&w.get_location())
+        .unwrap_or_default()
+    })
+    .collect();
+  assert_eq!(
+    warnings,
+    vec![
+      "parse_doc_attrs_placement:6".to_string(),
+      "parse_doc_attrs_placement:9".to_string()
+    ],
+    "Dangling -doc attributes are reported where they are"
+  );
+}
+
+/// Counts variables and scopes, records the order in which variables are visited
+#[derive(Default)]
+struct VarCounter {
+  vars: Vec<String>,
+  scopes_entered: usize,
+  scopes_exited: usize,
+}
+
+impl Visitor for VarCounter {
+  fn enter_scope(&mut self, _scope: AstScope<'_>) {
+    self.scopes_entered += 1;
+  }
+
+  fn exit_scope(&mut self, _scope: AstScope<'_>) {
+    self.scopes_exited += 1;
+  }
+
+  fn visit_var(&mut self, _node: &AstNode, var: &ErlVar) {
+    self.vars.push(var.name.clone());
+  }
+}
+
+/// Replaces atom `foo` with atom `bar`
+struct RenameFoo;
+
+impl Folder for RenameFoo {
+  fn fold_lit(&mut self, node: AstNode) -> AstNode {
+    match &node.content {
+      Lit { value } if **value == Literal::Atom("foo".to_string()) => AstNodeImpl {
+        location: node.location.clone(),
+        content: Lit { value: Literal::Atom("bar".to_string()).into() },
+      }
+      .into(),
+      _ => node,
+    }
+  }
+}
+
+#[named]
+#[test]
+fn ast_visitor_and_folder() {
+  test_util::start(function_name!(), "Walk a module with a Visitor and rewrite it with a Folder");
+  let module = test_util::parse_module(
+    function_name!(),
+    "f(A, B) -> [X + A || X <- B];
+f(_, C) -> case C of foo -> {foo, C}; _ -> baz end.",
+  );
+  let ast = module.ast.borrow().clone();
+
+  let mut counter = VarCounter::default();
+  walk_node(&mut counter, &ast);
+  assert_eq!(counter.scopes_entered, 3, "Two function clauses and one comprehension");
+  assert_eq!(counter.scopes_exited, 3);
+  assert_eq!(counter.vars, vec!["A", "B", "B", "X", "X", "A", "_", "C", "C", "C", "_"]);
+
+  let folded = fold_node(&mut RenameFoo, &ast);
+  let folded_text = format!("{}", folded);
+  assert!(!folded_text.contains("foo"), "{}", folded_text);
+  assert!(folded_text.contains("{'bar', C}"), "{}", folded_text);
+  assert!(format!("{}", ast).contains("foo"), "The original tree is not changed");
+}
+
+#[named]
+#[test]
+fn fold_constants() {
+  test_util::start(function_name!(), "Fold literal containers and constant operators");
+  let module = test_util::parse_module(
+    function_name!(),
+    "list() -> [1, 2 | [3]].
+tuple() -> {ok, 1 + 2 * 3, -5}.
+map() -> #{b => 2, a => 1, a => 3}.
+binary() -> <<1, 256:16, \"hi\">>.
+bignum() -> (1 bsl 100) * 4.
+short_circuit() -> 3 > 2 andalso foo.
+not_constant(X) -> {X, 1 + 1}.
+div_by_zero() -> 1 / 0.
+atom_plus() -> atom + 1.",
+  );
+  module.fold_constants();
+  let folded = format!("{}", module.ast.borrow());
+  for expected in [
+    "list() -> [1, 2, 3]",
+    "tuple() -> {'ok', 7, -5}",
+    "map() -> #{'a' => 3, 'b' => 2}",
+    "binary() -> <<1, 1, 0, 104, 105>>",
+    "bignum() -> ↑5070602400912917605986812821504",
+    "short_circuit() -> 'foo'",
+    "not_constant(X) -> {X, 2}",
+    "div_by_zero() -> (1 / 0)",
+  ] {
+    assert!(folded.contains(expected), "Expected {} in {}", expected, folded);
+  }
+
+  // The root scope sees the folded functions
+  let fn_def = module
+    .root_scope
+    .fn_defs
+    .get(&MFArity::new_local("list", 0))
+    .unwrap();
+  assert!(format!("{}", fn_def).contains("[1, 2, 3]"));
+
+  let warnings = test_util::messages(&module.warnings);
+  assert_eq!(warnings.len(), 2, "{:?}", warnings);
+  assert!(warnings[0].contains("badarith in 1 / 0"), "{}", warnings[0]);
+  assert!(warnings[1].contains("badarith in 'atom' + 1"), "{}", warnings[1]);
+}
+
+#[named]
+#[test]
+fn export_module_json() {
+  test_util::start(function_name!(), "Export a module as JSON and compare with the snapshot");
+  let module = test_util::parse_module(
+    function_name!(),
+    "-export([start/1]).
+-export_type([state/0]).
+-import(lists, [reverse/1]).
+-define(TIMEOUT, 5000).
+-define(WRAP(X), {wrapped, X}).
+-record(state, {count = 0 :: integer(), name :: atom()}).
+-type state() :: #state{}.
+-author(\"Someone\").
+-spec start(atom()) -> state().
+start(Name) ->
+  case Name of
+    undefined -> ?WRAP(#state{});
+    _ -> #state{name = Name, count = ?TIMEOUT}
+  end.
+limit() -> 5070602400912917605986812821504.",
+  );
+  let json = module.to_json().to_pretty_string();
+
+  let snapshot_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+    .join("tests/snapshots")
+    .join(function_name!())
+    .with_extension("json");
+  if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+    std::fs::create_dir_all(snapshot_path.parent().unwrap()).unwrap();
+    std::fs::write(&snapshot_path, &json).unwrap();
+  }
+  let expected = std::fs::read_to_string(&snapshot_path)
+    .expect("Snapshot is missing, run the test with UPDATE_SNAPSHOTS=1 to create it");
+  assert_eq!(json, expected, "Set UPDATE_SNAPSHOTS=1 to accept the new output");
+}
+
+#[named]
+#[test]
+fn verify_variables() {
+  test_util::start(function_name!(), "Report unbound, unsafe, unused and shadowed variables");
+  let module = test_util::parse_module(
+    function_name!(),
+    "unbound(A) -> A + B.
+unsafe_case(X) ->
+  case X of
+    1 -> Y = 1, Z = 2;
+    _ -> Z = 3
+  end,
+  {Y, Z}.
+unsafe_try() ->
+  try T = 1, T of V -> V catch _:_ -> ok end,
+  T.
+unsafe_andalso(X) -> X andalso (W = true), W.
+unused(Unused, _Ignored, {Pair, Pair}) -> ok.
+underscore(_Marked) -> _Marked.
+shadow(S) -> F = fun(S) -> S end, F(1).
+generate(L, E) -> [E || E <- L].
+if_branches(X) -> if X > 0 -> R = pos; true -> R = neg end, R.
+bin_size(Size, <<Val:Size>>) -> Val.
+named_fun() -> F = fun Loop(0) -> 0; Loop(N) -> Loop(N - 1) end, F(1).",
+  );
+  assert!(!module.verify_variables());
+
+  let errors = test_util::messages(&module.errors);
+  assert_eq!(
+    errors,
+    vec![
+      "variable 'B' is unbound",
+      "variable 'Y' unsafe in 'case'",
+      "variable 'T' unsafe in 'try'",
+      "variable 'W' unsafe in 'andalso'",
+    ]
+  );
+
+  let warnings = test_util::messages(&module.warnings);
+  assert_eq!(
+    warnings,
+    vec![
+      "variable 'Unused' is unused",
+      "variable '_Marked' is used after being marked unused",
+      "variable 'S' shadowed in 'fun'",
+      "variable 'S' is unused",
+      "variable 'E' shadowed in generate",
+      "variable 'E' is unused",
+    ]
+  );
+
+  // The shadowing variables are reported where they are bound, `S` in `fun(S)` and `E` in `E <- L`
+  let positions: Vec<(usize, usize)> = module
+    .warnings
+    .data
+    .read()
+    .unwrap()
+    .iter()
+    .filter(|e| e.get_message().contains("shadowed"))
+    .map(|e| {
+      let (_offset, line, column) = module
+        .source_file
+        .position_of(e.get_location().start().unwrap())
+        .unwrap();
+      (line, column)
+    })
+    .collect();
+  assert_eq!(positions, vec![(15, 22), (16, 25)]);
+}
+
+#[named]
+#[test]
+fn verify_variables_named_fun() {
+  test_util::start(function_name!(), "A named fun shadows a bound variable of the same name");
+  let module =
+    test_util::parse_module(function_name!(), "named_shadow() -> F = 1, fun F() -> ok end.");
+  assert!(module.verify_variables());
+  let warnings = test_util::messages(&module.warnings);
+  assert_eq!(
+    warnings,
+    vec![
+      "variable 'F' shadowed in 'named fun'",
+      "variable 'F' is unused"
+    ]
+  );
+}
+
+#[named]
+#[test]
+fn verify_variables_nowarn() {
+  test_util::start(function_name!(), "Unused and shadowed variable warnings can be disabled");
+  let module = test_util::parse_module(
+    function_name!(),
+    "-compile([nowarn_unused_vars, {nowarn_shadow_vars, [shadow/1]}]).
+unused(Unused) -> ok.
+shadow(S) -> fun(S) -> S end.
+shadow2(S) -> fun(S) -> S end.",
+  );
+  assert!(module.verify_variables());
+  let warnings = test_util::messages(&module.warnings);
+  assert_eq!(warnings, vec!["variable 'S' shadowed in 'fun'"]);
+}
+
+#[named]
+#[test]
+fn verify_unused() {
+  test_util::start(function_name!(), "Warnings for unused functions, records, types, macros");
+  let module = test_util::parse_module(
+    function_name!(),
+    "-export([start/0]).
+-export_type([pub_t/0]).
+-on_load(init/0).
+-import(lists, [reverse/1, sort/1]).
+-define(USED, 1).
+-define(TESTED, 2).
+-define(UNUSED, 3).
+-define(UNUSED_FN(X), X).
+-record(used_rec, {a}).
+-record(info_rec, {b}).
+-record(typed_rec, {c}).
+-record(unused_rec, {d}).
+-record(is_rec, {e}).
+-record(is_rec3, {f}).
+-record(type_only_rec, {g}).
+-type pub_t() :: integer().
+-type spec_t() :: #typed_rec{}.
+-type unused_t(A, B) :: {A, B} | unused_t(A, B) | inner_t().
+-type inner_t() :: #type_only_rec{}.
+-type ping_t() :: pong_t() | ok.
+-type pong_t() :: ping_t().
+-ifdef(TESTED).
+-endif.
+-spec start() -> spec_t().
+start() -> helper(#used_rec{a = ?USED}).
+helper(R) ->
+  {R, reverse([]), record_info(fields, info_rec), is_record(R, is_rec), is_record(R, is_rec3, 2)}.
+init() -> ok.
+unused(X) -> unused(X).",
+  );
+  module.verify_unused();
+  let warnings = test_util::messages(&module.warnings);
+  assert_eq!(
+    warnings,
+    vec![
+      "function unused/1 is unused",
+      "record type_only_rec is unused",
+      "record unused_rec is unused",
+      "type inner_t() is unused",
+      "type ping_t() is unused",
+      "type pong_t() is unused",
+      "type unused_t(_,_) is unused",
+      "macro UNUSED is unused",
+      "macro UNUSED_FN/1 is unused",
+    ]
+  );
+}
+
+#[named]
+#[test]
+fn verify_unused_import() {
+  test_util::start(function_name!(), "Unused imports are reported with warn_unused_import");
+  let module = test_util::parse_module(
+    function_name!(),
+    "-compile([warn_unused_import]).
+-export([start/0]).
+-import(lists, [reverse/1, sort/1]).
+start() -> reverse([]).",
+  );
+  module.verify_unused();
+  let warnings = test_util::messages(&module.warnings);
+  assert_eq!(warnings, vec!["import lists:sort/1 is unused"]);
+}
+
+#[named]
+#[test]
+fn verify_unused_nowarn() {
+  test_util::start(function_name!(), "Unused definition warnings can be disabled");
+  let module = test_util::parse_module(
+    function_name!(),
+    "-compile([nowarn_unused_record, nowarn_unused_macros, nowarn_unused_import]).
+-compile([{nowarn_unused_function, [quiet/0]}, {nowarn_unused_type, [quiet_t/0]}]).
+-import(lists, [sort/1]).
+-define(UNUSED, 1).
+-record(unused_rec, {a}).
+-type quiet_t() :: integer().
+-type loud_t() :: atom().
+quiet() -> ok.
+loud() -> ok.",
+  );
+  module.verify_unused();
+  let warnings = test_util::messages(&module.warnings);
+  assert_eq!(warnings, vec!["function loud/0 is unused", "type loud_t() is unused"]);
+}
+
+#[named]
+#[test]
+fn verify_unused_macro_arity() {
+  test_util::start(
+    function_name!(),
+    "Macros of the same name and different arity are used separately",
+  );
+  let module = test_util::parse_module(
+    function_name!(),
+    "-export([start/0]).
+-define(F, 1).
+-define(F(X), X).
+-define(G(X), X).
+-define(G(X, Y), {X, Y}).
+-ifdef(G).
+-endif.
+start() -> ?F.",
+  );
+  module.verify_unused();
+  let warnings = test_util::messages(&module.warnings);
+  assert_eq!(warnings, vec!["macro F/1 is unused"]);
+}
+
+#[named]
+#[test]
+fn verify_unused_included() {
+  test_util::start(function_name!(), "Definitions from the included files are not reported");
+  let files = [("defs.hrl", "-define(INC, 1).\n-record(inc_rec, {a}).\n-type inc_t() :: ok.\n")];
+  let module = test_util::parse_module_with_files(
+    function_name!(),
+    &files,
+    "-include(\"defs.hrl\").\n-export([f/0]).\nf() -> ok.\n",
+  );
+  module.verify_unused();
+  assert!(module.warnings.data.read().unwrap().is_empty());
+}
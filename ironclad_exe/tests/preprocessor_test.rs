@@ -1,10 +1,17 @@
 mod test_util;
 
 use ::function_name::named;
+use libironclad_erlang::erl_syntax::erl_ast::node_impl::AstNodeType;
 use libironclad_erlang::erl_syntax::parsers::token_stream::token_kind::TokenKind;
 use libironclad_erlang::error::ic_error::IroncladResult;
+use libironclad_erlang::project::conf::ProjectConf;
+use libironclad_erlang::project::module::module_features::ErlFeature;
+use libironclad_erlang::project::module::module_impl::ErlModuleImpl;
+use libironclad_erlang::project::project_impl::ErlProjectImpl;
 use libironclad_erlang::typing::erl_integer::ErlInteger;
 use libironclad_util::mfarity::MFArity;
+use libironclad_util::source_file::SourceFileImpl;
+use std::path::PathBuf;
 
 // #[test]
 // #[named]
@@ -19,14 +26,15 @@ use libironclad_util::mfarity::MFArity;
 
 #[test]
 #[named]
-#[should_panic]
-/// Try parse a simple pp directive -if(3). and expect a panic
+/// Try parse a simple pp directive -if(3). Like in `epp` a non-boolean condition is false, and the
+/// missing `-endif` is reported as an error
 fn test_fragment_if_3() {
-  test_util::start(function_name!(), "Parse -if(3) directive for a panic");
+  test_util::start(function_name!(), "Parse -if(3) directive for an error");
   let input = "-if(3).
 -end.";
-  let _module = test_util::parse_module(function_name!(), input);
-  // assert!(result, "Parsing -if(3). must produce a panic");
+  let module = test_util::parse_module(function_name!(), input);
+  assert!(module.has_errors(), "Parsing -if(3). without -endif must produce an error");
+  assert!(!module.root_scope.attributes.contains(&"end".to_string()));
 }
 
 #[test]
@@ -383,3 +391,644 @@ fn test_ast_macro_args_substitution() {
   assert!(pdef.tokens[2].is_tok(TokenKind::Integer(ErlInteger::Small(2))));
   println!("{:?}", pdef);
 }
+
+#[test]
+#[named]
+/// Include a header which includes a sibling header, found relative to the including file
+fn test_include_relative_to_including_file() {
+  test_util::start(function_name!(), "Nested include is resolved next to the including file");
+  let files = [
+    ("inc/outer.hrl", "-include(\"inner.hrl\").\n-define(OUTER, outer).\n"),
+    ("inc/inner.hrl", "-define(INNER, inner).\n"),
+  ];
+  let input = "-include(\"inc/outer.hrl\").\nmyfun() -> ?INNER.";
+  let module = test_util::parse_module_with_files(function_name!(), &files, input);
+  assert!(!module.has_errors());
+  assert!(module.root_scope.is_defined("OUTER"));
+  assert!(module.root_scope.is_defined("INNER"));
+
+  let included = module.included_files();
+  assert_eq!(included.len(), 2);
+  assert!(included[0].ends_with("inc/outer.hrl"));
+  assert!(included[1].ends_with("inc/inner.hrl"));
+
+  let graph = module.include_graph.clone_contents();
+  assert!(graph[1].from.ends_with("inc/outer.hrl"));
+  assert!(graph[1].to.ends_with("inc/inner.hrl"));
+  assert!(
+    module.include_chain().is_empty(),
+    "Include chain must be empty after preprocessing"
+  );
+}
+
+#[test]
+#[named]
+/// Two headers including each other must produce an error with the include chain
+fn test_include_circular() {
+  test_util::start(function_name!(), "Circular include is reported with the full chain");
+  let files = [
+    ("a.hrl", "-include(\"b.hrl\").\n-define(A, a).\n"),
+    ("b.hrl", "-include(\"a.hrl\").\n-define(B, b).\n"),
+  ];
+  let module = test_util::parse_module_with_files(function_name!(), &files, "-include(\"a.hrl\").");
+  assert!(module.has_errors());
+  assert!(module.root_scope.is_defined("A"));
+  assert!(module.root_scope.is_defined("B"));
+
+  let msg = module.errors.data.read().unwrap()[0]
+    .get_message()
+    .to_string();
+  assert!(msg.starts_with("Circular include detected"), "Unexpected error: {}", msg);
+  let chain: Vec<&str> = msg.split(" -> ").collect();
+  assert_eq!(chain.len(), 4, "Chain must be module -> a -> b -> a: {}", msg);
+  assert!(chain[0].ends_with("test_include_circular.erl"));
+  assert!(chain[1].ends_with("a.hrl"));
+  assert!(chain[2].ends_with("b.hrl"));
+  assert!(chain[3].ends_with("a.hrl"));
+}
+
+/// Parse the input and check that attribute `-test_success` was seen and `-test_fail` was not
+fn check_if_success(function_name: &str, input: &str) {
+  let module = test_util::parse_module(function_name, input);
+  assert!(!module.has_errors(), "Module must have no errors");
+  let attrs = &module.root_scope.attributes;
+  assert!(attrs.contains(&"test_success".to_string()), "-test_success must be seen");
+  assert!(!attrs.contains(&"test_fail".to_string()), "-test_fail must be skipped");
+}
+
+#[test]
+#[named]
+/// Evaluate -if conditions with macros, arithmetic, comparisons and `defined()`
+fn parse_if_const_expr() {
+  test_util::start(function_name!(), "Evaluate -if conditions as constant expressions");
+  let input = "-define(LEVEL, 3).
+-define(DEBUG, true).
+-if(?OTP_RELEASE >= 26).
+-test_success.
+-else.
+-test_fail.
+-endif.
+-if(defined(DEBUG) andalso ?LEVEL > 2).
+-test_success.
+-endif.
+-if(defined(NODEBUG) orelse (?LEVEL * 2 + 1) rem 4 =/= 3).
+-test_fail.
+-endif.
+-if(?LEVEL == 3.0 andalso not (?LEVEL =:= 3.0) andalso 7 div 2 == 3).
+-test_success.
+-endif.
+-if(is_integer(?LEVEL) and is_atom(?DEBUG) and is_boolean(?DEBUG) and not is_float(?LEVEL)).
+-test_success.
+-endif.
+-if(element(2, {a, ?LEVEL}) == length([1, 2, 3]) andalso {1, b} < [] andalso 1 < a).
+-test_success.
+-endif.";
+  check_if_success(function_name!(), input);
+}
+
+#[test]
+#[named]
+/// Like in `epp`, a condition raising an exception or returning a non-boolean is false
+fn parse_if_const_expr_exception() {
+  test_util::start(function_name!(), "Exception in an -if condition makes it false");
+  let input = "-if(1 div 0 == 0).
+-test_fail.
+-elif(atom + 1).
+-test_fail.
+-elif(5).
+-test_fail.
+-else.
+-test_success.
+-endif.";
+  check_if_success(function_name!(), input);
+}
+
+#[test]
+#[named]
+/// Only the first true branch of -if/-elif/-else is taken, nested sections in an inactive branch
+/// are not evaluated
+fn parse_if_elif_nested() {
+  test_util::start(function_name!(), "Take only the first true -elif branch");
+  let input = "-if(false).
+-test_fail.
+-if(true).
+-test_fail.
+-else.
+-test_fail.
+-endif.
+-elif(1 + 1 == 2).
+-test_success.
+-elif(true).
+-test_fail.
+-else.
+-test_fail.
+-endif.";
+  check_if_success(function_name!(), input);
+}
+
+#[test]
+#[named]
+/// A variable or a non-guard call in -if condition is an error
+fn parse_if_not_constant() {
+  test_util::start(function_name!(), "Report an error for a non-constant -if condition");
+  let input = "-if(lists:member(a, [a])).
+-test_fail.
+-endif.";
+  let module = test_util::parse_module(function_name!(), input);
+  assert!(module.has_errors());
+  assert!(!module
+    .root_scope
+    .attributes
+    .contains(&"test_fail".to_string()));
+}
+
+#[test]
+#[named]
+/// Preprocess-only mode prints the module with includes pasted, conditions resolved and macros
+/// expanded, and marks include boundaries with `-file` attributes
+fn preprocess_only_output() {
+  test_util::start(function_name!(), "Write the preprocessed module source");
+  let files = [("inc/defs.hrl", "-define(GREETING, \"hello\").\n-record(point, {x = 0}).\n")];
+  let input = "-include(\"inc/defs.hrl\").
+-define(TWICE(X), X * 2).
+-ifdef(NOT_DEFINED).
+-removed.
+-else.
+-kept.
+-endif.
+f() -> {?GREETING, ?TWICE(21), 'Quoted atom', 1.5}.";
+  let (module, text) = test_util::preprocess_module_with_files(function_name!(), &files, input);
+  assert!(!module.has_errors(), "Module must have no errors");
+
+  let lines: Vec<&str> = text.lines().filter(|l| !l.is_empty()).collect();
+  assert!(lines[0].starts_with("-file(\"") && lines[0].ends_with(".erl\", 1)."));
+  assert_eq!(lines[1], "-module(preprocess_only_output).");
+  assert!(lines[2].starts_with("-file(\"") && lines[2].ends_with("defs.hrl\", 1)."));
+  assert_eq!(lines[3], "-record(point, {x = 0}).");
+  // The line after the -include directive in the module file
+  assert!(lines[4].starts_with("-file(\"") && lines[4].ends_with(".erl\", 3)."));
+  assert_eq!(lines[5], "-kept.");
+  assert_eq!(lines[6], "f() -> {\"hello\", 21 * 2, 'Quoted atom', 1.5}.");
+  assert_eq!(lines.len(), 7);
+}
+
+#[test]
+#[named]
+/// Defines from the project configuration, with values and with arguments, are visible to the
+/// module preprocessor
+fn config_defines_with_values() {
+  test_util::start(function_name!(), "Use defines from the project configuration");
+  let toml = r#"
+[compiler_options]
+defines = ["DEBUG", "LEVEL=3", "ADD(A, B)=A + B", "NAME = \"srv\""]
+"#;
+  let input = "-if(?DEBUG andalso ?LEVEL > 2 andalso ?ADD(1, 2) == 3).
+-test_success.
+-else.
+-test_fail.
+-endif.
+-ifdef(NAME).
+-name(?NAME).
+-endif.";
+  let module = test_util::parse_module_with_config(function_name!(), toml, input);
+  assert!(!module.has_errors(), "Module must have no errors");
+  let attrs = &module.root_scope.attributes;
+  assert!(attrs.contains(&"test_success".to_string()));
+  assert!(!attrs.contains(&"test_fail".to_string()));
+  assert!(attrs.contains(&"name".to_string()));
+}
+
+#[test]
+#[named]
+/// Malformed defines in the project configuration are reported as config errors
+fn config_defines_malformed() {
+  test_util::start(function_name!(), "Report malformed defines in the project configuration");
+  let toml = r#"
+[compiler_options]
+defines = ["GOOD=1", "1BAD", "F(x)=1", "EMPTY=", "G(X=1"]
+"#;
+  let conf = ProjectConf::from_string(toml).unwrap();
+  match ErlProjectImpl::try_from(conf) {
+    Ok(_) => panic!("Malformed defines must produce an error"),
+    Err(e) => {
+      let msg = format!("{}", e);
+      println!("{}", msg);
+      assert!(!msg.contains("\"GOOD=1\""));
+      for bad in ["\"1BAD\"", "\"F(x)=1\"", "\"EMPTY=\"", "\"G(X=1\""] {
+        assert!(msg.contains(bad), "Error must mention {}", bad);
+      }
+    }
+  }
+}
+
+#[test]
+#[named]
+/// Invoking an undefined macro is an error, the invocation is replaced with a placeholder and the
+/// module is still parsed
+fn test_macro_undefined() {
+  test_util::start(function_name!(), "Report an undefined macro and continue");
+  let input = "-define(FOO(X), X).
+f() -> ?FOO(1, 2).
+g() -> ?BAR.";
+  let module = test_util::parse_module(function_name!(), input);
+  let errors = test_util::messages(&module.errors);
+  assert_eq!(
+    errors,
+    vec![
+      "?FOO/2 is not defined, did you mean ?FOO/1?".to_string(),
+      "?BAR/0 is not defined".to_string()
+    ]
+  );
+  for name in ["f", "g"] {
+    let fndef = module
+      .root_scope
+      .fn_defs
+      .get(&MFArity::new_local(name, 0))
+      .unwrap();
+    assert!(fndef.as_fn_def().clauses[0].body.is_atom_of("undefined"));
+  }
+}
+
+#[test]
+#[named]
+/// Like epp, a macro without arguments is pasted before a parenthesized list, when no definition
+/// takes the arguments. Malformed arguments are reported without stopping the preprocessor.
+fn test_macro_arity_fallback() {
+  test_util::start(function_name!(), "Paste ?F before (Args) and report malformed arguments");
+  let input = "-define(F, lists:reverse).
+-define(G(X), X).
+f() -> ?F([1, 2]).
+g() -> ?G(case).
+h() -> ok.";
+  let module = test_util::parse_module(function_name!(), input);
+  let errors = test_util::messages(&module.errors);
+  assert_eq!(errors, vec!["Malformed arguments of the macro invocation ?G".to_string()]);
+  let f = module
+    .root_scope
+    .fn_defs
+    .get(&MFArity::new_local("f", 0))
+    .unwrap();
+  assert_eq!(format!("{}", f.as_fn_def().clauses[0].body), "lists:reverse([1, 2])");
+  let g = module
+    .root_scope
+    .fn_defs
+    .get(&MFArity::new_local("g", 0))
+    .unwrap();
+  assert!(g.as_fn_def().clauses[0].body.is_atom_of("undefined"));
+  assert!(module
+    .root_scope
+    .fn_defs
+    .contains(&MFArity::new_local("h", 0)));
+}
+
+#[test]
+#[named]
+/// `?MODULE` is defined by the `-module()` attribute, before it the macro is not defined
+fn test_macro_module() {
+  test_util::start(function_name!(), "Expand ?MODULE before and after -module()");
+  let input = "-ifdef(MODULE).
+-module_was_defined.
+-endif.
+-module(macro_module).
+-ifdef(MODULE).
+-module_is_defined.
+-endif.
+f() -> ?MODULE.
+";
+  let source_file = SourceFileImpl::new(&PathBuf::from(function_name!()), input.to_string());
+  let project = ErlProjectImpl::default().into();
+  let (module, text) = ErlModuleImpl::preprocess_to_source(&project, &source_file, None).unwrap();
+  println!("{}: Out=«{}»", function_name!(), text);
+  assert!(!module.has_errors(), "Module must have no errors");
+  assert!(!text.contains("-module_was_defined."), "{}", text);
+  assert!(text.contains("-module_is_defined."), "{}", text);
+  assert!(text.contains("f() -> macro_module."), "{}", text);
+}
+
+#[test]
+#[named]
+/// Code pasted from macro bodies must remember the macro invocations which produced it
+fn test_macro_expansion_provenance() {
+  test_util::start(function_name!(), "Record macro expansion chains on pasted code");
+  let input = "-define(AAA, foo).
+-define(BBB(X), {?AAA, X}).
+f() -> ?BBB(1).
+g() -> ?MODULE.";
+  let module = test_util::parse_module(function_name!(), input);
+  let at = |line: usize| format!("{}:{}", function_name!(), line);
+  let fn_body = |name: &str| {
+    let fndef = module
+      .root_scope
+      .fn_defs
+      .get(&MFArity::new_local(name, 0))
+      .unwrap();
+    fndef.as_fn_def().clauses[0].body.clone()
+  };
+
+  let tuple = fn_body("f");
+  let bbb = format!("\n    in expansion of ?BBB at {} (defined at {})", at(4), at(3));
+  assert_eq!(tuple.location.format_expansion(), bbb);
+  if let AstNodeType::Tuple { elements } = &tuple.content {
+    // The atom is pasted from ?AAA, which is pasted from ?BBB
+    let aaa = format!("\n    in expansion of ?AAA at {} (defined at {})", at(3), at(2));
+    assert_eq!(elements[0].location.format_expansion(), format!("{}{}", aaa, bbb));
+    // The macro argument comes from the use site
+    assert_eq!(elements[1].location.format_expansion(), "");
+  } else {
+    panic!("Expected a tuple, got {}", tuple);
+  }
+
+  // Predefined macros have no definition site, but get the location of the invocation
+  let module_atom = fn_body("g");
+  assert!(module_atom.location.start().is_some());
+  assert_eq!(
+    module_atom.location.format_expansion(),
+    format!("\n    in expansion of ?MODULE at {}", at(5))
+  );
+}
+
+#[test]
+#[named]
+/// `-file` attribute remaps the following lines, `?FILE`, `?LINE` and the error locations
+fn test_file_attr_remap() {
+  test_util::start(function_name!(), "Remap source locations with -file()");
+  let input = "e() -> {?FILE, ?LINE}.
+-file(\"src/grammar.yrl\", 120).
+f() -> ?LINE.
+g() -> ?FILE.
+h() -> ?UNDEFINED.";
+  let module = test_util::parse_module(function_name!(), input);
+  let fn_body = |name: &str| {
+    let fndef = module
+      .root_scope
+      .fn_defs
+      .get(&MFArity::new_local(name, 0))
+      .unwrap();
+    fndef.as_fn_def().clauses[0].body.clone()
+  };
+
+  // Before the attribute, the physical file and line are used
+  let e_body = fn_body("e");
+  assert_eq!(format!("{}", e_body.as_tuple()[0]), format!("\"{}\"", function_name!()));
+  assert_eq!(e_body.as_tuple()[1].as_small(), 2);
+
+  assert_eq!(fn_body("f").as_small(), 120);
+  assert_eq!(format!("{}", fn_body("g")), "\"src/grammar.yrl\"");
+
+  let errors = module.errors.data.read().unwrap();
+  assert_eq!(errors.len(), 1);
+  assert_eq!(
+    module.format_location(&errors[0].get_location()),
+    Some("src/grammar.yrl:122".to_string())
+  );
+}
+
+#[test]
+#[named]
+/// Preprocess-only output keeps the `-file` attribute, and marks the end of an include with the
+/// remapped location
+fn preprocess_only_file_attr() {
+  test_util::start(function_name!(), "Keep -file() in the preprocessed module source");
+  let files = [("inc/empty.hrl", "-included.\n")];
+  let input = "-file(\"src/grammar.yrl\", 120).
+-include(\"inc/empty.hrl\").
+f() -> ?LINE.";
+  let (module, text) = test_util::preprocess_module_with_files(function_name!(), &files, input);
+  assert!(!module.has_errors(), "Module must have no errors");
+
+  let lines: Vec<&str> = text.lines().filter(|l| !l.is_empty()).collect();
+  assert_eq!(lines[2], "-file(\"src/grammar.yrl\", 120).");
+  assert!(lines[3].ends_with("empty.hrl\", 1)."));
+  assert_eq!(lines[4], "-included.");
+  assert_eq!(lines[5], "-file(\"src/grammar.yrl\", 121).");
+  assert_eq!(lines[6], "f() -> 121.");
+}
+
+#[test]
+#[named]
+/// With `maybe_expr` disabled by `-feature`, `maybe` and `else` are atoms
+fn feature_disable_keywords() {
+  test_util::start(function_name!(), "Disable maybe_expr with -feature()");
+  let input = "-feature(maybe_expr, disable).
+f() -> maybe.
+g() -> else.";
+  let module = test_util::parse_module(function_name!(), input);
+  assert!(!module.has_errors(), "Module must have no errors");
+  assert!(!module.is_feature_enabled(ErlFeature::MaybeExpr));
+  for (name, atom) in [("f", "maybe"), ("g", "else")] {
+    let fndef = module
+      .root_scope
+      .fn_defs
+      .get(&MFArity::new_local(name, 0))
+      .unwrap();
+    assert!(fndef.as_fn_def().clauses[0].body.is_atom_of(atom));
+  }
+}
+
+#[test]
+#[named]
+/// Features can be disabled in the project configuration, unknown features are config errors
+fn feature_config_defaults() {
+  test_util::start(function_name!(), "Disable maybe_expr in the project configuration");
+  let toml = r#"
+[compiler_options]
+disable_features = ["maybe_expr"]
+"#;
+  let module = test_util::parse_module_with_config(function_name!(), toml, "f() -> maybe.");
+  assert!(!module.has_errors(), "Module must have no errors");
+  assert!(!module.is_feature_enabled(ErlFeature::MaybeExpr));
+
+  let bad_toml = r#"
+[compiler_options]
+enable_features = ["no_such_feature"]
+"#;
+  let conf = ProjectConf::from_string(bad_toml).unwrap();
+  match ErlProjectImpl::try_from(conf) {
+    Ok(_) => panic!("Unknown feature must produce an error"),
+    Err(e) => assert!(format!("{}", e).contains("\"no_such_feature\"")),
+  }
+}
+
+#[test]
+#[named]
+/// `-feature` after the first function is an error
+fn feature_after_function() {
+  test_util::start(function_name!(), "Report a misplaced -feature()");
+  let input = "-feature(maybe_expr, disable).
+f() -> ok.
+-feature(maybe_expr, enable).";
+  let module = test_util::parse_module(function_name!(), input);
+  let errors = test_util::messages(&module.errors);
+  assert_eq!(
+    errors,
+    vec!["-feature(maybe_expr, enable) is not allowed after the first function".to_string()]
+  );
+  assert!(!module.is_feature_enabled(ErlFeature::MaybeExpr));
+}
+
+#[test]
+#[named]
+/// `-compile()` options change the compiler options of this module only, unknown options are
+/// reported as warnings
+fn compile_attr_options() {
+  test_util::start(function_name!(), "Interpret -compile() options");
+  let input = "-compile([export_all, nowarn_export_all, {nowarn_unused_function, [f/1]}]).
+-compile({inline, [f/1]}).
+-compile([{parse_transform, my_transform}, {d, 'DEBUG', 1}, nowarn_deprecated_function]).
+-compile([no_such_option, nowarn_unused_var]).
+f(X) -> X.";
+  let module = test_util::parse_module(function_name!(), input);
+  assert!(!module.has_errors(), "Module must have no errors");
+  let opts = module.get_compiler_options();
+  assert!(opts.export_all);
+  assert!(module.is_exported(&MFArity::new_local("f", 1)));
+  assert!(!opts.is_warning_enabled("export_all", None));
+  assert!(!opts.is_warning_enabled("unused_function", Some(&MFArity::new_local("f", 1))));
+  assert!(opts.is_warning_enabled("unused_function", Some(&MFArity::new_local("g", 0))));
+  assert!(opts.inline.is_some());
+
+  let warnings = test_util::messages(&module.warnings);
+  assert_eq!(
+    warnings,
+    vec![
+      "Unknown compile option: 'no_such_option'".to_string(),
+      "Unknown compile option: 'nowarn_unused_var'".to_string()
+    ]
+  );
+}
+
+#[test]
+#[named]
+/// `export_all` is reported unless `nowarn_export_all` is given, and with `warnings_as_errors` the
+/// warnings become errors
+fn compile_attr_warnings_as_errors() {
+  test_util::start(function_name!(), "Report export_all as an error with warnings_as_errors");
+  let input = "-compile([export_all, warnings_as_errors]).
+f() -> ok.";
+  let module = test_util::parse_module(function_name!(), input);
+  assert!(module.warnings.is_empty());
+  let errors = test_util::messages(&module.errors);
+  assert_eq!(
+    errors,
+    vec!["export_all flag enabled - all functions will be exported".to_string()]
+  );
+}
+
+#[test]
+#[named]
+/// `warnings_as_errors` applies to the whole module, also to the warnings found before it, and
+/// the `export_all` warning points at the `-compile` attribute
+fn compile_attr_warnings_as_errors_module_wide() {
+  test_util::start(function_name!(), "Apply warnings_as_errors to the earlier warnings");
+  let input = "-compile(no_such_option).
+-compile(export_all).
+-compile(warnings_as_errors).
+f() -> ok.";
+  let module = test_util::parse_module(function_name!(), input);
+  assert!(module.warnings.is_empty());
+  let errors = module.errors.data.read().unwrap();
+  assert_eq!(errors.len(), 2, "Both warnings must become errors");
+  assert!(
+    errors[0].get_message().contains("no_such_option"),
+    "{}",
+    errors[0].get_message()
+  );
+  assert_eq!(
+    errors[1].get_message(),
+    "export_all flag enabled - all functions will be exported"
+  );
+  let export_all_start = errors[1].get_location().start().unwrap();
+  let (_offset, line, _column) = module.source_file.position_of(export_all_start).unwrap();
+  assert_eq!(line, 3, "export_all must be reported at its -compile attribute");
+}
+
+#[test]
+#[named]
+/// `[[overrides]]` in the project configuration add compiler options for the matching files only
+fn config_overrides() {
+  test_util::start(function_name!(), "Apply [[overrides]] from the project configuration");
+  let toml = r#"
+[compiler_options]
+defines = ["LEVEL=1"]
+
+[[overrides]]
+paths = ["config_over*"]
+defines = ["LEGACY"]
+max_errors_per_module = 5
+nowarn = ["export_all"]
+
+[[overrides]]
+paths = ["src/other/**"]
+defines = ["OTHER"]
+"#;
+  let input = "-compile(export_all).
+-ifdef(LEGACY).
+-legacy(?LEVEL).
+-endif.
+-ifdef(OTHER).
+-other.
+-endif.";
+  let module = test_util::parse_module_with_config(function_name!(), toml, input);
+  assert!(!module.has_errors(), "Module must have no errors");
+  assert!(module.warnings.is_empty(), "export_all warning is disabled by the override");
+  let attrs = &module.root_scope.attributes;
+  assert!(attrs.contains(&"legacy".to_string()));
+  assert!(!attrs.contains(&"other".to_string()));
+  assert_eq!(module.get_compiler_options().max_errors_per_module, 5);
+
+  let conf = ProjectConf::from_string(toml).unwrap();
+  let project = ErlProjectImpl::try_from(conf).unwrap();
+  let description = project.describe_compiler_options_for(&PathBuf::from(function_name!()));
+  assert!(description.contains("override #1 matches"), "{}", description);
+  assert!(!description.contains("override #2"), "{}", description);
+  let unaffected = project.describe_compiler_options_for(&PathBuf::from("other_file.erl"));
+  assert!(unaffected.contains("no overrides match"), "{}", unaffected);
+}
+
+#[test]
+#[named]
+/// `[[overrides]]` paths are relative to the project file directory, not to the current directory,
+/// and `warnings_as_errors = false` in an override disables the project option
+fn config_overrides_project_dir() {
+  test_util::start(function_name!(), "Match [[overrides]] relative to the project file");
+  let dir = std::env::temp_dir()
+    .join("ironclad_tests")
+    .join(function_name!());
+  let _ = std::fs::remove_dir_all(&dir);
+  std::fs::create_dir_all(&dir).unwrap();
+  let dir = std::fs::canonicalize(&dir).unwrap();
+  let project_file = dir.join("ironclad.toml");
+  std::fs::write(
+    &project_file,
+    r#"
+[compiler_options]
+warnings_as_errors = true
+include_paths = ["include"]
+
+[inputs]
+directories = ["src"]
+
+[[overrides]]
+paths = ["legacy/**"]
+warnings_as_errors = false
+include_paths = ["legacy/include"]
+"#,
+  )
+  .unwrap();
+
+  let conf = ProjectConf::from_project_file(project_file.to_str().unwrap()).unwrap();
+  assert_eq!(conf.project_dir.as_deref(), Some(dir.as_path()));
+  // Only the override patterns are relative to the project file, other paths are kept as written
+  let inputs = conf.inputs.as_ref().unwrap();
+  assert_eq!(inputs.directories, Some(vec!["src".to_string()]));
+  let compiler_options = conf.compiler_options.as_ref().unwrap();
+  assert_eq!(compiler_options.include_paths, Some(vec!["include".to_string()]));
+  let ovr = &conf.overrides.as_ref().unwrap()[0];
+  assert_eq!(ovr.compiler_options.include_paths, Some(vec!["legacy/include".to_string()]));
+  let project = ErlProjectImpl::try_from(conf).unwrap();
+
+  let legacy_opts = project.get_compiler_options_for(&dir.join("legacy").join("old.erl"));
+  assert!(!legacy_opts.warnings_as_errors, "The override must disable warnings_as_errors");
+  let other_opts = project.get_compiler_options_for(&dir.join("src").join("new.erl"));
+  assert!(other_opts.warnings_as_errors, "The project option applies outside the override");
+}
@@ -3,14 +3,19 @@
 use ::function_name::named;
 use libironclad_erlang::erl_syntax::erl_ast::ast_iter::IterableAstNodeT;
 use libironclad_erlang::erl_syntax::erl_ast::AstNode;
+use libironclad_erlang::erl_syntax::parsers::token_stream::lossless::{
+  lossless_to_source, tokenize_lossless,
+};
 use libironclad_erlang::erl_syntax::parsers::token_stream::token::Token;
-use libironclad_erlang::erl_syntax::parsers::token_stream::tokenizer::tokenize_source;
+use libironclad_erlang::error::ic_error_trait::GenericIroncladError;
+use libironclad_erlang::project::conf::ProjectConf;
 use libironclad_erlang::project::module::module_impl::{ErlModule, ErlModuleImpl};
 use libironclad_erlang::project::project_impl::ErlProjectImpl;
+use libironclad_erlang::project::ErlProject;
 use libironclad_erlang::typing::erl_type::ErlType;
-use libironclad_util::source_file::SourceFileImpl;
+use libironclad_util::rw_vec::RwVec;
+use libironclad_util::source_file::{SourceFile, SourceFileImpl};
 use std::path::PathBuf;
-use std::sync::Arc;
 
 pub fn fail_unexpected<T>(val: &T)
 where
@@ -32,6 +37,7 @@ pub fn parse_module(function_name: &str, input: &str) -> ErlModule {
 
   println!("{}: Input=«{}»", function_name!(), input);
 
+  check_lossless(&input);
   let source_file = SourceFileImpl::new(&filename, input);
   let project = ErlProjectImpl::default().into();
   let module = ErlModuleImpl::from_module_source(&project, &source_file, None).unwrap();
@@ -46,10 +52,103 @@ pub fn parse_module(function_name: &str, input: &str) -> ErlModule {
   module
 }
 
+/// Parse the input as a module with the given name, and register it in the project, so that the
+/// other modules parsed in the same project can refer to its types.
+/// Returns `ErlModule`
+pub fn parse_module_in_project(project: &ErlProject, module_name: &str, input: &str) -> ErlModule {
+  let input = format!("-module({}).\n{}", module_name, input);
+  check_lossless(&input);
+  let source_file = SourceFileImpl::new(&PathBuf::from(module_name), input);
+  let module = ErlModuleImpl::from_module_source(project, &source_file, None).unwrap();
+  project.register_new_module(&module);
+  module
+}
+
+/// Write `files` (relative path, contents) into a fresh temporary directory, then write `input`
+/// as a module source file located in that directory.
+fn write_module_with_files(function_name: &str, files: &[(&str, &str)], input: &str) -> SourceFile {
+  let dir = std::env::temp_dir()
+    .join("ironclad_tests")
+    .join(function_name);
+  let _ = std::fs::remove_dir_all(&dir);
+  for (path, contents) in files {
+    check_lossless(contents);
+    let file_path = dir.join(path);
+    std::fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+    std::fs::write(&file_path, contents).unwrap();
+  }
+  std::fs::create_dir_all(&dir).unwrap();
+
+  let input = format!("-module({}).\n{}", function_name, input);
+  let filename = dir.join(format!("{}.erl", function_name));
+  check_lossless(&input);
+  std::fs::write(&filename, &input).unwrap();
+
+  SourceFileImpl::new(&filename, input)
+}
+
+/// Write `files` (relative path, contents) into a fresh temporary directory, then parse `input` as
+/// a module located in that directory. Use this to test file inclusion.
+/// Returns `ErlModule`
+pub fn parse_module_with_files(
+  function_name: &str,
+  files: &[(&str, &str)],
+  input: &str,
+) -> ErlModule {
+  let source_file = write_module_with_files(function_name, files, input);
+  let project = ErlProjectImpl::default().into();
+  ErlModuleImpl::from_module_source(&project, &source_file, None).unwrap()
+}
+
+/// Same as `parse_module_with_files` but only runs the preprocessor.
+/// Returns `ErlModule` and the preprocessed source text
+pub fn preprocess_module_with_files(
+  function_name: &str,
+  files: &[(&str, &str)],
+  input: &str,
+) -> (ErlModule, String) {
+  let source_file = write_module_with_files(function_name, files, input);
+  let project = ErlProjectImpl::default().into();
+  let (module, text) = ErlModuleImpl::preprocess_to_source(&project, &source_file, None).unwrap();
+  println!("{}: Out=«{}»", function_name, text);
+  (module, text)
+}
+
+/// Load project configuration from `toml` text and parse the input as a module, with the compiler
+/// options from that configuration.
+/// Returns `ErlModule`
+pub fn parse_module_with_config(function_name: &str, toml: &str, input: &str) -> ErlModule {
+  let input = format!("-module({}).\n{}", function_name, input);
+  let filename = PathBuf::from(function_name);
+  check_lossless(&input);
+  let source_file = SourceFileImpl::new(&filename, input);
+
+  let conf = ProjectConf::from_string(toml).unwrap();
+  let project: ErlProject = ErlProjectImpl::try_from(conf).unwrap().into();
+  let compiler_opts = project.get_compiler_options_for(&filename);
+  ErlModuleImpl::from_module_source(&project, &source_file, Some(compiler_opts)).unwrap()
+}
+
+/// Tokenize the input in the lossless mode, and check that the tokens with their trivia reproduce
+/// the input exactly. Every parser test input goes through this check.
+pub fn check_lossless(input: &str) {
+  let tokens = tokenize_lossless(input);
+  let mut prev_end = 0;
+  for t in tokens.iter() {
+    assert_eq!(&input[t.span.clone()], t.text, "Token span does not match its text");
+    assert!(t.span.start >= prev_end, "Token spans must not overlap: {:?}", t);
+    prev_end = t.span.end;
+  }
+  assert_eq!(lossless_to_source(&tokens), input, "Lossless tokens must reproduce the input");
+}
+
 pub fn tokenize(input: &str) -> Vec<Token> {
-  let project = Arc::new(ErlProjectImpl::default());
+  check_lossless(input);
+  let module = ErlModuleImpl::new_default();
   let src_file = SourceFileImpl::new(&PathBuf::from("test"), input.to_string());
-  ErlModuleImpl::tokenize_helper(&project, src_file.clone(), tokenize_source).unwrap()
+  let tokens = ErlModuleImpl::tokenize_helper(&module, &src_file);
+  assert!(!module.has_errors(), "Tokenizer errors for «{}»", input);
+  tokens
 }
 
 /// Try parse a define macro where value contains another macro
@@ -61,6 +160,7 @@ pub fn parse_module_unwrap(function_name: &str, input: &str) -> Vec<AstNode> {
 }
 
 pub fn parse_expr(function_name: &str, input: &str) -> AstNode {
+  check_lossless(input);
   let project = ErlProjectImpl::default().into();
   let source_file = SourceFileImpl::new(&PathBuf::from(function_name), input.to_string());
   let module = ErlModuleImpl::from_expr_source(&project, &source_file, None).unwrap();
@@ -69,9 +169,21 @@ pub fn parse_expr(function_name: &str, input: &str) -> AstNode {
 }
 
 pub fn parse_type(function_name: &str, input: &str) -> ErlType {
+  check_lossless(input);
   let project = ErlProjectImpl::default().into();
   let source_file = SourceFileImpl::new(&PathBuf::from(function_name), input.to_string());
   let module = ErlModuleImpl::from_type_source(&project, &source_file, None).unwrap();
   let ast_b = module.ast.borrow();
   ast_b.as_type()
 }
+
+/// Messages of the module errors or warnings, in the order they were reported
+pub fn messages(list: &RwVec<GenericIroncladError>) -> Vec<String> {
+  list
+    .data
+    .read()
+    .unwrap()
+    .iter()
+    .map(|e| e.get_message().to_string())
+    .collect()
+}
@@ -2,9 +2,14 @@ mod test_util;
 
 use ::function_name::named;
 use libironclad_erlang::erl_syntax::parsers::misc::panicking_tokenizer_error_reporter;
+use libironclad_erlang::erl_syntax::parsers::token_stream::lossless::{
+  lossless_to_source, tokenize_lossless, Trivia, TriviaKind,
+};
 use libironclad_erlang::erl_syntax::parsers::token_stream::token::format_tok_stream;
 use libironclad_erlang::erl_syntax::parsers::token_stream::token_kind::TokenKind;
-use libironclad_erlang::erl_syntax::parsers::token_stream::tokenizer::tokenize_source;
+use libironclad_erlang::erl_syntax::parsers::token_stream::tokenizer::{
+  tokenize_source, tokenize_source_recovering,
+};
 use nom::Finish;
 
 #[test]
@@ -86,3 +91,219 @@ fn tok_dollar_char_test() {
     );
   }
 }
+
+#[named]
+#[test]
+fn tok_triple_quoted_string() {
+  test_util::start(function_name!(), "Tokenize a triple-quoted string");
+  let input = "X = \"\"\"\n    first \"line\"\n\n      indented \\n\n    \"\"\", Y";
+  let tokens = test_util::tokenize(input);
+  println!("TOKENS: {}", format_tok_stream(&tokens, tokens.len()));
+  assert!(
+    matches!(&tokens[2].kind, TokenKind::Str(s) if s.as_str() == "first \"line\"\n\n  indented \\n"),
+    "Expected a verbatim string with the indentation removed, got {:?}",
+    tokens[2]
+  );
+  assert!(tokens[3].kind.is_same_type(&TokenKind::Comma));
+}
+
+#[named]
+#[test]
+fn tok_triple_quoted_string_bad_indent() {
+  test_util::start(function_name!(), "Tokenize a triple-quoted string with a bad indentation");
+  let input = "\"\"\"\n  text\n    \"\"\"";
+  assert!(tokenize_source(input).finish().is_err());
+}
+
+#[named]
+#[test]
+fn tok_sigils() {
+  test_util::start(function_name!(), "Tokenize sigil strings");
+  let binary_of = |input: &str| match &test_util::tokenize(input)[0].kind {
+    TokenKind::BinaryStr(s) => s.to_string(),
+    other => panic!("Expected a binary string sigil for {}, got {:?}", input, other),
+  };
+  let list_of = |input: &str| match &test_util::tokenize(input)[0].kind {
+    TokenKind::Str(s) => s.to_string(),
+    other => panic!("Expected a string for {}, got {:?}", input, other),
+  };
+  assert_eq!(binary_of("~\"a\\tb\""), "a\tb");
+  assert_eq!(binary_of("~b[a(b)\\]]"), "a(b)]");
+  assert_eq!(binary_of("~B<a\\tb>"), "a\\tb");
+  assert_eq!(binary_of("~\"\"\"\n  a\\tb\n  \"\"\""), "a\\tb");
+  assert_eq!(list_of("~s{a\\nb}"), "a\nb");
+  assert_eq!(list_of("~S/a\\nb/"), "a\\nb");
+  assert_eq!(list_of("~s\"\"\"\n  a\\tb\n  \"\"\""), "a\tb");
+}
+
+#[named]
+#[test]
+fn tok_generator_arrows() {
+  test_util::start(function_name!(), "Tokenize strict and zip generator arrows");
+  let tokens = test_util::tokenize("X <:- L && <<B>> <:= Bin, Y <- M, <<C>> <= D");
+  let kinds: Vec<&TokenKind> = tokens.iter().map(|t| &t.kind).collect();
+  assert!(matches!(kinds[1], TokenKind::StrictLeftArr));
+  assert!(matches!(kinds[3], TokenKind::DoubleAmpersand));
+  assert!(matches!(kinds[7], TokenKind::StrictLeftDoubleArr));
+  assert!(matches!(kinds[11], TokenKind::LeftArr));
+  assert!(matches!(kinds[17], TokenKind::LeftDoubleArr));
+}
+
+#[named]
+#[test]
+fn tok_number_literals() {
+  test_util::start(function_name!(), "Tokenize integers and floats in all forms");
+  let int_of = |input: &str| match &test_util::tokenize(input)[0].kind {
+    TokenKind::Integer(i) => i.clone(),
+    other => panic!("Expected an integer for {}, got {:?}", input, other),
+  };
+  let float_of = |input: &str| match &test_util::tokenize(input)[0].kind {
+    TokenKind::Float(f) => *f,
+    other => panic!("Expected a float for {}, got {:?}", input, other),
+  };
+  assert_eq!(int_of("1_000_000").as_usize(), Some(1_000_000));
+  assert_eq!(int_of("16#FF_FF").as_usize(), Some(0xFFFF));
+  assert_eq!(int_of("2#1010").as_usize(), Some(10));
+  assert_eq!(int_of("36#zz").as_usize(), Some(36 * 36 - 1));
+  assert_eq!(
+    format!("{}", int_of("123456789012345678901234567890")),
+    "↑123456789012345678901234567890"
+  );
+  assert_eq!(float_of("1.0e-10"), 1.0e-10);
+  assert_eq!(float_of("1_0.2_5E+2"), 1025.0);
+  assert_eq!(float_of("2#0.101"), 0.625);
+  assert_eq!(float_of("16#1.8#e2"), 384.0);
+
+  // A period without digits after it does not belong to the number
+  let tokens = test_util::tokenize("1..2");
+  assert!(tokens[1].kind.is_same_type(&TokenKind::PeriodPeriod));
+}
+
+#[named]
+#[test]
+fn tok_minus_after_operand() {
+  test_util::start(function_name!(), "Tokenize a subtraction without spaces");
+
+  let input = "X-1";
+  let tokens = panicking_tokenizer_error_reporter(input, tokenize_source(input).finish());
+
+  assert_eq!(
+    tokens.len(),
+    3,
+    "Expected X, minus and 1; got {}",
+    format_tok_stream(&tokens, 5)
+  );
+  assert!(tokens[1].kind.is_same_type(&TokenKind::Minus), "Bad token 1 expected Minus");
+  if let TokenKind::Integer(i) = &tokens[2].kind {
+    assert_eq!(i.as_isize(), Some(1));
+  } else {
+    panic!("Bad token 2, expected integer")
+  }
+}
+
+#[named]
+#[test]
+fn tok_number_literal_errors() {
+  test_util::start(function_name!(), "Report malformed number literals");
+  for input in [
+    "37#10",
+    "8#19",
+    "2#",
+    "1.0e999",
+    "16#1.0#e9999",
+    "1__0",
+    "1_",
+    "16#F__F",
+    "1.0e",
+    "1.0e+",
+    "2#0.1#e",
+  ] {
+    match tokenize_source(input).finish() {
+      Err(e) => println!("{}: {:?}", input, e),
+      Ok((_, tokens)) => panic!("Expected an error for {}, got {:?}", input, tokens),
+    }
+  }
+}
+
+#[named]
+#[test]
+fn tok_dollar_char_escapes() {
+  test_util::start(function_name!(), "Tokenize $characters with escapes");
+  let value_of = |input: &str| match &test_util::tokenize(input)[0].kind {
+    TokenKind::Character(c) => *c,
+    TokenKind::EscapedCharacter { value, .. } => *value,
+    other => panic!("Expected a character for {}, got {:?}", input, other),
+  };
+  assert_eq!(value_of("$\\s"), ' ');
+  assert_eq!(value_of("$\\d"), '\x7f');
+  assert_eq!(value_of("$\\z"), 'z');
+  assert_eq!(value_of("$\\101"), 'A');
+  assert_eq!(value_of("$\\x41"), 'A');
+  assert_eq!(value_of("$\\x{1F600}"), '\u{1F600}');
+  assert_eq!(value_of("$\\^A"), '\x01');
+  assert_eq!(value_of("$\\^z"), '\x1a');
+}
+
+#[named]
+#[test]
+fn tok_error_recovery() {
+  test_util::start(function_name!(), "Tokenize past invalid input");
+  let input = "X = `a, \"abc\nY = 8#19, Z.";
+  let (tokens, errors) = tokenize_source_recovering(input);
+  println!("TOKENS: {}", format_tok_stream(&tokens, tokens.len()));
+
+  let invalid: Vec<String> = tokens
+    .iter()
+    .filter_map(|t| match &t.kind {
+      TokenKind::Invalid(text) => Some(text.to_string()),
+      _ => None,
+    })
+    .collect();
+  assert_eq!(invalid, vec!["`", "\"abc", "8#19"]);
+  assert_eq!(errors.len(), 3);
+  assert_eq!(errors[1].1, "string is not terminated");
+  assert!(tokens
+    .iter()
+    .any(|t| matches!(&t.kind, TokenKind::Variable(v) if v == "Z")));
+  assert!(tokens.last().unwrap().kind.is_same_type(&TokenKind::Period));
+}
+
+#[named]
+#[test]
+fn tok_error_in_module() {
+  test_util::start(function_name!(), "Report tokenizer errors as module errors");
+  let module = test_util::parse_module(function_name!(), "f() -> ok.\ng() -> 'abc.\n");
+  let errors = module.errors.data.read().unwrap();
+  assert_eq!(errors.len(), 1);
+  assert_eq!(errors[0].get_message(), "quoted atom is not terminated");
+  assert_eq!(
+    module.format_location(&errors[0].get_location()),
+    Some("tok_error_in_module:3".to_string())
+  );
+}
+
+#[named]
+#[test]
+fn tok_lossless_trivia() {
+  test_util::start(function_name!(), "Tokenize with trivia, and reproduce the source");
+  let input = "f() -> ok. % done\r\n\n  %% next\ng() ->\t`X.  \n";
+  let tokens = tokenize_lossless(input);
+  assert_eq!(lossless_to_source(&tokens), input);
+
+  fn text_of<'a>(trivia: &[Trivia<'a>]) -> Vec<&'a str> {
+    trivia.iter().map(|t| t.text).collect()
+  }
+  let period = tokens.iter().find(|t| t.text == ".").unwrap();
+  assert_eq!(text_of(&period.trailing), vec![" ", "% done"]);
+  assert_eq!(period.trailing[1].kind, TriviaKind::Comment);
+
+  let g = tokens.iter().find(|t| t.text == "g").unwrap();
+  assert_eq!(text_of(&g.leading), vec!["\r\n", "\n", "  ", "%% next", "\n"]);
+  assert_eq!(g.span.start, input.find('g').unwrap());
+
+  // Invalid input is kept too, and the trivia at the end belongs to the end of input token
+  assert!(tokens.iter().any(|t| t.text == "`" && t.token.is_invalid()));
+  let end = tokens.last().unwrap();
+  assert!(end.token.kind.is_same_type(&TokenKind::EndOfInput));
+  assert_eq!(text_of(&end.leading), vec!["\n"]);
+}
@@ -10,10 +10,13 @@ use libironclad_erlang::erl_syntax::erl_ast::node_impl::AstNodeType::FnDef;
 use libironclad_erlang::erl_syntax::erl_op::ErlBinaryOp;
 use libironclad_erlang::error::ic_error::IroncladResult;
 use libironclad_erlang::project::module::module_impl::ErlModuleImpl;
-use libironclad_erlang::project::module::scope::scope_impl::ScopeImpl;
+use libironclad_erlang::project::module::scope::scope_impl::{Scope, ScopeImpl};
 use libironclad_erlang::typing::erl_type::typekind::TypeKind;
+use libironclad_erlang::typing::erl_type::ErlType;
 use libironclad_erlang::typing::erl_type::TypeImpl;
 use libironclad_util::mfarity::MFArity;
+use std::collections::HashMap;
+use std::sync::Weak;
 
 #[named]
 #[test]
@@ -254,3 +257,209 @@ fn synth_multiple_clause_test() -> IroncladResult<()> {
 
   // TODO: narrowing test, calling 2 clause function with 'one' and with 'two'
 }
+
+#[named]
+#[test]
+fn synth_named_lambda() -> IroncladResult<()> {
+  test_util::start(function_name!(), "synthesize type for a named recursive lambda");
+
+  let module = ErlModuleImpl::new_default();
+  let scope1 = ScopeImpl::new_root_scope(function_name!().to_string());
+  let parsed =
+    test_util::parse_expr(function_name!(), "fun Loop(0) -> ok; Loop(N) -> Loop(N - 1) end");
+  let lambda_ty = parsed.synthesize(&module, &scope1)?;
+  println!("{}: Inferred {} 🡆 {}", function_name!(), parsed, lambda_ty);
+
+  if let TypeKind::Fn(fntype) = &lambda_ty.kind {
+    assert_eq!(fntype.clauses().len(), 2);
+    assert!(fntype.clauses().iter().all(|fc| fc.arity() == 1));
+  } else {
+    panic!("Synth type for a named lambda must be a function type, got {}", lambda_ty);
+  }
+  Ok(())
+}
+
+#[named]
+#[test]
+fn synth_fn_reference() -> IroncladResult<()> {
+  test_util::start(function_name!(), "synthesize types for remote and dynamic fun references");
+
+  let module = ErlModuleImpl::new_default();
+  let scope1 = ScopeImpl::new_root_scope(function_name!().to_string());
+
+  let remote = test_util::parse_expr(function_name!(), "fun lists:map/2");
+  let remote_ty = remote.synthesize(&module, &scope1)?;
+  assert!(
+    matches!(&remote_ty.kind, TypeKind::FnRef { fun } if *fun == MFArity::new("lists", "map", 2)),
+    "Remote fun reference must synthesize to a fun reference type, got {}",
+    remote_ty
+  );
+
+  let dynamic = test_util::parse_expr(function_name!(), "fun erlang:F/1");
+  let dynamic_ty = dynamic.synthesize(&module, &scope1)?;
+  assert!(matches!(&dynamic_ty.kind, TypeKind::AnyFn), "Got {}", dynamic_ty);
+  Ok(())
+}
+
+#[named]
+#[test]
+fn synth_map_comprehension() -> IroncladResult<()> {
+  test_util::start(function_name!(), "synthesize type for a map comprehension");
+
+  let module = ErlModuleImpl::new_default();
+  let scope1 = ScopeImpl::new_root_scope(function_name!().to_string());
+  let parsed = test_util::parse_expr(function_name!(), "#{K => {K, ok} || K := _ <- M}");
+  let map_ty = parsed.synthesize(&module, &scope1)?;
+  println!("{}: Inferred {} 🡆 {}", function_name!(), parsed, map_ty);
+
+  if let TypeKind::Map { members } = &map_ty.kind {
+    assert_eq!(members.len(), 1);
+    assert!(
+      members[0].key.is_kind(&TypeKind::Any),
+      "Key must be any(), got {}",
+      members[0].key
+    );
+    assert!(
+      matches!(&members[0].value.kind, TypeKind::Tuple { elements } if elements.len() == 2),
+      "Value must be a 2-tuple, got {}",
+      members[0].value
+    );
+  } else {
+    panic!("Synth type for a map comprehension must be a map type, got {}", map_ty);
+  }
+  Ok(())
+}
+
+#[named]
+#[test]
+fn synth_strict_generator() -> IroncladResult<()> {
+  test_util::start(function_name!(), "strict generator pattern must cover the input elements");
+
+  let module = ErlModuleImpl::new_default();
+  let scope1 = ScopeImpl::new_root_scope(function_name!().to_string());
+
+  let covered = test_util::parse_expr(function_name!(), "[X || {ok, X} <:- [{ok, 1}, {ok, 2}]]");
+  let covered_ty = covered.synthesize(&module, &scope1)?;
+  assert!(covered_ty.is_list(), "Expected a list type, got {}", covered_ty);
+
+  let skipping = test_util::parse_expr(function_name!(), "[X || {ok, X} <- [1, {ok, 2}]]");
+  assert!(skipping.synthesize(&module, &scope1).is_ok());
+
+  let strict = test_util::parse_expr(function_name!(), "[X || {ok, X} <:- [1, {ok, 2}]]");
+  assert!(
+    strict.synthesize(&module, &scope1).is_err(),
+    "Strict generator with a pattern not covering the element 1 must fail"
+  );
+  Ok(())
+}
+
+/// Root scope with the variables of the given types
+fn scope_with_variables(function_name: &str, variables: &[(&str, &str)]) -> Scope {
+  let variables: HashMap<String, ErlType> = variables
+    .iter()
+    .map(|(name, ty)| (name.to_string(), test_util::parse_type(function_name, ty)))
+    .collect();
+  ScopeImpl::new(function_name.to_string(), Weak::new(), variables)
+}
+
+#[named]
+#[test]
+fn synth_strict_generator_partial_types() -> IroncladResult<()> {
+  test_util::start(
+    function_name!(),
+    "strict generator over any() elements fails only when nothing can match, union elements \
+    must all match",
+  );
+
+  let module = ErlModuleImpl::new_default();
+  let scope1 = scope_with_variables(
+    function_name!(),
+    &[
+      ("Any", "[any()]"),
+      ("Union", "[{ok, integer()} | error]"),
+      ("Oks", "[{ok, integer()} | {ok, atom()}]"),
+      ("Errors", "[{error, integer()}]"),
+    ],
+  );
+
+  let any_elements = test_util::parse_expr(function_name!(), "[X || {ok, X} <:- Any]");
+  assert!(any_elements.synthesize(&module, &scope1).is_ok());
+
+  let union_elements = test_util::parse_expr(function_name!(), "[X || {ok, X} <:- Union]");
+  assert!(
+    union_elements.synthesize(&module, &scope1).is_err(),
+    "Strict generator must fail on the error element of the union"
+  );
+
+  let union_covered = test_util::parse_expr(function_name!(), "[X || {ok, X} <:- Oks]");
+  assert!(union_covered.synthesize(&module, &scope1).is_ok());
+
+  let no_match = test_util::parse_expr(function_name!(), "[X || {ok, X} <:- Errors]");
+  assert!(
+    no_match.synthesize(&module, &scope1).is_err(),
+    "Strict generator with a pattern not matching any element must fail"
+  );
+  Ok(())
+}
+
+#[named]
+#[test]
+fn synth_strict_generator_patterns() -> IroncladResult<()> {
+  test_util::start(function_name!(), "strict generators with map, record and binary patterns");
+
+  let module = ErlModuleImpl::new_default();
+  let scope1 = scope_with_variables(function_name!(), &[("L", "[any()]"), ("Atoms", "[atom()]")]);
+
+  for input in [
+    "[V || #{k := V} <:- L]",
+    "[X || #r{a = X} <:- L]",
+    "[X || <<X>> <:- L]",
+  ] {
+    let expr = test_util::parse_expr(function_name!(), input);
+    assert!(expr.synthesize(&module, &scope1).is_ok(), "Must accept {}", input);
+  }
+
+  let map_of_atoms = test_util::parse_expr(function_name!(), "[V || #{k := V} <:- Atoms]");
+  assert!(
+    map_of_atoms.synthesize(&module, &scope1).is_err(),
+    "A map pattern can not match an atom"
+  );
+  Ok(())
+}
+
+#[named]
+#[test]
+fn synth_strict_binary_generator() -> IroncladResult<()> {
+  test_util::start(function_name!(), "strict binary generators check the input and the pattern");
+
+  let module = ErlModuleImpl::new_default();
+  let scope1 = scope_with_variables(
+    function_name!(),
+    &[
+      ("Any", "any()"),
+      ("Bin", "<<_:_*8>>"),
+      ("Words", "<<_:_*16>>"),
+      ("Atom", "atom()"),
+    ],
+  );
+
+  for input in [
+    "[X || <<X>> <:= Bin]",
+    "[X || <<X:16>> <:= Words]",
+    "[X || <<X:4, Y:4>> <:= Bin]",
+    "[ok || <<1>> <:= Any]",
+  ] {
+    let expr = test_util::parse_expr(function_name!(), input);
+    assert!(expr.synthesize(&module, &scope1).is_ok(), "Must accept {}", input);
+  }
+
+  for input in [
+    "[X || <<X>> <:= Atom]",
+    "[X || <<X:16>> <:= Bin]",
+    "[X || <<1, X>> <:= Bin]",
+  ] {
+    let expr = test_util::parse_expr(function_name!(), input);
+    assert!(expr.synthesize(&module, &scope1).is_err(), "Must reject {}", input);
+  }
+  Ok(())
+}
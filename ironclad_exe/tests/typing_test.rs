@@ -7,9 +7,14 @@ use ::function_name::named;
 use libironclad_erlang::error::ic_error::IroncladResult;
 use libironclad_erlang::project::module::module_impl::ErlModuleImpl;
 use libironclad_erlang::project::module::scope::scope_impl::ScopeImpl;
+use libironclad_erlang::project::project_impl::ErlProjectImpl;
+use libironclad_erlang::project::ErlProject;
 use libironclad_erlang::typing::check::TypeCheck;
 use libironclad_erlang::typing::erl_type::typekind::TypeKind;
 use libironclad_erlang::typing::erl_type::TypeImpl;
+use libironclad_util::mfarity::MFArity;
+use std::collections::HashMap;
+use std::sync::Weak;
 
 #[named]
 #[test]
@@ -186,3 +191,187 @@ fn typing_subtyping_list() -> IroncladResult<()> {
 
   Ok(())
 }
+
+#[named]
+#[test]
+fn typing_opaque_type_definition() -> IroncladResult<()> {
+  test_util::start(function_name!(), "Typing.Opaque.Definition");
+  let module = test_util::parse_module(
+    function_name!(),
+    "-opaque point() :: {integer(), integer()}.
+-type name() :: atom().",
+  );
+  let point = module
+    .root_scope
+    .user_types
+    .get(&MFArity::new_local("point", 0))
+    .unwrap();
+  assert!(point.opaque, "point() must be registered as opaque");
+  let name = module
+    .root_scope
+    .user_types
+    .get(&MFArity::new_local("name", 0))
+    .unwrap();
+  assert!(!name.opaque, "name() must be registered as transparent");
+  Ok(())
+}
+
+#[named]
+#[test]
+fn typing_opaque_type_transparent_inside() -> IroncladResult<()> {
+  test_util::start(function_name!(), "Typing.Opaque.TransparentInside");
+  let project: ErlProject = ErlProjectImpl::default().into();
+  let owner = test_util::parse_module_in_project(
+    &project,
+    "opaque_owner",
+    "-opaque point() :: {integer(), integer()}.",
+  );
+  let scope = ScopeImpl::new_root_scope(function_name!().to_string());
+  let expr = test_util::parse_expr(function_name!(), "{1, 2}");
+  let point_t = test_util::parse_type(function_name!(), "point()");
+  assert!(
+    TypeCheck::check(&owner, &scope, &expr, &point_t)?,
+    "Inside its defining module the opaque type must accept its representation"
+  );
+  Ok(())
+}
+
+#[named]
+#[test]
+fn typing_opaque_type_violation() -> IroncladResult<()> {
+  test_util::start(function_name!(), "Typing.Opaque.Violation");
+  let project: ErlProject = ErlProjectImpl::default().into();
+  test_util::parse_module_in_project(
+    &project,
+    "geometry",
+    "-opaque point() :: {integer(), integer()}.
+-type name() :: atom().",
+  );
+  let client = test_util::parse_module_in_project(&project, "geometry_client", "");
+  let scope = ScopeImpl::new_root_scope(function_name!().to_string());
+
+  // A transparent remote type is checked by its definition
+  let name_t = test_util::parse_type(function_name!(), "geometry:name()");
+  let atom_expr = test_util::parse_expr(function_name!(), "origin");
+  assert!(TypeCheck::check(&client, &scope, &atom_expr, &name_t)?);
+
+  // Constructing the representation of an opaque type outside its module is not allowed
+  let point_t = test_util::parse_type(function_name!(), "geometry:point()");
+  let tuple_expr = test_util::parse_expr(function_name!(), "{1, 2}");
+  let result = TypeCheck::check(&client, &scope, &tuple_expr, &point_t);
+  assert!(result.is_err(), "Expected an opacity violation, got {:?}", result);
+  let message = format!("{}", result.unwrap_err());
+  assert!(message.contains("Opacity violation"), "Unexpected error: {}", message);
+  Ok(())
+}
+
+#[named]
+#[test]
+fn typing_opaque_type_pattern_violation() -> IroncladResult<()> {
+  test_util::start(function_name!(), "Typing.Opaque.PatternViolation");
+  let project: ErlProject = ErlProjectImpl::default().into();
+  test_util::parse_module_in_project(
+    &project,
+    "geometry",
+    "-opaque point() :: {integer(), integer()}.",
+  );
+  let client = test_util::parse_module_in_project(
+    &project,
+    "geometry_matcher",
+    "-spec arg_pattern({geometry:point(), integer()}) -> integer().
+arg_pattern({{X, _}, N}) -> X + N.
+-spec arg_var(geometry:point()) -> geometry:point().
+arg_var(P = Q) -> P.
+-spec case_pattern(geometry:point()) -> integer().
+case_pattern(P) -> case P of {X, _} -> X; _ -> 0 end.
+-spec case_var(geometry:point()) -> ok.
+case_var(P) -> case P of Other -> ok end.",
+  );
+  assert!(!client.verify_opaque_patterns(), "Matching on an opaque term must fail");
+
+  let errors: Vec<String> = client
+    .errors
+    .data
+    .read()
+    .unwrap()
+    .iter()
+    .map(|e| format!("{}", e))
+    .collect();
+  assert_eq!(errors.len(), 2, "Expected two opacity violations, got {:?}", errors);
+  assert!(errors[0].contains("{X, _}"), "Unexpected error: {}", errors[0]);
+  assert!(
+    errors.iter().all(|e| e.contains("Opacity violation")),
+    "Unexpected errors: {:?}",
+    errors
+  );
+  Ok(())
+}
+
+#[named]
+#[test]
+fn typing_opaque_type_in_union() -> IroncladResult<()> {
+  test_util::start(function_name!(), "Typing.Opaque.InUnion");
+  let project: ErlProject = ErlProjectImpl::default().into();
+  test_util::parse_module_in_project(
+    &project,
+    "geometry",
+    "-opaque point() :: {integer(), integer()}.",
+  );
+  let client = test_util::parse_module_in_project(
+    &project,
+    "geometry_user",
+    "-type maybe_point() :: geometry:point() | undefined.",
+  );
+  let variables =
+    HashMap::from([("P".to_string(), test_util::parse_type(function_name!(), "geometry:point()"))]);
+  let scope = ScopeImpl::new(function_name!().to_string(), Weak::new(), variables);
+  let var_expr = test_util::parse_expr(function_name!(), "P");
+
+  // A union or an alias containing the same opaque type accepts the opaque term
+  let union_t = test_util::parse_type(function_name!(), "geometry:point() | undefined");
+  assert!(TypeCheck::check(&client, &scope, &var_expr, &union_t)?);
+  let alias_t = test_util::parse_type(function_name!(), "maybe_point()");
+  assert!(TypeCheck::check(&client, &scope, &var_expr, &alias_t)?);
+
+  // The structure of the opaque type does not
+  let tuple_t = test_util::parse_type(function_name!(), "{integer(), integer()} | undefined");
+  let result = TypeCheck::check(&client, &scope, &var_expr, &tuple_t);
+  assert!(result.is_err(), "Expected an opacity violation, got {:?}", result);
+  Ok(())
+}
+
+#[named]
+#[test]
+fn typing_opaque_type_in_remote_alias() -> IroncladResult<()> {
+  test_util::start(function_name!(), "Typing.Opaque.InRemoteAlias");
+  let project: ErlProject = ErlProjectImpl::default().into();
+  test_util::parse_module_in_project(
+    &project,
+    "geometry",
+    "-opaque point() :: {integer(), integer()}.
+-type segment() :: {point(), point()}.",
+  );
+  let client = test_util::parse_module_in_project(
+    &project,
+    "segment_matcher",
+    "-type point() :: {integer(), integer()}.
+-type loop() :: loop2().
+-type loop2() :: loop().
+-spec first_x(geometry:segment()) -> integer().
+first_x({{X, _}, _}) -> X.
+-spec looped(loop()) -> ok.
+looped({_}) -> ok.",
+  );
+  assert!(
+    !client.verify_opaque_patterns(),
+    "The opaque type inside a remote alias must be resolved in the defining module"
+  );
+  let errors = client.errors.data.read().unwrap();
+  assert_eq!(errors.len(), 1, "Expected one opacity violation, got {:?}", errors);
+  assert!(
+    format!("{}", errors[0]).contains("geometry:point"),
+    "Unexpected error: {}",
+    errors[0]
+  );
+  Ok(())
+}
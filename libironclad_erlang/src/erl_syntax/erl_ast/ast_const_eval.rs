@@ -0,0 +1,637 @@
+//! Compile-time evaluation of constant guard-like expressions over `Literal`s, as used by the
+//! `-if()` and `-elif()` preprocessor directives.
+
+use crate::erl_syntax::erl_ast::node_impl::{AstNodeImpl, AstNodeType};
+use crate::erl_syntax::erl_ast::AstNode;
+use crate::erl_syntax::erl_op::{ErlBinaryOp, ErlUnaryOp};
+use crate::erl_syntax::node::erl_callable_target::CallableTarget;
+use crate::literal::Literal;
+use crate::typing::erl_integer::ErlInteger;
+use num::FromPrimitive;
+use num_bigint::BigInt;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::ops::Deref;
+
+/// Reasons why a constant expression did not produce a value
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ConstEvalError {
+  /// The expression is not a constant guard expression: it contains variables, calls to non-guard
+  /// functions, or other runtime constructs. The preprocessor reports this as an error.
+  NotConstant(String),
+  /// The evaluation raised an exception, like `badarith` or `badarg`. Like in `epp`, an `-if()`
+  /// condition which raises an exception is false.
+  Exception(String),
+}
+
+impl Display for ConstEvalError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ConstEvalError::NotConstant(msg) => write!(f, "{}", msg),
+      ConstEvalError::Exception(msg) => write!(f, "exception {}", msg),
+    }
+  }
+}
+
+/// Result of a constant expression evaluation
+pub type ConstEvalResult = Result<Literal, ConstEvalError>;
+
+/// Bit shifts larger than this raise `system_limit`, to avoid allocating huge integers
+const MAX_SHIFT_BITS: i64 = 1 << 20;
+
+/// A number extracted from a literal, for arithmetic and comparisons
+enum ConstNumber {
+  Int(ErlInteger),
+  Float(f64),
+}
+
+impl ConstNumber {
+  fn as_f64(&self) -> f64 {
+    match self {
+      ConstNumber::Int(i) => i.as_f64().unwrap_or_else(|| {
+        if i.is_non_negative() {
+          f64::INFINITY
+        } else {
+          f64::NEG_INFINITY
+        }
+      }),
+      ConstNumber::Float(f) => *f,
+    }
+  }
+}
+
+fn badarith(op: impl Display, left: &Literal, right: &Literal) -> ConstEvalError {
+  ConstEvalError::Exception(format!("badarith in {} {} {}", left, op, right))
+}
+
+fn badarg(what: impl Display) -> ConstEvalError {
+  ConstEvalError::Exception(format!("badarg in {}", what))
+}
+
+/// Atoms `true` and `false` become `Literal::Bool`, characters become integers, so that the
+/// evaluator only deals with one representation of each value.
+fn normalize(lit: &Literal) -> Literal {
+  match lit {
+    Literal::Atom(a) if a == "true" => Literal::Bool(true),
+    Literal::Atom(a) if a == "false" => Literal::Bool(false),
+    Literal::Character(c) => Literal::Integer(ErlInteger::Small(*c as i64)),
+    Literal::EscapedCharacter { value, .. } => Literal::Integer(ErlInteger::Small(*value as i64)),
+    other => other.clone(),
+  }
+}
+
+fn as_number(lit: &Literal) -> Option<ConstNumber> {
+  match lit {
+    Literal::Integer(i) => Some(ConstNumber::Int(i.clone())),
+    Literal::Float(f) => Some(ConstNumber::Float(*f)),
+    _ => None,
+  }
+}
+
+fn as_bool(lit: &Literal, context: &str) -> Result<bool, ConstEvalError> {
+  match lit {
+    Literal::Bool(b) => Ok(*b),
+    other => Err(badarg(format!("{}: {} is not a boolean", context, other))),
+  }
+}
+
+fn as_integer(lit: &Literal) -> Option<BigInt> {
+  match lit {
+    Literal::Integer(i) => Some(i.to_bigint()),
+    _ => None,
+  }
+}
+
+fn float_result(value: f64, op: ErlBinaryOp, left: &Literal, right: &Literal) -> ConstEvalResult {
+  if value.is_finite() {
+    Ok(Literal::Float(value))
+  } else {
+    Err(badarith(op, left, right))
+  }
+}
+
+/// Build a proper list literal from elements
+pub(crate) fn make_list(elements: Vec<Literal>) -> Literal {
+  if elements.is_empty() {
+    Literal::Nil
+  } else {
+    Literal::List { elements, tail: None }
+  }
+}
+
+/// Return elements of a proper list, or `None` if the literal is not a proper list
+pub(crate) fn list_elements(lit: &Literal) -> Option<Vec<Literal>> {
+  match lit {
+    Literal::Nil => Some(Vec::new()),
+    Literal::String(s) => Some(
+      s.chars()
+        .map(|c| Literal::Integer(ErlInteger::Small(c as i64)))
+        .collect(),
+    ),
+    Literal::List { elements, tail: None } => Some(elements.iter().map(normalize).collect()),
+    Literal::List { elements, tail: Some(tail) } => {
+      let mut result: Vec<Literal> = elements.iter().map(normalize).collect();
+      result.extend(list_elements(tail)?);
+      Some(result)
+    }
+    _ => None,
+  }
+}
+
+/// Order of term classes in the Erlang term order:
+/// `number < atom < reference < fun < port < pid < tuple < map < nil < list < bit string`
+fn term_class(lit: &Literal) -> u8 {
+  match lit {
+    Literal::Integer(_)
+    | Literal::Float(_)
+    | Literal::Character(_)
+    | Literal::EscapedCharacter { .. } => 0,
+    Literal::Atom(_) | Literal::Bool(_) => 1,
+    Literal::Tuple(_) => 6,
+    Literal::Map(_) => 7,
+    Literal::Nil | Literal::List { .. } | Literal::String(_) => 8,
+    Literal::EmptyBinary | Literal::BinaryString(_) | Literal::Binary(_) => 9,
+  }
+}
+
+fn atom_text(lit: &Literal) -> String {
+  match lit {
+    Literal::Atom(a) => a.clone(),
+    Literal::Bool(b) => b.to_string(),
+    other => unreachable!("atom_text: {} is not an atom", other),
+  }
+}
+
+fn compare_numbers(a: &ConstNumber, b: &ConstNumber, exact: bool) -> Ordering {
+  match (a, b) {
+    (ConstNumber::Int(x), ConstNumber::Int(y)) => x.partial_cmp(y).unwrap_or(Ordering::Equal),
+    (ConstNumber::Float(x), ConstNumber::Float(y)) => x.partial_cmp(y).unwrap_or(Ordering::Equal),
+    (left, right) => {
+      let order = left
+        .as_f64()
+        .partial_cmp(&right.as_f64())
+        .unwrap_or(Ordering::Equal);
+      // Exact comparison never considers an integer equal to a float
+      if exact && order == Ordering::Equal {
+        if matches!(left, ConstNumber::Int(_)) {
+          Ordering::Less
+        } else {
+          Ordering::Greater
+        }
+      } else {
+        order
+      }
+    }
+  }
+}
+
+/// Compare two literals in the Erlang term order. With `exact` set, integers and floats never
+/// compare equal (as in `=:=`), otherwise they are compared by value (as in `==`).
+pub(crate) fn compare_terms(a: &Literal, b: &Literal, exact: bool) -> Ordering {
+  let a = normalize(a);
+  let b = normalize(b);
+  if let (Some(x), Some(y)) = (as_number(&a), as_number(&b)) {
+    return compare_numbers(&x, &y, exact);
+  }
+  let class_order = term_class(&a).cmp(&term_class(&b));
+  if class_order != Ordering::Equal {
+    return class_order;
+  }
+  match (&a, &b) {
+    (Literal::Tuple(x), Literal::Tuple(y)) => x
+      .len()
+      .cmp(&y.len())
+      .then_with(|| compare_term_slices(x, y, exact)),
+    // Maps compare by size, then by keys in term order, then by values in the key order
+    (Literal::Map(x), Literal::Map(y)) => x.len().cmp(&y.len()).then_with(|| {
+      let keys = |m: &[(Literal, Literal)]| m.iter().map(|(k, _)| k.clone()).collect::<Vec<_>>();
+      let values = |m: &[(Literal, Literal)]| m.iter().map(|(_, v)| v.clone()).collect::<Vec<_>>();
+      compare_term_slices(&keys(x), &keys(y), exact)
+        .then_with(|| compare_term_slices(&values(x), &values(y), exact))
+    }),
+    _ if term_class(&a) == 9 => a.binary_bytes().cmp(&b.binary_bytes()),
+    _ if term_class(&a) == 1 => atom_text(&a).cmp(&atom_text(&b)),
+    _ => match (list_elements(&a), list_elements(&b)) {
+      (Some(x), Some(y)) => compare_term_slices(&x, &y, exact),
+      // Improper lists are compared by their printed form, good enough for a compile-time check
+      _ => format!("{}", a).cmp(&format!("{}", b)),
+    },
+  }
+}
+
+fn compare_term_slices(a: &[Literal], b: &[Literal], exact: bool) -> Ordering {
+  for (x, y) in a.iter().zip(b.iter()) {
+    let order = compare_terms(x, y, exact);
+    if order != Ordering::Equal {
+      return order;
+    }
+  }
+  a.len().cmp(&b.len())
+}
+
+fn integer_arith(op: ErlBinaryOp, x: BigInt, y: BigInt) -> Option<BigInt> {
+  match op {
+    ErlBinaryOp::Add => Some(x + y),
+    ErlBinaryOp::Sub => Some(x - y),
+    ErlBinaryOp::Mul => Some(x * y),
+    ErlBinaryOp::IntegerDiv if y != BigInt::from(0) => Some(x / y),
+    ErlBinaryOp::Remainder if y != BigInt::from(0) => Some(x % y),
+    ErlBinaryOp::BinaryAnd => Some(x & y),
+    ErlBinaryOp::BinaryOr => Some(x | y),
+    ErlBinaryOp::BinaryXor => Some(x ^ y),
+    _ => None,
+  }
+}
+
+fn shift(op: ErlBinaryOp, left: &Literal, right: &Literal) -> ConstEvalResult {
+  let (value, amount) = match (as_integer(left), right) {
+    (Some(value), Literal::Integer(ErlInteger::Small(amount))) => (value, *amount),
+    (Some(_), Literal::Integer(ErlInteger::Big(_))) => {
+      return Err(ConstEvalError::Exception(format!("system_limit in {} {} {}", left, op, right)))
+    }
+    _ => return Err(badarith(op, left, right)),
+  };
+  let amount = if op == ErlBinaryOp::BinaryShiftLeft { amount } else { -amount };
+  if amount > MAX_SHIFT_BITS {
+    return Err(ConstEvalError::Exception(format!("system_limit in {} {} {}", left, op, right)));
+  }
+  let result = if amount >= 0 {
+    value << (amount as usize)
+  } else {
+    value >> (amount.unsigned_abs().min(MAX_SHIFT_BITS as u64) as usize)
+  };
+  Ok(Literal::Integer(ErlInteger::from_bigint(result)))
+}
+
+/// Apply a binary operator to two evaluated literals. Short-circuit operators `andalso` and
+/// `orelse` are handled by the caller, here they are evaluated strictly.
+pub(crate) fn eval_binop_literals(
+  op: ErlBinaryOp,
+  left: &Literal,
+  right: &Literal,
+) -> ConstEvalResult {
+  let left = &normalize(left);
+  let right = &normalize(right);
+  match op {
+    ErlBinaryOp::And | ErlBinaryOp::AndAlso | ErlBinaryOp::Comma => {
+      Ok(Literal::Bool(as_bool(left, "and")? & as_bool(right, "and")?))
+    }
+    ErlBinaryOp::Or | ErlBinaryOp::OrElse | ErlBinaryOp::Semicolon => {
+      Ok(Literal::Bool(as_bool(left, "or")? | as_bool(right, "or")?))
+    }
+    ErlBinaryOp::Xor => Ok(Literal::Bool(as_bool(left, "xor")? ^ as_bool(right, "xor")?)),
+
+    ErlBinaryOp::Less => Ok(Literal::Bool(compare_terms(left, right, false) == Ordering::Less)),
+    ErlBinaryOp::Greater => {
+      Ok(Literal::Bool(compare_terms(left, right, false) == Ordering::Greater))
+    }
+    ErlBinaryOp::LessEq => {
+      Ok(Literal::Bool(compare_terms(left, right, false) != Ordering::Greater))
+    }
+    ErlBinaryOp::GreaterEq => {
+      Ok(Literal::Bool(compare_terms(left, right, false) != Ordering::Less))
+    }
+    ErlBinaryOp::Eq => Ok(Literal::Bool(compare_terms(left, right, false) == Ordering::Equal)),
+    ErlBinaryOp::NotEq => Ok(Literal::Bool(compare_terms(left, right, false) != Ordering::Equal)),
+    ErlBinaryOp::HardEq => Ok(Literal::Bool(compare_terms(left, right, true) == Ordering::Equal)),
+    ErlBinaryOp::HardNotEq => {
+      Ok(Literal::Bool(compare_terms(left, right, true) != Ordering::Equal))
+    }
+
+    ErlBinaryOp::Add | ErlBinaryOp::Sub | ErlBinaryOp::Mul => {
+      match (as_number(left), as_number(right)) {
+        (Some(ConstNumber::Int(x)), Some(ConstNumber::Int(y))) => {
+          let result = integer_arith(op, x.to_bigint(), y.to_bigint()).unwrap();
+          Ok(Literal::Integer(ErlInteger::from_bigint(result)))
+        }
+        (Some(x), Some(y)) => {
+          let (x, y) = (x.as_f64(), y.as_f64());
+          let value = match op {
+            ErlBinaryOp::Add => x + y,
+            ErlBinaryOp::Sub => x - y,
+            _ => x * y,
+          };
+          float_result(value, op, left, right)
+        }
+        _ => Err(badarith(op, left, right)),
+      }
+    }
+    ErlBinaryOp::Div => match (as_number(left), as_number(right)) {
+      (Some(x), Some(y)) if y.as_f64() != 0.0 => {
+        float_result(x.as_f64() / y.as_f64(), op, left, right)
+      }
+      _ => Err(badarith(op, left, right)),
+    },
+    ErlBinaryOp::IntegerDiv
+    | ErlBinaryOp::Remainder
+    | ErlBinaryOp::BinaryAnd
+    | ErlBinaryOp::BinaryOr
+    | ErlBinaryOp::BinaryXor => match (as_integer(left), as_integer(right)) {
+      (Some(x), Some(y)) => match integer_arith(op, x, y) {
+        Some(result) => Ok(Literal::Integer(ErlInteger::from_bigint(result))),
+        None => Err(badarith(op, left, right)),
+      },
+      _ => Err(badarith(op, left, right)),
+    },
+    ErlBinaryOp::BinaryShiftLeft | ErlBinaryOp::BinaryShiftRight => shift(op, left, right),
+
+    ErlBinaryOp::ListAppend => match list_elements(left) {
+      Some(mut elements) => match list_elements(right) {
+        Some(right_elements) => {
+          elements.extend(right_elements);
+          Ok(make_list(elements))
+        }
+        None if elements.is_empty() => Ok(right.clone()),
+        None => Ok(Literal::List { elements, tail: Some(Box::new(right.clone())) }),
+      },
+      None => Err(badarg(format!("{} ++ {}", left, right))),
+    },
+    ErlBinaryOp::ListSubtract => match (list_elements(left), list_elements(right)) {
+      (Some(mut elements), Some(remove)) => {
+        for r in remove.iter() {
+          if let Some(pos) = elements
+            .iter()
+            .position(|e| compare_terms(e, r, true) == Ordering::Equal)
+          {
+            elements.remove(pos);
+          }
+        }
+        Ok(make_list(elements))
+      }
+      _ => Err(badarg(format!("{} -- {}", left, right))),
+    },
+
+    ErlBinaryOp::Match | ErlBinaryOp::Send => Err(ConstEvalError::NotConstant(format!(
+      "operator {} is not allowed in a constant expression",
+      op
+    ))),
+  }
+}
+
+/// Apply an unary operator to an evaluated literal
+pub(crate) fn eval_unop_literal(op: &ErlUnaryOp, value: &Literal) -> ConstEvalResult {
+  let value = &normalize(value);
+  match op {
+    ErlUnaryOp::Not => Ok(Literal::Bool(!as_bool(value, "not")?)),
+    ErlUnaryOp::Negative => match as_number(value) {
+      Some(ConstNumber::Int(i)) => Ok(Literal::Integer(i.negate())),
+      Some(ConstNumber::Float(f)) => Ok(Literal::Float(-f)),
+      None => Err(ConstEvalError::Exception(format!("badarith in -{}", value))),
+    },
+    ErlUnaryOp::Positive => match as_number(value) {
+      Some(_) => Ok(value.clone()),
+      None => Err(ConstEvalError::Exception(format!("badarith in +{}", value))),
+    },
+    ErlUnaryOp::BinaryNot => match as_integer(value) {
+      Some(i) => Ok(Literal::Integer(ErlInteger::from_bigint(!i))),
+      None => Err(ConstEvalError::Exception(format!("badarith in bnot {}", value))),
+    },
+    ErlUnaryOp::Catch => Err(ConstEvalError::NotConstant(
+      "catch is not allowed in a constant expression".to_string(),
+    )),
+  }
+}
+
+/// Name of a guard BIF being called: a local call or an `erlang:` remote call.
+fn guard_bif_name(target: &CallableTarget) -> Option<String> {
+  match target {
+    CallableTarget::Expr(e) if e.is_atom() => Some(e.as_atom().to_string()),
+    CallableTarget::MFArity(mfa) => match &mfa.module {
+      None => Some(mfa.name.clone()),
+      Some(m) if m == "erlang" => Some(mfa.name.clone()),
+      Some(_) => None,
+    },
+    _ => None,
+  }
+}
+
+fn float_to_integer(name: &str, f: f64) -> ConstEvalResult {
+  let rounded = match name {
+    "trunc" => f.trunc(),
+    _ => f.round(),
+  };
+  match BigInt::from_f64(rounded) {
+    Some(big) => Ok(Literal::Integer(ErlInteger::from_bigint(big))),
+    None => Err(badarg(format!("{}({})", name, f))),
+  }
+}
+
+/// Evaluate a guard BIF with already evaluated arguments
+fn eval_guard_bif(name: &str, args: &[Literal]) -> ConstEvalResult {
+  let bad_call = || badarg(format!("{}/{}", name, args.len()));
+  let result = match (name, args) {
+    ("is_atom", [a]) => Literal::Bool(matches!(a, Literal::Atom(_) | Literal::Bool(_))),
+    ("is_boolean", [a]) => Literal::Bool(matches!(a, Literal::Bool(_))),
+    ("is_integer", [a]) => Literal::Bool(matches!(a, Literal::Integer(_))),
+    ("is_float", [a]) => Literal::Bool(matches!(a, Literal::Float(_))),
+    ("is_number", [a]) => Literal::Bool(as_number(a).is_some()),
+    ("is_list", [a]) => Literal::Bool(term_class(a) == 8),
+    ("is_tuple", [a]) => Literal::Bool(matches!(a, Literal::Tuple(_))),
+    ("is_binary", [a]) | ("is_bitstring", [a]) => Literal::Bool(a.is_binary_lit()),
+    ("is_map", [a]) => Literal::Bool(matches!(a, Literal::Map(_))),
+    // Runtime-only values can never be constant literals
+    ("is_pid", [_]) | ("is_port", [_]) | ("is_reference", [_]) => Literal::Bool(false),
+    ("is_function", [_]) | ("is_function", [_, _]) => Literal::Bool(false),
+    ("is_record", [rec, Literal::Atom(tag)]) => Literal::Bool(
+      matches!(rec, Literal::Tuple(elements) if matches!(elements.first(), Some(Literal::Atom(t)) if t == tag)),
+    ),
+    ("is_record", [rec, Literal::Atom(tag), Literal::Integer(size)]) => Literal::Bool(matches!(
+      rec,
+      Literal::Tuple(elements) if Some(elements.len()) == size.as_usize()
+        && matches!(elements.first(), Some(Literal::Atom(t)) if t == tag)
+    )),
+
+    ("abs", [a]) => match as_number(a) {
+      Some(ConstNumber::Int(i)) if !i.is_non_negative() => Literal::Integer(i.negate()),
+      Some(ConstNumber::Float(f)) => Literal::Float(f.abs()),
+      Some(_) => a.clone(),
+      None => return Err(bad_call()),
+    },
+    ("float", [a]) => match as_number(a) {
+      Some(n) => Literal::Float(n.as_f64()),
+      None => return Err(bad_call()),
+    },
+    ("trunc", [a]) | ("round", [a]) => match as_number(a) {
+      Some(ConstNumber::Int(_)) => a.clone(),
+      Some(ConstNumber::Float(f)) => return float_to_integer(name, f),
+      None => return Err(bad_call()),
+    },
+    ("length", [a]) => match list_elements(a) {
+      Some(elements) => Literal::Integer(ErlInteger::Small(elements.len() as i64)),
+      None => return Err(bad_call()),
+    },
+    ("hd", [a]) => match list_elements(a) {
+      Some(elements) if !elements.is_empty() => elements[0].clone(),
+      _ => return Err(bad_call()),
+    },
+    ("tl", [a]) => match list_elements(a) {
+      Some(elements) if !elements.is_empty() => make_list(elements[1..].to_vec()),
+      _ => return Err(bad_call()),
+    },
+    ("tuple_size", [Literal::Tuple(elements)]) | ("size", [Literal::Tuple(elements)]) => {
+      Literal::Integer(ErlInteger::Small(elements.len() as i64))
+    }
+    ("size", [a]) | ("byte_size", [a]) if a.is_binary_lit() => {
+      Literal::Integer(ErlInteger::Small(a.binary_bytes().unwrap_or_default().len() as i64))
+    }
+    ("map_size", [Literal::Map(members)]) => {
+      Literal::Integer(ErlInteger::Small(members.len() as i64))
+    }
+    ("element", [Literal::Integer(index), Literal::Tuple(elements)]) => match index.as_usize() {
+      Some(i) if index.is_non_negative() && i >= 1 && i <= elements.len() => {
+        normalize(&elements[i - 1])
+      }
+      _ => return Err(bad_call()),
+    },
+    ("min", [a, b]) => {
+      if compare_terms(a, b, false) == Ordering::Greater {
+        b.clone()
+      } else {
+        a.clone()
+      }
+    }
+    ("max", [a, b]) => {
+      if compare_terms(a, b, false) == Ordering::Less {
+        b.clone()
+      } else {
+        a.clone()
+      }
+    }
+    (
+      "is_atom" | "is_boolean" | "is_integer" | "is_float" | "is_number" | "is_list" | "is_tuple"
+      | "is_binary" | "is_bitstring" | "is_map" | "is_record" | "abs" | "float" | "trunc" | "round"
+      | "length" | "hd" | "tl" | "tuple_size" | "size" | "byte_size" | "map_size" | "element"
+      | "min" | "max",
+      _,
+    ) => return Err(bad_call()),
+    _ => {
+      return Err(ConstEvalError::NotConstant(format!(
+        "call to {}/{} is not allowed in a constant expression",
+        name,
+        args.len()
+      )))
+    }
+  };
+  Ok(result)
+}
+
+impl AstNodeImpl {
+  /// Evaluate an `-if()` or `-elif()` condition. `is_defined` answers the `defined(Name)` queries.
+  /// Like in `epp`, the condition is true only if it evaluates to the atom `true`, other values and
+  /// exceptions make it false. A non-constant expression is an error.
+  pub fn eval_const_condition(
+    &self,
+    is_defined: &dyn Fn(&str) -> bool,
+  ) -> Result<bool, ConstEvalError> {
+    match self.eval_const_expr(is_defined) {
+      Ok(value) => Ok(matches!(value, Literal::Bool(true))),
+      Err(ConstEvalError::Exception(_)) => Ok(false),
+      Err(e) => Err(e),
+    }
+  }
+
+  /// Evaluate a constant guard-like expression into a literal value.
+  /// Supports literals, tuples, lists, boolean, comparison and arithmetic operators, the guard
+  /// BIFs like `is_integer/1` or `element/2`, and `defined(Name)` for macro names.
+  pub fn eval_const_expr(&self, is_defined: &dyn Fn(&str) -> bool) -> ConstEvalResult {
+    match &self.content {
+      AstNodeType::Lit { value } => Ok(normalize(value.deref())),
+      AstNodeType::Tuple { elements } => {
+        Ok(Literal::Tuple(Self::eval_const_exprs(elements, is_defined)?))
+      }
+      AstNodeType::List { elements, tail } => {
+        let mut result = Self::eval_const_exprs(elements, is_defined)?;
+        match tail {
+          None => Ok(make_list(result)),
+          Some(tail_expr) => {
+            let tail_value = tail_expr.eval_const_expr(is_defined)?;
+            match list_elements(&tail_value) {
+              Some(tail_elements) => {
+                result.extend(tail_elements);
+                Ok(make_list(result))
+              }
+              None => Ok(Literal::List { elements: result, tail: Some(Box::new(tail_value)) }),
+            }
+          }
+        }
+      }
+      AstNodeType::BinaryExpr { elements } if elements.is_empty() => Ok(Literal::EmptyBinary),
+      AstNodeType::BinaryOp { binop_expr } => {
+        let left = &binop_expr.left;
+        let right = &binop_expr.right;
+        match binop_expr.operator {
+          // Short-circuit operators, the right side is only evaluated if needed
+          ErlBinaryOp::AndAlso | ErlBinaryOp::Comma => {
+            if as_bool(&left.eval_const_expr(is_defined)?, "andalso")? {
+              right.eval_const_expr(is_defined)
+            } else {
+              Ok(Literal::Bool(false))
+            }
+          }
+          ErlBinaryOp::OrElse => {
+            if as_bool(&left.eval_const_expr(is_defined)?, "orelse")? {
+              Ok(Literal::Bool(true))
+            } else {
+              right.eval_const_expr(is_defined)
+            }
+          }
+          // Guard sequence: a guard raising an exception fails, and the next guard is tried
+          ErlBinaryOp::Semicolon => {
+            if left.eval_const_condition(is_defined)? {
+              Ok(Literal::Bool(true))
+            } else {
+              right.eval_const_expr(is_defined)
+            }
+          }
+          op => {
+            let left_value = left.eval_const_expr(is_defined)?;
+            let right_value = right.eval_const_expr(is_defined)?;
+            eval_binop_literals(op, &left_value, &right_value)
+          }
+        }
+      }
+      AstNodeType::UnaryOp { unop_expr } => {
+        let value = unop_expr.expr.eval_const_expr(is_defined)?;
+        eval_unop_literal(&unop_expr.operator, &value)
+      }
+      AstNodeType::Apply(app) => match guard_bif_name(&app.target) {
+        Some(name) if name == "defined" && app.args.len() == 1 => {
+          let macro_name = match &app.args[0].content {
+            AstNodeType::Var(v) => v.name.clone(),
+            _ if app.args[0].is_atom() => app.args[0].as_atom().to_string(),
+            _ => {
+              return Err(ConstEvalError::NotConstant(format!(
+                "defined() expects a macro name, got {}",
+                app.args[0]
+              )))
+            }
+          };
+          Ok(Literal::Bool(is_defined(&macro_name)))
+        }
+        Some(name) => {
+          let args = Self::eval_const_exprs(&app.args, is_defined)?;
+          eval_guard_bif(&name, &args)
+        }
+        None => Err(ConstEvalError::NotConstant(format!(
+          "call to {} is not allowed in a constant expression",
+          app.target
+        ))),
+      },
+      AstNodeType::Var(v) => Err(ConstEvalError::NotConstant(format!(
+        "variable {} is not allowed in a constant expression",
+        v.name
+      ))),
+      _ => Err(ConstEvalError::NotConstant(format!("{} is not a constant expression", self))),
+    }
+  }
+
+  fn eval_const_exprs(
+    nodes: &[AstNode],
+    is_defined: &dyn Fn(&str) -> bool,
+  ) -> Result<Vec<Literal>, ConstEvalError> {
+    nodes
+      .iter()
+      .map(|n| n.eval_const_expr(is_defined))
+      .collect()
+  }
+}
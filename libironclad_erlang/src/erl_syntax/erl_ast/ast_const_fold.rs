@@ -0,0 +1,251 @@
+//! Constant folding: lists, tuples, maps and binaries of literals become literal nodes, and
+//! operators with literal arguments are evaluated at compile time. A folded literal synthesizes a
+//! singleton type, which is more precise than the type of the expression it replaces.
+
+use crate::erl_syntax::erl_ast::ast_const_eval::{
+  compare_terms, eval_binop_literals, eval_unop_literal, list_elements, make_list, ConstEvalError,
+  ConstEvalResult,
+};
+use crate::erl_syntax::erl_ast::ast_folder::Folder;
+use crate::erl_syntax::erl_ast::node_impl::{AstNodeImpl, AstNodeType};
+use crate::erl_syntax::erl_ast::AstNode;
+use crate::erl_syntax::erl_op::ErlBinaryOp;
+use crate::erl_syntax::node::erl_binary_element::{BinaryElement, ValueWidth};
+use crate::erl_syntax::node::erl_binop::ErlBinaryOperatorExpr;
+use crate::erl_syntax::node::erl_map::{MapBuilderMember, MapMemberOperation};
+use crate::error::ic_error::IroncladError;
+use crate::error::ic_error_trait::GenericIroncladError;
+use crate::literal::Literal;
+use num_bigint::BigInt;
+use std::cmp::Ordering;
+use std::ops::Deref;
+
+/// Binary elements wider than this are not folded, to avoid building huge literals
+const MAX_FOLDED_WIDTH_BITS: usize = 1 << 16;
+
+/// Folds constant expressions, see the module description. Expressions which always raise an
+/// exception, like `1 / 0` or `atom + 1`, are not folded, and a warning is collected instead.
+#[derive(Default)]
+pub struct ConstFolder {
+  /// Warnings for the expressions which are guaranteed to fail
+  pub warnings: Vec<GenericIroncladError>,
+}
+
+/// Literal value of a `Lit` node
+fn literal_of(node: &AstNode) -> Option<&Literal> {
+  match &node.content {
+    AstNodeType::Lit { value } => Some(value.deref()),
+    _ => None,
+  }
+}
+
+/// Boolean value of a `Bool` literal or of the atoms `true` and `false`
+fn boolean_of(lit: &Literal) -> Option<bool> {
+  match lit {
+    Literal::Bool(b) => Some(*b),
+    Literal::Atom(a) if a == "true" => Some(true),
+    Literal::Atom(a) if a == "false" => Some(false),
+    _ => None,
+  }
+}
+
+/// Literal values of the nodes, or `None` if any node is not a literal
+fn literals_of(nodes: &[AstNode]) -> Option<Vec<Literal>> {
+  nodes.iter().map(|n| literal_of(n).cloned()).collect()
+}
+
+/// Bytes of a binary element, if it is a literal with no type specifiers and a whole number of
+/// bytes wide. Integers are truncated to the width like the runtime does, string characters are
+/// truncated to one byte each.
+fn binary_element_bytes(element: &BinaryElement) -> Option<Vec<u8>> {
+  if !element.type_specs.is_empty() {
+    return None;
+  }
+  let value = literal_of(&element.value)?;
+  if let (Literal::String(s), ValueWidth::DefaultWidth) = (value, &element.width) {
+    return Some(s.chars().map(|c| c as u32 as u8).collect());
+  }
+  let int_value = match value {
+    Literal::Integer(i) => i.to_bigint(),
+    Literal::Character(c) => BigInt::from(*c as u32),
+    Literal::EscapedCharacter { value, .. } => BigInt::from(*value as u32),
+    _ => return None,
+  };
+  let width = match &element.width {
+    ValueWidth::DefaultWidth => 8,
+    ValueWidth::Literal(0) => return Some(Vec::new()),
+    ValueWidth::Literal(w) if w % 8 == 0 && *w <= MAX_FOLDED_WIDTH_BITS => *w,
+    _ => return None,
+  };
+  let modulus = BigInt::from(1) << width;
+  let mut truncated = int_value % &modulus;
+  if truncated < BigInt::from(0) {
+    truncated += &modulus;
+  }
+  let (_, magnitude) = truncated.to_bytes_be();
+  let mut bytes = vec![0u8; (width / 8).saturating_sub(magnitude.len())];
+  bytes.extend(magnitude);
+  Some(bytes)
+}
+
+/// Literal for a list of literals with an optional literal tail
+fn list_literal(elements: &[AstNode], tail: &Option<AstNode>) -> Option<Literal> {
+  let mut values = literals_of(elements)?;
+  match tail {
+    None => Some(make_list(values)),
+    Some(tail_node) => {
+      let tail_value = literal_of(tail_node)?;
+      match list_elements(tail_value) {
+        Some(tail_elements) => {
+          values.extend(tail_elements);
+          Some(make_list(values))
+        }
+        None => Some(Literal::List {
+          elements: values,
+          tail: Some(Box::new(tail_value.clone())),
+        }),
+      }
+    }
+  }
+}
+
+/// Literal for a map construction with literal keys and values. Map updates need the base map,
+/// `:=` members only occur in updates and patterns, and an empty map in a pattern matches any map,
+/// so these are not folded.
+fn map_literal(base: &Option<AstNode>, members: &[MapBuilderMember]) -> Option<Literal> {
+  if base.is_some() || members.is_empty() {
+    return None;
+  }
+  let mut pairs: Vec<(Literal, Literal)> = Vec::with_capacity(members.len());
+  for member in members.iter() {
+    if !matches!(member.operation, MapMemberOperation::Assign) {
+      return None;
+    }
+    let key = literal_of(&member.key)?;
+    let value = literal_of(&member.expr)?;
+    // A repeated key takes the last value
+    match pairs
+      .iter_mut()
+      .find(|(k, _)| compare_terms(k, key, true) == Ordering::Equal)
+    {
+      Some(pair) => pair.1 = value.clone(),
+      None => pairs.push((key.clone(), value.clone())),
+    }
+  }
+  pairs.sort_by(|(a, _), (b, _)| compare_terms(a, b, true));
+  Some(Literal::Map(pairs))
+}
+
+/// Literal for a binary where every element is a literal of whole bytes
+fn binary_literal(elements: &[BinaryElement]) -> Option<Literal> {
+  let mut bytes = Vec::new();
+  for element in elements.iter() {
+    bytes.extend(binary_element_bytes(element)?);
+  }
+  if bytes.is_empty() {
+    Some(Literal::EmptyBinary)
+  } else {
+    Some(Literal::Binary(bytes))
+  }
+}
+
+/// Evaluate a binary operator with literal arguments. Returns `None` if an argument is not a
+/// literal, or for the match and send operators.
+fn eval_binop_expr(binop_expr: &ErlBinaryOperatorExpr) -> Option<ConstEvalResult> {
+  let left = literal_of(&binop_expr.left)?;
+  let right = literal_of(&binop_expr.right)?;
+  let result = match binop_expr.operator {
+    ErlBinaryOp::Match | ErlBinaryOp::Send => return None,
+    // Short-circuit operators return the right side as is, it is not required to be a boolean
+    ErlBinaryOp::AndAlso => match boolean_of(left) {
+      Some(true) => Ok(right.clone()),
+      Some(false) => Ok(Literal::Bool(false)),
+      None => eval_binop_literals(ErlBinaryOp::AndAlso, left, right),
+    },
+    ErlBinaryOp::OrElse => match boolean_of(left) {
+      Some(true) => Ok(Literal::Bool(true)),
+      Some(false) => Ok(right.clone()),
+      None => eval_binop_literals(ErlBinaryOp::OrElse, left, right),
+    },
+    op => eval_binop_literals(op, left, right),
+  };
+  Some(result)
+}
+
+/// Replace the node with a literal node if the value is known
+fn replace_with_literal(node: AstNode, value: Option<Literal>) -> AstNode {
+  match value {
+    Some(v) => AstNodeImpl::new_lit(node.location.clone(), v),
+    None => node,
+  }
+}
+
+impl ConstFolder {
+  /// Produce a literal node for the evaluation result, or keep the node and warn if the evaluation
+  /// raised an exception
+  fn fold_evaluated(&mut self, node: AstNode, result: Option<ConstEvalResult>) -> AstNode {
+    match result {
+      Some(Ok(value)) => AstNodeImpl::new_lit(node.location.clone(), value),
+      Some(Err(ConstEvalError::Exception(msg))) => {
+        self.warnings.push(IroncladError::erlang_warning(
+          node.location.clone(),
+          format!("Expression {} is guaranteed to fail: {}", node, msg),
+        ));
+        node
+      }
+      Some(Err(ConstEvalError::NotConstant(_))) | None => node,
+    }
+  }
+}
+
+impl Folder for ConstFolder {
+  fn fold_list(&mut self, node: AstNode) -> AstNode {
+    let value = match &node.content {
+      AstNodeType::List { elements, tail } => list_literal(elements, tail),
+      _ => None,
+    };
+    replace_with_literal(node, value)
+  }
+
+  fn fold_tuple(&mut self, node: AstNode) -> AstNode {
+    let value = match &node.content {
+      AstNodeType::Tuple { elements } => literals_of(elements).map(Literal::Tuple),
+      _ => None,
+    };
+    replace_with_literal(node, value)
+  }
+
+  fn fold_map_builder(&mut self, node: AstNode) -> AstNode {
+    let value = match &node.content {
+      AstNodeType::MapBuilder { base, members } => map_literal(base, members),
+      _ => None,
+    };
+    replace_with_literal(node, value)
+  }
+
+  fn fold_binary_expr(&mut self, node: AstNode) -> AstNode {
+    let value = match &node.content {
+      AstNodeType::BinaryExpr { elements } => binary_literal(elements),
+      _ => None,
+    };
+    replace_with_literal(node, value)
+  }
+
+  fn fold_binary_op(&mut self, node: AstNode) -> AstNode {
+    let result = match &node.content {
+      AstNodeType::BinaryOp { binop_expr } => eval_binop_expr(binop_expr),
+      _ => None,
+    };
+    self.fold_evaluated(node, result)
+  }
+
+  fn fold_unary_op(&mut self, node: AstNode) -> AstNode {
+    let result = match &node.content {
+      AstNodeType::UnaryOp { unop_expr } => {
+        literal_of(&unop_expr.expr).map(|value| eval_unop_literal(&unop_expr.operator, value))
+      }
+      _ => None,
+    };
+    self.fold_evaluated(node, result)
+  }
+}
@@ -3,7 +3,6 @@
 
 use crate::erl_syntax::erl_ast::node_impl::{AstNodeImpl, AstNodeType};
 use crate::erl_syntax::literal_bool::LiteralBool;
-use crate::literal::Literal;
 
 impl AstNodeImpl {
   /// Checks whether an expression is a `ErlAst::Lit`
@@ -34,10 +33,4 @@ impl AstNodeImpl {
       _ => LiteralBool::NotABoolean,
     }
   }
-
-  /// Walk a literal expression and return `Some()` if its calculatable in compile time.
-  #[allow(dead_code)]
-  pub(crate) fn walk_litexpr(&self) -> Option<Literal> {
-    unimplemented!("Walk litexpr")
-  }
 }
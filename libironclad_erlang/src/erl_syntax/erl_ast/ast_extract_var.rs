@@ -4,7 +4,6 @@ use crate::erl_syntax::erl_ast::ast_iter::IterableAstNodeT;
 use crate::erl_syntax::erl_ast::node_impl::{AstNodeImpl, AstNodeType};
 use crate::erl_syntax::erl_ast::AstNode;
 use crate::erl_syntax::ic_parser_error::IcParserError;
-use crate::erl_syntax::node::erl_binary_element::ValueWidth;
 use crate::error::ic_error::{IcSeverity, IroncladResult};
 use crate::typing::erl_type::{ErlType, TypeImpl};
 use std::collections::HashMap;
@@ -23,11 +22,9 @@ impl AstNodeImpl {
         Ok(())
       }
       AstNodeType::BinaryExpr { elements, .. } => {
+        // The width is an expression using the variables bound before, it binds nothing
         for e in elements {
           Self::extract_variables(&e.value, variables)?;
-          if let ValueWidth::Expr(expr_width) = &e.width {
-            Self::extract_variables(expr_width, variables)?;
-          }
         }
         Ok(())
       }
@@ -51,8 +48,25 @@ impl AstNodeImpl {
         }
         Ok(())
       }
-      AstNodeType::ListComprehensionGenerator { left, right, .. } => {
+      AstNodeType::MapComprehension { key, value, generators } => {
+        Self::extract_variables(key, variables)?;
+        Self::extract_variables(value, variables)?;
+        for g in generators {
+          Self::extract_variables(g, variables)?;
+        }
+        Ok(())
+      }
+      AstNodeType::ZipGenerator { generators } => {
+        for g in generators {
+          Self::extract_variables(g, variables)?;
+        }
+        Ok(())
+      }
+      AstNodeType::ListComprehensionGenerator { left, map_value, right, .. } => {
         Self::extract_variables(left, variables)?;
+        if let Some(v) = map_value {
+          Self::extract_variables(v, variables)?;
+        }
         Self::extract_variables(right, variables)
       }
       AstNodeType::IfStatement { clauses, .. } => {
@@ -140,6 +154,7 @@ impl AstNodeImpl {
       | AstNodeType::MFA { .. }
       | AstNodeType::ModuleForms { .. }
       | AstNodeType::FnRef { .. }
+      | AstNodeType::FnRefDynamic { .. }
       | AstNodeType::FnDef(_)
       | AstNodeType::CClause(_, _)
       | AstNodeType::CaseExpr { .. }
@@ -0,0 +1,502 @@
+//! Rewriting traversal of the AST, producing a new tree.
+//!
+//! Implement `Folder` and override the hooks for the node types to rewrite, then call `fold_node`
+//! on the root. The children are folded first, then the type-specific hook receives the rebuilt
+//! node and returns its replacement. `fold_children` matches every `AstNodeType` variant without a
+//! catch-all arm, so a new node type can't be added without deciding how it is rebuilt.
+
+use crate::erl_syntax::erl_ast::ast_visitor::AstScope;
+use crate::erl_syntax::erl_ast::node_impl::{AstNodeImpl, AstNodeType};
+use crate::erl_syntax::erl_ast::AstNode;
+use crate::erl_syntax::node::erl_apply::ErlApply;
+use crate::erl_syntax::node::erl_binary_element::{BinaryElement, ValueWidth};
+use crate::erl_syntax::node::erl_binop::ErlBinaryOperatorExpr;
+use crate::erl_syntax::node::erl_callable_target::CallableTarget;
+use crate::erl_syntax::node::erl_case_clause::ErlCaseClause;
+use crate::erl_syntax::node::erl_catch_clause::CatchClause;
+use crate::erl_syntax::node::erl_exception_pattern::ExceptionPattern;
+use crate::erl_syntax::node::erl_fn_clause::ErlFnClause;
+use crate::erl_syntax::node::erl_fn_def::ErlFnDef;
+use crate::erl_syntax::node::erl_if_clause::ErlIfClause;
+use crate::erl_syntax::node::erl_map::MapBuilderMember;
+use crate::erl_syntax::node::erl_record::RecordBuilderMember;
+use crate::erl_syntax::node::erl_unop::ErlUnaryOperatorExpr;
+
+/// AST rewriter. Every node hook receives the node with its children already folded, and by
+/// default returns it unchanged, so an implementation only overrides the hooks it needs.
+#[allow(unused_variables)]
+pub trait Folder {
+  /// Pre-order hook, called for every node before its children are folded. Return a replacement
+  /// to use it instead of the node, then the children and the other hooks are not called.
+  fn pre_fold(&mut self, node: &AstNode) -> Option<AstNode> {
+    None
+  }
+
+  /// Post-order hook, called for every node after its type-specific hook
+  fn post_fold(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// Called when entering a function clause or a comprehension of the original tree
+  fn enter_scope(&mut self, scope: AstScope<'_>) {}
+
+  /// Called when leaving a function clause or a comprehension of the original tree
+  fn exit_scope(&mut self, scope: AstScope<'_>) {}
+
+  /// `Empty` node
+  fn fold_empty(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// Module root with forms
+  fn fold_module_forms(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// Function definition or a lambda
+  fn fold_fn_def(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// A clause of a function or a lambda, called inside of the clause scope. The clause scope is
+  /// kept from the original clause.
+  fn fold_fn_clause(&mut self, clause: ErlFnClause) -> ErlFnClause {
+    clause
+  }
+
+  /// `fun f/1` or `fun m:f/1`
+  fn fold_fn_ref(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// `fun M:F/A` with non-literal parts
+  fn fold_fn_ref_dynamic(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// A parsed type wrapped in a node
+  fn fold_type(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// A standalone case clause node
+  fn fold_cclause(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// A function name with arity
+  fn fold_mfa(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// A variable
+  fn fold_var(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// Function call
+  fn fold_apply(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// `case Expr of Clauses end`
+  fn fold_case_expr(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// A clause of `case`, also of a standalone `CClause` node
+  fn fold_case_clause(&mut self, clause: ErlCaseClause) -> ErlCaseClause {
+    clause
+  }
+
+  /// A literal
+  fn fold_lit(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// Binary operator, including the match `=`
+  fn fold_binary_op(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// Unary operator
+  fn fold_unary_op(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// List with an optional tail
+  fn fold_list(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// Tuple
+  fn fold_tuple(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// Map construction or update
+  fn fold_map_builder(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// Record construction or update
+  fn fold_record_builder(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// Record field access or record field index
+  fn fold_record_field(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// Comma-separated expressions
+  fn fold_comma_expr(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// List comprehension
+  fn fold_list_comprehension(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// Binary comprehension
+  fn fold_binary_comprehension(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// Map comprehension
+  fn fold_map_comprehension(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// A list, binary or map generator in a comprehension
+  fn fold_generator(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// Zip generator `G1 && G2`
+  fn fold_zip_generator(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// `try ... of ... catch ... end`
+  fn fold_try_catch(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// A clause of `try ... of`
+  fn fold_try_of_clause(&mut self, clause: ErlCaseClause) -> ErlCaseClause {
+    clause
+  }
+
+  /// A `catch Class:Error:Stack when Guard -> Body` clause
+  fn fold_catch_clause(&mut self, clause: CatchClause) -> CatchClause {
+    clause
+  }
+
+  /// `if Clauses end`
+  fn fold_if_statement(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// A clause of `if`
+  fn fold_if_clause(&mut self, clause: ErlIfClause) -> ErlIfClause {
+    clause
+  }
+
+  /// `begin ... end`
+  fn fold_begin_end(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// Binary construction or binary match `<<...>>`
+  fn fold_binary_expr(&mut self, node: AstNode) -> AstNode {
+    node
+  }
+
+  /// An element of a binary
+  fn fold_binary_element(&mut self, element: BinaryElement) -> BinaryElement {
+    element
+  }
+}
+
+/// Fold a node: call the pre-order hook, fold the children, then call the hook for the node type
+/// and the post-order hook
+pub fn fold_node<F: Folder + ?Sized>(f: &mut F, node: &AstNode) -> AstNode {
+  if let Some(replacement) = f.pre_fold(node) {
+    return replacement;
+  }
+  let rebuilt = fold_children(f, node);
+  let hook: fn(&mut F, AstNode) -> AstNode = match &rebuilt.content {
+    AstNodeType::Empty { .. } => F::fold_empty,
+    AstNodeType::ModuleForms { .. } => F::fold_module_forms,
+    AstNodeType::FnDef(_) => F::fold_fn_def,
+    AstNodeType::FnRef { .. } => F::fold_fn_ref,
+    AstNodeType::FnRefDynamic { .. } => F::fold_fn_ref_dynamic,
+    AstNodeType::Type { .. } => F::fold_type,
+    AstNodeType::CClause(..) => F::fold_cclause,
+    AstNodeType::MFA { .. } => F::fold_mfa,
+    AstNodeType::Var(_) => F::fold_var,
+    AstNodeType::Apply(_) => F::fold_apply,
+    AstNodeType::CaseExpr { .. } => F::fold_case_expr,
+    AstNodeType::Lit { .. } => F::fold_lit,
+    AstNodeType::BinaryOp { .. } => F::fold_binary_op,
+    AstNodeType::UnaryOp { .. } => F::fold_unary_op,
+    AstNodeType::List { .. } => F::fold_list,
+    AstNodeType::Tuple { .. } => F::fold_tuple,
+    AstNodeType::MapBuilder { .. } => F::fold_map_builder,
+    AstNodeType::RecordBuilder { .. } => F::fold_record_builder,
+    AstNodeType::RecordField { .. } => F::fold_record_field,
+    AstNodeType::CommaExpr { .. } => F::fold_comma_expr,
+    AstNodeType::ListComprehension { .. } => F::fold_list_comprehension,
+    AstNodeType::BinaryComprehension { .. } => F::fold_binary_comprehension,
+    AstNodeType::MapComprehension { .. } => F::fold_map_comprehension,
+    AstNodeType::ListComprehensionGenerator { .. } => F::fold_generator,
+    AstNodeType::ZipGenerator { .. } => F::fold_zip_generator,
+    AstNodeType::TryCatch { .. } => F::fold_try_catch,
+    AstNodeType::IfStatement { .. } => F::fold_if_statement,
+    AstNodeType::BeginEnd { .. } => F::fold_begin_end,
+    AstNodeType::BinaryExpr { .. } => F::fold_binary_expr,
+  };
+  let folded = hook(f, rebuilt);
+  f.post_fold(folded)
+}
+
+/// Fold each node in order
+pub fn fold_nodes<F: Folder + ?Sized>(f: &mut F, nodes: &[AstNode]) -> Vec<AstNode> {
+  nodes.iter().map(|n| fold_node(f, n)).collect()
+}
+
+/// Fold the node if present
+pub fn fold_optional<F: Folder + ?Sized>(f: &mut F, node: &Option<AstNode>) -> Option<AstNode> {
+  node.as_ref().map(|n| fold_node(f, n))
+}
+
+/// Rebuild the node with folded children. Nodes without children are returned as is.
+/// Children are folded in the same order as `Visitor` visits them.
+pub fn fold_children<F: Folder + ?Sized>(f: &mut F, node: &AstNode) -> AstNode {
+  let content = match &node.content {
+    AstNodeType::Empty { .. }
+    | AstNodeType::FnRef { .. }
+    | AstNodeType::Type { .. }
+    | AstNodeType::MFA { .. }
+    | AstNodeType::Var(_)
+    | AstNodeType::Lit { .. } => return node.clone(),
+
+    AstNodeType::ModuleForms { forms } => AstNodeType::ModuleForms { forms: fold_nodes(f, forms) },
+    AstNodeType::FnDef(fn_def) => AstNodeType::FnDef(ErlFnDef {
+      location: fn_def.location.clone(),
+      funarity: fn_def.funarity.clone(),
+      clauses: fn_def
+        .clauses
+        .iter()
+        .map(|clause| {
+          f.enter_scope(AstScope::FnClause(clause));
+          let folded = fold_fn_clause(f, clause);
+          f.exit_scope(AstScope::FnClause(clause));
+          folded
+        })
+        .collect(),
+    }),
+    AstNodeType::FnRefDynamic { module, function, arity } => AstNodeType::FnRefDynamic {
+      module: fold_node(f, module),
+      function: fold_node(f, function),
+      arity: fold_node(f, arity),
+    },
+    AstNodeType::CClause(location, clause) => {
+      AstNodeType::CClause(location.clone(), fold_case_clause(f, clause, false))
+    }
+    AstNodeType::Apply(apply) => AstNodeType::Apply(fold_apply(f, apply)),
+    AstNodeType::CaseExpr { expr, clauses } => AstNodeType::CaseExpr {
+      expr: fold_node(f, expr),
+      clauses: clauses
+        .iter()
+        .map(|c| fold_case_clause(f, c, false))
+        .collect(),
+    },
+    AstNodeType::BinaryOp { binop_expr } => AstNodeType::BinaryOp {
+      binop_expr: ErlBinaryOperatorExpr {
+        left: fold_node(f, &binop_expr.left),
+        right: fold_node(f, &binop_expr.right),
+        operator: binop_expr.operator,
+      },
+    },
+    AstNodeType::UnaryOp { unop_expr } => AstNodeType::UnaryOp {
+      unop_expr: ErlUnaryOperatorExpr {
+        expr: fold_node(f, &unop_expr.expr),
+        operator: unop_expr.operator.clone(),
+      },
+    },
+    AstNodeType::List { elements, tail } => AstNodeType::List {
+      elements: fold_nodes(f, elements),
+      tail: fold_optional(f, tail),
+    },
+    AstNodeType::Tuple { elements } => AstNodeType::Tuple { elements: fold_nodes(f, elements) },
+    AstNodeType::MapBuilder { base, members } => AstNodeType::MapBuilder {
+      base: fold_optional(f, base),
+      members: members
+        .iter()
+        .map(|m| MapBuilderMember {
+          operation: m.operation.clone(),
+          key: fold_node(f, &m.key),
+          expr: fold_node(f, &m.expr),
+        })
+        .collect(),
+    },
+    AstNodeType::RecordBuilder { base, tag, members } => AstNodeType::RecordBuilder {
+      base: fold_optional(f, base),
+      tag: tag.clone(),
+      members: members
+        .iter()
+        .map(|m| RecordBuilderMember {
+          field: m.field.clone(),
+          expr: fold_node(f, &m.expr),
+        })
+        .collect(),
+    },
+    AstNodeType::RecordField { base, tag, field } => AstNodeType::RecordField {
+      base: fold_optional(f, base),
+      tag: tag.clone(),
+      field: field.clone(),
+    },
+    AstNodeType::CommaExpr { elements } => {
+      AstNodeType::CommaExpr { elements: fold_nodes(f, elements) }
+    }
+    AstNodeType::ListComprehension { expr, generators } => {
+      f.enter_scope(AstScope::Comprehension(node));
+      let generators = fold_nodes(f, generators);
+      let expr = fold_node(f, expr);
+      f.exit_scope(AstScope::Comprehension(node));
+      AstNodeType::ListComprehension { expr, generators }
+    }
+    AstNodeType::BinaryComprehension { expr, generators } => {
+      f.enter_scope(AstScope::Comprehension(node));
+      let generators = fold_nodes(f, generators);
+      let expr = fold_node(f, expr);
+      f.exit_scope(AstScope::Comprehension(node));
+      AstNodeType::BinaryComprehension { expr, generators }
+    }
+    AstNodeType::MapComprehension { key, value, generators } => {
+      f.enter_scope(AstScope::Comprehension(node));
+      let generators = fold_nodes(f, generators);
+      let key = fold_node(f, key);
+      let value = fold_node(f, value);
+      f.exit_scope(AstScope::Comprehension(node));
+      AstNodeType::MapComprehension { key, value, generators }
+    }
+    AstNodeType::ListComprehensionGenerator { left, map_value, right, kind } => {
+      let right = fold_node(f, right);
+      let left = fold_node(f, left);
+      let map_value = fold_optional(f, map_value);
+      AstNodeType::ListComprehensionGenerator { left, map_value, right, kind: *kind }
+    }
+    AstNodeType::ZipGenerator { generators } => {
+      AstNodeType::ZipGenerator { generators: fold_nodes(f, generators) }
+    }
+    AstNodeType::TryCatch { body, of_branches, catch_clauses } => AstNodeType::TryCatch {
+      body: fold_node(f, body),
+      of_branches: of_branches.as_ref().map(|branches| {
+        branches
+          .iter()
+          .map(|c| fold_case_clause(f, c, true))
+          .collect()
+      }),
+      catch_clauses: catch_clauses
+        .iter()
+        .map(|c| fold_catch_clause(f, c))
+        .collect(),
+    },
+    AstNodeType::IfStatement { clauses } => AstNodeType::IfStatement {
+      clauses: clauses
+        .iter()
+        .map(|c| {
+          let clause = ErlIfClause {
+            cond: fold_node(f, &c.cond),
+            body: fold_node(f, &c.body),
+          };
+          f.fold_if_clause(clause)
+        })
+        .collect(),
+    },
+    AstNodeType::BeginEnd { exprs } => AstNodeType::BeginEnd { exprs: fold_nodes(f, exprs) },
+    AstNodeType::BinaryExpr { elements } => AstNodeType::BinaryExpr {
+      elements: elements.iter().map(|e| fold_binary_element(f, e)).collect(),
+    },
+  };
+  AstNodeImpl::construct_with_location(node.location.clone(), content)
+}
+
+/// Fold function clause arguments, guard and body, then call the clause hook
+fn fold_fn_clause<F: Folder + ?Sized>(f: &mut F, clause: &ErlFnClause) -> ErlFnClause {
+  let folded = ErlFnClause {
+    name: clause.name.clone(),
+    args: fold_nodes(f, &clause.args),
+    guard_expr: fold_optional(f, &clause.guard_expr),
+    body: fold_node(f, &clause.body),
+    scope: clause.scope.clone(),
+  };
+  f.fold_fn_clause(folded)
+}
+
+/// Fold the call target expressions and the arguments
+fn fold_apply<F: Folder + ?Sized>(f: &mut F, apply: &ErlApply) -> ErlApply {
+  let target = match &apply.target {
+    CallableTarget::Expr(expr) => CallableTarget::Expr(fold_node(f, expr)),
+    CallableTarget::MFArity(mfa) => CallableTarget::MFArity(mfa.clone()),
+    CallableTarget::MFAExpression { module, function, arity } => CallableTarget::MFAExpression {
+      module: fold_optional(f, module),
+      function: fold_node(f, function),
+      arity: *arity,
+    },
+  };
+  ErlApply::new(target, fold_nodes(f, &apply.args))
+}
+
+/// Fold case clause pattern, guard and body, then call the clause hook for `case` or `try ... of`
+fn fold_case_clause<F: Folder + ?Sized>(
+  f: &mut F,
+  clause: &ErlCaseClause,
+  is_try_of: bool,
+) -> ErlCaseClause {
+  let folded = ErlCaseClause {
+    pattern: fold_node(f, &clause.pattern),
+    guard: fold_optional(f, &clause.guard),
+    body: fold_node(f, &clause.body),
+  };
+  if is_try_of {
+    f.fold_try_of_clause(folded)
+  } else {
+    f.fold_case_clause(folded)
+  }
+}
+
+/// Fold catch clause exception pattern, guard and body, then call the clause hook
+fn fold_catch_clause<F: Folder + ?Sized>(f: &mut F, clause: &CatchClause) -> CatchClause {
+  let folded = CatchClause {
+    exc_pattern: ExceptionPattern {
+      class: fold_node(f, &clause.exc_pattern.class),
+      error: fold_node(f, &clause.exc_pattern.error),
+      stack: fold_optional(f, &clause.exc_pattern.stack),
+    },
+    when_guard: fold_optional(f, &clause.when_guard),
+    body: fold_node(f, &clause.body),
+  };
+  f.fold_catch_clause(folded)
+}
+
+/// Fold binary element value and width, then call the element hook
+fn fold_binary_element<F: Folder + ?Sized>(f: &mut F, element: &BinaryElement) -> BinaryElement {
+  let value = fold_node(f, &element.value);
+  let width = match &element.width {
+    ValueWidth::Literal(w) => ValueWidth::Literal(*w),
+    ValueWidth::Expr(expr) => ValueWidth::Expr(fold_node(f, expr)),
+    ValueWidth::DefaultWidth => ValueWidth::DefaultWidth,
+  };
+  let folded = BinaryElement {
+    location: element.location.clone(),
+    value,
+    width,
+    type_specs: element.type_specs.clone(),
+  };
+  f.fold_binary_element(folded)
+}
@@ -61,6 +61,11 @@ impl AstNodeImpl {
     matches!(&self.content, AstNodeType::BinaryComprehension { .. })
   }
 
+  /// Checks whether an ErlAst node is a Map Comprehension
+  pub fn is_map_comprehension(&self) -> bool {
+    matches!(&self.content, AstNodeType::MapComprehension { .. })
+  }
+
   /// Checks whether an ErlAst node is a Record Field Accessor
   pub fn is_record_field(&self) -> bool {
     matches!(&self.content, AstNodeType::RecordField { .. })
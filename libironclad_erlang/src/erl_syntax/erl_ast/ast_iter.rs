@@ -33,6 +33,9 @@ impl IterableAstNodeT for AstNodeImpl {
       | AstNodeType::Var { .. } => None,
 
       AstNodeType::ModuleForms { forms: f, .. } => Some(f.to_vec()),
+      AstNodeType::FnRefDynamic { module, function, arity } => {
+        Some(vec![module.clone(), function.clone(), arity.clone()])
+      }
       AstNodeType::FnDef(fn_def) => fn_def.children(),
       AstNodeType::Apply(app) => app.children(),
 
@@ -73,8 +76,17 @@ impl IterableAstNodeT for AstNodeImpl {
         result.extend(generators.iter().cloned());
         Some(result)
       }
-      AstNodeType::ListComprehensionGenerator { left, right, .. } => {
-        Some(vec![left.clone(), right.clone()])
+      AstNodeType::MapComprehension { key, value, generators } => {
+        let mut result = vec![key.clone(), value.clone()];
+        result.extend(generators.iter().cloned());
+        Some(result)
+      }
+      AstNodeType::ZipGenerator { generators } => Some(generators.to_vec()),
+      AstNodeType::ListComprehensionGenerator { left, map_value, right, .. } => {
+        let mut result = vec![left.clone()];
+        result.extend(map_value.iter().cloned());
+        result.push(right.clone());
+        Some(result)
       }
 
       AstNodeType::TryCatch { body, of_branches, catch_clauses, .. } => {
@@ -0,0 +1,335 @@
+//! Conversion of the AST to JSON for the external tools, see `module_json` for the schema.
+//! Every node becomes an object with `"type"` set to the `AstNodeType` variant name and `"span"`
+//! set to the node location, followed by the fields of the variant. For the variants which hold
+//! one struct, like `FnDef`, `Apply` and `BinaryOp`, the fields of the struct are written instead,
+//! and the clauses, members and binary elements are objects with the fields of their structs.
+
+use crate::erl_syntax::erl_ast::node_impl::{AstNodeImpl, AstNodeType};
+use crate::erl_syntax::erl_ast::AstNode;
+use crate::erl_syntax::node::erl_binary_element::{BinaryElement, ValueWidth};
+use crate::erl_syntax::node::erl_callable_target::CallableTarget;
+use crate::erl_syntax::node::erl_case_clause::ErlCaseClause;
+use crate::erl_syntax::node::erl_catch_clause::CatchClause;
+use crate::erl_syntax::node::erl_fn_clause::ErlFnClause;
+use crate::erl_syntax::node::erl_map::MapMemberOperation;
+use crate::literal::Literal;
+use crate::source_loc::SourceLoc;
+use libironclad_util::json::JsonValue;
+
+/// Converts a source location to a JSON span, or `null` if the location is not known
+pub type SpanConverter<'a> = &'a dyn Fn(&SourceLoc) -> JsonValue;
+
+impl Literal {
+  /// Name of the literal variant, used as `"kind"` in JSON
+  pub fn kind_name(&self) -> &'static str {
+    match self {
+      Literal::Integer(_) => "Integer",
+      Literal::Float(_) => "Float",
+      Literal::Atom(_) => "Atom",
+      Literal::Character(_) => "Character",
+      Literal::EscapedCharacter { .. } => "EscapedCharacter",
+      Literal::Bool(_) => "Bool",
+      Literal::List { .. } => "List",
+      Literal::Nil => "Nil",
+      Literal::EmptyBinary => "EmptyBinary",
+      Literal::String(_) => "String",
+      Literal::BinaryString(_) => "BinaryString",
+      Literal::Tuple(_) => "Tuple",
+      Literal::Map(_) => "Map",
+      Literal::Binary(_) => "Binary",
+    }
+  }
+
+  /// Literal as JSON: the kind, and the value printed in Erlang syntax. Integers are numbers,
+  /// big integers are written in full.
+  pub fn to_json(&self) -> JsonValue {
+    let value = match self {
+      Literal::Integer(i) => JsonValue::number(i.to_bigint()),
+      _ => JsonValue::string(self),
+    };
+    JsonValue::object(vec![
+      ("kind", JsonValue::string(self.kind_name())),
+      ("value", value),
+    ])
+  }
+}
+
+fn nodes_json(nodes: &[AstNode], span: SpanConverter) -> JsonValue {
+  JsonValue::Array(nodes.iter().map(|n| n.to_json(span)).collect())
+}
+
+fn optional_json(node: &Option<AstNode>, span: SpanConverter) -> JsonValue {
+  JsonValue::optional(node.as_ref(), |n| n.to_json(span))
+}
+
+fn fn_clause_json(clause: &ErlFnClause, span: SpanConverter) -> JsonValue {
+  JsonValue::object(vec![
+    ("name", JsonValue::optional(clause.name.as_ref(), JsonValue::string)),
+    ("args", nodes_json(&clause.args, span)),
+    ("guard_expr", optional_json(&clause.guard_expr, span)),
+    ("body", clause.body.to_json(span)),
+  ])
+}
+
+fn case_clause_json(clause: &ErlCaseClause, span: SpanConverter) -> JsonValue {
+  JsonValue::object(vec![
+    ("pattern", clause.pattern.to_json(span)),
+    ("guard", optional_json(&clause.guard, span)),
+    ("body", clause.body.to_json(span)),
+  ])
+}
+
+fn catch_clause_json(clause: &CatchClause, span: SpanConverter) -> JsonValue {
+  JsonValue::object(vec![
+    ("class", clause.exc_pattern.class.to_json(span)),
+    ("error", clause.exc_pattern.error.to_json(span)),
+    ("stack", optional_json(&clause.exc_pattern.stack, span)),
+    ("when_guard", optional_json(&clause.when_guard, span)),
+    ("body", clause.body.to_json(span)),
+  ])
+}
+
+fn binary_element_json(element: &BinaryElement, span: SpanConverter) -> JsonValue {
+  let width = match &element.width {
+    ValueWidth::Literal(w) => JsonValue::number(w),
+    ValueWidth::Expr(expr) => expr.to_json(span),
+    ValueWidth::DefaultWidth => JsonValue::Null,
+  };
+  JsonValue::object(vec![
+    ("span", span(&element.location)),
+    ("value", element.value.to_json(span)),
+    ("width", width),
+    (
+      "type_specs",
+      JsonValue::Array(element.type_specs.iter().map(JsonValue::string).collect()),
+    ),
+  ])
+}
+
+fn callable_target_json(target: &CallableTarget, span: SpanConverter) -> JsonValue {
+  match target {
+    CallableTarget::Expr(expr) => JsonValue::object(vec![
+      ("kind", JsonValue::string("Expr")),
+      ("expr", expr.to_json(span)),
+    ]),
+    CallableTarget::MFArity(mfa) => JsonValue::object(vec![
+      ("kind", JsonValue::string("MFArity")),
+      ("mfa", JsonValue::string(mfa)),
+    ]),
+    CallableTarget::MFAExpression { module, function, arity } => JsonValue::object(vec![
+      ("kind", JsonValue::string("MFAExpression")),
+      ("module", optional_json(module, span)),
+      ("function", function.to_json(span)),
+      ("arity", JsonValue::number(arity)),
+    ]),
+  }
+}
+
+impl AstNodeType {
+  /// Name of the variant, used as `"type"` in JSON
+  pub fn variant_name(&self) -> &'static str {
+    match self {
+      AstNodeType::Empty { .. } => "Empty",
+      AstNodeType::ModuleForms { .. } => "ModuleForms",
+      AstNodeType::FnDef(_) => "FnDef",
+      AstNodeType::FnRef { .. } => "FnRef",
+      AstNodeType::FnRefDynamic { .. } => "FnRefDynamic",
+      AstNodeType::Type { .. } => "Type",
+      AstNodeType::CClause(..) => "CClause",
+      AstNodeType::MFA { .. } => "MFA",
+      AstNodeType::Var(_) => "Var",
+      AstNodeType::Apply(_) => "Apply",
+      AstNodeType::CaseExpr { .. } => "CaseExpr",
+      AstNodeType::Lit { .. } => "Lit",
+      AstNodeType::BinaryOp { .. } => "BinaryOp",
+      AstNodeType::UnaryOp { .. } => "UnaryOp",
+      AstNodeType::List { .. } => "List",
+      AstNodeType::Tuple { .. } => "Tuple",
+      AstNodeType::MapBuilder { .. } => "MapBuilder",
+      AstNodeType::RecordBuilder { .. } => "RecordBuilder",
+      AstNodeType::RecordField { .. } => "RecordField",
+      AstNodeType::CommaExpr { .. } => "CommaExpr",
+      AstNodeType::ListComprehension { .. } => "ListComprehension",
+      AstNodeType::BinaryComprehension { .. } => "BinaryComprehension",
+      AstNodeType::MapComprehension { .. } => "MapComprehension",
+      AstNodeType::ListComprehensionGenerator { .. } => "ListComprehensionGenerator",
+      AstNodeType::ZipGenerator { .. } => "ZipGenerator",
+      AstNodeType::TryCatch { .. } => "TryCatch",
+      AstNodeType::IfStatement { .. } => "IfStatement",
+      AstNodeType::BeginEnd { .. } => "BeginEnd",
+      AstNodeType::BinaryExpr { .. } => "BinaryExpr",
+    }
+  }
+}
+
+impl AstNodeImpl {
+  /// Convert the node and its children to JSON, `span` converts the node locations
+  pub fn to_json(&self, span: SpanConverter) -> JsonValue {
+    let mut members = vec![
+      ("type", JsonValue::string(self.content.variant_name())),
+      ("span", span(&self.location)),
+    ];
+    let fields = match &self.content {
+      AstNodeType::Empty { comment } => vec![("comment", JsonValue::string(comment))],
+      AstNodeType::ModuleForms { forms } => vec![("forms", nodes_json(forms, span))],
+      AstNodeType::FnDef(fn_def) => vec![
+        ("funarity", JsonValue::string(&fn_def.funarity)),
+        (
+          "clauses",
+          JsonValue::Array(
+            fn_def
+              .clauses
+              .iter()
+              .map(|c| fn_clause_json(c, span))
+              .collect(),
+          ),
+        ),
+      ],
+      AstNodeType::FnRef { mfa } => vec![("mfa", JsonValue::string(mfa))],
+      AstNodeType::FnRefDynamic { module, function, arity } => vec![
+        ("module", module.to_json(span)),
+        ("function", function.to_json(span)),
+        ("arity", arity.to_json(span)),
+      ],
+      AstNodeType::Type { ty } => vec![("ty", JsonValue::string(ty))],
+      AstNodeType::CClause(_, clause) => vec![("clause", case_clause_json(clause, span))],
+      AstNodeType::MFA { mfarity } => vec![("mfarity", JsonValue::string(mfarity))],
+      AstNodeType::Var(var) => vec![("name", JsonValue::string(&var.name))],
+      AstNodeType::Apply(apply) => vec![
+        ("target", callable_target_json(&apply.target, span)),
+        ("args", nodes_json(&apply.args, span)),
+      ],
+      AstNodeType::CaseExpr { expr, clauses } => vec![
+        ("expr", expr.to_json(span)),
+        (
+          "clauses",
+          JsonValue::Array(clauses.iter().map(|c| case_clause_json(c, span)).collect()),
+        ),
+      ],
+      AstNodeType::Lit { value } => vec![("value", value.to_json())],
+      AstNodeType::BinaryOp { binop_expr } => vec![
+        ("operator", JsonValue::string(binop_expr.operator)),
+        ("left", binop_expr.left.to_json(span)),
+        ("right", binop_expr.right.to_json(span)),
+      ],
+      AstNodeType::UnaryOp { unop_expr } => vec![
+        ("operator", JsonValue::string(&unop_expr.operator)),
+        ("expr", unop_expr.expr.to_json(span)),
+      ],
+      AstNodeType::List { elements, tail } => vec![
+        ("elements", nodes_json(elements, span)),
+        ("tail", optional_json(tail, span)),
+      ],
+      AstNodeType::Tuple { elements } => vec![("elements", nodes_json(elements, span))],
+      AstNodeType::MapBuilder { base, members } => vec![
+        ("base", optional_json(base, span)),
+        (
+          "members",
+          JsonValue::Array(
+            members
+              .iter()
+              .map(|m| {
+                let operation = match m.operation {
+                  MapMemberOperation::Assign => "=>",
+                  MapMemberOperation::Match => ":=",
+                };
+                JsonValue::object(vec![
+                  ("operation", JsonValue::string(operation)),
+                  ("key", m.key.to_json(span)),
+                  ("expr", m.expr.to_json(span)),
+                ])
+              })
+              .collect(),
+          ),
+        ),
+      ],
+      AstNodeType::RecordBuilder { base, tag, members } => vec![
+        ("base", optional_json(base, span)),
+        ("tag", JsonValue::string(tag)),
+        (
+          "members",
+          JsonValue::Array(
+            members
+              .iter()
+              .map(|m| {
+                JsonValue::object(vec![
+                  ("field", JsonValue::string(&m.field)),
+                  ("expr", m.expr.to_json(span)),
+                ])
+              })
+              .collect(),
+          ),
+        ),
+      ],
+      AstNodeType::RecordField { base, tag, field } => vec![
+        ("base", optional_json(base, span)),
+        ("tag", JsonValue::string(tag)),
+        ("field", JsonValue::string(field)),
+      ],
+      AstNodeType::CommaExpr { elements } => vec![("elements", nodes_json(elements, span))],
+      AstNodeType::ListComprehension { expr, generators }
+      | AstNodeType::BinaryComprehension { expr, generators } => vec![
+        ("expr", expr.to_json(span)),
+        ("generators", nodes_json(generators, span)),
+      ],
+      AstNodeType::MapComprehension { key, value, generators } => vec![
+        ("key", key.to_json(span)),
+        ("value", value.to_json(span)),
+        ("generators", nodes_json(generators, span)),
+      ],
+      AstNodeType::ListComprehensionGenerator { left, map_value, right, kind } => vec![
+        ("kind", JsonValue::string(kind)),
+        ("left", left.to_json(span)),
+        ("map_value", optional_json(map_value, span)),
+        ("right", right.to_json(span)),
+      ],
+      AstNodeType::ZipGenerator { generators } => {
+        vec![("generators", nodes_json(generators, span))]
+      }
+      AstNodeType::TryCatch { body, of_branches, catch_clauses } => vec![
+        ("body", body.to_json(span)),
+        (
+          "of_branches",
+          JsonValue::optional(of_branches.as_ref(), |branches| {
+            JsonValue::Array(branches.iter().map(|c| case_clause_json(c, span)).collect())
+          }),
+        ),
+        (
+          "catch_clauses",
+          JsonValue::Array(
+            catch_clauses
+              .iter()
+              .map(|c| catch_clause_json(c, span))
+              .collect(),
+          ),
+        ),
+      ],
+      AstNodeType::IfStatement { clauses } => vec![(
+        "clauses",
+        JsonValue::Array(
+          clauses
+            .iter()
+            .map(|c| {
+              JsonValue::object(vec![
+                ("cond", c.cond.to_json(span)),
+                ("body", c.body.to_json(span)),
+              ])
+            })
+            .collect(),
+        ),
+      )],
+      AstNodeType::BeginEnd { exprs } => vec![("exprs", nodes_json(exprs, span))],
+      AstNodeType::BinaryExpr { elements } => vec![(
+        "elements",
+        JsonValue::Array(
+          elements
+            .iter()
+            .map(|e| binary_element_json(e, span))
+            .collect(),
+        ),
+      )],
+    };
+    members.extend(fields);
+    JsonValue::object(members)
+  }
+}
@@ -2,8 +2,9 @@
 
 use crate::erl_syntax::erl_ast::node_impl::AstNodeType::{
   Apply, BeginEnd, BinaryComprehension, BinaryExpr, BinaryOp, CaseExpr, CommaExpr, Empty, FnDef,
-  FnRef, IfStatement, List, ListComprehension, ListComprehensionGenerator, Lit, MapBuilder,
-  ModuleForms, RecordBuilder, RecordField, TryCatch, Tuple, Var,
+  FnRef, FnRefDynamic, IfStatement, List, ListComprehension, ListComprehensionGenerator, Lit,
+  MapBuilder, MapComprehension, ModuleForms, RecordBuilder, RecordField, TryCatch, Tuple, Var,
+  ZipGenerator,
 };
 use crate::erl_syntax::erl_ast::node_impl::{AstNodeImpl, AstNodeType};
 use crate::erl_syntax::erl_ast::AstNode;
@@ -16,6 +17,7 @@ use crate::erl_syntax::node::erl_case_clause::ErlCaseClause;
 use crate::erl_syntax::node::erl_catch_clause::CatchClause;
 use crate::erl_syntax::node::erl_fn_clause::ErlFnClause;
 use crate::erl_syntax::node::erl_fn_def::ErlFnDef;
+use crate::erl_syntax::node::erl_generator::GeneratorKind;
 use crate::erl_syntax::node::erl_if_clause::ErlIfClause;
 use crate::erl_syntax::node::erl_map::MapBuilderMember;
 use crate::erl_syntax::node::erl_record::RecordBuilderMember;
@@ -80,6 +82,11 @@ impl AstNodeImpl {
     AstNodeImpl::construct_with_location(location, binop_node)
   }
 
+  /// Create a new literal AST node of any literal value
+  pub(crate) fn new_lit(location: SourceLoc, value: Literal) -> AstNode {
+    AstNodeImpl::construct_with_location(location, Lit { value: value.into() })
+  }
+
   /// Create a new literal AST node of an integer
   #[allow(dead_code)]
   pub(crate) fn new_lit_int(location: SourceLoc, val: ErlInteger) -> AstNode {
@@ -146,8 +153,7 @@ impl AstNodeImpl {
     elements: Vec<AstNode>,
     tail: Option<AstNode>,
   ) -> AstNode {
-    // TODO: Constant folding, detect list to be a literal list and fold it into a literal node
-    // Use Self::walk_litexpr
+    // Lists of literals are folded into a literal node later, by `ConstFolder`
     AstNodeImpl::construct_with_location(location, List { elements, tail })
   }
 
@@ -158,8 +164,7 @@ impl AstNodeImpl {
 
   /// Create a new AST node for a tuple of some expressions
   pub(crate) fn new_tuple(location: SourceLoc, elements: Vec<AstNode>) -> AstNode {
-    // TODO: Constant folding, detect list to be a literal list and fold it into a literal node
-    // Use Self::walk_litexpr
+    // Tuples of literals are folded into a literal node later, by `ConstFolder`
     AstNodeImpl::construct_with_location(location, Tuple { elements })
   }
 
@@ -221,16 +226,49 @@ impl AstNodeImpl {
     AstNodeImpl::construct_with_location(location, lc_node)
   }
 
-  /// Create a new AST node for a list comprehension generator `Expr <- Expr`
+  /// Create a new AST node for a map comprehension `#{K => V || generators}`
+  pub(crate) fn new_map_comprehension(
+    location: SourceLoc,
+    key: AstNode,
+    value: AstNode,
+    generators: Vec<AstNode>,
+  ) -> AstNode {
+    let mc_node = MapComprehension { key, value, generators };
+    AstNodeImpl::construct_with_location(location, mc_node)
+  }
+
+  /// Create a new AST node for a list comprehension generator `Expr <- Expr`, or other arrows
   pub(crate) fn new_list_comprehension_generator(
     location: SourceLoc,
     left: AstNode,
+    kind: GeneratorKind,
+    right: AstNode,
+  ) -> AstNode {
+    let lc_node = ListComprehensionGenerator { left, map_value: None, right, kind };
+    AstNodeImpl::construct_with_location(location, lc_node)
+  }
+
+  /// Create a new AST node for a map generator `K := V <- Expr` or `K := V <:- Expr`
+  pub(crate) fn new_map_generator(
+    location: SourceLoc,
+    key: AstNode,
+    value: AstNode,
+    kind: GeneratorKind,
     right: AstNode,
   ) -> AstNode {
-    let lc_node = ListComprehensionGenerator { left, right };
+    let lc_node = ListComprehensionGenerator { left: key, map_value: Some(value), right, kind };
     AstNodeImpl::construct_with_location(location, lc_node)
   }
 
+  /// Create a new zip generator `Gen1 && Gen2 && ...`, or returns the only generator
+  pub(crate) fn new_zip_generator(location: SourceLoc, mut generators: Vec<AstNode>) -> AstNode {
+    match generators.len() {
+      0 => panic!("Empty generators when creating a ErlAst::ZipGenerator"),
+      1 => generators.pop().unwrap(),
+      _ => AstNodeImpl::construct_with_location(location, ZipGenerator { generators }),
+    }
+  }
+
   // /// Takes preprocessor group nodes and unfolds them into flat list
   // fn flatten_forms(forms: Vec<AstNode>) -> Vec<AstNode> {
   //   let mut result = Vec::new();
@@ -260,6 +298,16 @@ impl AstNodeImpl {
     AstNodeImpl::construct_with_location(location, FnRef { mfa })
   }
 
+  /// Create a new `fun M:F/A` with some parts being variables
+  pub(crate) fn new_fn_ref_dynamic(
+    location: SourceLoc,
+    module: AstNode,
+    function: AstNode,
+    arity: AstNode,
+  ) -> AstNode {
+    AstNodeImpl::construct_with_location(location, FnRefDynamic { module, function, arity })
+  }
+
   /// Create a new try-catch AST node
   pub(crate) fn new_try_catch(
     location: SourceLoc,
@@ -296,8 +344,8 @@ impl AstNodeImpl {
     funarity: MFArity,
     clauses: Vec<ErlFnClause>,
   ) -> AstNode {
-    let fndef = ErlFnDef { location, funarity, clauses };
-    AstNodeImpl::construct_without_location(FnDef(fndef))
+    let fndef = ErlFnDef { location: location.clone(), funarity, clauses };
+    AstNodeImpl::construct_with_location(location, FnDef(fndef))
   }
 
   /// Create a new binary expression
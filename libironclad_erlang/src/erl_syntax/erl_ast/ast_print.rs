@@ -2,8 +2,9 @@
 
 use crate::erl_syntax::erl_ast::node_impl::AstNodeType::{
   Apply, BeginEnd, BinaryComprehension, BinaryExpr, BinaryOp, CClause, CaseExpr, CommaExpr, FnDef,
-  FnRef, IfStatement, List, ListComprehension, ListComprehensionGenerator, Lit, MapBuilder,
-  ModuleForms, RecordBuilder, RecordField, TryCatch, Tuple, Type, UnaryOp, Var, MFA,
+  FnRef, FnRefDynamic, IfStatement, List, ListComprehension, ListComprehensionGenerator, Lit,
+  MapBuilder, MapComprehension, ModuleForms, RecordBuilder, RecordField, TryCatch, Tuple, Type,
+  UnaryOp, Var, ZipGenerator, MFA,
 };
 use crate::erl_syntax::erl_ast::node_impl::{AstNodeImpl, AstNodeType};
 use crate::erl_syntax::erl_op::{ErlBinaryOp, ErlUnaryOp};
@@ -27,6 +28,9 @@ impl std::fmt::Display for AstNodeImpl {
         writeln!(f, "%%% end module",)
       }
       FnRef { mfa, .. } => write!(f, "fun {}", mfa),
+      FnRefDynamic { module, function, arity } => {
+        write!(f, "fun {}:{}/{}", module, function, arity)
+      }
       FnDef(erl_fndef) => {
         write!(f, "def-fun {} {{", erl_fndef.funarity.name).unwrap();
         for fc in erl_fndef.clauses.iter() {
@@ -100,9 +104,18 @@ impl std::fmt::Display for AstNodeImpl {
         Pretty::display_comma_separated(generators.iter(), f).unwrap();
         write!(f, ">>")
       }
-      ListComprehensionGenerator { left, right, .. } => {
-        write!(f, "{} <- {}", left, right)
+      MapComprehension { key, value, generators } => {
+        write!(f, "#{{{} => {} || ", key, value).unwrap();
+        Pretty::display_comma_separated(generators.iter(), f).unwrap();
+        write!(f, "}}")
+      }
+      ListComprehensionGenerator { left, map_value: Some(value), right, kind } => {
+        write!(f, "{} := {} {} {}", left, value, kind, right)
+      }
+      ListComprehensionGenerator { left, right, kind, .. } => {
+        write!(f, "{} {} {}", left, kind, right)
       }
+      ZipGenerator { generators } => Pretty::display_separated(generators.iter(), " && ", f),
       TryCatch { body, of_branches, catch_clauses, .. } => {
         write!(f, "try {}", body).unwrap();
         if let Some(ofb) = of_branches {
@@ -136,7 +149,23 @@ impl std::fmt::Display for Literal {
       Literal::List { elements, .. } => Pretty::display_square_list(elements.iter(), f),
       Literal::Nil => write!(f, "[]"),
       Literal::String(s) => write!(f, "\"{}\"", s),
+      Literal::BinaryString(s) => write!(f, "<<\"{}\">>", s),
       Literal::Tuple(t) => Pretty::display_curly_list(t.iter(), f),
+      Literal::Map(members) => {
+        write!(f, "#{{")?;
+        for (i, (k, v)) in members.iter().enumerate() {
+          if i > 0 {
+            write!(f, ", ")?;
+          }
+          write!(f, "{} => {}", k, v)?;
+        }
+        write!(f, "}}")
+      }
+      Literal::Binary(bytes) => {
+        write!(f, "<<")?;
+        Pretty::display_comma_separated(bytes.iter(), f)?;
+        write!(f, ">>")
+      }
       Literal::Character(c) => write!(f, "${}", c),
       Literal::EscapedCharacter { in_source, .. } => write!(f, "$\\{}", in_source),
     }
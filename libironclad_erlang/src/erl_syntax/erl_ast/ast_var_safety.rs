@@ -0,0 +1,639 @@
+//! Variable safety analysis over function clauses, following the erlc rules:
+//! * A variable must be bound before it is used, otherwise it is unbound (error).
+//! * A variable bound in some, but not all branches of `case`, `if` or `try`, or on the right side
+//!   of `andalso` and `orelse`, is unsafe to use after the construct (error). Variables bound in
+//!   `try` are always unsafe after it, because any part of it can be skipped by an exception.
+//! * A bound variable which is never used is reported (warning), unless its name starts with `_`.
+//!   Using a variable with a name starting with `_` is reported too (warning).
+//! * A fun head or a generator pattern always binds new variables, a variable with the same name in
+//!   the enclosing scope is shadowed (warning).
+//!
+//! The warnings are disabled with the `nowarn_unused_vars` and `nowarn_shadow_vars` options.
+
+use crate::erl_syntax::erl_ast::ast_visitor::{walk_node, Visitor};
+use crate::erl_syntax::erl_ast::node_impl::{AstNodeImpl, AstNodeType};
+use crate::erl_syntax::erl_ast::AstNode;
+use crate::erl_syntax::erl_op::ErlBinaryOp;
+use crate::erl_syntax::node::erl_binary_element::{BinaryElement, ValueWidth};
+use crate::erl_syntax::node::erl_callable_target::CallableTarget;
+use crate::erl_syntax::node::erl_case_clause::ErlCaseClause;
+use crate::erl_syntax::node::erl_catch_clause::CatchClause;
+use crate::erl_syntax::node::erl_fn_clause::ErlFnClause;
+use crate::erl_syntax::node::erl_fn_def::ErlFnDef;
+use crate::erl_syntax::node::erl_var::ErlVar;
+use crate::error::ic_error::IroncladError;
+use crate::error::ic_error_trait::GenericIroncladError;
+use crate::project::compiler_opts::CompilerOpts;
+use crate::source_loc::SourceLoc;
+use libironclad_util::mfarity::MFArity;
+use std::collections::{HashMap, HashSet};
+
+/// A variable binding, the same name can have several bindings, one per branch of a `case`
+struct BoundVar {
+  name: String,
+  location: SourceLoc,
+  used: bool,
+}
+
+/// Variables visible at a point of a function clause. Values are indexes of the bindings in
+/// `VarSafetyCheck::vars`, a use of the variable marks all of them as used.
+#[derive(Clone, Default)]
+struct VarTable {
+  /// Variables which are safe to use
+  bound: HashMap<String, Vec<usize>>,
+  /// Variables bound in some branches only, with the name of the construct
+  unsafe_in: HashMap<String, (&'static str, Vec<usize>)>,
+}
+
+impl VarTable {
+  /// Combine the tables after each branch of a construct. The variables bound in every branch are
+  /// bound after the construct, the variables bound in some branches are unsafe.
+  fn merge_branches(before: &VarTable, branches: &[VarTable], construct: &'static str) -> Self {
+    let mut names: Vec<&String> = branches
+      .iter()
+      .flat_map(|b| b.bound.keys().chain(b.unsafe_in.keys()))
+      .filter(|name| !before.bound.contains_key(*name))
+      .collect();
+    names.sort();
+    names.dedup();
+
+    let mut result = before.clone();
+    for name in names {
+      let mut ids = Vec::new();
+      let mut in_all_branches = true;
+      let mut unsafe_construct = construct;
+      for branch in branches.iter() {
+        if let Some(branch_ids) = branch.bound.get(name) {
+          ids.extend(branch_ids.iter().copied());
+        } else {
+          in_all_branches = false;
+        }
+        if let Some((inner_construct, branch_ids)) = branch.unsafe_in.get(name) {
+          ids.extend(branch_ids.iter().copied());
+          unsafe_construct = inner_construct;
+          in_all_branches = false;
+        }
+      }
+      ids.sort_unstable();
+      ids.dedup();
+      if in_all_branches {
+        result.unsafe_in.remove(name);
+        result.bound.insert(name.clone(), ids);
+      } else {
+        result
+          .unsafe_in
+          .insert(name.clone(), (unsafe_construct, ids));
+      }
+    }
+    result
+  }
+
+  /// Forget the variables, they are shadowed by the new bindings
+  fn remove(&mut self, name: &str) {
+    self.bound.remove(name);
+    self.unsafe_in.remove(name);
+  }
+
+  fn contains(&self, name: &str) -> bool {
+    self.bound.contains_key(name) || self.unsafe_in.contains_key(name)
+  }
+}
+
+/// Checks the variables in all functions of a module, see the module description. Errors and
+/// warnings are collected for the caller to report.
+pub struct VarSafetyCheck {
+  options: CompilerOpts,
+  /// The function being checked, warnings are enabled and disabled per function
+  funarity: MFArity,
+  /// All bindings in the current function clause
+  vars: Vec<BoundVar>,
+  /// Unbound and unsafe variables
+  pub errors: Vec<GenericIroncladError>,
+  /// Unused and shadowed variables
+  pub warnings: Vec<GenericIroncladError>,
+}
+
+impl VarSafetyCheck {
+  /// Create a checker which uses the compiler options to disable the warnings
+  pub fn new(options: CompilerOpts) -> Self {
+    Self {
+      options,
+      funarity: MFArity::new_local("", 0),
+      vars: Vec::new(),
+      errors: Vec::new(),
+      warnings: Vec::new(),
+    }
+  }
+
+  /// Check every function in the module forms
+  pub fn check_module_forms(&mut self, ast: &AstNode) {
+    if let AstNodeType::ModuleForms { forms } = &ast.content {
+      for form in forms.iter() {
+        if let AstNodeType::FnDef(fn_def) = &form.content {
+          self.check_fn_def(fn_def);
+        }
+      }
+    }
+  }
+
+  fn check_fn_def(&mut self, fn_def: &ErlFnDef) {
+    self.funarity = fn_def.funarity.clone();
+    for clause in fn_def.clauses.iter() {
+      let mut table = VarTable::default();
+      self.check_fn_clause_body(clause, &mut table);
+    }
+  }
+
+  /// Bind the arguments, check the guard and the body, then report the unused variables of the
+  /// clause
+  fn check_fn_clause_body(&mut self, clause: &ErlFnClause, table: &mut VarTable) {
+    let start = self.vars.len();
+    for arg in clause.args.iter() {
+      self.check_pattern(arg, table);
+    }
+    if let Some(guard) = &clause.guard_expr {
+      self.check_expr(guard, table);
+    }
+    self.check_expr(&clause.body, table);
+    self.report_unused(start);
+  }
+
+  /// A lambda sees the variables of the enclosing clause, but the variables in its head are new
+  /// bindings, and nothing bound inside of it is visible outside
+  fn check_lambda(&mut self, node: &AstNode, fn_def: &ErlFnDef, table: &VarTable) {
+    for clause in fn_def.clauses.iter() {
+      let mut clause_table = table.clone();
+      // The clause scope has the variables of the fun head, as extracted by the parser, and the
+      // name of a named fun, which is checked separately
+      let head_vars: Vec<String> = clause
+        .scope
+        .variables
+        .collection
+        .read()
+        .unwrap()
+        .keys()
+        .filter(|name| clause.name.as_ref() != Some(*name))
+        .cloned()
+        .collect();
+      if let Some(fun_name) = &clause.name {
+        let name_loc = [(fun_name.clone(), node.location.clone())];
+        self.shadow(&name_loc, &mut clause_table, "'named fun'");
+        // A named fun can refer to itself, this does not count as a use
+        clause_table
+          .bound
+          .insert(fun_name.clone(), vec![self.vars.len()]);
+        self.vars.push(BoundVar {
+          name: fun_name.clone(),
+          location: node.location.clone(),
+          used: true,
+        });
+      }
+      let head_args: Vec<&AstNode> = clause.args.iter().collect();
+      let head_vars = with_locations(head_vars, &head_args, &node.location);
+      self.shadow(&head_vars, &mut clause_table, "'fun'");
+      self.check_fn_clause_body(clause, &mut clause_table);
+    }
+  }
+
+  /// Comprehension generators bind new variables, which are not visible after the comprehension
+  fn check_comprehension(
+    &mut self,
+    generators: &[AstNode],
+    results: &[&AstNode],
+    table: &VarTable,
+  ) {
+    let mut comp_table = table.clone();
+    let start = self.vars.len();
+    for generator in generators.iter() {
+      self.check_generator(generator, &mut comp_table);
+    }
+    for result in results.iter() {
+      self.check_expr(result, &mut comp_table);
+    }
+    self.report_unused(start);
+  }
+
+  /// A generator or a filter of a comprehension
+  fn check_generator(&mut self, generator: &AstNode, table: &mut VarTable) {
+    match &generator.content {
+      AstNodeType::ListComprehensionGenerator { right, .. } => {
+        self.check_expr(right, table);
+        self.bind_generator_pattern(generator, table);
+      }
+      AstNodeType::ZipGenerator { generators } => {
+        // All inputs are evaluated before any pattern is matched
+        for g in generators.iter() {
+          if let AstNodeType::ListComprehensionGenerator { right, .. } = &g.content {
+            self.check_expr(right, table);
+          }
+        }
+        for g in generators.iter() {
+          self.bind_generator_pattern(g, table);
+        }
+      }
+      _ => self.check_expr(generator, table),
+    }
+  }
+
+  fn bind_generator_pattern(&mut self, generator: &AstNode, table: &mut VarTable) {
+    if let AstNodeType::ListComprehensionGenerator { left, map_value, .. } = &generator.content {
+      let mut variables = HashMap::new();
+      let _ = AstNodeImpl::extract_variables(left, &mut variables);
+      if let Some(value) = map_value {
+        let _ = AstNodeImpl::extract_variables(value, &mut variables);
+      }
+      let mut patterns = vec![left];
+      patterns.extend(map_value.iter());
+      let names = with_locations(variables.into_keys().collect(), &patterns, &generator.location);
+      self.shadow(&names, table, "generate");
+
+      self.check_pattern(left, table);
+      if let Some(value) = map_value {
+        self.check_pattern(value, table);
+      }
+    }
+  }
+
+  /// Clauses of `case` or the `of` part of `try`, each starts with the same variables
+  fn check_case_clauses(&mut self, clauses: &[ErlCaseClause], table: &VarTable) -> Vec<VarTable> {
+    clauses
+      .iter()
+      .map(|clause| {
+        let mut clause_table = table.clone();
+        self.check_pattern(&clause.pattern, &mut clause_table);
+        if let Some(guard) = &clause.guard {
+          self.check_expr(guard, &mut clause_table);
+        }
+        self.check_expr(&clause.body, &mut clause_table);
+        clause_table
+      })
+      .collect()
+  }
+
+  fn check_catch_clause(&mut self, clause: &CatchClause, table: &mut VarTable) {
+    self.check_pattern(&clause.exc_pattern.class, table);
+    self.check_pattern(&clause.exc_pattern.error, table);
+    if let Some(stack) = &clause.exc_pattern.stack {
+      self.check_pattern(stack, table);
+    }
+    if let Some(guard) = &clause.when_guard {
+      self.check_expr(guard, table);
+    }
+    self.check_expr(&clause.body, table);
+  }
+
+  fn check_try(
+    &mut self,
+    body: &AstNode,
+    of_branches: &Option<Vec<ErlCaseClause>>,
+    catch_clauses: &[CatchClause],
+    table: &mut VarTable,
+  ) {
+    let before = table.clone();
+    let mut after_body = table.clone();
+    self.check_expr(body, &mut after_body);
+
+    let mut branches = match of_branches {
+      Some(clauses) => self.check_case_clauses(clauses, &after_body),
+      None => vec![after_body.clone()],
+    };
+    // The catch clauses run when the body has failed, its variables are unsafe there
+    let catch_table = VarTable::merge_branches(&before, &[after_body, before.clone()], "try");
+    for clause in catch_clauses.iter() {
+      let mut clause_table = catch_table.clone();
+      self.check_catch_clause(clause, &mut clause_table);
+      branches.push(clause_table);
+    }
+    // Merging with the table before the `try` makes every new variable unsafe
+    branches.push(before.clone());
+    *table = VarTable::merge_branches(&before, &branches, "try");
+  }
+
+  fn check_binary_elements(
+    &mut self,
+    elements: &[BinaryElement],
+    table: &mut VarTable,
+    is_pattern: bool,
+  ) {
+    for element in elements.iter() {
+      if is_pattern {
+        self.check_pattern(&element.value, table);
+      } else {
+        self.check_expr(&element.value, table);
+      }
+      // The width is an expression, in a pattern it uses the variables bound before it
+      if let ValueWidth::Expr(width) = &element.width {
+        self.check_expr(width, table);
+      }
+    }
+  }
+
+  fn check_exprs(&mut self, nodes: &[AstNode], table: &mut VarTable) {
+    for node in nodes.iter() {
+      self.check_expr(node, table);
+    }
+  }
+
+  /// Check an expression, which uses variables and can bind new ones with `=`
+  fn check_expr(&mut self, node: &AstNode, table: &mut VarTable) {
+    match &node.content {
+      AstNodeType::Empty { .. }
+      | AstNodeType::Lit { .. }
+      | AstNodeType::Type { .. }
+      | AstNodeType::MFA { .. }
+      | AstNodeType::FnRef { .. } => {}
+      AstNodeType::ModuleForms { forms } => self.check_exprs(forms, table),
+      AstNodeType::FnDef(fn_def) => self.check_lambda(node, fn_def, table),
+      AstNodeType::FnRefDynamic { module, function, arity } => {
+        self.check_expr(module, table);
+        self.check_expr(function, table);
+        self.check_expr(arity, table);
+      }
+      AstNodeType::CClause(_, clause) => {
+        let branches = self.check_case_clauses(std::slice::from_ref(clause), table);
+        *table = VarTable::merge_branches(table, &branches, "case");
+      }
+      AstNodeType::Var(var) => self.use_var(&node.location, &var.name, table),
+      AstNodeType::Apply(apply) => {
+        match &apply.target {
+          CallableTarget::Expr(expr) => self.check_expr(expr, table),
+          CallableTarget::MFArity(_) => {}
+          CallableTarget::MFAExpression { module, function, .. } => {
+            if let Some(m) = module {
+              self.check_expr(m, table);
+            }
+            self.check_expr(function, table);
+          }
+        }
+        self.check_exprs(&apply.args, table);
+      }
+      AstNodeType::CaseExpr { expr, clauses } => {
+        self.check_expr(expr, table);
+        let branches = self.check_case_clauses(clauses, table);
+        *table = VarTable::merge_branches(table, &branches, "case");
+      }
+      AstNodeType::BinaryOp { binop_expr } => match binop_expr.operator {
+        ErlBinaryOp::Match => {
+          self.check_expr(&binop_expr.right, table);
+          self.check_pattern(&binop_expr.left, table);
+        }
+        ErlBinaryOp::AndAlso | ErlBinaryOp::OrElse => {
+          self.check_expr(&binop_expr.left, table);
+          // The right side might not be evaluated
+          let mut right_table = table.clone();
+          self.check_expr(&binop_expr.right, &mut right_table);
+          let construct =
+            if binop_expr.operator == ErlBinaryOp::AndAlso { "andalso" } else { "orelse" };
+          *table = VarTable::merge_branches(table, &[right_table, table.clone()], construct);
+        }
+        _ => {
+          self.check_expr(&binop_expr.left, table);
+          self.check_expr(&binop_expr.right, table);
+        }
+      },
+      AstNodeType::UnaryOp { unop_expr } => self.check_expr(&unop_expr.expr, table),
+      AstNodeType::List { elements, tail } => {
+        self.check_exprs(elements, table);
+        if let Some(t) = tail {
+          self.check_expr(t, table);
+        }
+      }
+      AstNodeType::Tuple { elements } => self.check_exprs(elements, table),
+      AstNodeType::MapBuilder { base, members } => {
+        if let Some(b) = base {
+          self.check_expr(b, table);
+        }
+        for member in members.iter() {
+          self.check_expr(&member.key, table);
+          self.check_expr(&member.expr, table);
+        }
+      }
+      AstNodeType::RecordBuilder { base, members, .. } => {
+        if let Some(b) = base {
+          self.check_expr(b, table);
+        }
+        for member in members.iter() {
+          self.check_expr(&member.expr, table);
+        }
+      }
+      AstNodeType::RecordField { base, .. } => {
+        if let Some(b) = base {
+          self.check_expr(b, table);
+        }
+      }
+      AstNodeType::CommaExpr { elements } => self.check_exprs(elements, table),
+      AstNodeType::BeginEnd { exprs } => self.check_exprs(exprs, table),
+      AstNodeType::ListComprehension { expr, generators }
+      | AstNodeType::BinaryComprehension { expr, generators } => {
+        self.check_comprehension(generators, &[expr], table)
+      }
+      AstNodeType::MapComprehension { key, value, generators } => {
+        self.check_comprehension(generators, &[key, value], table)
+      }
+      AstNodeType::ListComprehensionGenerator { .. } | AstNodeType::ZipGenerator { .. } => {
+        self.check_generator(node, table)
+      }
+      AstNodeType::TryCatch { body, of_branches, catch_clauses } => {
+        self.check_try(body, of_branches, catch_clauses, table)
+      }
+      AstNodeType::IfStatement { clauses } => {
+        let branches: Vec<VarTable> = clauses
+          .iter()
+          .map(|clause| {
+            let mut clause_table = table.clone();
+            self.check_expr(&clause.cond, &mut clause_table);
+            self.check_expr(&clause.body, &mut clause_table);
+            clause_table
+          })
+          .collect();
+        *table = VarTable::merge_branches(table, &branches, "if");
+      }
+      AstNodeType::BinaryExpr { elements } => self.check_binary_elements(elements, table, false),
+    }
+  }
+
+  /// Check a pattern: a new variable is bound, a variable which is already bound is compared.
+  /// Map keys, binary widths and operators other than `=` and `++` are expressions.
+  fn check_pattern(&mut self, node: &AstNode, table: &mut VarTable) {
+    match &node.content {
+      AstNodeType::Var(var) => self.bind_var(&node.location, &var.name, table),
+      AstNodeType::Empty { .. } | AstNodeType::Lit { .. } => {}
+      AstNodeType::List { elements, tail } => {
+        for element in elements.iter() {
+          self.check_pattern(element, table);
+        }
+        if let Some(t) = tail {
+          self.check_pattern(t, table);
+        }
+      }
+      AstNodeType::Tuple { elements } => {
+        for element in elements.iter() {
+          self.check_pattern(element, table);
+        }
+      }
+      AstNodeType::MapBuilder { base, members } => {
+        if let Some(b) = base {
+          self.check_expr(b, table);
+        }
+        for member in members.iter() {
+          self.check_expr(&member.key, table);
+          self.check_pattern(&member.expr, table);
+        }
+      }
+      AstNodeType::RecordBuilder { members, .. } => {
+        for member in members.iter() {
+          self.check_pattern(&member.expr, table);
+        }
+      }
+      AstNodeType::BinaryExpr { elements } => self.check_binary_elements(elements, table, true),
+      AstNodeType::BinaryOp { binop_expr } if binop_expr.operator == ErlBinaryOp::Match => {
+        self.check_pattern(&binop_expr.left, table);
+        self.check_pattern(&binop_expr.right, table);
+      }
+      AstNodeType::BinaryOp { binop_expr } if binop_expr.operator == ErlBinaryOp::ListAppend => {
+        // `"prefix" ++ Tail`, the prefix is a literal
+        self.check_expr(&binop_expr.left, table);
+        self.check_pattern(&binop_expr.right, table);
+      }
+      _ => self.check_expr(node, table),
+    }
+  }
+
+  /// A variable in a pattern, binds it or compares with the bound value
+  fn bind_var(&mut self, location: &SourceLoc, name: &str, table: &mut VarTable) {
+    if name == "_" {
+      return;
+    }
+    if let Some((construct, ids)) = table.unsafe_in.remove(name) {
+      self.report_unsafe(location, name, construct, &ids);
+    } else if let Some(ids) = table.bound.get(name) {
+      self.mark_used(ids);
+      return;
+    }
+    table.bound.insert(name.to_string(), vec![self.vars.len()]);
+    self.vars.push(BoundVar {
+      name: name.to_string(),
+      location: location.clone(),
+      used: false,
+    });
+  }
+
+  /// A variable in an expression, must be bound
+  fn use_var(&mut self, location: &SourceLoc, name: &str, table: &VarTable) {
+    if let Some((construct, ids)) = table.unsafe_in.get(name) {
+      let ids = ids.clone();
+      self.report_unsafe(location, name, construct, &ids);
+      return;
+    }
+    match table.bound.get(name) {
+      Some(ids) if name != "_" => {
+        let ids = ids.clone();
+        self.mark_used(&ids);
+        if name.starts_with('_') && self.is_warning_enabled("unused_vars") {
+          self.warnings.push(IroncladError::erlang_warning(
+            location.clone(),
+            format!("variable '{}' is used after being marked unused", name),
+          ));
+        }
+      }
+      _ => self.errors.push(IroncladError::erlang_error(
+        location.clone(),
+        format!("variable '{}' is unbound", name),
+      )),
+    }
+  }
+
+  fn mark_used(&mut self, ids: &[usize]) {
+    for id in ids.iter() {
+      self.vars[*id].used = true;
+    }
+  }
+
+  fn report_unsafe(&mut self, location: &SourceLoc, name: &str, construct: &str, ids: &[usize]) {
+    // The variable is used, even if it is an error, do not also report it as unused
+    self.mark_used(ids);
+    self.errors.push(IroncladError::erlang_error(
+      location.clone(),
+      format!("variable '{}' unsafe in '{}'", name, construct),
+    ));
+  }
+
+  /// New bindings in a fun head or a generator pattern hide the variables of the enclosing scope.
+  /// The warning is reported at the new binding. Like in erlc, the `construct` is quoted by the
+  /// caller if it is a keyword: `'fun'`, but `generate`.
+  fn shadow(&mut self, names: &[(String, SourceLoc)], table: &mut VarTable, construct: &str) {
+    for (name, location) in names.iter().filter(|(n, _)| n != "_") {
+      if !table.contains(name) {
+        continue;
+      }
+      table.remove(name);
+      if self.is_warning_enabled("shadow_vars") {
+        self.warnings.push(IroncladError::erlang_warning(
+          location.clone(),
+          format!("variable '{}' shadowed in {}", name, construct),
+        ));
+      }
+    }
+  }
+
+  /// Report the bindings made since `start` which were never used, and forget them. A variable
+  /// bound in several branches is reported once.
+  fn report_unused(&mut self, start: usize) {
+    let enabled = self.is_warning_enabled("unused_vars");
+    let mut reported = HashSet::new();
+    for var in self.vars.drain(start..) {
+      if !var.used && !var.name.starts_with('_') && enabled && reported.insert(var.name.clone()) {
+        self.warnings.push(IroncladError::erlang_warning(
+          var.location,
+          format!("variable '{}' is unused", var.name),
+        ));
+      }
+    }
+  }
+
+  fn is_warning_enabled(&self, warning: &str) -> bool {
+    self
+      .options
+      .is_warning_enabled(warning, Some(&self.funarity))
+  }
+}
+
+/// Collects the location of the first occurrence of every variable
+#[derive(Default)]
+struct VarLocations {
+  locations: HashMap<String, SourceLoc>,
+}
+
+impl Visitor for VarLocations {
+  fn visit_var(&mut self, node: &AstNode, var: &ErlVar) {
+    self
+      .locations
+      .entry(var.name.clone())
+      .or_insert_with(|| node.location.clone());
+  }
+}
+
+/// Pair the variable names, bound by the patterns, with their first locations in the patterns,
+/// sorted by name. A variable not found in the patterns gets the `fallback` location.
+fn with_locations(
+  mut names: Vec<String>,
+  patterns: &[&AstNode],
+  fallback: &SourceLoc,
+) -> Vec<(String, SourceLoc)> {
+  let mut visitor = VarLocations::default();
+  for pattern in patterns.iter() {
+    walk_node(&mut visitor, pattern);
+  }
+  names.sort();
+  names
+    .into_iter()
+    .map(|name| {
+      let location = visitor
+        .locations
+        .get(&name)
+        .cloned()
+        .unwrap_or_else(|| fallback.clone());
+      (name, location)
+    })
+    .collect()
+}
@@ -0,0 +1,403 @@
+//! Read-only traversal of the AST with a hook for every node type.
+//!
+//! Implement `Visitor` and override the hooks for the node types of interest, then call
+//! `walk_node` on the root. A hook which is overridden and still needs the children visited calls
+//! the matching `walk_*` function. The `walk_*` functions match every `AstNodeType` variant without
+//! a catch-all arm, so a new node type can't be added without deciding how it is traversed.
+
+use crate::erl_syntax::erl_ast::node_impl::AstNodeType;
+use crate::erl_syntax::erl_ast::AstNode;
+use crate::erl_syntax::node::erl_apply::ErlApply;
+use crate::erl_syntax::node::erl_binary_element::{BinaryElement, ValueWidth};
+use crate::erl_syntax::node::erl_binop::ErlBinaryOperatorExpr;
+use crate::erl_syntax::node::erl_callable_target::CallableTarget;
+use crate::erl_syntax::node::erl_case_clause::ErlCaseClause;
+use crate::erl_syntax::node::erl_catch_clause::CatchClause;
+use crate::erl_syntax::node::erl_fn_clause::ErlFnClause;
+use crate::erl_syntax::node::erl_fn_def::ErlFnDef;
+use crate::erl_syntax::node::erl_generator::GeneratorKind;
+use crate::erl_syntax::node::erl_if_clause::ErlIfClause;
+use crate::erl_syntax::node::erl_map::MapBuilderMember;
+use crate::erl_syntax::node::erl_record::RecordBuilderMember;
+use crate::erl_syntax::node::erl_unop::ErlUnaryOperatorExpr;
+use crate::erl_syntax::node::erl_var::ErlVar;
+use crate::literal::Literal;
+use crate::typing::erl_type::ErlType;
+use libironclad_util::mfarity::MFArity;
+
+/// A construct which introduces its own variable scope, the variables bound inside do not leak out
+#[derive(Debug, Clone, Copy)]
+pub enum AstScope<'a> {
+  /// A function or a lambda clause, the arguments are bound in the clause scope
+  FnClause(&'a ErlFnClause),
+  /// A list, binary or map comprehension node, the generator patterns are bound in its scope
+  Comprehension(&'a AstNode),
+}
+
+/// Read-only AST visitor. Every hook by default visits the children of the node, so an
+/// implementation only overrides the hooks it needs.
+/// Children are visited in the evaluation order: comprehension generators before the result
+/// expression, and generator input before the generator pattern.
+#[allow(unused_variables)]
+pub trait Visitor {
+  /// Pre-order hook, called for every node before its type-specific hook. Return `false` to skip
+  /// the node and its children, then `exit_node` is not called either.
+  fn enter_node(&mut self, node: &AstNode) -> bool {
+    true
+  }
+
+  /// Post-order hook, called for every node after its children were visited
+  fn exit_node(&mut self, node: &AstNode) {}
+
+  /// Called when entering a function clause or a comprehension
+  fn enter_scope(&mut self, scope: AstScope<'_>) {}
+
+  /// Called when leaving a function clause or a comprehension
+  fn exit_scope(&mut self, scope: AstScope<'_>) {}
+
+  /// `Empty` node, has no children
+  fn visit_empty(&mut self, node: &AstNode, comment: &str) {}
+
+  /// Module root with forms
+  fn visit_module_forms(&mut self, node: &AstNode, forms: &[AstNode]) {
+    walk_nodes(self, forms)
+  }
+
+  /// Function definition or a lambda
+  fn visit_fn_def(&mut self, node: &AstNode, fn_def: &ErlFnDef) {
+    walk_fn_def(self, fn_def)
+  }
+
+  /// A clause of a function or a lambda, called inside of the clause scope
+  fn visit_fn_clause(&mut self, clause: &ErlFnClause) {
+    walk_fn_clause(self, clause)
+  }
+
+  /// `fun f/1` or `fun m:f/1`, has no children
+  fn visit_fn_ref(&mut self, node: &AstNode, mfa: &MFArity) {}
+
+  /// `fun M:F/A` with non-literal parts
+  fn visit_fn_ref_dynamic(
+    &mut self,
+    node: &AstNode,
+    module: &AstNode,
+    function: &AstNode,
+    arity: &AstNode,
+  ) {
+    walk_node(self, module);
+    walk_node(self, function);
+    walk_node(self, arity);
+  }
+
+  /// A parsed type wrapped in a node, has no children
+  fn visit_type(&mut self, node: &AstNode, ty: &ErlType) {}
+
+  /// A function name with arity, has no children
+  fn visit_mfa(&mut self, node: &AstNode, mfarity: &MFArity) {}
+
+  /// A variable, has no children
+  fn visit_var(&mut self, node: &AstNode, var: &ErlVar) {}
+
+  /// Function call
+  fn visit_apply(&mut self, node: &AstNode, apply: &ErlApply) {
+    walk_apply(self, apply)
+  }
+
+  /// `case Expr of Clauses end`
+  fn visit_case_expr(&mut self, node: &AstNode, expr: &AstNode, clauses: &[ErlCaseClause]) {
+    walk_node(self, expr);
+    for clause in clauses.iter() {
+      self.visit_case_clause(clause);
+    }
+  }
+
+  /// A clause of `case`, also a standalone `CClause` node
+  fn visit_case_clause(&mut self, clause: &ErlCaseClause) {
+    walk_case_clause(self, clause)
+  }
+
+  /// A literal, has no children
+  fn visit_lit(&mut self, node: &AstNode, value: &Literal) {}
+
+  /// Binary operator, including the match `=`
+  fn visit_binary_op(&mut self, node: &AstNode, binop_expr: &ErlBinaryOperatorExpr) {
+    walk_node(self, &binop_expr.left);
+    walk_node(self, &binop_expr.right);
+  }
+
+  /// Unary operator
+  fn visit_unary_op(&mut self, node: &AstNode, unop_expr: &ErlUnaryOperatorExpr) {
+    walk_node(self, &unop_expr.expr)
+  }
+
+  /// List with an optional tail
+  fn visit_list(&mut self, node: &AstNode, elements: &[AstNode], tail: &Option<AstNode>) {
+    walk_nodes(self, elements);
+    walk_optional(self, tail);
+  }
+
+  /// Tuple
+  fn visit_tuple(&mut self, node: &AstNode, elements: &[AstNode]) {
+    walk_nodes(self, elements)
+  }
+
+  /// Map construction or update
+  fn visit_map_builder(
+    &mut self,
+    node: &AstNode,
+    base: &Option<AstNode>,
+    members: &[MapBuilderMember],
+  ) {
+    walk_optional(self, base);
+    for member in members.iter() {
+      walk_node(self, &member.key);
+      walk_node(self, &member.expr);
+    }
+  }
+
+  /// Record construction or update
+  fn visit_record_builder(
+    &mut self,
+    node: &AstNode,
+    base: &Option<AstNode>,
+    tag: &str,
+    members: &[RecordBuilderMember],
+  ) {
+    walk_optional(self, base);
+    for member in members.iter() {
+      walk_node(self, &member.expr);
+    }
+  }
+
+  /// Record field access or record field index
+  fn visit_record_field(&mut self, node: &AstNode, base: &Option<AstNode>, tag: &str, field: &str) {
+    walk_optional(self, base)
+  }
+
+  /// Comma-separated expressions
+  fn visit_comma_expr(&mut self, node: &AstNode, elements: &[AstNode]) {
+    walk_nodes(self, elements)
+  }
+
+  /// List comprehension, visited inside of its scope
+  fn visit_list_comprehension(&mut self, node: &AstNode, expr: &AstNode, generators: &[AstNode]) {
+    walk_nodes(self, generators);
+    walk_node(self, expr);
+  }
+
+  /// Binary comprehension, visited inside of its scope
+  fn visit_binary_comprehension(&mut self, node: &AstNode, expr: &AstNode, generators: &[AstNode]) {
+    walk_nodes(self, generators);
+    walk_node(self, expr);
+  }
+
+  /// Map comprehension, visited inside of its scope
+  fn visit_map_comprehension(
+    &mut self,
+    node: &AstNode,
+    key: &AstNode,
+    value: &AstNode,
+    generators: &[AstNode],
+  ) {
+    walk_nodes(self, generators);
+    walk_node(self, key);
+    walk_node(self, value);
+  }
+
+  /// A list, binary or map generator in a comprehension
+  fn visit_generator(
+    &mut self,
+    node: &AstNode,
+    left: &AstNode,
+    map_value: &Option<AstNode>,
+    right: &AstNode,
+    kind: GeneratorKind,
+  ) {
+    walk_node(self, right);
+    walk_node(self, left);
+    walk_optional(self, map_value);
+  }
+
+  /// Zip generator `G1 && G2`
+  fn visit_zip_generator(&mut self, node: &AstNode, generators: &[AstNode]) {
+    walk_nodes(self, generators)
+  }
+
+  /// `try ... of ... catch ... end`
+  fn visit_try_catch(
+    &mut self,
+    node: &AstNode,
+    body: &AstNode,
+    of_branches: &Option<Vec<ErlCaseClause>>,
+    catch_clauses: &[CatchClause],
+  ) {
+    walk_node(self, body);
+    for clause in of_branches.iter().flatten() {
+      self.visit_try_of_clause(clause);
+    }
+    for clause in catch_clauses.iter() {
+      self.visit_catch_clause(clause);
+    }
+  }
+
+  /// A clause of `try ... of`
+  fn visit_try_of_clause(&mut self, clause: &ErlCaseClause) {
+    walk_case_clause(self, clause)
+  }
+
+  /// A `catch Class:Error:Stack when Guard -> Body` clause
+  fn visit_catch_clause(&mut self, clause: &CatchClause) {
+    walk_catch_clause(self, clause)
+  }
+
+  /// `if Clauses end`
+  fn visit_if_statement(&mut self, node: &AstNode, clauses: &[ErlIfClause]) {
+    for clause in clauses.iter() {
+      self.visit_if_clause(clause);
+    }
+  }
+
+  /// A clause of `if`
+  fn visit_if_clause(&mut self, clause: &ErlIfClause) {
+    walk_node(self, &clause.cond);
+    walk_node(self, &clause.body);
+  }
+
+  /// `begin ... end`
+  fn visit_begin_end(&mut self, node: &AstNode, exprs: &[AstNode]) {
+    walk_nodes(self, exprs)
+  }
+
+  /// Binary construction or binary match `<<...>>`
+  fn visit_binary_expr(&mut self, node: &AstNode, elements: &[BinaryElement]) {
+    for element in elements.iter() {
+      self.visit_binary_element(element);
+    }
+  }
+
+  /// An element of a binary, with the optional width expression
+  fn visit_binary_element(&mut self, element: &BinaryElement) {
+    walk_node(self, &element.value);
+    if let ValueWidth::Expr(width) = &element.width {
+      walk_node(self, width);
+    }
+  }
+}
+
+/// Visit a node: call the pre-order hook, the hook for the node type, and the post-order hook
+pub fn walk_node<V: Visitor + ?Sized>(v: &mut V, node: &AstNode) {
+  if !v.enter_node(node) {
+    return;
+  }
+  match &node.content {
+    AstNodeType::Empty { comment } => v.visit_empty(node, comment),
+    AstNodeType::ModuleForms { forms } => v.visit_module_forms(node, forms),
+    AstNodeType::FnDef(fn_def) => v.visit_fn_def(node, fn_def),
+    AstNodeType::FnRef { mfa } => v.visit_fn_ref(node, mfa),
+    AstNodeType::FnRefDynamic { module, function, arity } => {
+      v.visit_fn_ref_dynamic(node, module, function, arity)
+    }
+    AstNodeType::Type { ty } => v.visit_type(node, ty),
+    AstNodeType::CClause(_, clause) => v.visit_case_clause(clause),
+    AstNodeType::MFA { mfarity } => v.visit_mfa(node, mfarity),
+    AstNodeType::Var(var) => v.visit_var(node, var),
+    AstNodeType::Apply(apply) => v.visit_apply(node, apply),
+    AstNodeType::CaseExpr { expr, clauses } => v.visit_case_expr(node, expr, clauses),
+    AstNodeType::Lit { value } => v.visit_lit(node, value),
+    AstNodeType::BinaryOp { binop_expr } => v.visit_binary_op(node, binop_expr),
+    AstNodeType::UnaryOp { unop_expr } => v.visit_unary_op(node, unop_expr),
+    AstNodeType::List { elements, tail } => v.visit_list(node, elements, tail),
+    AstNodeType::Tuple { elements } => v.visit_tuple(node, elements),
+    AstNodeType::MapBuilder { base, members } => v.visit_map_builder(node, base, members),
+    AstNodeType::RecordBuilder { base, tag, members } => {
+      v.visit_record_builder(node, base, tag, members)
+    }
+    AstNodeType::RecordField { base, tag, field } => v.visit_record_field(node, base, tag, field),
+    AstNodeType::CommaExpr { elements } => v.visit_comma_expr(node, elements),
+    AstNodeType::ListComprehension { expr, generators } => {
+      v.enter_scope(AstScope::Comprehension(node));
+      v.visit_list_comprehension(node, expr, generators);
+      v.exit_scope(AstScope::Comprehension(node));
+    }
+    AstNodeType::BinaryComprehension { expr, generators } => {
+      v.enter_scope(AstScope::Comprehension(node));
+      v.visit_binary_comprehension(node, expr, generators);
+      v.exit_scope(AstScope::Comprehension(node));
+    }
+    AstNodeType::MapComprehension { key, value, generators } => {
+      v.enter_scope(AstScope::Comprehension(node));
+      v.visit_map_comprehension(node, key, value, generators);
+      v.exit_scope(AstScope::Comprehension(node));
+    }
+    AstNodeType::ListComprehensionGenerator { left, map_value, right, kind } => {
+      v.visit_generator(node, left, map_value, right, *kind)
+    }
+    AstNodeType::ZipGenerator { generators } => v.visit_zip_generator(node, generators),
+    AstNodeType::TryCatch { body, of_branches, catch_clauses } => {
+      v.visit_try_catch(node, body, of_branches, catch_clauses)
+    }
+    AstNodeType::IfStatement { clauses } => v.visit_if_statement(node, clauses),
+    AstNodeType::BeginEnd { exprs } => v.visit_begin_end(node, exprs),
+    AstNodeType::BinaryExpr { elements } => v.visit_binary_expr(node, elements),
+  }
+  v.exit_node(node);
+}
+
+/// Visit each node in order
+pub fn walk_nodes<V: Visitor + ?Sized>(v: &mut V, nodes: &[AstNode]) {
+  for node in nodes.iter() {
+    walk_node(v, node);
+  }
+}
+
+/// Visit the node if present
+pub fn walk_optional<V: Visitor + ?Sized>(v: &mut V, node: &Option<AstNode>) {
+  if let Some(n) = node {
+    walk_node(v, n);
+  }
+}
+
+/// Visit each clause of a function definition, each in its own scope
+pub fn walk_fn_def<V: Visitor + ?Sized>(v: &mut V, fn_def: &ErlFnDef) {
+  for clause in fn_def.clauses.iter() {
+    v.enter_scope(AstScope::FnClause(clause));
+    v.visit_fn_clause(clause);
+    v.exit_scope(AstScope::FnClause(clause));
+  }
+}
+
+/// Visit function clause arguments, guard and body
+pub fn walk_fn_clause<V: Visitor + ?Sized>(v: &mut V, clause: &ErlFnClause) {
+  walk_nodes(v, &clause.args);
+  walk_optional(v, &clause.guard_expr);
+  walk_node(v, &clause.body);
+}
+
+/// Visit the call target expressions, and the arguments
+pub fn walk_apply<V: Visitor + ?Sized>(v: &mut V, apply: &ErlApply) {
+  match &apply.target {
+    CallableTarget::Expr(expr) => walk_node(v, expr),
+    CallableTarget::MFArity(_) => {}
+    CallableTarget::MFAExpression { module, function, .. } => {
+      walk_optional(v, module);
+      walk_node(v, function);
+    }
+  }
+  walk_nodes(v, &apply.args);
+}
+
+/// Visit case clause pattern, guard and body
+pub fn walk_case_clause<V: Visitor + ?Sized>(v: &mut V, clause: &ErlCaseClause) {
+  walk_node(v, &clause.pattern);
+  walk_optional(v, &clause.guard);
+  walk_node(v, &clause.body);
+}
+
+/// Visit catch clause exception pattern, guard and body
+pub fn walk_catch_clause<V: Visitor + ?Sized>(v: &mut V, clause: &CatchClause) {
+  walk_node(v, &clause.exc_pattern.class);
+  walk_node(v, &clause.exc_pattern.error);
+  walk_optional(v, &clause.exc_pattern.stack);
+  walk_optional(v, &clause.when_guard);
+  walk_node(v, &clause.body);
+}
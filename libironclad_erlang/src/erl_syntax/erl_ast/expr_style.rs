@@ -53,7 +53,7 @@ impl AstNodeImpl {
   fn verify_expr_style(node: &AstNode, style: ExprStyle) -> IroncladResult<()> {
     match &node.content {
       // AstNodeType::FnRef { .. } => {}
-      AstNodeType::MFA { .. } => {
+      AstNodeType::MFA { .. } | AstNodeType::FnRefDynamic { .. } => {
         if style == ExprStyle::Const {
           return Err(IcParserError::new(
             IcSeverity::Error,
@@ -142,6 +142,15 @@ impl AstNodeImpl {
           ));
         }
       }
+      AstNodeType::MapComprehension { .. } => {
+        if style == ExprStyle::Const {
+          return Err(IcParserError::new(
+            IcSeverity::Error,
+            node.location.clone(),
+            format!("Map comprehensions are not allowed in {}", style),
+          ));
+        }
+      }
       AstNodeType::BinaryComprehension { .. } => {
         if style == ExprStyle::Const {
           return Err(IcParserError::new(
@@ -186,7 +195,8 @@ impl AstNodeImpl {
       AstNodeType::CClause(_, _)
       | AstNodeType::FnDef(_)
       | AstNodeType::Type { .. }
-      | AstNodeType::ListComprehensionGenerator { .. } => {
+      | AstNodeType::ListComprehensionGenerator { .. }
+      | AstNodeType::ZipGenerator { .. } => {
         unreachable!("Node must not occur in the wild: {:?}", &node.content);
       }
       //-----------------------------------
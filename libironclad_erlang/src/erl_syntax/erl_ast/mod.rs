@@ -3,12 +3,18 @@ use crate::erl_syntax::erl_ast::node_impl::AstNodeImpl;
 use std::sync::Arc;
 
 pub mod ast_as;
+pub mod ast_const_eval;
+pub mod ast_const_fold;
 pub mod ast_expr;
 pub mod ast_extract_var;
+pub mod ast_folder;
 pub mod ast_is;
 pub mod ast_iter;
+pub mod ast_json;
 pub mod ast_new;
 pub mod ast_print;
+pub mod ast_var_safety;
+pub mod ast_visitor;
 pub mod expr_style;
 pub mod node_impl;
 
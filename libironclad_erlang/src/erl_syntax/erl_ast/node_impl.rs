@@ -8,6 +8,7 @@ use crate::erl_syntax::node::erl_binop::ErlBinaryOperatorExpr;
 use crate::erl_syntax::node::erl_case_clause::ErlCaseClause;
 use crate::erl_syntax::node::erl_catch_clause::CatchClause;
 use crate::erl_syntax::node::erl_fn_def::ErlFnDef;
+use crate::erl_syntax::node::erl_generator::GeneratorKind;
 use crate::erl_syntax::node::erl_if_clause::ErlIfClause;
 use crate::erl_syntax::node::erl_map::MapBuilderMember;
 use crate::erl_syntax::node::erl_record::RecordBuilderMember;
@@ -58,6 +59,17 @@ pub enum AstNodeType {
     mfa: MFArity,
   },
 
+  /// `fun M:F/A` reference where the module, the function or the arity are not known at compile
+  /// time. Each part is an atom (or integer) literal, or a variable.
+  FnRefDynamic {
+    /// Module expression
+    module: AstNode,
+    /// Function name expression
+    function: AstNode,
+    /// Arity expression
+    arity: AstNode,
+  },
+
   /// A temporary node wrapper for parsed types. TODO: Use more extensively in typespecs and maybe in the augmented syntax?
   Type {
     /// The type
@@ -166,12 +178,32 @@ pub enum AstNodeType {
     /// The generators which produce the list comprehension inpits, and the conditions
     generators: Vec<AstNode>,
   },
-  /// A list comprehension generator expression `Expr <- Expr`
+  /// A map comprehension expression `#{K => V || generators}`
+  MapComprehension {
+    /// The result key expression
+    key: AstNode,
+    /// The result value expression
+    value: AstNode,
+    /// The generators which produce the map comprehension inputs, and the conditions
+    generators: Vec<AstNode>,
+  },
+  /// A list comprehension generator expression `Expr <- Expr`, or a map generator
+  /// `KeyExpr := ValueExpr <- Expr`. The arrow can also be a strict `<:-`, or a binary generator
+  /// arrow `<=` and `<:=`.
   ListComprehensionGenerator {
-    /// The output match expression
+    /// The output match expression, or the key match expression for a map generator
     left: AstNode,
+    /// For a map generator, the value match expression
+    map_value: Option<AstNode>,
     /// The input expression (source of the values)
     right: AstNode,
+    /// The generator arrow
+    kind: GeneratorKind,
+  },
+  /// Zip generator `Gen1 && Gen2 && ...` which takes the elements of its generators in parallel
+  ZipGenerator {
+    /// Two or more generators
+    generators: Vec<AstNode>,
   },
 
   /// Try/Catch block with optional OF... branches and multiple catch clauses
@@ -26,7 +26,14 @@ impl IcParserError {
 
 impl Display for IcParserError {
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-    write!(f, "{} Parser {}: {}", self.location, self.severity, self.message)
+    write!(
+      f,
+      "{} Parser {}: {}{}",
+      self.location,
+      self.severity,
+      self.message,
+      self.location.format_expansion()
+    )
   }
 }
 
@@ -25,7 +25,14 @@ impl IcPreprocessorError {
 
 impl Display for IcPreprocessorError {
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-    write!(f, "{} Preprocessor {}: {}", self.location, self.severity, self.message)
+    write!(
+      f,
+      "{} Preprocessor {}: {}{}",
+      self.location,
+      self.severity,
+      self.message,
+      self.location.format_expansion()
+    )
   }
 }
 impl IcErrorTrait for IcPreprocessorError {
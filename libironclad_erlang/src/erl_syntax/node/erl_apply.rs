@@ -75,7 +75,16 @@ impl ErlApply {
       TypeKind::AnyFn => Ok(TypeImpl::any()),
 
       TypeKind::Fn(fn_type) => self.synthesize_call_to_fn(location, fn_type, &arg_types),
-      TypeKind::FnRef { .. } => unimplemented!("Callable is a fun reference"),
+      // Remote function reference, only the arity is known
+      TypeKind::FnRef { fun } if fun.arity != self.args.len() => {
+        let msg = format!(
+          "Attempt to call a function reference {} with {} arguments",
+          fun,
+          self.args.len()
+        );
+        Err(TypeError::new_not_a_fn(Some(location), None, msg))
+      }
+      TypeKind::FnRef { .. } => Ok(TypeImpl::any()),
       TypeKind::Lambda => unimplemented!("Callable is a lambda"),
 
       _other => {
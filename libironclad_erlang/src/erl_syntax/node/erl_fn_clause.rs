@@ -6,7 +6,8 @@ use crate::erl_syntax::erl_ast::AstNode;
 use crate::error::ic_error::IroncladResult;
 use crate::project::module::module_impl::ErlModule;
 use crate::project::module::scope::scope_impl::{Scope, ScopeImpl};
-use crate::typing::erl_type::ErlType;
+use crate::typing::erl_type::typekind::TypeKind;
+use crate::typing::erl_type::{ErlType, TypeImpl};
 use crate::typing::fn_clause_type::FnClauseType;
 use libironclad_util::pretty::Pretty;
 use std::sync::Weak;
@@ -15,7 +16,8 @@ use std::sync::Weak;
 /// a new function.
 pub struct ErlFnClause {
   /// Name, because it comes from AST, prefer to use funarity.name in the parent `FnDef`
-  /// For inline defined lambdas name will be `None`, take care and give it a good name later
+  /// For inline defined lambdas name will be `None`, take care and give it a good name later.
+  /// For named lambdas `fun Name(...) -> ... end` this is the variable name of the fun.
   pub name: Option<String>,
   /// Function clause arguments, binding/match expressions
   pub args: Vec<AstNode>,
@@ -52,6 +54,23 @@ impl ErlFnClause {
     ErlFnClause { name, args, body, guard_expr, scope: clause_scope }
   }
 
+  /// Create a new lambda clause. For a named lambda `fun Name(...) -> ... end` the name is a
+  /// variable, which is bound in the clause scope and refers to the lambda itself.
+  pub(crate) fn new_lambda(
+    fun_name: Option<String>,
+    args: Vec<AstNode>,
+    body: AstNode,
+    guard_expr: Option<AstNode>,
+  ) -> Self {
+    let clause = Self::new(fun_name, args, body, guard_expr);
+    if let Some(fun_name) = &clause.name {
+      // The lambda type is not known before the body is synthesized, so bind a callable for now
+      let fun_t = TypeImpl::new_unnamed(TypeKind::AnyFn);
+      clause.scope.variables.add(fun_name.clone(), fun_t);
+    }
+    clause
+  }
+
   /// Returns true if all args are variables, and not expressions, i.e. accepting any value of any type
   #[allow(dead_code)]
   pub(crate) fn is_all_variable_args(&self) -> bool {
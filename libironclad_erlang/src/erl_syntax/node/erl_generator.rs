@@ -0,0 +1,40 @@
+//! Defines the kinds of comprehension generators
+
+use std::fmt::{Display, Formatter};
+
+/// The arrow of a comprehension generator. It tells whether the input is a list (or a map) or a
+/// binary, and what happens to the input elements which do not match the pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeneratorKind {
+  /// `Pattern <- List` or a map generator `K := V <- Map`, elements not matching are skipped
+  List,
+  /// `Pattern <:- List` or `K := V <:- Map`, every element must match the pattern
+  StrictList,
+  /// `Pattern <= Binary`, binary elements not matching are skipped
+  Binary,
+  /// `Pattern <:= Binary`, every binary element must match the pattern
+  StrictBinary,
+}
+
+impl GeneratorKind {
+  /// A strict generator crashes on an element which does not match the pattern
+  pub fn is_strict(&self) -> bool {
+    matches!(self, GeneratorKind::StrictList | GeneratorKind::StrictBinary)
+  }
+
+  /// A binary generator takes its elements from a binary
+  pub fn is_binary(&self) -> bool {
+    matches!(self, GeneratorKind::Binary | GeneratorKind::StrictBinary)
+  }
+}
+
+impl Display for GeneratorKind {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      GeneratorKind::List => write!(f, "<-"),
+      GeneratorKind::StrictList => write!(f, "<:-"),
+      GeneratorKind::Binary => write!(f, "<="),
+      GeneratorKind::StrictBinary => write!(f, "<:="),
+    }
+  }
+}
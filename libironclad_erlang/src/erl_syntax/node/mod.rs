@@ -8,6 +8,7 @@ pub mod erl_catch_clause;
 pub mod erl_exception_pattern;
 pub mod erl_fn_clause;
 pub mod erl_fn_def;
+pub mod erl_generator;
 pub mod erl_if_clause;
 pub mod erl_map;
 pub mod erl_record;
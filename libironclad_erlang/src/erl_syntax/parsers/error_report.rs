@@ -2,9 +2,11 @@
 
 use crate::erl_syntax::parsers::parser_error::{ErlParserError, ErlParserErrorKind};
 use crate::erl_syntax::parsers::parser_input::ParserInput;
+use crate::erl_syntax::parsers::token_stream::tok_input::TokenizerError;
 use crate::erl_syntax::parsers::token_stream::token::{format_tok_stream, Token};
 use crate::erl_syntax::parsers::token_stream::token_kind::TokenKind;
 use crate::erl_syntax::parsers::token_stream::token_line_iter::TokenLinesIter;
+use nom::error::VerboseErrorKind;
 use nom::Offset;
 use std::fmt::Write;
 
@@ -41,7 +43,6 @@ pub fn convert_token_stream_parser_error(
   tokens_input: ParserInput,
   err: ErlParserError,
 ) -> String {
-  // TODO: Same treatment for tokenizer errors, which also call nom's convert_error
   let mut result = String::new();
 
   for (i, (substring, kind)) in err.errors.iter().enumerate() {
@@ -150,3 +151,27 @@ pub fn convert_token_stream_parser_error(
 
   result
 }
+
+/// Describe a tokenizer error for a diagnostic, its location is reported separately. Messages given
+/// with `context()` are preferred, otherwise the character which could not be tokenized is named.
+pub fn tokenizer_error_message(err: &TokenizerError) -> String {
+  let contexts: Vec<&str> = err
+    .errors
+    .iter()
+    .filter_map(|(_, kind)| match kind {
+      VerboseErrorKind::Context(c) => Some(*c),
+      _ => None,
+    })
+    .collect();
+  if !contexts.is_empty() {
+    return contexts.join(", in ");
+  }
+  match err.errors.first() {
+    Some((_, VerboseErrorKind::Char(c))) => format!("expected '{}'", c),
+    Some((at, _)) => match at.chars().next() {
+      Some(c) => format!("unexpected character '{}'", c.escape_default()),
+      None => "unexpected end of input".to_string(),
+    },
+    None => "can't tokenize the input".to_string(),
+  }
+}
@@ -24,6 +24,7 @@ pub enum LangConstruct {
   RecordField,
   ListComprehension,
   BinaryComprehension,
+  MapComprehension,
   Binary,
   //------------------
   // Type constructs
@@ -62,6 +63,7 @@ impl Display for LangConstruct {
       LangConstruct::RecordField => write!(f, "record field"),
       LangConstruct::ListComprehension => write!(f, "list comprehension"),
       LangConstruct::BinaryComprehension => write!(f, "binary comprehension"),
+      LangConstruct::MapComprehension => write!(f, "map comprehension"),
       LangConstruct::Binary => write!(f, "binary"),
       //------------------
       // Type constructs
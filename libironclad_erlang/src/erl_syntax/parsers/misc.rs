@@ -94,6 +94,19 @@ fn tok_string_1(input: ParserInput) -> ParserResult<Arc<String>> {
   }
 }
 
+/// Recognizes one binary string sigil token, returns the string.
+#[inline]
+pub fn tok_binary_string(input: ParserInput) -> ParserResult<Arc<String>> {
+  ws_before(tok_binary_string_1)(input)
+}
+
+fn tok_binary_string_1(input: ParserInput) -> ParserResult<Arc<String>> {
+  match input.tokens.iter().next() {
+    Some(Token { kind: TokenKind::BinaryStr(s), .. }) => Ok((input.slice(1..), s.clone())),
+    _other => Err(nom::Err::Error(ErlParserError::string_literal_expected(input))),
+  }
+}
+
 /// Recognizes one variable name token, returns the string.
 #[inline]
 pub fn tok_var(input: ParserInput) -> ParserResult<String> {
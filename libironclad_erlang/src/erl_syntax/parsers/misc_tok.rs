@@ -97,6 +97,10 @@ make_tok_fn!(tok_assign, TokenKind::Assign);
 
 // -> <-- [ ] \n
 make_tok_fn!(tok_left_arrow, TokenKind::LeftArr);
+make_tok_fn!(tok_strict_left_arrow, TokenKind::StrictLeftArr);
+make_tok_fn!(tok_left_double_arrow, TokenKind::LeftDoubleArr);
+make_tok_fn!(tok_strict_left_double_arrow, TokenKind::StrictLeftDoubleArr);
+make_tok_fn!(tok_double_ampersand, TokenKind::DoubleAmpersand);
 make_tok_fn!(tok_right_arrow, TokenKind::RightArr);
 make_tok_fn!(tok_square_open, TokenKind::SquareOpen);
 make_tok_fn!(tok_square_close, TokenKind::SquareClose);
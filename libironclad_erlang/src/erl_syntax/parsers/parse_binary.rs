@@ -5,22 +5,26 @@ use crate::erl_syntax::erl_ast::AstNode;
 use crate::erl_syntax::node::erl_binary_element::{
   BinaryElement, TypeSpecifier, ValueEndianness, ValueSignedness, ValueType, ValueWidth,
 };
+use crate::erl_syntax::node::erl_unop::ErlUnaryOperatorExpr;
 use crate::erl_syntax::parsers::defs::ParserResult;
 use crate::erl_syntax::parsers::lang_construct::LangConstruct;
 use crate::erl_syntax::parsers::misc;
 use crate::erl_syntax::parsers::misc::{tok_atom_of, tok_integer, tok_var};
 use crate::erl_syntax::parsers::misc_tok::*;
 use crate::erl_syntax::parsers::parse_expr::parenthesized_expr;
+use crate::erl_syntax::parsers::parse_expr_op::{
+  unop_bnot, unop_negative, unop_not, unop_positive,
+};
 use crate::erl_syntax::parsers::parse_lit::parse_erl_literal;
 use crate::erl_syntax::parsers::parser_input::ParserInput;
 use crate::literal::Literal;
 use crate::source_loc::SourceLoc;
 use crate::typing::erl_integer::ErlInteger;
 use nom::branch::alt;
-use nom::combinator::{cut, map, opt, value};
+use nom::combinator::{consumed, cut, map, opt, value};
 use nom::error::context;
 use nom::multi::{separated_list0, separated_list1};
-use nom::sequence::{preceded, terminated, tuple};
+use nom::sequence::{pair, preceded, terminated, tuple};
 use nom::Parser;
 use std::ops::Deref;
 
@@ -48,6 +52,20 @@ fn bin_element_value<'a>(input: ParserInput<'a>) -> ParserResult<AstNode> {
   )(input.clone())
 }
 
+/// Parse a binary element value, which may have a prefix operator, like `-1` in `<<-1:64>>`
+fn bin_element_bit_expr(input: ParserInput) -> ParserResult<AstNode> {
+  map(
+    consumed(pair(
+      alt((unop_negative, unop_positive, unop_bnot, unop_not)),
+      bin_element_value,
+    )),
+    |(consumed_input, (operator, expr))| {
+      ErlUnaryOperatorExpr::new_ast(SourceLoc::new(&consumed_input), operator, expr)
+    },
+  )(input.clone())
+  .or_else(|_err| bin_element_value(input.clone()))
+}
+
 /// Parse a `:Number`, `:Variable` or `:(Expr)` for bit width
 fn bin_element_width(input: ParserInput) -> ParserResult<ValueWidth> {
   map(bin_element_value, |v| {
@@ -172,7 +190,7 @@ fn bin_type_specs(input: ParserInput) -> ParserResult<Vec<TypeSpecifier>> {
 fn bin_element(input: ParserInput) -> ParserResult<BinaryElement> {
   map(
     tuple((
-      bin_element_value,
+      bin_element_bit_expr,
       opt(preceded(tok_colon, bin_element_width)),
       opt(bin_type_specs),
     )),
@@ -9,17 +9,19 @@ use crate::erl_syntax::erl_ast::expr_style::ExprStyle;
 use crate::erl_syntax::erl_ast::node_impl::AstNodeImpl;
 use crate::erl_syntax::erl_ast::node_impl::AstNodeType::Var;
 use crate::erl_syntax::erl_ast::AstNode;
+use crate::erl_syntax::node::erl_generator::GeneratorKind;
 use crate::erl_syntax::node::erl_var::ErlVar;
 use crate::erl_syntax::parsers::defs::ParserResult;
 use crate::erl_syntax::parsers::misc::{tok_atom, tok_integer, tok_var};
 use crate::erl_syntax::parsers::misc_tok::*;
 use crate::erl_syntax::parsers::parser_input::ParserInput;
 use crate::source_loc::SourceLoc;
+use crate::typing::erl_integer::ErlInteger;
 use nom::branch::alt;
 use nom::combinator::{consumed, cut, map, opt};
 use nom::error::context;
 use nom::multi::{separated_list0, separated_list1};
-use nom::sequence::{delimited, pair, preceded, separated_pair, terminated};
+use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
 
 fn parse_var(input: ParserInput) -> ParserResult<AstNode> {
   let mk_var = |(consumed_input, n): (ParserInput, String)| -> AstNode {
@@ -123,6 +125,40 @@ fn parse_fn_reference(input: ParserInput) -> ParserResult<AstNode> {
   )(input)
 }
 
+/// Parses an atom literal or a variable, for a part of `fun M:F/A`
+fn parse_atom_or_var(input: ParserInput) -> ParserResult<AstNode> {
+  let mk_atom = |(consumed_input, a): (ParserInput, String)| -> AstNode {
+    AstNodeImpl::new_lit_atom(SourceLoc::new(&consumed_input), &a)
+  };
+  alt((parse_var, map(consumed(tok_atom), mk_atom)))(input)
+}
+
+/// Parses an integer literal or a variable, for the arity of `fun M:F/A`
+fn parse_integer_or_var(input: ParserInput) -> ParserResult<AstNode> {
+  let mk_int = |(consumed_input, i): (ParserInput, ErlInteger)| -> AstNode {
+    AstNodeImpl::new_lit_int(SourceLoc::new(&consumed_input), i)
+  };
+  alt((parse_var, map(consumed(tok_integer), mk_int)))(input)
+}
+
+/// Parses `fun M:F/A` where any part can be a variable. Must be tried after
+/// `parse_fn_reference`, which takes the form with all parts known.
+fn parse_fn_reference_dynamic(input: ParserInput) -> ParserResult<AstNode> {
+  map(
+    consumed(preceded(
+      keyword_fun,
+      tuple((
+        terminated(parse_atom_or_var, tok_colon),
+        terminated(parse_atom_or_var, tok_forward_slash),
+        parse_integer_or_var,
+      )),
+    )),
+    |(consumed_input, (module, function, arity))| {
+      AstNodeImpl::new_fn_ref_dynamic(SourceLoc::new(&consumed_input), module, function, arity)
+    },
+  )(input)
+}
+
 /// Parse an expression OR a function application which is essentially `EXPR ( EXPRS... )`.
 #[inline]
 /// Express the intent of parsing any expression.
@@ -154,21 +190,78 @@ pub fn parse_constant_expr(input: ParserInput) -> ParserResult<AstNode> {
   parse_expr_prec::parse_expr_lowest_precedence(ExprStyle::Const, input)
 }
 
-/// Parses a `Expr <- Expr` generator for a list or binary comprehension
+/// Parses a generator arrow for a list or a map generator `<-`, `<:-`
+fn parse_list_generator_arrow(input: ParserInput) -> ParserResult<GeneratorKind> {
+  alt((
+    map(tok_left_arrow, |_| GeneratorKind::List),
+    map(tok_strict_left_arrow, |_| GeneratorKind::StrictList),
+  ))(input)
+}
+
+/// Parses a generator arrow for a binary generator `<=`, `<:=`
+fn parse_binary_generator_arrow(input: ParserInput) -> ParserResult<GeneratorKind> {
+  alt((
+    map(tok_left_double_arrow, |_| GeneratorKind::Binary),
+    map(tok_strict_left_double_arrow, |_| GeneratorKind::StrictBinary),
+  ))(input)
+}
+
+/// Parses a `Expr <- Expr` generator for a list or binary comprehension, also the strict `<:-`
+/// and the binary generators `<=`, `<:=`
 fn parse_comprehension_generator(input: ParserInput) -> ParserResult<AstNode> {
-  let make_comp_gen = |(consumed_input, (a, b)): (ParserInput, (AstNode, AstNode))| -> AstNode {
-    AstNodeImpl::new_list_comprehension_generator(SourceLoc::new(&consumed_input), a, b)
+  let make_comp_gen =
+    |(consumed_input, (a, kind, b)): (ParserInput, (AstNode, GeneratorKind, AstNode))| -> AstNode {
+      AstNodeImpl::new_list_comprehension_generator(SourceLoc::new(&consumed_input), a, kind, b)
+    };
+  map(
+    consumed(tuple((
+      parse_expr,
+      alt((parse_list_generator_arrow, parse_binary_generator_arrow)),
+      parse_expr,
+    ))),
+    make_comp_gen,
+  )(input.clone())
+}
+
+/// Parses a `KeyExpr := ValueExpr <- Expr` map generator for a comprehension, also the strict
+/// `<:-`
+fn parse_comprehension_map_generator(input: ParserInput) -> ParserResult<AstNode> {
+  let make_map_gen = |(consumed_input, ((k, v), kind, m)): (
+    ParserInput,
+    ((AstNode, AstNode), GeneratorKind, AstNode),
+  )|
+   -> AstNode {
+    AstNodeImpl::new_map_generator(SourceLoc::new(&consumed_input), k, v, kind, m)
   };
-  map(consumed(separated_pair(parse_expr, tok_left_arrow, parse_expr)), make_comp_gen)(
-    input.clone(),
-  )
+  map(
+    consumed(tuple((
+      separated_pair(parse_expr, tok_assign, parse_expr),
+      parse_list_generator_arrow,
+      parse_expr,
+    ))),
+    make_map_gen,
+  )(input.clone())
+}
+
+/// Parses a generator, or a zip generator `Gen1 && Gen2 && ...`
+fn parse_comprehension_zip_generator(input: ParserInput) -> ParserResult<AstNode> {
+  let make_zip_gen = |(consumed_input, generators): (ParserInput, Vec<AstNode>)| -> AstNode {
+    AstNodeImpl::new_zip_generator(SourceLoc::new(&consumed_input), generators)
+  };
+  map(
+    consumed(separated_list1(
+      tok_double_ampersand,
+      alt((parse_comprehension_map_generator, parse_comprehension_generator)),
+    )),
+    make_zip_gen,
+  )(input.clone())
 }
 
-/// Parses mix of generators and conditions for a list or binary comprehension
+/// Parses mix of generators and conditions for a list, binary or map comprehension
 pub fn parse_comprehension_exprs_and_generators(input: ParserInput) -> ParserResult<Vec<AstNode>> {
   separated_list1(
     tok_comma,
     // descend into precedence 11 instead of parse_expr, to ignore comma and semicolon
-    alt((parse_comprehension_generator, parse_expr)),
+    alt((parse_comprehension_zip_generator, parse_expr)),
   )(input)
 }
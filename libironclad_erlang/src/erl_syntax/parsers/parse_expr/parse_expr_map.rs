@@ -9,7 +9,8 @@ use crate::erl_syntax::parsers::parse_expr;
 use crate::erl_syntax::parsers::parser_input::ParserInput;
 use crate::source_loc::SourceLoc;
 use nom::branch::alt;
-use nom::combinator::map;
+use nom::combinator::{consumed, cut, map};
+use nom::error::context;
 use nom::multi::separated_list0;
 use nom::sequence::{delimited, pair, separated_pair};
 
@@ -42,6 +43,39 @@ pub fn parse_map_builder_no_base(input: ParserInput) -> ParserResult<AstNode> {
   )(input.clone())
 }
 
+fn parse_map_comprehension_1(input: ParserInput) -> ParserResult<AstNode> {
+  let mk_map_comp = |(consumed_input, ((key, value), generators)): (
+    ParserInput,
+    ((AstNode, AstNode), Vec<AstNode>),
+  )|
+   -> AstNode {
+    AstNodeImpl::new_map_comprehension(SourceLoc::new(&consumed_input), key, value, generators)
+  };
+
+  map(
+    consumed(separated_pair(
+      context(
+        "map comprehension output expression",
+        separated_pair(parse_expr::parse_expr, tok_right_darr, parse_expr::parse_expr),
+      ),
+      tok_double_vertical_bar,
+      context(
+        "map comprehension generators",
+        cut(parse_expr::parse_comprehension_exprs_and_generators),
+      ),
+    )),
+    mk_map_comp,
+  )(input)
+}
+
+/// Parses a map comprehension syntax `#{ KEY => VALUE || GENERATORS }`
+pub fn parse_map_comprehension(input: ParserInput) -> ParserResult<AstNode> {
+  context(
+    "map comprehension",
+    delimited(pair(tok_hash, tok_curly_open), parse_map_comprehension_1, tok_curly_close),
+  )(input)
+}
+
 // /// Parse a map builder expression
 // pub fn parse_map_builder_with_base(input: ParserInput) -> ParserResult<AstNode> {
 //   map(
@@ -16,13 +16,15 @@ use crate::erl_syntax::parsers::parse_case::parse_case_expression;
 use crate::erl_syntax::parsers::parse_expr::parse_expr_list::{
   parse_list_builder, parse_list_comprehension,
 };
-use crate::erl_syntax::parsers::parse_expr::parse_expr_map::parse_map_builder_no_base;
+use crate::erl_syntax::parsers::parse_expr::parse_expr_map::{
+  parse_map_builder_no_base, parse_map_comprehension,
+};
 use crate::erl_syntax::parsers::parse_expr::parse_expr_record::{
   parse_record_builder_no_base, parse_record_field_access_no_base,
 };
 use crate::erl_syntax::parsers::parse_expr::{
   parenthesized_expr, parse_begin_end, parse_binary_comprehension, parse_fn_reference,
-  parse_parenthesized_list_of_exprs, parse_tuple_builder, parse_var,
+  parse_fn_reference_dynamic, parse_parenthesized_list_of_exprs, parse_tuple_builder, parse_var,
 };
 use crate::erl_syntax::parsers::parse_expr_op::{
   binop_add, binop_and, binop_andalso, binop_band, binop_bang, binop_bor, binop_bsl, binop_bsr,
@@ -63,6 +65,7 @@ fn parse_expr_prec_primary<'a>(input: ParserInput<'a>) -> ParserResult<AstNode>
         LangConstruct::ListComprehension,
         LangConstruct::Literal,
         LangConstruct::Map,
+        LangConstruct::MapComprehension,
         LangConstruct::ParenthesizedExpression,
         LangConstruct::Record,
         LangConstruct::TryCatch,
@@ -86,6 +89,8 @@ fn parse_expr_prec_primary<'a>(input: ParserInput<'a>) -> ParserResult<AstNode>
       )),
       alt((
         parse_fn_reference,
+        parse_fn_reference_dynamic,
+        parse_map_comprehension,
         parse_map_builder_no_base,
         parse_record_builder_no_base,
         parse_record_field_access_no_base,
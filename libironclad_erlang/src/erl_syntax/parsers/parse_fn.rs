@@ -4,21 +4,23 @@ use crate::erl_syntax::erl_ast::node_impl::AstNodeImpl;
 use crate::erl_syntax::erl_ast::AstNode;
 use crate::erl_syntax::node::erl_fn_clause::ErlFnClause;
 use crate::erl_syntax::parsers::defs::ParserResult;
-use crate::erl_syntax::parsers::misc::tok_atom;
+use crate::erl_syntax::parsers::misc::{tok_atom, tok_var};
 use crate::erl_syntax::parsers::misc_tok::*;
 use crate::erl_syntax::parsers::parse_expr::{
   parse_comma_sep_exprs1, parse_guardexpr, parse_parenthesized_list_of_exprs,
 };
+use crate::erl_syntax::parsers::parser_error::ErlParserError;
 use crate::erl_syntax::parsers::parser_input::ParserInput;
 use crate::source_loc::SourceLoc;
 use libironclad_util::mfarity::MFArity;
 use nom::combinator::{cut, map, not, opt, peek};
-use nom::error::context;
+use nom::error::{context, ContextError, ErrorKind, ParseError};
 use nom::multi::separated_list1;
 use nom::sequence::{delimited, preceded, terminated, tuple};
 
 /// Function will succeed if an atom is parsed and FN_NAME is true, will return Some<Value>.
-/// Function will succeed if no atom found but also FN_NAME is false, will return None.
+/// Function will succeed if no atom found but also FN_NAME is false, will return None, or the
+/// variable name of a named lambda `fun Name() -> ... end`.
 /// Function will fail otherwise.
 fn parse_fnclause_name<const REQUIRE_FN_NAME: bool>(
   input: ParserInput,
@@ -30,7 +32,7 @@ fn parse_fnclause_name<const REQUIRE_FN_NAME: bool>(
   // Succeed if FN_NAME=false and there is no atom
   context(
     "function clause without a name (must not begin with an atom)",
-    map(peek(not(tok_atom)), |_| None),
+    preceded(peek(not(tok_atom)), opt(tok_var)),
   )(input)
 }
 
@@ -59,12 +61,12 @@ fn parse_fnclause<const REQUIRE_FN_NAME: bool>(input: ParserInput) -> ParserResu
       ),
     )),
     |(maybe_name, args, when_expr, body)| {
-      ErlFnClause::new(
-        maybe_name,
-        args,
-        AstNodeImpl::new_comma_expr(SourceLoc::new(&input), body),
-        when_expr,
-      )
+      let body = AstNodeImpl::new_comma_expr(SourceLoc::new(&input), body);
+      if REQUIRE_FN_NAME {
+        ErlFnClause::new(maybe_name, args, body, when_expr)
+      } else {
+        ErlFnClause::new_lambda(maybe_name, args, body, when_expr)
+      }
     },
   )(input.clone())
 }
@@ -77,7 +79,7 @@ fn _construct_fndef(location: SourceLoc, fnclauses: Vec<ErlFnClause>) -> AstNode
 
   let arity = fnclauses[0].args.len();
   let fn_name = match &fnclauses[0].name {
-    None => "TODO: lambda_name".to_string(),
+    None => "$unnamed-lambda".to_string(),
     Some(s) => s.clone(),
   };
   let funarity = MFArity::new_local(&fn_name, arity);
@@ -106,17 +108,25 @@ pub fn parse_fndef(input: ParserInput) -> ParserResult<AstNode> {
   )(input.clone())
 }
 
-/// Lambda is an inline function definition
+/// Lambda is an inline function definition. A named lambda `fun Name(...) -> ... end` must use
+/// the same name in all clauses.
 pub(crate) fn parse_lambda(input: ParserInput) -> ParserResult<AstNode> {
   // Lambda is made of "fun" keyword, followed by multiple ";" separated clauses
-  map(
-    preceded(
-      keyword_fun,
-      terminated(
-        context("", separated_list1(tok_semicolon, parse_fnclause::<false>)),
-        keyword_end,
-      ),
+  let (rest, fnclauses) = preceded(
+    keyword_fun,
+    terminated(
+      context("", separated_list1(tok_semicolon, parse_fnclause::<false>)),
+      keyword_end,
     ),
-    |t| _construct_fndef(SourceLoc::new(&input), t),
-  )(input.clone())
+  )(input.clone())?;
+
+  if !fnclauses.iter().all(|fnc| fnc.name == fnclauses[0].name) {
+    let err = ErlParserError::from_error_kind(input.clone(), ErrorKind::Verify);
+    return Err(nom::Err::Failure(ErlParserError::add_context(
+      input,
+      "all clauses of a named fun must have the same name",
+      err,
+    )));
+  }
+  Ok((rest, _construct_fndef(SourceLoc::new(&input), fnclauses)))
 }
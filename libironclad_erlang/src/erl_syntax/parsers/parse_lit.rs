@@ -4,7 +4,7 @@ use crate::erl_syntax::erl_ast::node_impl::AstNodeImpl;
 use crate::erl_syntax::erl_ast::node_impl::AstNodeType::Lit;
 use crate::erl_syntax::erl_ast::AstNode;
 use crate::erl_syntax::parsers::defs::ParserResult;
-use crate::erl_syntax::parsers::misc::{tok_atom, tok_string, ws_before};
+use crate::erl_syntax::parsers::misc::{tok_atom, tok_binary_string, tok_string, ws_before};
 use crate::erl_syntax::parsers::misc_tok::*;
 use crate::erl_syntax::parsers::parse_lit_numbers;
 use crate::erl_syntax::parsers::parser_error::ErlParserError;
@@ -35,6 +35,14 @@ fn parse_string_to_ast(input: ParserInput) -> ParserResult<AstNode> {
   })(input.clone())
 }
 
+/// A binary string sigil `~"text"` becomes a binary literal, an empty one becomes `<<>>`
+fn parse_binary_string_to_ast(input: ParserInput) -> ParserResult<AstNode> {
+  map(tok_binary_string, |s| {
+    let value = if s.is_empty() { Literal::EmptyBinary } else { Literal::BinaryString(s) };
+    AstNodeImpl::construct_with_location(SourceLoc::new(&input), Lit { value: value.into() })
+  })(input.clone())
+}
+
 fn parse_atom_to_ast(input: ParserInput) -> ParserResult<AstNode> {
   map(tok_atom, |s| {
     AstNodeImpl::construct_with_location(
@@ -94,6 +102,7 @@ pub(crate) fn parse_erl_literal(input: ParserInput) -> ParserResult<AstNode> {
       parse_lit_numbers::parse_int_to_ast,
       parse_atom_to_ast,
       parse_string_to_ast,
+      parse_binary_string_to_ast,
       parse_character,
     ))),
   )(input)
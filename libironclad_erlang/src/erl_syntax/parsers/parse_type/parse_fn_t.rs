@@ -19,23 +19,18 @@ use nom::error::context;
 use nom::multi::separated_list1;
 use nom::sequence::{delimited, preceded, tuple};
 
-/// Given function spec module attribute `-spec name(args...) -> ...` parse into an AST node
-/// Dash `-` is matched outside by the caller.
-pub fn parse_fn_spec(input: ParserInput) -> ParserResult<PreprocessorNode> {
+/// Parses the function name and the clauses of a function spec, following `-spec` or `-callback`,
+/// and returns the function name with arity, and the function type.
+fn parse_fn_spec_body(input: ParserInput) -> ParserResult<(MFArity, ErlType)> {
   map(
-    // all between -spec and .
-    delimited(
-      |i1| dash_atom(i1, "spec"),
-      // TODO: Can be wrapped with parentheses
-      tuple((
-        context("function name in a -spec() attribute", cut(tok_atom)),
-        separated_list1(
-          tok_semicolon,
-          context("function clause in a -spec() attribute", cut(parse_fn_spec_fnclause)),
-        ),
-      )),
-      period_eol_eof,
-    ),
+    // TODO: Can be wrapped with parentheses
+    tuple((
+      context("function name in a -spec() attribute", cut(tok_atom)),
+      separated_list1(
+        tok_semicolon,
+        context("function clause in a -spec() attribute", cut(parse_fn_spec_fnclause)),
+      ),
+    )),
     |(name, clauses)| {
       let arity = clauses[0].arity();
       assert!(
@@ -44,12 +39,28 @@ pub fn parse_fn_spec(input: ParserInput) -> ParserResult<PreprocessorNode> {
       );
       let funarity = MFArity::new_local(&name, arity);
       let fntypespec_kind = TypeKind::new_fn_type(clauses);
-      PreprocessorNodeImpl::new_fn_spec(
-        SourceLoc::new(&input),
-        funarity,
-        TypeImpl::new_unnamed(fntypespec_kind),
-      )
+      (funarity, TypeImpl::new_unnamed(fntypespec_kind))
     },
+  )(input)
+}
+
+/// Given function spec module attribute `-spec name(args...) -> ...` parse into an AST node
+/// Dash `-` is matched outside by the caller.
+pub fn parse_fn_spec(input: ParserInput) -> ParserResult<PreprocessorNode> {
+  map(
+    // all between -spec and .
+    delimited(|i1| dash_atom(i1, "spec"), parse_fn_spec_body, period_eol_eof),
+    |(funarity, spec)| PreprocessorNodeImpl::new_fn_spec(SourceLoc::new(&input), funarity, spec),
+  )(input.clone())
+}
+
+/// Given behaviour callback module attribute `-callback name(args...) -> ...` parse into an AST
+/// node. Dash `-` is matched outside by the caller.
+pub fn parse_callback_spec(input: ParserInput) -> ParserResult<PreprocessorNode> {
+  map(
+    // all between -callback and .
+    delimited(|i1| dash_atom(i1, "callback"), parse_fn_spec_body, period_eol_eof),
+    |(funarity, spec)| PreprocessorNodeImpl::new_callback(SourceLoc::new(&input), funarity, spec),
   )(input.clone())
 }
 
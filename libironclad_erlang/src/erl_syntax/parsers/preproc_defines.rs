@@ -1,5 +1,6 @@
 //! Parser scope for the current translation unit, contains currently known macros, records etc
 use crate::erl_syntax::preprocessor::pp_define::{PreprocessorDefine, PreprocessorDefineImpl};
+use crate::error::ic_error::{IroncladError, IroncladResult};
 use libironclad_util::mfarity::MFArity;
 use std::collections::HashMap;
 
@@ -25,25 +26,39 @@ impl FromIterator<(MFArity, PreprocessorDefine)> for PreprocessorDefinesMap {
 
 impl PreprocessorDefinesMap {
   /// Parse defines in the configuration file, or from command line specified as -DNAME or -DNAME=XXX
-  pub(crate) fn new_from_config_lines(inputs: &[String]) -> PreprocessorDefinesMap {
-    inputs
-      .iter()
-      .map(|inp| {
-        let new_def = PreprocessorDefineImpl::new_from_command_line(inp);
-        (new_def.get_name_arity(), new_def)
-      })
-      .collect()
+  /// All malformed defines are reported as config errors.
+  pub(crate) fn new_from_config_lines(inputs: &[String]) -> IroncladResult<PreprocessorDefinesMap> {
+    let mut errors = Vec::new();
+    let mut result = PreprocessorDefinesMap::default();
+
+    for inp in inputs.iter() {
+      match PreprocessorDefineImpl::new_from_command_line(inp) {
+        Ok(new_def) => {
+          result.data.insert(new_def.get_name_arity(), new_def);
+        }
+        Err(reason) => errors.push(IroncladError::config_value(format!(
+          "Malformed preprocessor define \"{}\": {}",
+          inp, reason
+        ))),
+      }
+    }
+
+    if errors.is_empty() {
+      Ok(result)
+    } else {
+      Err(IroncladError::multiple(errors))
+    }
   }
 
   /// Create a new scope from a parsed project configuration
   pub fn new_from_config(
     maybe_inputs: Option<Vec<String>>,
     defaults: &PreprocessorDefinesMap,
-  ) -> PreprocessorDefinesMap {
+  ) -> IroncladResult<PreprocessorDefinesMap> {
     if let Some(inputs) = &maybe_inputs {
       PreprocessorDefinesMap::new_from_config_lines(inputs)
     } else {
-      defaults.clone()
+      Ok(defaults.clone())
     }
   }
 
@@ -0,0 +1,144 @@
+//! Lossless token stream for tooling: every token knows its exact source span, and the whitespace,
+//! newlines and comments around it are kept as trivia. Concatenating the tokens with their trivia
+//! reproduces the source text byte for byte.
+
+use crate::erl_syntax::parsers::token_stream::tok_input::TokenizerInput;
+use crate::erl_syntax::parsers::token_stream::token::Token;
+use crate::erl_syntax::parsers::token_stream::token_kind::TokenKind;
+use crate::erl_syntax::parsers::token_stream::tokenizer::tokenize_spans_recovering;
+use std::ops::Range;
+
+/// The kind of source text between the significant tokens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+  /// Spaces and tabs
+  Whitespace,
+  /// A line break `\n`, `\r\n` or `\r`
+  Newline,
+  /// A `% text` line comment, without the line break
+  Comment,
+}
+
+/// A piece of source text which is not a token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trivia<'a> {
+  /// What this trivia is
+  pub kind: TriviaKind,
+  /// Byte range in the source text
+  pub span: Range<usize>,
+  /// The source text
+  pub text: &'a str,
+}
+
+/// A token with its exact source span and the trivia around it. The trailing trivia is the
+/// whitespace and the comment which follow the token on the same line; everything else, including
+/// the line breaks, is leading trivia of the next token.
+#[derive(Debug, Clone)]
+pub struct LosslessToken<'a> {
+  /// The token, as the tokenizer produces it. The input end is marked with `EndOfInput`, which
+  /// carries the trivia at the end of the source.
+  pub token: Token,
+  /// Byte range of the token in the source text
+  pub span: Range<usize>,
+  /// The source text of the token
+  pub text: &'a str,
+  /// Trivia before the token
+  pub leading: Vec<Trivia<'a>>,
+  /// Trivia after the token, up to the end of its line
+  pub trailing: Vec<Trivia<'a>>,
+}
+
+impl<'a> LosslessToken<'a> {
+  /// Append the token with its trivia as source text
+  pub fn write_source(&self, out: &mut String) {
+    self.leading.iter().for_each(|t| out.push_str(t.text));
+    out.push_str(self.text);
+    self.trailing.iter().for_each(|t| out.push_str(t.text));
+  }
+}
+
+/// Print the lossless tokens back, this reproduces the tokenized source text
+pub fn lossless_to_source(tokens: &[LosslessToken]) -> String {
+  let mut out = String::new();
+  tokens.iter().for_each(|t| t.write_source(&mut out));
+  out
+}
+
+/// Split a source range into trivia. The tokenizer returns line comments and line breaks as
+/// `EOL` tokens, and leaves the spaces between the tokens uncovered; these are split here.
+fn split_trivia<'a>(input: TokenizerInput<'a>, span: Range<usize>, out: &mut Vec<Trivia<'a>>) {
+  let mut pos = span.start;
+  while pos < span.end {
+    let rest = &input[pos..span.end];
+    let (kind, len) = if rest.starts_with("\r\n") {
+      (TriviaKind::Newline, 2)
+    } else if rest.starts_with(['\n', '\r']) {
+      (TriviaKind::Newline, 1)
+    } else if rest.starts_with('%') {
+      let line_end = rest.find(['\n', '\r']).unwrap_or(rest.len());
+      (TriviaKind::Comment, rest[..line_end].trim_end_matches([' ', '\t']).len())
+    } else {
+      let len = rest.len() - rest.trim_start_matches([' ', '\t']).len();
+      (TriviaKind::Whitespace, len.max(rest.chars().next().map_or(1, char::len_utf8)))
+    };
+    out.push(Trivia {
+      kind,
+      span: pos..pos + len,
+      text: &input[pos..pos + len],
+    });
+    pos += len;
+  }
+}
+
+/// Break the source into tokens without losing any text: every token gets its span and trivia.
+/// Like `tokenize_source_recovering`, never fails, invalid input becomes `Invalid()` tokens. The
+/// last token is always `EndOfInput`.
+pub fn tokenize_lossless(input: TokenizerInput) -> Vec<LosslessToken> {
+  let (spans, _errors) = tokenize_spans_recovering(input);
+  let mut result: Vec<LosslessToken> = Vec::with_capacity(spans.len() + 1);
+  let mut pending: Vec<Trivia> = Vec::new();
+  let mut covered = 0;
+
+  // Line breaks and comments are `EOL` tokens, they become trivia
+  for (token, span) in spans.into_iter().filter(|(tok, _)| !tok.is_eol()) {
+    split_trivia(input, covered..span.start, &mut pending);
+    result.push(LosslessToken {
+      token,
+      span: span.clone(),
+      text: &input[span.clone()],
+      leading: std::mem::take(&mut pending),
+      trailing: Vec::new(),
+    });
+    covered = span.end;
+  }
+  split_trivia(input, covered..input.len(), &mut pending);
+  let end = input.len();
+  result.push(LosslessToken {
+    token: Token::new(input[end..].as_ptr(), TokenKind::EndOfInput),
+    span: end..end,
+    text: "",
+    leading: Vec::new(),
+    trailing: Vec::new(),
+  });
+
+  attach_trivia(&mut result, pending);
+  result
+}
+
+/// The trivia were collected as leading trivia of the following token. Move the part before the
+/// first line break to the trailing trivia of the preceding token. The trivia left at the end of
+/// the input belong to the `EndOfInput` token.
+fn attach_trivia<'a>(tokens: &mut [LosslessToken<'a>], end_trivia: Vec<Trivia<'a>>) {
+  let last = tokens.len() - 1;
+  tokens[last].leading = end_trivia;
+  for i in 1..tokens.len() {
+    let leading = std::mem::take(&mut tokens[i].leading);
+    let same_line = leading
+      .iter()
+      .position(|t| t.kind == TriviaKind::Newline)
+      .unwrap_or(leading.len());
+    let (trailing, leading) = leading.split_at(same_line);
+    tokens[i - 1].trailing = trailing.to_vec();
+    tokens[i].leading = leading.to_vec();
+  }
+}
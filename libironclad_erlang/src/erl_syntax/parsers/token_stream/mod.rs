@@ -1,10 +1,12 @@
 //! Declares temporary tokens for the AST
 
 pub mod keyword;
+pub mod lossless;
 pub mod misc;
 pub mod tok_input;
 pub mod tok_strings;
 pub mod token;
 pub mod token_kind;
 pub mod token_line_iter;
+pub mod token_source;
 pub mod tokenizer;
@@ -1,8 +1,11 @@
 //! Directory will group the tokenizer code for `"string"` and `'atom'` with support for backslash
 //! quoting and unicode.
 pub mod atom_literal;
+pub mod num_literal;
 pub mod shared;
+pub mod sigil;
 pub mod str_literal;
+pub mod triple_quoted;
 
 /// Replaces `char` where it overlaps with `nom::*::char`
 pub type Char = char;
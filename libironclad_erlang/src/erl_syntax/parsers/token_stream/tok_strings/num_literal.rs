@@ -0,0 +1,152 @@
+//! Parse Erlang numbers: decimal and based integers `2#1010`, `16#FF_FF`, floats with exponents
+//! `1.5e-10` and OTP 27 based floats `16#1.8#e2`. Digits can be separated with single `_`.
+
+use crate::erl_syntax::parsers::token_stream::tok_input::{TokenizerInput, TokensResult};
+use crate::erl_syntax::parsers::token_stream::tok_strings::shared::{
+  tokenizer_failure, TokenizerFailure,
+};
+use crate::typing::erl_integer::ErlInteger;
+use nom::bytes::complete::take_while1;
+use nom::character::complete::{char, one_of};
+use nom::combinator::{opt, recognize};
+use nom::multi::many0;
+use nom::sequence::{pair, preceded, tuple};
+use num::ToPrimitive;
+use num_bigint::BigInt;
+
+/// A number literal, sign is not included
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum NumberLiteral {
+  /// A small or a big integer
+  Integer(ErlInteger),
+  /// A floating point number
+  Float(f64),
+}
+
+/// Recognize digits valid for `radix`, optionally separated by single `_` characters.
+/// A separator which is not followed by a digit (`1__0`, `1_`) is an error.
+fn digits_with_separators(input: TokenizerInput, radix: u32) -> TokensResult<TokenizerInput> {
+  let is_digit = move |c: char| c.is_digit(radix);
+  let (rest, digits) = recognize(pair(
+    take_while1(is_digit),
+    many0(preceded(char('_'), take_while1(is_digit))),
+  ))(input)?;
+  if rest.starts_with('_') {
+    return Err(tokenizer_failure(rest, "digit separator _ must be followed by a digit"));
+  }
+  Ok((rest, digits))
+}
+
+/// Recognize a signed decimal exponent value, after `e` or `#e`. The marker is already consumed,
+/// so missing digits are an error.
+fn exponent_value(input: TokenizerInput) -> TokensResult<TokenizerInput> {
+  recognize(pair(opt(one_of("+-")), |i| digits_with_separators(i, 10)))(input)
+    .map_err(|_| tokenizer_failure(input, "exponent digits are expected"))
+}
+
+/// Remove the digit separators
+fn without_separators(digits: &str) -> String {
+  digits.chars().filter(|c| *c != '_').collect()
+}
+
+/// Parse a number without a sign: an integer, a based integer, a float or a based float
+pub(crate) fn parse_number_literal(input: TokenizerInput) -> TokensResult<NumberLiteral> {
+  let (rest, int_part) = digits_with_separators(input, 10)?;
+  match rest.strip_prefix('#') {
+    Some(based_body) => parse_based_number(input, int_part, based_body),
+    None => parse_decimal_number(input, int_part, rest),
+  }
+}
+
+/// Continue parsing a decimal integer or a float `1.5`, `1.0e-10` after its integer part
+fn parse_decimal_number<'a>(
+  input: TokenizerInput<'a>,
+  int_part: TokenizerInput<'a>,
+  rest: TokenizerInput<'a>,
+) -> TokensResult<'a, NumberLiteral> {
+  // A period which is not followed by a digit ends the form, or is a part of `..`
+  let fraction = preceded(char('.'), |i| digits_with_separators(i, 10));
+  let exponent = preceded(one_of("eE"), exponent_value);
+  match pair(fraction, opt(exponent))(rest) {
+    Err(failure @ nom::Err::Failure(_)) => Err(failure),
+    Ok((after_float, _)) => {
+      let text = without_separators(&input[..input.len() - after_float.len()]);
+      match text.parse::<f64>() {
+        Ok(f) if f.is_finite() => Ok((after_float, NumberLiteral::Float(f))),
+        _ => Err(tokenizer_failure(input, "float literal is out of range")),
+      }
+    }
+    Err(_) => match ErlInteger::new_from_string(&without_separators(int_part)) {
+      Some(i) => Ok((rest, NumberLiteral::Integer(i))),
+      None => Err(tokenizer_failure(input, "malformed integer literal")),
+    },
+  }
+}
+
+/// Continue parsing a based integer `16#FF` or a based float `2#0.101#e-3` after the `#`
+fn parse_based_number<'a>(
+  input: TokenizerInput<'a>,
+  base_str: TokenizerInput<'a>,
+  body: TokenizerInput<'a>,
+) -> TokensResult<'a, NumberLiteral> {
+  let radix = match without_separators(base_str).parse::<u32>() {
+    Ok(radix) if (2..=36).contains(&radix) => radix,
+    _ => return Err(tokenizer_failure(input, "the base of a number must be between 2 and 36")),
+  };
+  let (rest, int_digits) = digits_with_separators(body, radix)
+    .map_err(|_| tokenizer_failure(body, "a digit of the base is expected after #"))?;
+
+  let fraction = preceded(char('.'), |i| digits_with_separators(i, radix));
+  let exponent = preceded(pair(char('#'), char('e')), exponent_value);
+  let (rest, float_parts) = match tuple((fraction, opt(exponent)))(rest) {
+    Ok((after_float, parts)) => (after_float, Some(parts)),
+    Err(failure @ nom::Err::Failure(_)) => return Err(failure),
+    Err(_) => (rest, None),
+  };
+  if rest.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+    return Err(tokenizer_failure(rest, "digit is out of range for the base of the number"));
+  }
+
+  let value = match float_parts {
+    Some((frac_digits, exp)) => {
+      NumberLiteral::Float(based_float(input, radix, int_digits, frac_digits, exp)?)
+    }
+    None => match ErlInteger::new_from_string_radix(&without_separators(int_digits), radix) {
+      Some(i) => NumberLiteral::Integer(i),
+      None => return Err(tokenizer_failure(input, "malformed integer literal")),
+    },
+  };
+  Ok((rest, value))
+}
+
+/// Calculate a based float value: `int_digits.frac_digits` in `radix`, multiplied by `radix`
+/// to the power of `exp`
+fn based_float<'a>(
+  input: TokenizerInput<'a>,
+  radix: u32,
+  int_digits: &str,
+  frac_digits: &str,
+  exp: Option<&str>,
+) -> Result<f64, TokenizerFailure<'a>> {
+  let out_of_range = || tokenizer_failure(input, "float literal is out of range");
+  let frac_digits = without_separators(frac_digits);
+  let mantissa_digits = without_separators(int_digits) + &frac_digits;
+  let mantissa = BigInt::parse_bytes(mantissa_digits.as_bytes(), radix)
+    .and_then(|m| m.to_f64())
+    .ok_or_else(out_of_range)?;
+  let exp = match exp {
+    Some(e) => without_separators(e)
+      .parse::<i32>()
+      .map_err(|_| out_of_range())?,
+    None => 0,
+  };
+  let scale = exp
+    .checked_sub(frac_digits.len() as i32)
+    .ok_or_else(out_of_range)?;
+  let result = mantissa * (radix as f64).powi(scale);
+  if result.is_finite() {
+    Ok(result)
+  } else {
+    Err(out_of_range())
+  }
+}
@@ -3,11 +3,25 @@
 use crate::erl_syntax::parsers::token_stream::tok_input::{TokenizerInput, TokensResult};
 use crate::erl_syntax::parsers::token_stream::tok_strings::Char;
 use nom::branch::alt;
-use nom::bytes::complete::take_while_m_n;
-use nom::character::complete::{char, multispace1};
-use nom::combinator::{map_opt, map_res, value};
+use nom::bytes::complete::{take_while1, take_while_m_n};
+use nom::character::complete::{anychar, char, multispace1};
+use nom::combinator::{cut, map_opt, map_res, value};
+use nom::error::{context, VerboseError, VerboseErrorKind};
 use nom::sequence::{delimited, preceded};
 
+/// Non-recoverable tokenizer error type
+pub(crate) type TokenizerFailure<'a> = nom::Err<VerboseError<TokenizerInput<'a>>>;
+
+/// Produce a non-recoverable tokenizer error with a message
+pub(crate) fn tokenizer_failure<'a>(
+  input: TokenizerInput<'a>,
+  message: &'static str,
+) -> TokenizerFailure<'a> {
+  nom::Err::Failure(VerboseError {
+    errors: vec![(input, VerboseErrorKind::Context(message))],
+  })
+}
+
 /// A string fragment contains a fragment of a string being parsed: either
 /// a non-empty Literal (a series of non-escaped characters), a single
 /// parsed escaped character, or a block of escaped whitespace.
@@ -50,7 +64,6 @@ fn parse_delimited_hex(input: TokenizerInput) -> TokensResult<TokenizerInput> {
 /// `map_res` takes the result of a parser and applies a function that returns
 /// a Result. In this case we take the hex bytes from parse_hex and attempt to
 /// convert them to a u32.
-// TODO: Bignums!
 pub(crate) fn parse_u32(input: TokenizerInput) -> TokensResult<u32> {
   map_res(parse_delimited_hex, move |hex: TokenizerInput| u32::from_str_radix(hex, 16))(input)
 }
@@ -69,6 +82,61 @@ fn parse_unicode(input: TokenizerInput) -> TokensResult<Char> {
   map_opt(parse_u32, std::char::from_u32)(input)
 }
 
+/// Value of a single character escape `\C`. Characters without a special meaning stand for
+/// themselves, like `\'` does.
+pub(crate) fn escape_value(c: Char) -> Char {
+  match c {
+    'n' => '\n',
+    'r' => '\r',
+    't' => '\t',
+    'b' => '\x08',
+    'f' => '\x0c',
+    'a' => '\x07',
+    'e' => '\x1b',
+    's' => ' ',
+    'v' => '\x0b',
+    'd' => '\x7f',
+    other => other,
+  }
+}
+
+/// Parse an octal escape `\N`, `\NN` or `\NNN`, without the backslash
+fn parse_octal_escape(input: TokenizerInput) -> TokensResult<Char> {
+  map_opt(
+    take_while_m_n(1, 3, |c: char| ('0'..='7').contains(&c)),
+    |oct: TokenizerInput| std::char::from_u32(u32::from_str_radix(oct, 8).ok()?),
+  )(input)
+}
+
+/// Parse a hex escape `\xHH` or `\x{H...}`, without the backslash
+fn parse_hex_escape(input: TokenizerInput) -> TokensResult<Char> {
+  let to_char = |hex: TokenizerInput| std::char::from_u32(u32::from_str_radix(hex, 16).ok()?);
+  preceded(
+    char('x'),
+    context(
+      "invalid \\x escape, expected two hex digits or a valid character code in {}",
+      cut(map_opt(
+        alt((
+          delimited(char('{'), take_while1(|c: char| c.is_ascii_hexdigit()), char('}')),
+          take_while_m_n(2, 2, |c: char| c.is_ascii_hexdigit()),
+        )),
+        to_char,
+      )),
+    ),
+  )(input)
+}
+
+/// Parse a control character escape `\^X`, without the backslash
+fn parse_control_escape(input: TokenizerInput) -> TokensResult<Char> {
+  map_opt(preceded(char('^'), anychar), |c: Char| std::char::from_u32(c as u32 & 0x1f))(input)
+}
+
+/// Parse an escape which encodes the character code: octal, hex or control character. These have
+/// no single character in the source to remember, unlike `\n` or `\t`.
+pub(crate) fn parse_code_escape(input: TokenizerInput) -> TokensResult<Char> {
+  alt((parse_octal_escape, parse_hex_escape, parse_control_escape))(input)
+}
+
 /// Parse an escaped character: \n, \t, \r, \u{00AC}, etc.
 pub(crate) fn parse_escaped_char(input: TokenizerInput) -> TokensResult<Char> {
   preceded(
@@ -77,6 +145,11 @@ pub(crate) fn parse_escaped_char(input: TokenizerInput) -> TokensResult<Char> {
     // the first successful match
     alt((
       parse_unicode,
+      parse_code_escape,
+      value(' ', char('s')),
+      value('\x1b', char('e')),
+      value('\x0b', char('v')),
+      value('\x7f', char('d')),
       // The `value` parser returns a fixed value (the first argument) if its
       // parser (the second argument) succeeds. In these cases, it looks for
       // the marker characters (n, r, t, etc) and returns the matching
@@ -0,0 +1,117 @@
+//! Parse OTP 27 sigil strings: `~"text"`, `~b"text"`, `~s[text]`, `~B<text>`, `~S"""..."""` etc.
+//!
+//! * `~b` and `~s` process escape sequences, the closing delimiter can be escaped with `\`.
+//! * `~B` and `~S` take the text verbatim.
+//! * `~b`, `~B` and the default `~` produce binaries, `~s` and `~S` produce strings.
+//! * The default `~` is `~b`, and for triple-quoted strings it is `~B`.
+
+use crate::erl_syntax::parsers::token_stream::tok_input::{TokenizerInput, TokensResult};
+use crate::erl_syntax::parsers::token_stream::tok_strings::shared::{
+  parse_escaped_char, tokenizer_failure, TokenizerFailure,
+};
+use crate::erl_syntax::parsers::token_stream::tok_strings::triple_quoted::parse_triple_quoted_string;
+use nom::error::{VerboseError, VerboseErrorKind};
+
+/// The value of a sigil string
+#[derive(Debug, PartialEq)]
+pub(crate) enum SigilString {
+  /// Produced by `~s` and `~S`
+  List(String),
+  /// Produced by `~`, `~b` and `~B`
+  Binary(String),
+}
+
+/// Closing character for a sigil opening delimiter
+fn closing_delimiter(open: char) -> Option<char> {
+  match open {
+    '(' => Some(')'),
+    '[' => Some(']'),
+    '{' => Some('}'),
+    '<' => Some('>'),
+    '/' | '|' | '\'' | '"' | '`' | '#' => Some(open),
+    _ => None,
+  }
+}
+
+/// Process the escape sequences in the text. If `close` is given, stop at the unescaped `close`
+/// character and return the rest of the input after it.
+fn unescape<'a>(
+  input: TokenizerInput<'a>,
+  close: Option<char>,
+) -> Result<(TokenizerInput<'a>, String), TokenizerFailure<'a>> {
+  let mut result = String::new();
+  let mut rest = input;
+  while let Some(c) = rest.chars().next() {
+    if Some(c) == close {
+      return Ok((&rest[c.len_utf8()..], result));
+    }
+    if c == '\\' {
+      match rest[1..].chars().next() {
+        Some(escaped) if Some(escaped) == close => {
+          result.push(escaped);
+          rest = &rest[1 + escaped.len_utf8()..];
+          continue;
+        }
+        _ => match parse_escaped_char(rest) {
+          Ok((tail, ch)) => {
+            result.push(ch);
+            rest = tail;
+            continue;
+          }
+          Err(_) => return Err(tokenizer_failure(rest, "unknown escape sequence in a sigil")),
+        },
+      }
+    }
+    result.push(c);
+    rest = &rest[c.len_utf8()..];
+  }
+  match close {
+    Some(_) => Err(tokenizer_failure(input, "a sigil string is not closed")),
+    None => Ok((rest, result)),
+  }
+}
+
+/// Parse a sigil string starting with `~`
+pub(crate) fn parse_sigil_string(input: TokenizerInput) -> TokensResult<SigilString> {
+  let not_a_sigil = || {
+    nom::Err::Error(VerboseError {
+      errors: vec![(input, VerboseErrorKind::Context("sigil"))],
+    })
+  };
+  let after_tilde = input.strip_prefix('~').ok_or_else(not_a_sigil)?;
+  let (prefix, body) = match after_tilde.chars().next() {
+    Some(p @ ('b' | 'B' | 's' | 'S')) => (Some(p), &after_tilde[1..]),
+    _ => (None, after_tilde),
+  };
+  let is_binary = !matches!(prefix, Some('s' | 'S'));
+
+  let (rest, text) = if body.starts_with("\"\"\"") {
+    let (rest, verbatim) = parse_triple_quoted_string(body)?;
+    match prefix {
+      Some('b' | 's') => (
+        rest,
+        unescape(&verbatim, None)
+          .map_err(|_| tokenizer_failure(body, "unknown escape sequence in a sigil"))?
+          .1,
+      ),
+      _ => (rest, verbatim),
+    }
+  } else {
+    let open = body.chars().next().ok_or_else(not_a_sigil)?;
+    let close = match closing_delimiter(open) {
+      Some(close) => close,
+      None => return Err(tokenizer_failure(body, "a sigil must be followed by a delimiter")),
+    };
+    let text_start = &body[open.len_utf8()..];
+    match prefix {
+      Some('B' | 'S') => match text_start.find(close) {
+        Some(end) => (&text_start[end + close.len_utf8()..], text_start[..end].to_string()),
+        None => return Err(tokenizer_failure(input, "a sigil string is not closed")),
+      },
+      _ => unescape(text_start, Some(close))?,
+    }
+  };
+
+  let value = if is_binary { SigilString::Binary(text) } else { SigilString::List(text) };
+  Ok((rest, value))
+}
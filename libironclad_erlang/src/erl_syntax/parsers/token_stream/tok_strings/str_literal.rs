@@ -6,14 +6,12 @@ use crate::erl_syntax::parsers::token_stream::tok_strings::shared;
 use crate::erl_syntax::parsers::token_stream::tok_strings::shared::{
   parse_escaped_whitespace, StringFragment,
 };
-use crate::typing::erl_integer::ErlInteger;
 use nom::branch::alt;
 use nom::bytes::complete::is_not;
-use nom::character::complete::{alphanumeric1, char, one_of};
-use nom::combinator::{map, recognize, value, verify};
-use nom::multi::{fold_many0, many0, many1};
-use nom::number::complete::recognize_float;
-use nom::sequence::{delimited, separated_pair, terminated};
+use nom::character::complete::char;
+use nom::combinator::{map, value, verify};
+use nom::multi::fold_many0;
+use nom::sequence::delimited;
 
 /// Parse a non-empty block of text that doesn't include \ or "
 fn parse_doublequot_literal<'a>(input: TokenizerInput<'a>) -> TokensResult<&'a str> {
@@ -68,50 +66,3 @@ pub(crate) fn parse_doublequot_string(input: TokenizerInput) -> TokensResult<Str
   // loop won't accidentally match your closing delimiter!
   delimited(ws_before(char('\"')), build_quoted_str_body, char('\"'))(input)
 }
-
-fn parse_int_unsigned_body(input: TokenizerInput) -> TokensResult<TokenizerInput> {
-  recognize(many1(terminated(one_of("0123456789"), many0(char('_')))))(input)
-}
-
-/// Parse an any-base integer `0-9, a-z` (check is done when parsing is complete)
-fn parse_based_int_unsigned_body(input: TokenizerInput) -> TokensResult<TokenizerInput> {
-  recognize(many1(terminated(alphanumeric1, many0(char('_')))))(input)
-}
-
-// /// Matches + or -
-// fn parse_sign(input: TokenizerInput) -> TokensResult<TokenizerInput> {
-//   recognize(alt((char('-'), char('+'))))(input)
-// }
-
-/// Parse a decimal integer, without a base prefix and sign
-pub fn parse_int_decimal(input: TokenizerInput) -> TokensResult<ErlInteger> {
-  let mk_int = |num: &str| -> ErlInteger {
-    ErlInteger::new_from_string(num).unwrap_or_else(|| panic!("Can't parse {} as integer", num))
-  };
-  map(parse_int_unsigned_body, mk_int)(input)
-}
-
-/// Parse a based integer `<BASE> # <NUMBER>` where base is `2..36`
-fn parse_based_int(input: TokenizerInput) -> TokensResult<ErlInteger> {
-  map(
-    separated_pair(parse_int_unsigned_body, char('#'), parse_based_int_unsigned_body),
-    |(base_str, value_str): (&str, &str)| -> ErlInteger {
-      let base = base_str.parse::<u32>().unwrap();
-      assert!((2..=36).contains(&base));
-      ErlInteger::new_from_string_radix(value_str, base).unwrap()
-    },
-  )(input)
-}
-
-/// Parse an integer without a sign. Supports based integers with `<RADIX> # <BODY>` and decimals.
-/// Sign is not parsed.
-pub(crate) fn parse_int_any_base(input: TokenizerInput) -> TokensResult<ErlInteger> {
-  alt((parse_based_int, parse_int_decimal))(input)
-}
-
-/// Recognize a float in the input and try parse it as `f64`.
-pub(crate) fn parse_float(input: TokenizerInput) -> TokensResult<f64> {
-  // TODO: handle a parse error or, better, fail the parser?
-  let mk_float = |fstr: TokenizerInput| fstr.parse::<f64>().unwrap();
-  map(recognize_float, mk_float)(input)
-}
@@ -0,0 +1,86 @@
+//! Parse OTP 27 triple-quoted strings. The opening `"""` must end its line, and the closing `"""`
+//! must be alone on its line. The indentation before the closing quotes is removed from every line
+//! of the text. More than three quotes can be used, to allow `"""` in the text.
+
+use crate::erl_syntax::parsers::token_stream::tok_input::{TokenizerInput, TokensResult};
+use crate::erl_syntax::parsers::token_stream::tok_strings::shared::{
+  tokenizer_failure, TokenizerFailure,
+};
+use nom::error::{VerboseError, VerboseErrorKind};
+
+/// Split off one line from `input`, returns the line without the line ending, and the rest of
+/// the input after the line ending. Returns `None` at the end of input without a line ending.
+fn split_line(input: &str) -> Option<(&str, &str)> {
+  let eol = input.find('\n')?;
+  let line = &input[..eol];
+  Some((line.strip_suffix('\r').unwrap_or(line), &input[eol + 1..]))
+}
+
+/// Parse a triple-quoted string, the text is taken verbatim: escape sequences are not processed.
+pub(crate) fn parse_triple_quoted_string(input: TokenizerInput) -> TokensResult<String> {
+  let quotes = input.chars().take_while(|c| *c == '"').count();
+  if quotes < 3 {
+    return Err(nom::Err::Error(VerboseError {
+      errors: vec![(input, VerboseErrorKind::Context("triple-quoted string"))],
+    }));
+  }
+  let delimiter = &input[..quotes];
+
+  // The rest of the opening line must be empty
+  let (first_line, mut rest) = match split_line(&input[quotes..]) {
+    Some(split) => split,
+    None => {
+      return Err(tokenizer_failure(input, "a triple-quoted string must span multiple lines"))
+    }
+  };
+  if !first_line.trim().is_empty() {
+    return Err(tokenizer_failure(input, "text is not allowed on the opening line of \"\"\""));
+  }
+
+  let mut lines: Vec<TokenizerInput> = Vec::new();
+  loop {
+    let line_end = rest.find('\n').unwrap_or(rest.len());
+    let line = &rest[..line_end];
+    let trimmed = line.trim_start_matches([' ', '\t']);
+
+    if let Some(after_close) = trimmed.strip_prefix(delimiter) {
+      if after_close.starts_with('"') {
+        return Err(tokenizer_failure(rest, "too many quotes closing a triple-quoted string"));
+      }
+      let indent = &line[..line.len() - trimmed.len()];
+      let closing_pos = indent.len() + delimiter.len();
+      let text = strip_indentation(&lines, indent)?;
+      return Ok((&rest[closing_pos..], text));
+    }
+
+    match split_line(rest) {
+      Some((line, next)) => {
+        lines.push(line);
+        rest = next;
+      }
+      None => return Err(tokenizer_failure(input, "a triple-quoted string is not closed")),
+    }
+  }
+}
+
+/// Remove the indentation of the closing quotes from every line and join the lines. Lines which are
+/// only whitespace become empty, other lines must begin with the same indentation.
+fn strip_indentation<'a>(
+  lines: &[TokenizerInput<'a>],
+  indent: &str,
+) -> Result<String, TokenizerFailure<'a>> {
+  let mut stripped = Vec::with_capacity(lines.len());
+  for line in lines.iter() {
+    if line.trim().is_empty() {
+      stripped.push("");
+    } else if let Some(text) = line.strip_prefix(indent) {
+      stripped.push(text);
+    } else {
+      return Err(tokenizer_failure(
+        line,
+        "a line in a triple-quoted string must begin with the indentation of the closing \"\"\"",
+      ));
+    }
+  }
+  Ok(stripped.join("\n"))
+}
@@ -3,6 +3,7 @@
 use crate::colored::Colorize;
 use crate::erl_syntax::parsers::token_stream::keyword::Keyword;
 use crate::erl_syntax::parsers::token_stream::token_kind::TokenKind;
+use crate::erl_syntax::preprocessor::pp_expansion::MacroExpansion;
 use crate::typing::erl_integer::ErlInteger;
 use std::ptr::null;
 
@@ -13,16 +14,23 @@ pub struct Token {
   pub offset: *const u8,
   /// The token itself
   pub kind: TokenKind,
+  /// If the token was pasted from a macro body, the macro invocation which produced it
+  pub expansion: Option<MacroExpansion>,
 }
 
 impl Token {
   /// For float and integer token returns its negative value
   pub fn negate(&self) -> Self {
     match &self.kind {
-      TokenKind::Float(f) => Self { offset: self.offset, kind: TokenKind::Float(-*f) },
+      TokenKind::Float(f) => Self {
+        offset: self.offset,
+        kind: TokenKind::Float(-*f),
+        expansion: self.expansion.clone(),
+      },
       TokenKind::Integer(ei) => Self {
         offset: self.offset,
         kind: TokenKind::Integer(ei.negate()),
+        expansion: self.expansion.clone(),
       },
       _other => panic!("Tokenizer: Attempt to flip sign of a non-numeric token {:?}", self),
     }
@@ -34,20 +42,24 @@ impl Token {
     Self {
       offset,
       kind: TokenKind::Keyword(k),
-      // last_in_line: false,
+      expansion: None,
     }
   }
 
   /// Create a new symbol token
   #[inline]
   pub fn new(offset: *const u8, tt: TokenKind) -> Self {
-    Self { offset, kind: tt }
+    Self { offset, kind: tt, expansion: None }
   }
 
   /// Create a new End of Line
   #[inline]
   pub fn new_eol() -> Self {
-    Self { offset: null(), kind: TokenKind::EOL }
+    Self {
+      offset: null(),
+      kind: TokenKind::EOL,
+      expansion: None,
+    }
   }
 
   /// Create a new token for small integer
@@ -56,19 +68,28 @@ impl Token {
     Self {
       offset: null(),
       kind: TokenKind::Integer(ErlInteger::Small(i)),
+      expansion: None,
     }
   }
 
   /// Create a new token for string
   #[inline]
   pub fn new_string(s: String) -> Self {
-    Self { offset: null(), kind: TokenKind::Str(s.into()) }
+    Self {
+      offset: null(),
+      kind: TokenKind::Str(s.into()),
+      expansion: None,
+    }
   }
 
   /// Create a new token for atom
   #[inline]
   pub fn new_atom(s: String) -> Self {
-    Self { offset: null(), kind: TokenKind::Atom(s) }
+    Self {
+      offset: null(),
+      kind: TokenKind::Atom(s),
+      expansion: None,
+    }
   }
 
   /// Check whether the token is a newline token
@@ -118,6 +139,12 @@ impl Token {
     }
   }
 
+  /// Check whether the token is a span of input which could not be tokenized
+  #[inline]
+  pub fn is_invalid(&self) -> bool {
+    matches!(self.kind, TokenKind::Invalid(_))
+  }
+
   /// Check whether the token is a macro invocation
   #[inline]
   pub fn is_macro_invocation(&self) -> bool {
@@ -70,6 +70,12 @@ pub enum TokenKind {
   LeftDoubleArr,
   /// `<-` single left arrow
   LeftArr,
+  /// `<:-` strict list generator arrow
+  StrictLeftArr,
+  /// `<:=` strict binary generator arrow
+  StrictLeftDoubleArr,
+  /// `&&` joins the generators of a zip generator
+  DoubleAmpersand,
   /// A send operation `!`
   Send,
   /// `(` opening parenthesis
@@ -97,6 +103,8 @@ pub enum TokenKind {
   Underscore,
   /// A parsed string token_stream between `" TEXT "`
   Str(Arc<String>),
+  /// A sigil string which produces a binary: `~"TEXT"`, `~b[TEXT]`, `~B<TEXT>`
+  BinaryStr(Arc<String>),
   /// `% text` a line comment block
   Comment(Arc<String>),
   /// A `$`-prefixed any character
@@ -124,6 +132,8 @@ pub enum TokenKind {
   MacroStringifyArg(String),
   /// Something that was parsed like `- <NAME> ( SOMETHING... ) .`
   Preprocessor(PreprocessorNode),
+  /// A span of input text which could not be tokenized, an error is reported for it
+  Invalid(Arc<String>),
 }
 
 impl TokenKind {
@@ -170,6 +180,9 @@ impl TokenKind {
       TokenKind::Keyword(_) => "a keyword",
       TokenKind::LeftArr => "left arrow",
       TokenKind::LeftDoubleArr => "double left arrow",
+      TokenKind::StrictLeftArr => "strict left arrow",
+      TokenKind::StrictLeftDoubleArr => "strict double left arrow",
+      TokenKind::DoubleAmpersand => "double ampersand",
       TokenKind::LessThanEq => "less than or equal to",
       TokenKind::ListAppend => "double plus",
       TokenKind::ListSubtract => "double minus",
@@ -190,10 +203,12 @@ impl TokenKind {
       TokenKind::SquareClose => "closing square bracket",
       TokenKind::SquareOpen => "opening square bracket",
       TokenKind::Str(_) => "a string literal",
+      TokenKind::BinaryStr(_) => "a binary string sigil",
       TokenKind::Underscore => "underscore",
       TokenKind::Variable(_) => "a variable",
       TokenKind::VerticalBar => "vertical bar",
       TokenKind::EndOfInput => "end of input",
+      TokenKind::Invalid(_) => "invalid input",
     }
   }
 }
@@ -230,6 +245,9 @@ impl std::fmt::Display for TokenKind {
       TokenKind::Keyword(kw) => write!(f, " {}", kw),
       TokenKind::LeftArr => write!(f, "←"),
       TokenKind::LeftDoubleArr => write!(f, "⇐"),
+      TokenKind::StrictLeftArr => write!(f, "<:-"),
+      TokenKind::StrictLeftDoubleArr => write!(f, "<:="),
+      TokenKind::DoubleAmpersand => write!(f, "&&"),
       TokenKind::AngleOpen => write!(f, "<"),
       TokenKind::LessThanEq => write!(f, "≤"),
       TokenKind::ListAppend => write!(f, "⊕"),
@@ -251,10 +269,15 @@ impl std::fmt::Display for TokenKind {
       TokenKind::SquareClose => write!(f, "]"),
       TokenKind::SquareOpen => write!(f, "["),
       TokenKind::Str(s) => Pretty::doublequot_string(f, s),
+      TokenKind::BinaryStr(s) => {
+        write!(f, "~")?;
+        Pretty::doublequot_string(f, s)
+      }
       TokenKind::Underscore => write!(f, "_"),
       TokenKind::Variable(v) => v.fmt(f),
       TokenKind::VerticalBar => write!(f, "∣"),
       TokenKind::EndOfInput => write!(f, "<EOF>"),
+      TokenKind::Invalid(text) => write!(f, "<invalid «{}»>", text),
     }
   }
 }
@@ -90,12 +90,6 @@ impl<'a> TokenLinesIter<'a> {
     }
   }
 
-  /// Override to a new token stream, in case when it needs to be modified while iterating.
-  /// Example: Include files are pasted into the stream
-  pub(crate) fn set_base(&mut self, new_base: &[Token]) {
-    self.base = (new_base.as_ptr(), new_base.len());
-  }
-
   /// Check whether we have reached the end of the input. Consider `pos` + `slice_len`, as the last
   /// returned slice.
   #[inline]
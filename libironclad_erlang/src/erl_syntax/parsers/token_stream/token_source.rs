@@ -0,0 +1,216 @@
+//! Printing a token stream back as Erlang source text, for the preprocess-only output mode
+
+use crate::erl_syntax::parsers::token_stream::keyword::Keyword;
+use crate::erl_syntax::parsers::token_stream::token::Token;
+use crate::erl_syntax::parsers::token_stream::token_kind::TokenKind;
+use crate::typing::erl_integer::ErlInteger;
+use std::fmt::Write;
+
+/// Words which must be quoted when used as an atom
+const RESERVED_WORDS: [&str; 29] = [
+  "after", "and", "andalso", "band", "begin", "bnot", "bor", "bsl", "bsr", "bxor", "case", "catch",
+  "cond", "div", "else", "end", "fun", "if", "let", "maybe", "not", "of", "or", "orelse",
+  "receive", "rem", "try", "when", "xor",
+];
+
+/// Whether the atom can be printed without single quotes
+fn atom_needs_no_quotes(a: &str) -> bool {
+  let mut chars = a.chars();
+  match chars.next() {
+    Some(first) if first.is_ascii_lowercase() => {}
+    _ => return false,
+  }
+  chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '@') && !RESERVED_WORDS.contains(&a)
+}
+
+/// Write a character inside a quoted atom or a string, escaping where Erlang requires it
+fn write_escaped_char(out: &mut String, ch: char, quote: char) {
+  match ch {
+    '\\' => out.push_str("\\\\"),
+    '\n' => out.push_str("\\n"),
+    '\r' => out.push_str("\\r"),
+    '\t' => out.push_str("\\t"),
+    c if c == quote => {
+      out.push('\\');
+      out.push(c)
+    }
+    c if c.is_control() => write!(out, "\\x{{{:X}}}", c as u32).unwrap(),
+    c => out.push(c),
+  }
+}
+
+/// Write a quoted atom or a string
+fn write_quoted(out: &mut String, s: &str, quote: char) {
+  out.push(quote);
+  s.chars().for_each(|ch| write_escaped_char(out, ch, quote));
+  out.push(quote);
+}
+
+/// Write a float so that Erlang reads it back as a float: digits are required on both sides of the
+/// decimal point, also when an exponent is present.
+fn write_float(out: &mut String, f: f64) {
+  let text = format!("{:?}", f);
+  if text.contains('.') {
+    out.push_str(&text)
+  } else if let Some(e_pos) = text.find('e') {
+    write!(out, "{}.0{}", &text[..e_pos], &text[e_pos..]).unwrap()
+  } else {
+    write!(out, "{}.0", text).unwrap()
+  }
+}
+
+impl TokenKind {
+  /// Append the token as Erlang source text
+  pub fn write_source(&self, out: &mut String) {
+    match self {
+      TokenKind::EndOfInput => {}
+      TokenKind::EOL => out.push('\n'),
+      TokenKind::Comma => out.push(','),
+      TokenKind::Semicolon => out.push(';'),
+      TokenKind::Assign => out.push_str(":="),
+      TokenKind::Colon => out.push(':'),
+      TokenKind::ColonColon => out.push_str("::"),
+      TokenKind::Period => out.push('.'),
+      TokenKind::PeriodPeriod => out.push_str(".."),
+      TokenKind::Plus => out.push('+'),
+      TokenKind::Minus => out.push('-'),
+      TokenKind::ForwardSlash => out.push('/'),
+      TokenKind::Asterisk => out.push('*'),
+      TokenKind::ListAppend => out.push_str("++"),
+      TokenKind::ListSubtract => out.push_str("--"),
+      TokenKind::EqualEqual => out.push_str("=="),
+      TokenKind::NotEq => out.push_str("/="),
+      TokenKind::LessThanEq => out.push_str("=<"),
+      TokenKind::AngleOpen => out.push('<'),
+      TokenKind::GreaterEq => out.push_str(">="),
+      TokenKind::AngleClose => out.push('>'),
+      TokenKind::HardEq => out.push_str("=:="),
+      TokenKind::HardNotEq => out.push_str("=/="),
+      TokenKind::EqualSymbol => out.push('='),
+      TokenKind::Ellipsis => out.push_str("..."),
+      TokenKind::RightDoubleArr => out.push_str("=>"),
+      TokenKind::RightArr => out.push_str("->"),
+      TokenKind::LeftDoubleArr => out.push_str("<="),
+      TokenKind::LeftArr => out.push_str("<-"),
+      TokenKind::StrictLeftArr => out.push_str("<:-"),
+      TokenKind::StrictLeftDoubleArr => out.push_str("<:="),
+      TokenKind::DoubleAmpersand => out.push_str("&&"),
+      TokenKind::Send => out.push('!'),
+      TokenKind::ParOpen => out.push('('),
+      TokenKind::ParClose => out.push(')'),
+      TokenKind::SquareOpen => out.push('['),
+      TokenKind::SquareClose => out.push(']'),
+      TokenKind::CurlyOpen => out.push('{'),
+      TokenKind::CurlyClose => out.push('}'),
+      TokenKind::DoubleAngleOpen => out.push_str("<<"),
+      TokenKind::DoubleAngleClose => out.push_str(">>"),
+      TokenKind::Hash => out.push('#'),
+      TokenKind::VerticalBar => out.push('|'),
+      TokenKind::DoubleVerticalBar => out.push_str("||"),
+      TokenKind::Underscore => out.push('_'),
+      TokenKind::Str(s) => write_quoted(out, s, '"'),
+      TokenKind::BinaryStr(s) => {
+        out.push('~');
+        write_quoted(out, s, '"')
+      }
+      TokenKind::Invalid(text) => out.push_str(text),
+      TokenKind::Comment(c) => write!(out, "%{}", c).unwrap(),
+      TokenKind::Character(' ') => out.push_str("$\\s"),
+      TokenKind::Character('\\') => out.push_str("$\\\\"),
+      TokenKind::Character(c) if c.is_control() => write!(out, "$\\x{{{:X}}}", *c as u32).unwrap(),
+      TokenKind::Character(c) => write!(out, "${}", c).unwrap(),
+      TokenKind::EscapedCharacter { in_source, .. } => write!(out, "$\\{}", in_source).unwrap(),
+      TokenKind::Atom(a) if atom_needs_no_quotes(a) => out.push_str(a),
+      TokenKind::Atom(a) => write_quoted(out, a, '\''),
+      TokenKind::Variable(v) => out.push_str(v),
+      TokenKind::Keyword(kw) => out.push_str(kw.to_str()),
+      TokenKind::Integer(ErlInteger::Small(i)) => write!(out, "{}", i).unwrap(),
+      TokenKind::Integer(big) => write!(out, "{}", big.to_bigint()).unwrap(),
+      TokenKind::Float(f) => write_float(out, *f),
+      TokenKind::MacroInvocation(name) => write!(out, "?{}", name).unwrap(),
+      TokenKind::MacroStringifyArg(arg) => write!(out, "??{}", arg).unwrap(),
+      TokenKind::Preprocessor(pp) => write!(out, "{}", pp).unwrap(),
+    }
+  }
+}
+
+/// Whether a space is required or desired between two adjacent tokens on the same line
+fn space_between(prev: &TokenKind, next: &TokenKind) -> bool {
+  match (prev, next) {
+    (_, TokenKind::EOL) => false,
+    (
+      TokenKind::ParOpen
+      | TokenKind::SquareOpen
+      | TokenKind::CurlyOpen
+      | TokenKind::DoubleAngleOpen
+      | TokenKind::Hash
+      | TokenKind::Colon,
+      _,
+    ) => false,
+    (
+      _,
+      TokenKind::Comma
+      | TokenKind::Semicolon
+      | TokenKind::Period
+      | TokenKind::Colon
+      | TokenKind::ParClose
+      | TokenKind::SquareClose
+      | TokenKind::CurlyClose,
+    ) => false,
+    // `> >>` must stay separated, otherwise it reads as `>> >`
+    (TokenKind::AngleClose, TokenKind::DoubleAngleClose) => true,
+    (_, TokenKind::DoubleAngleClose) => false,
+    (
+      TokenKind::Atom(_)
+      | TokenKind::Variable(_)
+      | TokenKind::MacroInvocation(_)
+      | TokenKind::ParClose
+      | TokenKind::Keyword(Keyword::Fun),
+      TokenKind::ParOpen,
+    ) => false,
+    _ => true,
+  }
+}
+
+/// Print a token stream as Erlang source text. Line breaks follow the `EOL` tokens in the stream,
+/// and runs of empty lines are collapsed into one.
+pub fn format_tokens_as_source(tokens: &[Token]) -> String {
+  let mut out = String::new();
+  let mut prev: Option<&TokenKind> = None;
+  // Count of `EOL` tokens since the last printed token, and whether the line began with `-`
+  let mut newlines = 0;
+  let mut attr_start = false;
+
+  for tok in tokens.iter() {
+    match &tok.kind {
+      TokenKind::EOL => {
+        newlines += 1;
+        if newlines <= 2 && !out.is_empty() {
+          out.push('\n');
+        }
+        prev = None;
+        continue;
+      }
+      TokenKind::EndOfInput => continue,
+      kind => {
+        if let Some(p) = prev {
+          // Attributes are printed `-name` without space after the minus
+          if space_between(p, kind) && !attr_start {
+            out.push(' ');
+          }
+          attr_start = false;
+        } else {
+          attr_start = matches!(kind, TokenKind::Minus);
+        }
+        kind.write_source(&mut out);
+        newlines = 0;
+        prev = Some(kind);
+      }
+    }
+  }
+
+  if !out.is_empty() && !out.ends_with('\n') {
+    out.push('\n');
+  }
+  out
+}
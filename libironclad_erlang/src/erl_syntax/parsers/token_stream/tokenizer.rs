@@ -1,5 +1,6 @@
 //! Nom parser breaking input text into `ErlToken`s
 
+use crate::erl_syntax::parsers::error_report::tokenizer_error_message;
 use crate::erl_syntax::parsers::token_stream::keyword::Keyword;
 use crate::erl_syntax::parsers::token_stream::misc::{
   bigcapacity_many0, ident_continuation, line_comment, parse_macro_ident, parse_varname,
@@ -7,20 +8,28 @@ use crate::erl_syntax::parsers::token_stream::misc::{
 };
 use crate::erl_syntax::parsers::token_stream::tok_input::{TokenizerInput, TokensResult};
 use crate::erl_syntax::parsers::token_stream::tok_strings::atom_literal::parse_tok_atom;
-use crate::erl_syntax::parsers::token_stream::tok_strings::str_literal::{
-  parse_doublequot_string, parse_float, parse_int_any_base, parse_int_decimal,
+use crate::erl_syntax::parsers::token_stream::tok_strings::num_literal::{
+  parse_number_literal, NumberLiteral,
 };
+use crate::erl_syntax::parsers::token_stream::tok_strings::shared::{
+  escape_value, parse_code_escape,
+};
+use crate::erl_syntax::parsers::token_stream::tok_strings::sigil::{
+  parse_sigil_string, SigilString,
+};
+use crate::erl_syntax::parsers::token_stream::tok_strings::str_literal::parse_doublequot_string;
+use crate::erl_syntax::parsers::token_stream::tok_strings::triple_quoted::parse_triple_quoted_string;
 use crate::erl_syntax::parsers::token_stream::tok_strings::Char;
 use crate::erl_syntax::parsers::token_stream::token::Token;
 use crate::erl_syntax::parsers::token_stream::token_kind::TokenKind;
-use crate::typing::erl_integer::ErlInteger;
 use nom::branch::alt;
 use nom::bytes::complete::tag;
 use nom::character::complete::{anychar, char};
-use nom::combinator::{complete, cut, map, not, opt, peek, recognize};
+use nom::combinator::{complete, cut, map, not, peek, recognize};
 use nom::error::context;
-use nom::sequence::{pair, preceded, separated_pair, terminated};
+use nom::sequence::{preceded, terminated};
 use nom::Parser;
+use std::ops::Range;
 
 #[inline]
 fn tokenize_line_comment(input: TokenizerInput) -> TokensResult<Token> {
@@ -44,44 +53,17 @@ fn tokenize_variable_name(input: TokenizerInput) -> TokensResult<Token> {
   map(parse_varname, mk_var)(input)
 }
 
-fn tokenize_single_integer(input: TokenizerInput) -> TokensResult<Token> {
-  map(parse_int_any_base, |i: ErlInteger| {
-    Token::new(input.as_ptr(), TokenKind::Integer(i))
-  })(input)
-}
-
-fn tokenize_two_integers_with_dot(input: TokenizerInput) -> TokensResult<Token> {
-  map(
-    recognize(separated_pair(parse_int_decimal, char('.'), parse_int_decimal)),
-    |fstr| {
-      let f = fstr.parse::<f64>().unwrap();
-      Token::new(input.as_ptr(), TokenKind::Float(f))
-    },
-  )(input)
-}
-
-#[inline]
-fn tokenize_integer(input: TokenizerInput) -> TokensResult<Token> {
-  map(
-    pair(
-      opt(alt((char('-'), char('+')))),
-      alt((tokenize_two_integers_with_dot, tokenize_single_integer)),
-    ),
-    |(maybe_sign, tok)| {
-      if let Some(sign) = maybe_sign {
-        if sign == '-' {
-          return tok.negate();
-        }
-        return tok;
-      }
-      tok
-    },
-  )(input)
-}
-
+/// Parse a number, and produce an `Integer()` or a `Float()` token. A preceding sign is a separate
+/// token, the parser applies it as a unary operator.
 #[inline]
-fn tokenize_float(input: TokenizerInput) -> TokensResult<Token> {
-  map(parse_float, |f: f64| Token::new(input.as_ptr(), TokenKind::Float(f)))(input)
+fn tokenize_number(input: TokenizerInput) -> TokensResult<Token> {
+  map(parse_number_literal, |number| {
+    let kind = match number {
+      NumberLiteral::Integer(i) => TokenKind::Integer(i),
+      NumberLiteral::Float(f) => TokenKind::Float(f),
+    };
+    Token::new(input.as_ptr(), kind)
+  })(input)
 }
 
 #[inline]
@@ -157,7 +139,7 @@ fn symbol_hardeq(input: TokenizerInput) -> TokensResult<Token> {
 
 #[inline]
 fn symbol_equalequal(input: TokenizerInput) -> TokensResult<Token> {
-  map(tag("=="), |_| Token::new(input.as_ptr(), TokenKind::HardEq))(input)
+  map(tag("=="), |_| Token::new(input.as_ptr(), TokenKind::EqualEqual))(input)
 }
 
 #[inline]
@@ -175,6 +157,21 @@ fn symbol_leftdoublearr(input: TokenizerInput) -> TokensResult<Token> {
   map(tag("<="), |_| Token::new(input.as_ptr(), TokenKind::LeftDoubleArr))(input)
 }
 
+#[inline]
+fn symbol_strictleftarr(input: TokenizerInput) -> TokensResult<Token> {
+  map(tag("<:-"), |_| Token::new(input.as_ptr(), TokenKind::StrictLeftArr))(input)
+}
+
+#[inline]
+fn symbol_strictleftdoublearr(input: TokenizerInput) -> TokensResult<Token> {
+  map(tag("<:="), |_| Token::new(input.as_ptr(), TokenKind::StrictLeftDoubleArr))(input)
+}
+
+#[inline]
+fn symbol_doubleampersand(input: TokenizerInput) -> TokensResult<Token> {
+  map(tag("&&"), |_| Token::new(input.as_ptr(), TokenKind::DoubleAmpersand))(input)
+}
+
 #[inline]
 fn symbol_lessthan(input: TokenizerInput) -> TokensResult<Token> {
   // TODO? and not =, -, etc
@@ -301,29 +298,38 @@ fn tokenize_string(input: TokenizerInput) -> TokensResult<Token> {
   map(parse_doublequot_string, map_fn)(input)
 }
 
+/// Parse a `"""`-quoted string, produces a `Str()` token with the text taken verbatim
 #[inline]
-fn dollar_escaped_character(input: TokenizerInput) -> TokensResult<Token> {
-  // let map_fn = |s: TokenizerInput| s.chars().next().unwrap();
-  let map_backquot_fn = |c: Char| -> Token {
-    let value = match c {
-      'n' => '\n',
-      'r' => '\r',
-      't' => '\t',
-      'b' => '\x08',
-      'f' => '\x0c',
-      'a' => '\x07',
-      'e' => '\x1b',
-      '\\' => '\\',
-      _ => panic!(
-        "Unexpected backquoted character, only allowed: \\n \\r \\t \\b \\f \\a \\e and \\\\"
-      ),
+fn tokenize_triple_quoted(input: TokenizerInput) -> TokensResult<Token> {
+  let map_fn = |s: String| Token::new(input.as_ptr(), TokenKind::Str(s.into()));
+  map(parse_triple_quoted_string, map_fn)(input)
+}
+
+/// Parse a `~`-prefixed sigil string, produces a `Str()` or a `BinaryStr()` token
+#[inline]
+fn tokenize_sigil(input: TokenizerInput) -> TokensResult<Token> {
+  let map_fn = |s: SigilString| {
+    let kind = match s {
+      SigilString::List(text) => TokenKind::Str(text.into()),
+      SigilString::Binary(text) => TokenKind::BinaryStr(text.into()),
     };
-    Token::new(input.as_ptr(), TokenKind::EscapedCharacter { value, in_source: c })
+    Token::new(input.as_ptr(), kind)
   };
+  map(parse_sigil_string, map_fn)(input)
+}
 
-  // Recognize a $ \\ <character> to produce a \n \r \t \b \f \a \e and \
-  // or a $ <character> to produce the character itself.
-  map(anychar, map_backquot_fn)(input)
+/// Recognize a `$\` escape: a character code escape like `$\x{41}` or `$\^A` produces a
+/// `Character()` token, a single character escape like `$\n` produces an `EscapedCharacter()`.
+#[inline]
+fn dollar_escaped_character(input: TokenizerInput) -> TokensResult<Token> {
+  let code_escape_fn = |c: Char| Token::new(input.as_ptr(), TokenKind::Character(c));
+  let single_escape_fn = |c: Char| {
+    Token::new(
+      input.as_ptr(),
+      TokenKind::EscapedCharacter { value: escape_value(c), in_source: c },
+    )
+  };
+  alt((map(parse_code_escape, code_escape_fn), map(anychar, single_escape_fn)))(input)
 }
 
 /// Parse a `$`-prefixed character, or `$\`-prefixed character, and produce `Character()` token
@@ -367,15 +373,17 @@ fn tokenize_other_symbols(input: TokenizerInput) -> TokensResult<Token> {
       symbol_period,           // .
     )),
     alt((
-      symbol_leftarr,         // < -
-      symbol_doubleangleopen, // <<
-      symbol_leftdoublearr,   // < =
-      symbol_lessthan,        // <
-      symbol_listappend,      // ++
-      symbol_noteq,           // / =
-      symbol_parclose,        // )
-      symbol_paropen,         // (
-      symbol_underscore,      // _
+      symbol_leftarr,             // < -
+      symbol_doubleangleopen,     // <<
+      symbol_leftdoublearr,       // < =
+      symbol_strictleftarr,       // < : -
+      symbol_strictleftdoublearr, // < : =
+      symbol_lessthan,            // <
+      symbol_listappend,          // ++
+      symbol_noteq,               // / =
+      symbol_parclose,            // )
+      symbol_paropen,             // (
+      symbol_underscore,          // _
     )),
     alt((
       symbol_rightarr,       // - >
@@ -388,14 +396,15 @@ fn tokenize_other_symbols(input: TokenizerInput) -> TokensResult<Token> {
       symbol_equalsymbol,    // =
     )),
     alt((
-      symbol_mul,         // *
-      symbol_hash,        // #
-      symbol_barbar,      // ||
-      symbol_bar,         // |
-      symbol_semicolon,   // ;
-      symbol_send,        // !
-      symbol_squareclose, // ]
-      symbol_squareopen,  // [
+      symbol_mul,             // *
+      symbol_hash,            // #
+      symbol_barbar,          // ||
+      symbol_doubleampersand, // &&
+      symbol_bar,             // |
+      symbol_semicolon,       // ;
+      symbol_send,            // !
+      symbol_squareclose,     // ]
+      symbol_squareopen,      // [
     )),
     alt((
       symbol_coloncolon, // ::
@@ -614,23 +623,107 @@ fn tokenize_keyword(input: TokenizerInput) -> TokensResult<Token> {
   )(input)
 }
 
-/// Break module source into tokens
-pub fn tokenize_source(input: TokenizerInput) -> TokensResult<Vec<Token>> {
-  // Comments after the code are consumed by the outer ws_mut
-  // Comments and spaces between the tokens are consumed by the inner ws_before_mut
-  complete(ws_mut(bigcapacity_many0(ws_before_mut(alt((
+/// Recognize one token
+fn tokenize_token(input: TokenizerInput) -> TokensResult<Token> {
+  alt((
     tokenize_line_comment,
     tokenize_newline,
     tokenize_macro_stringify_arg,
     tokenize_macro_invocation,
     tokenize_dollar_character,
+    tokenize_triple_quoted,
     tokenize_string,
+    tokenize_sigil,
     tokenize_keyword,
     tokenize_atom,
     tokenize_variable_name,
-    tokenize_integer,
-    tokenize_float,
+    tokenize_number,
     tokenize_plus_minus,
     tokenize_other_symbols,
-  ))))))(input)
+  ))(input)
+}
+
+/// Break module source into tokens
+pub fn tokenize_source(input: TokenizerInput) -> TokensResult<Vec<Token>> {
+  // Comments after the code are consumed by the outer ws_mut
+  // Comments and spaces between the tokens are consumed by the inner ws_before_mut
+  complete(ws_mut(bigcapacity_many0(ws_before_mut(tokenize_token))))(input)
+}
+
+/// Length of the text to skip after a tokenizer error at `bad`. An unterminated string or quoted
+/// atom is skipped to the end of the line, other errors skip the rest of the word, or one
+/// character.
+fn bad_span_length(bad: TokenizerInput, failure_at: Option<TokenizerInput>) -> usize {
+  if bad.starts_with(['"', '\'']) {
+    return bad.find(['\r', '\n']).unwrap_or(bad.len());
+  }
+  let first_len = bad.chars().next().map(char::len_utf8).unwrap_or(0);
+  let word_start = match failure_at {
+    Some(at) if at.as_ptr() > bad.as_ptr() => bad.len() - at.len(),
+    Some(_) => first_len,
+    None => return first_len,
+  };
+  let mut chars = bad[word_start..].char_indices().peekable();
+  while let Some((pos, c)) = chars.next() {
+    let is_word = c.is_alphanumeric()
+      || c == '_'
+      || c == '#'
+      || (c == '.' && matches!(chars.peek(), Some((_, next)) if next.is_alphanumeric()));
+    if !is_word {
+      return word_start + pos;
+    }
+  }
+  bad.len()
+}
+
+/// Error messages from the tokenizer, with the `Invalid()` tokens they refer to
+pub type TokenizerErrors = Vec<(Token, String)>;
+
+/// Break module source into tokens, never fails. A span which can't be tokenized becomes an
+/// `Invalid()` token, and the tokenizing continues after it. Returns the tokens and the error
+/// messages with the invalid tokens they refer to.
+pub fn tokenize_source_recovering(input: TokenizerInput) -> (Vec<Token>, TokenizerErrors) {
+  let (spans, errors) = tokenize_spans_recovering(input);
+  (spans.into_iter().map(|(tok, _)| tok).collect(), errors)
+}
+
+/// Same as `tokenize_source_recovering`, but also returns the byte range in `input` for every
+/// token. Spaces and tabs between the tokens are not covered by any range.
+pub(crate) fn tokenize_spans_recovering(
+  input: TokenizerInput,
+) -> (Vec<(Token, Range<usize>)>, TokenizerErrors) {
+  let mut tokens = Vec::with_capacity(input.len() / 4);
+  let mut errors = Vec::new();
+  let mut rest = input;
+  loop {
+    let start = rest.trim_start_matches([' ', '\t']);
+    let start_pos = input.len() - start.len();
+    match tokenize_token(start) {
+      Ok((tail, tok)) => {
+        tokens.push((tok, start_pos..input.len() - tail.len()));
+        rest = tail;
+      }
+      Err(_) if start.is_empty() => break,
+      Err(err) => {
+        let bad = start;
+        let (message, failure_at) = match &err {
+          nom::Err::Failure(e) => (tokenizer_error_message(e), e.errors.first().map(|(at, _)| *at)),
+          nom::Err::Error(_) if bad.starts_with('"') => {
+            ("string is not terminated".to_string(), None)
+          }
+          nom::Err::Error(_) if bad.starts_with('\'') => {
+            ("quoted atom is not terminated".to_string(), None)
+          }
+          nom::Err::Error(e) => (tokenizer_error_message(e), None),
+          nom::Err::Incomplete(_) => ("unexpected end of input".to_string(), None),
+        };
+        let skip = bad_span_length(bad, failure_at).max(1).min(bad.len());
+        let invalid = Token::new(bad.as_ptr(), TokenKind::Invalid(bad[..skip].to_string().into()));
+        errors.push((invalid.clone(), message));
+        tokens.push((invalid, start_pos..start_pos + skip));
+        rest = &bad[skip..];
+      }
+    }
+  }
+  (tokens, errors)
 }
@@ -1,5 +1,6 @@
 //! Grouping preprocessor code
 pub mod parsers;
 pub mod pp_define;
+pub mod pp_expansion;
 pub mod pp_name_arity;
 pub mod pp_node;
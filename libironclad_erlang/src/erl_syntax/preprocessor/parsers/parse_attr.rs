@@ -6,7 +6,7 @@ use crate::erl_syntax::parsers::misc::{
 };
 use crate::erl_syntax::parsers::misc_tok::*;
 use crate::erl_syntax::parsers::parse_expr::parse_expr;
-use crate::erl_syntax::parsers::parse_type::parse_fn_t::parse_fn_spec;
+use crate::erl_syntax::parsers::parse_type::parse_fn_t::{parse_callback_spec, parse_fn_spec};
 use crate::erl_syntax::parsers::parse_type::parse_type;
 use crate::erl_syntax::parsers::parser_input::ParserInput;
 use crate::erl_syntax::preprocessor::parsers::parse_record::parse_record_def;
@@ -15,10 +15,10 @@ use crate::erl_syntax::preprocessor::pp_node::PreprocessorNode;
 use crate::source_loc::SourceLoc;
 use libironclad_util::mfarity::MFArity;
 use nom::branch::alt;
-use nom::combinator::{cut, map};
+use nom::combinator::{cut, map, value};
 use nom::error::context;
 use nom::multi::{separated_list0, separated_list1};
-use nom::sequence::{delimited, pair, preceded, separated_pair, tuple};
+use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
 
 /// Parse a `()` for a generic attribute `-<atom>().` and return empty `ErlAst`
 fn attr_body_empty_parens(input: ParserInput) -> ParserResult<Option<AstNode>> {
@@ -108,6 +108,60 @@ pub(crate) fn export_type_attr(input: ParserInput) -> ParserResult<PreprocessorN
   )(input.clone())
 }
 
+/// Parses an `-optional_callbacks([fn/arity, ...]).` attribute.
+/// Dash `-` and trailing `.` are matched outside by the caller.
+pub(crate) fn optional_callbacks_attr(input: ParserInput) -> ParserResult<PreprocessorNode> {
+  map(
+    delimited(
+      |i1| dash_atom(i1, "optional_callbacks"),
+      context(
+        "list of callbacks in an -optional_callbacks() attribute",
+        cut(delimited(
+          tok_par_open,
+          delimited(tok_square_open, separated_list0(tok_comma, parse_funarity), tok_square_close),
+          tok_par_close,
+        )),
+      ),
+      period_eol_eof,
+    ),
+    |t| PreprocessorNodeImpl::new_optional_callbacks_attr(SourceLoc::new(&input), t),
+  )(input.clone())
+}
+
+/// Parses a `-doc VALUE.` or a `-moduledoc VALUE.` attribute, the value can be in parentheses.
+/// Dash `-` and trailing `.` are matched outside by the caller.
+pub(crate) fn doc_attr(input: ParserInput) -> ParserResult<PreprocessorNode> {
+  map(
+    terminated(
+      pair(
+        alt((
+          value(false, |i1| dash_atom(i1, "doc")),
+          value(true, |i1| dash_atom(i1, "moduledoc")),
+        )),
+        context("documentation in a -doc() attribute", cut(parse_expr)),
+      ),
+      period_eol_eof,
+    ),
+    |(module_doc, doc)| PreprocessorNodeImpl::new_doc_attr(SourceLoc::new(&input), module_doc, doc),
+  )(input.clone())
+}
+
+/// Parses a `-behaviour(module).` or a `-behavior(module).` attribute.
+/// Dash `-` and trailing `.` are matched outside by the caller.
+pub(crate) fn behaviour_attr(input: ParserInput) -> ParserResult<PreprocessorNode> {
+  map(
+    delimited(
+      alt((|i1| dash_atom(i1, "behaviour"), |i1| dash_atom(i1, "behavior"))),
+      context(
+        "behaviour module name in a -behaviour() attribute",
+        cut(delimited(tok_par_open, tok_atom, tok_par_close)),
+      ),
+      period_eol_eof,
+    ),
+    |name| PreprocessorNodeImpl::new_behaviour_attr(SourceLoc::new(&input), name),
+  )(input.clone())
+}
+
 /// Parses an `-import(module [fn/arity, ...]).` attribute.
 /// Dash `-` and trailing `.` are matched outside by the caller.
 pub(crate) fn import_attr(input: ParserInput) -> ParserResult<PreprocessorNode> {
@@ -138,27 +192,38 @@ pub(crate) fn parse_parenthesized_list_of_vars(input: ParserInput) -> ParserResu
   )(input)
 }
 
-/// Parses a `-type IDENT(ARG, ...) :: TYPE.` attribute.
+/// Parses a `-type IDENT(ARG, ...) :: TYPE.` or an `-opaque IDENT(ARG, ...) :: TYPE.` attribute.
 /// Dash `-` and trailing `.` are matched outside by the caller.
 pub fn parse_new_type_attr(input: ParserInput) -> ParserResult<PreprocessorNode> {
   map(
-    delimited(
-      |i1| dash_atom(i1, "type"),
-      context(
-        "new type definition",
-        cut(tuple((
-          tok_atom,
-          parse_parenthesized_list_of_vars,
-          preceded(
-            tok_double_colon,
-            context("type in a -type() definition attribute", cut(parse_type)),
-          ),
-        ))),
+    terminated(
+      pair(
+        alt((
+          value(false, |i1| dash_atom(i1, "type")),
+          value(true, |i1| dash_atom(i1, "opaque")),
+        )),
+        context(
+          "new type definition",
+          cut(tuple((
+            tok_atom,
+            parse_parenthesized_list_of_vars,
+            preceded(
+              tok_double_colon,
+              context("type in a -type() definition attribute", cut(parse_type)),
+            ),
+          ))),
+        ),
       ),
       period_eol_eof,
     ),
-    |(type_name, type_args, new_type)| {
-      PreprocessorNodeImpl::new_type_attr(SourceLoc::new(&input), type_name, type_args, new_type)
+    |(opaque, (type_name, type_args, new_type))| {
+      PreprocessorNodeImpl::new_type_attr(
+        SourceLoc::new(&input),
+        type_name,
+        type_args,
+        new_type,
+        opaque,
+      )
     },
   )(input.clone())
 }
@@ -174,6 +239,10 @@ pub(crate) fn parse_any_module_attr(input: ParserInput) -> ParserResult<Preproce
       import_attr,
       context("new type definition", parse_new_type_attr),
       parse_fn_spec,
+      parse_callback_spec,
+      optional_callbacks_attr,
+      behaviour_attr,
+      doc_attr,
       // Generic parser will try consume any `-IDENT(EXPR).`
       parse_generic_attr,
       parse_generic_attr_no_parentheses,
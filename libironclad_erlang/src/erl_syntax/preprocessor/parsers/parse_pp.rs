@@ -1,6 +1,8 @@
 //! Groups type definitions shared by all preprocessor parse modules
 use crate::erl_syntax::parsers::defs::ParserResult;
-use crate::erl_syntax::parsers::misc::{dash_atom, period_eol_eof, tok_atom, tok_string};
+use crate::erl_syntax::parsers::misc::{
+  dash_atom, period_eol_eof, tok_atom, tok_integer, tok_string,
+};
 use crate::erl_syntax::parsers::misc_tok::*;
 use crate::erl_syntax::parsers::parse_expr::parse_expr;
 use crate::erl_syntax::parsers::parser_input::ParserInput;
@@ -20,7 +22,7 @@ use nom::branch::alt;
 use nom::combinator::{cut, map, opt, recognize};
 use nom::error::context;
 use nom::multi::separated_list0;
-use nom::sequence::delimited;
+use nom::sequence::{delimited, separated_pair};
 
 /// Parse a `Macroident1, Macroident2, ...` into a list
 pub(crate) fn comma_sep_macro_idents(input: ParserInput) -> ParserResult<Vec<String>> {
@@ -56,6 +58,33 @@ fn include_lib_directive(input: ParserInput) -> ParserResult<PreprocessorNode> {
   )(input.clone())
 }
 
+/// Parse a `-file(STRING, INTEGER)`
+fn file_directive(input: ParserInput) -> ParserResult<PreprocessorNode> {
+  map(
+    delimited(
+      |i1| dash_atom(i1, "file"),
+      delimited(tok_par_open, separated_pair(tok_string, tok_comma, tok_integer), tok_par_close),
+      period_eol_eof,
+    ),
+    |(file, line)| {
+      let line = line.as_usize().unwrap_or_default();
+      PreprocessorNodeImpl::new_file(SourceLoc::new(&input), file.as_str().to_string(), line)
+    },
+  )(input.clone())
+}
+
+/// Parse a `-feature(ATOM, ATOM)`
+fn feature_directive(input: ParserInput) -> ParserResult<PreprocessorNode> {
+  map(
+    delimited(
+      |i1| dash_atom(i1, "feature"),
+      delimited(tok_par_open, separated_pair(tok_atom, tok_comma, tok_atom), tok_par_close),
+      period_eol_eof,
+    ),
+    |(name, action)| PreprocessorNodeImpl::new_feature(SourceLoc::new(&input), name, action),
+  )(input.clone())
+}
+
 /// Parse a `-error(STRING)`
 fn error_directive(input: ParserInput) -> ParserResult<PreprocessorNode> {
   map(
@@ -143,6 +172,8 @@ pub(crate) fn parse_preproc_directive(input: ParserInput) -> ParserResult<Prepro
       context("'-error()' directive", error_directive),
       context("'-include_lib()' directive", include_lib_directive),
       context("'-include()' directive", include_directive),
+      context("'-file()' directive", file_directive),
+      context("'-feature()' directive", feature_directive),
       parse_any_module_attr,
     )),
   ))(input)
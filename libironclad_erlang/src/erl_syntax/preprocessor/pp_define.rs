@@ -1,8 +1,11 @@
 //! Preprocessor definition, sometimes with args
 
 use crate::erl_syntax::parsers::token_stream::token::{format_tok_stream, Token};
+use crate::erl_syntax::parsers::token_stream::tokenizer::tokenize_source;
 use libironclad_util::mfarity::MFArity;
 use libironclad_util::pretty::Pretty;
+use nom::Finish;
+use std::ptr::null;
 use std::sync::Arc;
 
 /// A preprocessor definition created by `-define(X...)` or from the project settings or
@@ -15,6 +18,8 @@ pub struct PreprocessorDefineImpl {
   pub args: Vec<String>,
   /// The substitution, if provided, otherwise the symbol just evaluates as true in ifdefs and ifs
   pub tokens: Vec<Token>,
+  /// The `-define` location as `file:line`, or `None` for predefined and command line macros
+  pub defined_at: Option<String>,
 }
 
 /// Wrapper for `Arc<>`
@@ -31,14 +36,101 @@ impl std::fmt::Debug for PreprocessorDefineImpl {
 impl PreprocessorDefineImpl {
   /// Create a new empty preprocessor definition without body and args
   pub(crate) fn new(name: String, args: &[String], tokens: &[Token]) -> PreprocessorDefine {
-    Self { name, args: args.into(), tokens: tokens.into() }.into()
+    Self::new_at(name, args, tokens, None)
   }
 
-  /// Given NAME=VALUE or NAME style option, convert it into a record in preprocessor definition
-  /// symbols table. This will be passed then to preprocessor parser.
-  pub(crate) fn new_from_command_line(key_value: &str) -> PreprocessorDefine {
-    println!("TODO: new preproc-define from: {}", key_value);
-    Self::new(key_value.to_string(), &Vec::default(), &[])
+  /// Create a new preprocessor definition which was found at `defined_at` in the source
+  pub(crate) fn new_at(
+    name: String,
+    args: &[String],
+    tokens: &[Token],
+    defined_at: Option<String>,
+  ) -> PreprocessorDefine {
+    Self {
+      name,
+      args: args.into(),
+      tokens: tokens.into(),
+      defined_at,
+    }
+    .into()
+  }
+
+  /// Given `NAME`, `NAME=VALUE` or `NAME(ARG1, ARG2)=VALUE` style option, convert it into a
+  /// record in preprocessor definition symbols table. This will be passed then to preprocessor
+  /// parser. Like with `erlc -DNAME`, a define without a value is set to `true`.
+  /// Returns the reason as an error, if the define is malformed.
+  pub(crate) fn new_from_command_line(key_value: &str) -> Result<PreprocessorDefine, String> {
+    let (name_args, value) = match key_value.split_once('=') {
+      Some((name_args, value)) => (name_args.trim(), Some(value.trim())),
+      None => (key_value.trim(), None),
+    };
+
+    let (name, args) = match name_args.split_once('(') {
+      Some((name, args)) => {
+        let args = args
+          .trim_end()
+          .strip_suffix(')')
+          .ok_or_else(|| "macro arguments must end with a closing parenthesis".to_string())?;
+        (name.trim_end(), Self::parse_command_line_args(args)?)
+      }
+      None => (name_args, Vec::new()),
+    };
+    if !Self::is_valid_macro_name(name) {
+      return Err(format!("'{}' is not a valid macro name", name));
+    }
+
+    let tokens = match value {
+      None if args.is_empty() => vec![Token::new_atom("true".to_string())],
+      None => return Err("a macro with arguments must have a value".to_string()),
+      Some("") => return Err("the value after '=' is empty".to_string()),
+      Some(text) => Self::tokenize_command_line_value(text)?,
+    };
+    Ok(Self::new(name.to_string(), &args, &tokens))
+  }
+
+  /// Macro name is an atom or a variable name without quotes
+  fn is_valid_macro_name(name: &str) -> bool {
+    match name.chars().next() {
+      Some(c) if c.is_alphabetic() || c == '_' => {}
+      _ => return false,
+    }
+    name
+      .chars()
+      .all(|c| c.is_alphanumeric() || c == '_' || c == '@')
+  }
+
+  /// Split comma separated macro arguments, which must be variable names
+  fn parse_command_line_args(args: &str) -> Result<Vec<String>, String> {
+    if args.trim().is_empty() {
+      return Ok(Vec::new());
+    }
+    args
+      .split(',')
+      .map(|arg| {
+        let arg = arg.trim();
+        match arg.chars().next() {
+          Some(c) if (c.is_uppercase() || c == '_') && Self::is_valid_macro_name(arg) => {
+            Ok(arg.to_string())
+          }
+          _ => Err(format!("macro argument '{}' is not a variable name", arg)),
+        }
+      })
+      .collect()
+  }
+
+  /// Tokenize the define value. The source text is not kept, so the token locations are dropped.
+  fn tokenize_command_line_value(text: &str) -> Result<Vec<Token>, String> {
+    match tokenize_source(text).finish() {
+      Ok((tail, tokens)) if tail.trim().is_empty() => Ok(
+        tokens
+          .into_iter()
+          .filter(|t| !t.is_eol())
+          .map(|t| Token::new(null(), t.kind))
+          .collect(),
+      ),
+      Ok((tail, _)) => Err(format!("can't tokenize the value starting at: {}", tail.trim())),
+      Err(_) => Err("can't tokenize the value".to_string()),
+    }
   }
 
   /// Return the name/arity pair for this macro
@@ -0,0 +1,81 @@
+//! Provenance of tokens pasted from a macro body: which macro invocation produced them
+
+use std::fmt::Formatter;
+use std::sync::Arc;
+
+/// Records a macro invocation which pasted a token into the output
+#[derive(Debug)]
+pub struct MacroExpansionImpl {
+  /// The name of the expanded macro
+  pub macro_name: String,
+  /// Number of arguments in the invocation
+  pub arity: usize,
+  /// The use site as `file:line`, if known
+  pub invoked_at: Option<String>,
+  /// The `-define` site as `file:line`, if the macro was defined in the source
+  pub defined_at: Option<String>,
+  /// If the invocation itself was pasted from another macro body, the outer expansion
+  pub parent: Option<MacroExpansion>,
+}
+
+/// Wrapper for `Arc<>`
+pub type MacroExpansion = Arc<MacroExpansionImpl>;
+
+impl MacroExpansionImpl {
+  /// Create a new expansion record without a parent
+  pub(crate) fn new(
+    macro_name: &str,
+    arity: usize,
+    invoked_at: Option<String>,
+    defined_at: Option<String>,
+  ) -> MacroExpansion {
+    Self {
+      macro_name: macro_name.to_string(),
+      arity,
+      invoked_at,
+      defined_at,
+      parent: None,
+    }
+    .into()
+  }
+
+  /// A token pasted from a macro body can already carry an expansion, if the body contained another
+  /// macro invocation. Returns a copy of that expansion chain with `outer` appended at its end.
+  pub(crate) fn nested_in(this: &MacroExpansion, outer: &MacroExpansion) -> MacroExpansion {
+    let parent = match &this.parent {
+      Some(p) => Self::nested_in(p, outer),
+      None => outer.clone(),
+    };
+    Self {
+      macro_name: this.macro_name.clone(),
+      arity: this.arity,
+      invoked_at: this.invoked_at.clone(),
+      defined_at: this.defined_at.clone(),
+      parent: Some(parent),
+    }
+    .into()
+  }
+
+  /// Iterate the expansion chain from the innermost expansion to the outermost
+  pub fn chain(this: &MacroExpansion) -> Vec<MacroExpansion> {
+    let mut result = vec![this.clone()];
+    while let Some(p) = &result.last().unwrap().parent {
+      let p = p.clone();
+      result.push(p);
+    }
+    result
+  }
+}
+
+impl std::fmt::Display for MacroExpansionImpl {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "in expansion of ?{}", self.macro_name)?;
+    if let Some(use_site) = &self.invoked_at {
+      write!(f, " at {}", use_site)?;
+    }
+    if let Some(def_site) = &self.defined_at {
+      write!(f, " (defined at {})", def_site)?;
+    }
+    Ok(())
+  }
+}
@@ -13,6 +13,10 @@ impl std::fmt::Display for PreprocessorNodeImpl {
     match &self.content {
       PreprocessorNodeType::Include(p) => writeln!(f, "-include(\"{}\").", p),
       PreprocessorNodeType::IncludeLib(p) => write!(f, "-include_lib(\"{}\").", p),
+      PreprocessorNodeType::File { file, line } => write!(f, "-file(\"{}\", {}).", file, line),
+      PreprocessorNodeType::Feature { name, action } => {
+        write!(f, "-feature({}, {}).", name, action)
+      }
       // PreprocessorNodeType::IncludedFile { tokens, filename } => {
       //   writeln!(f, "%% included from: {}", filename.to_string_lossy())?;
       //   format_tok_stream(tokens, 100).fmt(f)
@@ -62,8 +66,8 @@ impl std::fmt::Display for PreprocessorNodeImpl {
         Pretty::display_square_list(fun_arities.iter(), f)?;
         writeln!(f, ").")
       }
-      PreprocessorNodeType::NewType { name, vars, ty, .. } => {
-        write!(f, "-type {}", name)?;
+      PreprocessorNodeType::NewType { name, vars, ty, opaque } => {
+        write!(f, "-{} {}", if *opaque { "opaque" } else { "type" }, name)?;
         Pretty::display_paren_list(vars.iter(), f)?;
         write!(f, " :: {}", ty)?;
         writeln!(f, ".")
@@ -78,6 +82,20 @@ impl std::fmt::Display for PreprocessorNodeImpl {
         Pretty::display_semicolon_separated(spec.as_fn_type().clauses().iter(), f)?;
         write!(f, ".")
       }
+      PreprocessorNodeType::Callback { funarity, spec, .. } => {
+        write!(f, "-callback {}", funarity.name)?;
+        Pretty::display_semicolon_separated(spec.as_fn_type().clauses().iter(), f)?;
+        write!(f, ".")
+      }
+      PreprocessorNodeType::OptionalCallbacks { fun_arities } => {
+        write!(f, "-optional_callbacks(")?;
+        Pretty::display_square_list(fun_arities.iter(), f)?;
+        writeln!(f, ").")
+      }
+      PreprocessorNodeType::Behaviour { name } => writeln!(f, "-behaviour({}).", name),
+      PreprocessorNodeType::Doc { module_doc, value } => {
+        writeln!(f, "-{} {}.", if *module_doc { "moduledoc" } else { "doc" }, value)
+      }
       PreprocessorNodeType::ModuleName { name, .. } => write!(f, "-module({}).", name),
       PreprocessorNodeType::Else => write!(f, "-else."),
       PreprocessorNodeType::Endif => write!(f, "-endif."),
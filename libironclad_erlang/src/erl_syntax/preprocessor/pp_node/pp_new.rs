@@ -148,6 +148,18 @@ impl PreprocessorNodeImpl {
     Self::new_with_location(location, PreprocessorNodeType::IncludeLib(p))
   }
 
+  /// Create a new `-file(PATH, LINE).` data node
+  #[inline]
+  pub(crate) fn new_file(location: SourceLoc, file: String, line: usize) -> PreprocessorNode {
+    Self::new_with_location(location, PreprocessorNodeType::File { file, line })
+  }
+
+  /// Create a new `-feature(NAME, ACTION).` data node
+  #[inline]
+  pub(crate) fn new_feature(location: SourceLoc, name: String, action: String) -> PreprocessorNode {
+    Self::new_with_location(location, PreprocessorNodeType::Feature { name, action })
+  }
+
   /// Create a new `-error()` node
   #[inline]
   pub(crate) fn new_error(location: SourceLoc, p: String) -> PreprocessorNode {
@@ -178,15 +190,16 @@ impl PreprocessorNodeImpl {
     Self::new_with_location(location, PreprocessorNodeType::ExportType { type_arities })
   }
 
-  /// Create a new `-type IDENT(ARG1, ...) :: TYPE.` module attr.
+  /// Create a new `-type IDENT(ARG1, ...) :: TYPE.` or `-opaque ...` module attr.
   #[inline]
   pub(crate) fn new_type_attr(
     location: SourceLoc,
     name: String,
     vars: Vec<String>,
     ty: ErlType,
+    opaque: bool,
   ) -> PreprocessorNode {
-    Self::new_with_location(location, PreprocessorNodeType::NewType { name, vars, ty })
+    Self::new_with_location(location, PreprocessorNodeType::NewType { name, vars, ty, opaque })
   }
 
   /// Create a new `-import(modulename, [...]).` module attr.
@@ -218,6 +231,37 @@ impl PreprocessorNodeImpl {
     Self::new_with_location(location, PreprocessorNodeType::FnSpec { funarity, spec })
   }
 
+  /// Create a new AST node for a behaviour callback `-callback FN(ARG, ...) -> RETURN.`
+  pub(crate) fn new_callback(
+    location: SourceLoc,
+    funarity: MFArity,
+    spec: ErlType,
+  ) -> PreprocessorNode {
+    Self::new_with_location(location, PreprocessorNodeType::Callback { funarity, spec })
+  }
+
+  /// Create a new `-optional_callbacks([...]).` module attr.
+  pub(crate) fn new_optional_callbacks_attr(
+    location: SourceLoc,
+    fun_arities: Vec<MFArity>,
+  ) -> PreprocessorNode {
+    Self::new_with_location(location, PreprocessorNodeType::OptionalCallbacks { fun_arities })
+  }
+
+  /// Create a new `-doc VALUE.` or `-moduledoc VALUE.` module attr.
+  pub(crate) fn new_doc_attr(
+    location: SourceLoc,
+    module_doc: bool,
+    value: AstNode,
+  ) -> PreprocessorNode {
+    Self::new_with_location(location, PreprocessorNodeType::Doc { module_doc, value })
+  }
+
+  /// Create a new `-behaviour(NAME).` or `-behavior(NAME).` module attr.
+  pub(crate) fn new_behaviour_attr(location: SourceLoc, name: String) -> PreprocessorNode {
+    Self::new_with_location(location, PreprocessorNodeType::Behaviour { name })
+  }
+
   /// Create a new module start node
   pub(crate) fn new_module_start(location: SourceLoc, module_name: String) -> PreprocessorNode {
     Self::new_with_location(location, PreprocessorNodeType::ModuleName { name: module_name })
@@ -26,6 +26,21 @@ pub enum PreprocessorNodeType {
     /// Body if specified, any tokens till the delimiter: `) . NEWLINE`
     body: Vec<Token>,
   },
+  /// Specific directive: `-file("path", Line).` The source lines which follow are reported as
+  /// coming from the given file, starting with the given line.
+  File {
+    /// The file name to report
+    file: String,
+    /// The line number to report for the line following the directive
+    line: usize,
+  },
+  /// Specific directive: `-feature(Name, enable | disable).` changes the language features
+  Feature {
+    /// Feature name, like `maybe_expr`
+    name: String,
+    /// Expected to be `enable` or `disable`
+    action: String,
+  },
   /// Specific directive: -undef(NAME). removes a named macro definition
   Undef(String),
   /// Proceed interpreting AST nodes if the named macro is defined
@@ -95,6 +110,8 @@ pub enum PreprocessorNodeType {
     vars: Vec<String>,
     /// Type definition
     ty: ErlType,
+    /// Created with `-opaque`, the type structure is hidden from other modules
+    opaque: bool,
   },
   /// A new record definition, created by `-record(name, {fields,...}).` attribute
   NewRecord {
@@ -110,4 +127,52 @@ pub enum PreprocessorNodeType {
     /// Type for all function clauses
     spec: ErlType,
   },
+  /// A behaviour callback, written as `-callback myfun(...) -> <ret type>.`
+  Callback {
+    /// The callback name and arity, module as None
+    funarity: MFArity,
+    /// Type for all callback clauses
+    spec: ErlType,
+  },
+  /// List of callbacks, which the behaviour implementers are not required to export
+  OptionalCallbacks {
+    /// The contents of `-optional_callbacks([...]).`
+    fun_arities: Vec<MFArity>,
+  },
+  /// Documentation attribute: `-doc VALUE.` or `-moduledoc VALUE.`
+  Doc {
+    /// Set for `-moduledoc`
+    module_doc: bool,
+    /// A string, `false`, a metadata map, or a `{file, Path}` tuple
+    value: AstNode,
+  },
+  /// Declares that the module implements a behaviour: `-behaviour(NAME).` or `-behavior(NAME).`
+  Behaviour {
+    /// The behaviour module name
+    name: String,
+  },
+}
+
+impl PreprocessorNodeType {
+  /// Whether the node is a module form (an attribute), as opposed to a preprocessor directive
+  /// which is consumed by the preprocessor and does not appear in the preprocessed source.
+  pub fn is_module_form(&self) -> bool {
+    matches!(
+      self,
+      PreprocessorNodeType::ModuleName { .. }
+        | PreprocessorNodeType::File { .. }
+        | PreprocessorNodeType::Feature { .. }
+        | PreprocessorNodeType::Attr { .. }
+        | PreprocessorNodeType::Export { .. }
+        | PreprocessorNodeType::ExportType { .. }
+        | PreprocessorNodeType::Import { .. }
+        | PreprocessorNodeType::NewType { .. }
+        | PreprocessorNodeType::NewRecord { .. }
+        | PreprocessorNodeType::FnSpec { .. }
+        | PreprocessorNodeType::Callback { .. }
+        | PreprocessorNodeType::OptionalCallbacks { .. }
+        | PreprocessorNodeType::Behaviour { .. }
+        | PreprocessorNodeType::Doc { .. }
+    )
+  }
 }
@@ -29,8 +29,10 @@ impl std::fmt::Display for IroncladError {
       IcErrorKind::Glob(gerr) => gerr.fmt(f),
       IcErrorKind::GlobPattern(gperr) => gperr.fmt(f),
       IcErrorKind::Config(cfgerr) => cfgerr.fmt(f),
-      IcErrorKind::Internal | IcErrorKind::TypeErr { .. } => {
-        write!(f, "{} (at {})", self.get_message(), self.get_location())
+      IcErrorKind::ConfigValue => writeln!(f, "{}", self.get_message()),
+      IcErrorKind::Internal | IcErrorKind::TypeErr { .. } | IcErrorKind::Behaviour => {
+        let loc = self.get_location();
+        write!(f, "{} (at {}){}", self.get_message(), loc, loc.format_expansion())
       }
       _ => unimplemented!("Format is not impl for {:?}", self.kind),
     }
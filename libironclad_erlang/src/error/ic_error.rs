@@ -140,6 +140,38 @@ impl IroncladError {
   //   IroncladError::new(IcErrorKind::ParserInternal, location, msg)
   // }
 
+  /// Create a config error for a malformed value in the project configuration
+  pub(crate) fn config_value(message: String) -> GenericIroncladError {
+    let new_err =
+      IroncladError::new(IcSeverity::Error, IcErrorKind::ConfigValue, SourceLoc::None, message);
+    Box::new(new_err)
+  }
+
+  /// Create a behaviour error, for a module not implementing the callbacks of its behaviour
+  pub(crate) fn behaviour(location: SourceLoc, message: String) -> GenericIroncladError {
+    let new_err = IroncladError::new(IcSeverity::Error, IcErrorKind::Behaviour, location, message);
+    Box::new(new_err)
+  }
+
+  /// Create a behaviour warning, for an unknown behaviour or a callback which is not exported
+  pub(crate) fn behaviour_warning(location: SourceLoc, message: String) -> GenericIroncladError {
+    let new_err =
+      IroncladError::new(IcSeverity::Warning, IcErrorKind::Behaviour, location, message);
+    Box::new(new_err)
+  }
+
+  /// Create a warning about the Erlang program, like an expression which always fails
+  pub(crate) fn erlang_warning(location: SourceLoc, message: String) -> GenericIroncladError {
+    let new_err = IroncladError::new(IcSeverity::Warning, IcErrorKind::Erlang, location, message);
+    Box::new(new_err)
+  }
+
+  /// Create an error about the Erlang program, like a variable which is not bound
+  pub(crate) fn erlang_error(location: SourceLoc, message: String) -> GenericIroncladError {
+    let new_err = IroncladError::new(IcSeverity::Error, IcErrorKind::Erlang, location, message);
+    Box::new(new_err)
+  }
+
   /// Given a vector of ErlErrors, return one, multiple error, or panic if no errors were given
   #[allow(dead_code)]
   pub(crate) fn multiple(mut errors: Vec<GenericIroncladError>) -> GenericIroncladError {
@@ -24,6 +24,9 @@ pub enum IcErrorKind {
   /// Project loading error produced when loading TOML
   Config(toml::de::Error),
 
+  /// A value in the project configuration is not valid, the message explains which and why
+  ConfigValue,
+
   /// Something unexpected like a TO-DO or assertion
   Internal,
 
@@ -33,6 +36,9 @@ pub enum IcErrorKind {
   /// Some type discrepancy has been detected
   TypeErr,
 
+  /// A behaviour implementer does not match the behaviour callbacks
+  Behaviour,
+
   /// A variable was referenced that's not in the scope
   VariableNotFound(String),
 
@@ -11,7 +11,14 @@ pub const EXIT_ERRORS_FOUND: i32 = 2;
 
 /// End program with the error message
 pub fn erl_fatal_error(err: GenericIroncladError) {
-  println!("{} @ {}: {}", err.get_severity(), err.get_location(), err.get_message());
+  let loc = err.get_location();
+  println!(
+    "{} @ {}: {}{}",
+    err.get_severity(),
+    loc,
+    err.get_message(),
+    loc.format_expansion()
+  );
   std::process::exit(EXIT_FATAL);
 }
 
@@ -17,3 +17,4 @@ pub mod project;
 pub mod record_def;
 pub mod source_loc;
 pub mod typing;
+pub mod user_type_def;
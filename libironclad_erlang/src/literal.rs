@@ -3,6 +3,7 @@ use std::cmp::Ordering;
 use std::hash::{Hash, Hasher};
 
 use crate::typing::erl_integer::ErlInteger;
+use crate::typing::erl_type::map_type::MapMemberType;
 use crate::typing::erl_type::typekind::TypeKind;
 use crate::typing::erl_type::{ErlType, TypeImpl};
 use std::sync::Arc;
@@ -28,7 +29,6 @@ pub enum Literal {
     in_source: char,
   },
 
-  // TODO: String/list lit, tuple lit, map lit, binary lit, etc
   /// A boolean value true or false atom, is-a(Atom)
   Bool(bool),
 
@@ -50,9 +50,17 @@ pub enum Literal {
 
   /// A list containing only unicode codepoints is-a(List)
   String(Arc<String>),
+  /// A binary with UTF-8 encoded text, produced by binary sigils like `~"text"`, is-a(Binary)
+  BinaryString(Arc<String>),
 
   /// A tuple of literals
   Tuple(Vec<Literal>),
+
+  /// A map of literal keys and values, sorted by key, keys are unique
+  Map(Vec<(Literal, Literal)>),
+
+  /// A non-empty binary of whole bytes, produced by constant folding of `<<...>>`
+  Binary(Vec<u8>),
 }
 
 impl Hash for Literal {
@@ -81,8 +89,10 @@ impl Hash for Literal {
       Literal::Nil => {
         "[]".hash(state);
       }
-      Literal::EmptyBinary => {
+      Literal::EmptyBinary | Literal::BinaryString(_) | Literal::Binary(_) => {
+        // Binaries with the same bytes are equal regardless of how they were written
         "<<>>".hash(state);
+        self.binary_bytes().hash(state);
       }
       Literal::String(s) => {
         's'.hash(state);
@@ -92,6 +102,10 @@ impl Hash for Literal {
         'T'.hash(state);
         elements.hash(state);
       }
+      Literal::Map(members) => {
+        'M'.hash(state);
+        members.hash(state);
+      }
       Literal::Character(c) => {
         '$'.hash(state);
         c.hash(state);
@@ -124,7 +138,9 @@ impl Literal {
       Literal::Float(_) => TypeImpl::float(),
       Literal::Atom(_) => TypeImpl::atom(),
       Literal::Bool(_) => TypeImpl::boolean(),
-      Literal::EmptyBinary => TypeImpl::new_unnamed(TypeKind::new_binary(None, None)),
+      Literal::EmptyBinary | Literal::BinaryString(_) | Literal::Binary(_) => {
+        TypeImpl::new_unnamed(TypeKind::new_binary(None, None))
+      }
       // Cannot have runtime values as literals
       // ErlLit::Pid => ErlType::Pid,
       // ErlLit::Reference => ErlType::Reference,
@@ -137,6 +153,16 @@ impl Literal {
         let element_types = items.iter().map(|it| it.synthesize_type()).collect();
         TypeImpl::new_unnamed(TypeKind::Tuple { elements: element_types })
       } // other => unimplemented!("Don't know how to synthesize type for {}", other),
+      Literal::Map(members) => {
+        let member_types = members
+          .iter()
+          .map(|(k, v)| MapMemberType {
+            key: k.synthesize_type(),
+            value: v.synthesize_type(),
+          })
+          .collect();
+        TypeImpl::new_unnamed(TypeKind::new_map(member_types))
+      }
       Literal::Character(_) => TypeImpl::integer(),
       Literal::EscapedCharacter { .. } => TypeImpl::integer(),
     }
@@ -157,9 +183,19 @@ impl Literal {
   //   }
   // }
 
-  /// Check for empty binary literal
+  /// Check for a binary literal, empty, a binary string or folded bytes
   pub fn is_binary_lit(&self) -> bool {
-    matches!(self, Literal::EmptyBinary)
+    matches!(self, Literal::EmptyBinary | Literal::BinaryString(_) | Literal::Binary(_))
+  }
+
+  /// Bytes of a binary literal, or `None` if the literal is not a binary
+  pub fn binary_bytes(&self) -> Option<&[u8]> {
+    match self {
+      Literal::EmptyBinary => Some(&[]),
+      Literal::BinaryString(s) => Some(s.as_bytes()),
+      Literal::Binary(bytes) => Some(bytes),
+      _ => None,
+    }
   }
 }
 
@@ -175,6 +211,9 @@ impl PartialEq for Literal {
       (Literal::List { elements: a, .. }, Literal::List { elements: b, .. }) => a == b,
       (Literal::String(a), Literal::String(b)) => a == b,
       (Literal::Tuple(a), Literal::Tuple(b)) => a == b,
+      (Literal::Map(a), Literal::Map(b)) => a == b,
+      (Literal::Nil, Literal::Nil) => true,
+      (a, b) if a.is_binary_lit() && b.is_binary_lit() => a.binary_bytes() == b.binary_bytes(),
       _ => false,
     }
   }
@@ -214,7 +253,9 @@ impl Literal {
       (Literal::Bool(a), Literal::Bool(b)) => a.cmp(b),
       (Literal::List { elements: a, .. }, Literal::List { elements: b, .. }) => a.cmp(b),
       (Literal::String(a), Literal::String(b)) => a.cmp(b),
+      (a, b) if a.is_binary_lit() && b.is_binary_lit() => a.binary_bytes().cmp(&b.binary_bytes()),
       (Literal::Tuple(a), Literal::Tuple(b)) => a.cmp(b),
+      (Literal::Map(a), Literal::Map(b)) => a.len().cmp(&b.len()).then_with(|| a.cmp(b)),
       _ => {
         unreachable!("Can't compare {} vs {}, only same type allowed in this function", self, other)
       }
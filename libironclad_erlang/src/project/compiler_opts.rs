@@ -1,8 +1,46 @@
 //! Defines libironclad options for a file
+use crate::erl_syntax::erl_ast::node_impl::AstNodeType;
+use crate::erl_syntax::erl_ast::AstNode;
+use crate::erl_syntax::erl_op::ErlBinaryOp;
 use crate::erl_syntax::parsers::preproc_defines::PreprocessorDefinesMap;
+use crate::error::ic_error::{IroncladError, IroncladResult};
+use crate::literal::Literal;
 use crate::project::conf::serializable_compiler_opts::SerializableCompilerOpts;
+use crate::project::module::module_features::ErlFeature;
+use libironclad_util::mfarity::MFArity;
+use std::collections::{HashMap, HashSet};
+use std::ops::Deref;
 use std::sync::Arc;
 
+/// Functions which a compiler option applies to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FunctionSelection {
+  /// The option was given without a list, like `nowarn_unused_function`, and applies to everything
+  All,
+  /// The option was given with a list, like `{nowarn_unused_function, [f/1]}`
+  Only(HashSet<MFArity>),
+}
+
+impl FunctionSelection {
+  /// Check whether the function is selected
+  pub fn contains(&self, funarity: &MFArity) -> bool {
+    match self {
+      FunctionSelection::All => true,
+      FunctionSelection::Only(set) => set.contains(funarity),
+    }
+  }
+
+  /// Combine two selections for the same option, given in different places
+  pub fn merge(&self, other: &FunctionSelection) -> FunctionSelection {
+    match (self, other) {
+      (FunctionSelection::Only(a), FunctionSelection::Only(b)) => {
+        FunctionSelection::Only(a.union(b).cloned().collect())
+      }
+      _ => FunctionSelection::All,
+    }
+  }
+}
+
 /// Compiler options for a file
 #[derive(Debug, Clone)]
 pub struct CompilerOptsImpl {
@@ -10,11 +48,31 @@ pub struct CompilerOptsImpl {
   pub include_paths: Vec<String>,
 
   // pub opts: Vec<CompilerOption> ...
-  /// Preprocessor macro defines in form of "NAME" or "NAME=VALUE" or NAME(ARGS...)=VALUE
+  /// Preprocessor macro defines in form of "NAME" or "NAME=VALUE" or "NAME(ARGS...)=VALUE"
   pub scope: PreprocessorDefinesMap,
 
   /// Tries to break the operations when this many errors found in 1 module
   pub max_errors_per_module: usize,
+
+  /// Features enabled or disabled in the configuration. Features not present here have their
+  /// default state, modules can change them with `-feature()`.
+  pub features: HashMap<ErlFeature, bool>,
+
+  /// `export_all`: all functions of the module are exported
+  pub export_all: bool,
+
+  /// `warnings_as_errors`: warnings are reported as errors
+  pub warnings_as_errors: bool,
+
+  /// Warnings disabled with `nowarn_<name>` or `{nowarn_<name>, [F/A]}` options, the key is the
+  /// warning name without the `nowarn_` prefix, like `unused_function` or `export_all`
+  pub nowarn: HashMap<String, FunctionSelection>,
+
+  /// Warnings which are off by default and were enabled with `warn_<name>`, like `unused_import`
+  pub warn: HashSet<String>,
+
+  /// Functions to inline, set by `inline` or `{inline, [F/A]}`
+  pub inline: Option<FunctionSelection>,
 }
 
 /// Wrap compiler options with refcounted box
@@ -24,7 +82,8 @@ impl CompilerOptsImpl {
   /// Default value for max errors limit. Will try to stop compilation when this count is reached.
   pub const MAX_ERRORS_PER_MODULE: usize = 20;
 
-  /// Given self (read-only) and other opts (read-only) combine them into self+other
+  /// Given self (read-only) and other opts (read-only) combine them into self+other. The
+  /// `max_errors_per_module` is kept from self.
   pub fn overlay(&self, other: &CompilerOptsImpl) -> Self {
     let mut result: CompilerOptsImpl = self.clone();
 
@@ -43,21 +102,326 @@ impl CompilerOptsImpl {
     //   panic!("Can't lock scopes for merging")
     // };
     result.scope = new_scope;
+
+    // Overlay feature states
+    result.features.extend(other.features.iter());
+
+    // Overlay module options and warning settings
+    result.export_all |= other.export_all;
+    result.warnings_as_errors |= other.warnings_as_errors;
+    for (warning, selection) in other.nowarn.iter() {
+      result.disable_warning(warning, selection.clone());
+    }
+    result.warn.extend(other.warn.iter().cloned());
+    result.inline = match (&result.inline, &other.inline) {
+      (Some(a), Some(b)) => Some(a.merge(b)),
+      (a, b) => a.clone().or_else(|| b.clone()),
+    };
     result
   }
 
-  pub(crate) fn new_from_opts(opts: SerializableCompilerOpts) -> Self {
+  /// Options accepted in `-compile()` which do not change how Ironclad checks the code, with or
+  /// without a value
+  const IGNORED_COMPILE_OPTIONS: &'static [&'static str] = &[
+    "absolute_source",
+    "basic_validation",
+    "beam_debug_info",
+    "bin_opt_info",
+    "binary",
+    "compile_info",
+    "compressed",
+    "d",
+    "debug_info",
+    "debug_info_key",
+    "deterministic",
+    "encrypt_debug_info",
+    "error_location",
+    "extra_chunks",
+    "from_abstr",
+    "from_asm",
+    "from_core",
+    "i",
+    "inline_effort",
+    "inline_list_funcs",
+    "inline_unroll",
+    "line_coverage",
+    "makedep",
+    "makedep_add_missing",
+    "makedep_output",
+    "makedep_phony",
+    "makedep_quote_target",
+    "makedep_side_effect",
+    "makedep_target",
+    "no_auto_import",
+    "no_badrecord",
+    "no_docs",
+    "no_error_module_mismatch",
+    "no_inline",
+    "no_line_info",
+    "no_spawn_compiler_process",
+    "outdir",
+    "parse_transform",
+    "recv_opt_info",
+    "report",
+    "report_errors",
+    "report_warnings",
+    "return",
+    "return_errors",
+    "return_warnings",
+    "source",
+    "strong_validation",
+    "time",
+    "to_asm",
+    "to_core",
+    "verbose",
+    "E",
+    "P",
+    "S",
+  ];
+
+  /// Warnings which Ironclad reports, by the names used in `nowarn_<name>` and `warn_<name>`
+  const EMITTED_WARNINGS: &'static [&'static str] = &[
+    "export_all",
+    "shadow_vars",
+    "undefined_behaviour",
+    "undefined_behaviour_func",
+    "unused_function",
+    "unused_import",
+    "unused_macros",
+    "unused_record",
+    "unused_type",
+    "unused_vars",
+  ];
+
+  /// Warnings which erlc only reports when enabled with `warn_<name>`
+  const DEFAULT_OFF_WARNINGS: &'static [&'static str] = &["unused_import"];
+
+  /// Other erlc warnings, the options to enable or disable them are accepted and have no effect
+  const OTHER_ERLC_WARNINGS: &'static [&'static str] = &[
+    "bif_clash",
+    "conflicting_behaviours",
+    "deprecated_callback",
+    "deprecated_catch",
+    "deprecated_function",
+    "deprecated_type",
+    "export_vars",
+    "failed",
+    "format",
+    "ignored",
+    "ill_defined_behaviour_callbacks",
+    "ill_defined_optional_callbacks",
+    "keywords",
+    "match_float_zero",
+    "missing_doc",
+    "missing_spec",
+    "missing_spec_all",
+    "missing_spec_documented",
+    "nif_inline",
+    "nomatch",
+    "obsolete_guard",
+    "redefined_builtin_type",
+    "removed",
+    "singleton_typevar",
+    "undefined_behaviour_callbacks",
+    "underscore_match",
+    "untyped_record",
+    "update_literal",
+  ];
+
+  /// The warning name of a `nowarn_<name>` or `warn_<name>` option, if the warning is known to erlc
+  fn warning_name<'a>(option: &'a str, prefix: &str) -> Option<&'a str> {
+    let name = option.strip_prefix(prefix)?;
+    if Self::EMITTED_WARNINGS.contains(&name) || Self::OTHER_ERLC_WARNINGS.contains(&name) {
+      Some(name)
+    } else {
+      None
+    }
+  }
+
+  /// Check whether a warning is enabled, for the whole module, or for the given function or type.
+  /// The `warning` is the name used in `nowarn_<name>` options, like `unused_function`. Some
+  /// warnings are off unless enabled with `warn_<name>`, like erlc does.
+  pub fn is_warning_enabled(&self, warning: &str, item: Option<&MFArity>) -> bool {
+    if Self::DEFAULT_OFF_WARNINGS.contains(&warning) && !self.warn.contains(warning) {
+      return false;
+    }
+    match (self.nowarn.get(warning), item) {
+      (None, _) => true,
+      (Some(selection), Some(item)) => !selection.contains(item),
+      (Some(selection), None) => *selection != FunctionSelection::All,
+    }
+  }
+
+  /// Disable a warning for the selected functions, merging with the previous settings
+  fn disable_warning(&mut self, warning: &str, selection: FunctionSelection) {
+    if selection == FunctionSelection::All {
+      self.warn.remove(warning);
+    }
+    let merged = match self.nowarn.get(warning) {
+      Some(previous) => previous.merge(&selection),
+      None => selection,
+    };
+    self.nowarn.insert(warning.to_string(), merged);
+  }
+
+  /// Apply an option from a `-compile()` attribute. Returns a message for unknown and malformed
+  /// options, the caller reports it as a warning.
+  pub fn apply_compile_option(&mut self, option: &AstNode) -> Result<(), String> {
+    if let Some(name) = Self::atom_of(option) {
+      return self.apply_named_option(name, None, option);
+    }
+    if let AstNodeType::Tuple { elements } = &option.content {
+      match elements.as_slice() {
+        [key, value] => {
+          if let Some(name) = Self::atom_of(key) {
+            return self.apply_named_option(name, Some(value), option);
+          }
+        }
+        // `{d, Macro, Value}` does not affect this module, it is already preprocessed
+        [key, _, _] if Self::atom_of(key) == Some("d") => return Ok(()),
+        _ => {}
+      }
+    }
+    Err(format!("Unknown compile option: {}", option))
+  }
+
+  /// Apply a compile option `name` or `{name, value}`
+  fn apply_named_option(
+    &mut self,
+    name: &str,
+    value: Option<&AstNode>,
+    option: &AstNode,
+  ) -> Result<(), String> {
+    let selection = match value {
+      None => Some(FunctionSelection::All),
+      Some(v) => Self::function_list(v).map(FunctionSelection::Only),
+    };
+    match (name, value, selection) {
+      ("export_all", None, _) => self.export_all = true,
+      ("warnings_as_errors", None, _) => self.warnings_as_errors = true,
+      ("inline", _, Some(selection)) => {
+        self.inline = Some(match &self.inline {
+          Some(previous) => previous.merge(&selection),
+          None => selection,
+        })
+      }
+      ("inline_size", Some(_), _) => {}
+      (ignored, _, _) if Self::IGNORED_COMPILE_OPTIONS.contains(&ignored) => {}
+      (nowarn, _, Some(selection)) if Self::warning_name(nowarn, "nowarn_").is_some() => {
+        self.disable_warning(&nowarn["nowarn_".len()..], selection)
+      }
+      (warn, None, _) if Self::warning_name(warn, "warn_").is_some() => {
+        let warning = &warn["warn_".len()..];
+        self.nowarn.remove(warning);
+        self.warn.insert(warning.to_string());
+      }
+      ("warn_format", Some(_), _) => {}
+      _ => return Err(format!("Unknown compile option: {}", option)),
+    }
+    Ok(())
+  }
+
+  /// Atom value of a literal node
+  pub(crate) fn atom_of(node: &AstNode) -> Option<&str> {
+    match &node.content {
+      AstNodeType::Lit { value, .. } => match value.deref() {
+        Literal::Atom(name) => Some(name.as_str()),
+        _ => None,
+      },
+      _ => None,
+    }
+  }
+
+  /// Interpret `F/A`, `{F, A}` or a list of them, as function names with arities
+  fn function_list(node: &AstNode) -> Option<HashSet<MFArity>> {
+    match &node.content {
+      AstNodeType::List { elements, tail: None } => {
+        elements.iter().map(Self::function_arity).collect()
+      }
+      AstNodeType::Lit { value, .. } if matches!(value.deref(), Literal::Nil) => {
+        Some(HashSet::new())
+      }
+      _ => Self::function_arity(node).map(|fa| HashSet::from([fa])),
+    }
+  }
+
+  /// Interpret `F/A` or `{F, A}` as a function name with arity
+  pub(crate) fn function_arity(node: &AstNode) -> Option<MFArity> {
+    let (name, arity) = match &node.content {
+      AstNodeType::BinaryOp { binop_expr } if binop_expr.operator == ErlBinaryOp::Div => {
+        (&binop_expr.left, &binop_expr.right)
+      }
+      AstNodeType::Tuple { elements } if elements.len() == 2 => (&elements[0], &elements[1]),
+      _ => return None,
+    };
+    match &arity.content {
+      AstNodeType::Lit { value, .. } => match value.deref() {
+        Literal::Integer(i) => Some(MFArity::new_local(Self::atom_of(name)?, i.as_usize()?)),
+        _ => None,
+      },
+      _ => None,
+    }
+  }
+
+  /// Look up feature names from the configuration. Unknown features are reported as config errors.
+  fn features_from_config(
+    enable: Option<Vec<String>>,
+    disable: Option<Vec<String>>,
+  ) -> IroncladResult<HashMap<ErlFeature, bool>> {
+    let mut errors = Vec::new();
+    let mut result = HashMap::new();
+    let named = [
+      (enable.unwrap_or_default(), true),
+      (disable.unwrap_or_default(), false),
+    ];
+
+    for (names, enabled) in named.iter() {
+      for name in names.iter() {
+        match ErlFeature::from_name(name) {
+          Some(feature) => {
+            result.insert(feature, *enabled);
+          }
+          None => errors.push(IroncladError::config_value(format!("Unknown feature \"{}\"", name))),
+        }
+      }
+    }
+
+    if errors.is_empty() {
+      Ok(result)
+    } else {
+      Err(IroncladError::multiple(errors))
+    }
+  }
+
+  pub(crate) fn new_from_opts(opts: SerializableCompilerOpts) -> IroncladResult<Self> {
     let self_default = Self::default();
-    Self {
+    let nowarn = opts
+      .nowarn
+      .unwrap_or_default()
+      .iter()
+      .map(|name| {
+        let warning = name.strip_prefix("nowarn_").unwrap_or(name);
+        (warning.to_string(), FunctionSelection::All)
+      })
+      .collect();
+    Ok(Self {
       include_paths: opts.include_paths.unwrap_or(self_default.include_paths),
-      scope: PreprocessorDefinesMap::new_from_config(opts.defines, &self_default.scope),
-      max_errors_per_module: Self::MAX_ERRORS_PER_MODULE,
-    }
+      scope: PreprocessorDefinesMap::new_from_config(opts.defines, &self_default.scope)?,
+      max_errors_per_module: opts
+        .max_errors_per_module
+        .unwrap_or(Self::MAX_ERRORS_PER_MODULE),
+      features: Self::features_from_config(opts.enable_features, opts.disable_features)?,
+      warnings_as_errors: opts.warnings_as_errors.unwrap_or_default(),
+      nowarn,
+      ..self_default
+    })
   }
 
-  pub(crate) fn new_from_maybe_opts(maybe_opts: Option<SerializableCompilerOpts>) -> Self {
+  pub(crate) fn new_from_maybe_opts(
+    maybe_opts: Option<SerializableCompilerOpts>,
+  ) -> IroncladResult<Self> {
     match maybe_opts {
-      None => Self::default(),
+      None => Ok(Self::default()),
       Some(conf_val) => CompilerOptsImpl::new_from_opts(conf_val),
     }
   }
@@ -69,6 +433,12 @@ impl Default for CompilerOptsImpl {
       include_paths: Default::default(),
       scope: Default::default(),
       max_errors_per_module: Self::MAX_ERRORS_PER_MODULE,
+      features: HashMap::new(),
+      export_all: false,
+      warnings_as_errors: false,
+      nowarn: HashMap::new(),
+      warn: HashSet::new(),
+      inline: None,
     }
   }
 }
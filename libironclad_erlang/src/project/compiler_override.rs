@@ -0,0 +1,74 @@
+//! Compiler options for a group of files, from `[[overrides]]` sections of the project file
+use crate::error::ic_error::{IroncladError, IroncladResult};
+use crate::project::compiler_opts::CompilerOptsImpl;
+use crate::project::conf::serializable_override::SerializableOverride;
+use std::path::{Path, PathBuf};
+
+/// Compiler options added to the project options, for the files matching the path patterns
+#[derive(Debug)]
+pub struct CompilerOverride {
+  /// Glob patterns as written in the project file
+  pub paths: Vec<String>,
+  /// Compiled `paths`
+  patterns: Vec<glob::Pattern>,
+  /// Options to overlay on top of the project options
+  pub opts: CompilerOptsImpl,
+  /// Errors limit, if set in the override
+  pub max_errors_per_module: Option<usize>,
+  /// `warnings_as_errors`, if set in the override, either value replaces the project option
+  pub warnings_as_errors: Option<bool>,
+  /// The directory `paths` are relative to, the current directory if `None`
+  base_dir: Option<PathBuf>,
+}
+
+impl CompilerOverride {
+  /// Convert an `[[overrides]]` section from the project file. The paths are relative to
+  /// `base_dir`, the directory of the project file.
+  pub(crate) fn new_from_conf(
+    conf: SerializableOverride,
+    base_dir: Option<&Path>,
+  ) -> IroncladResult<Self> {
+    let patterns = conf
+      .paths
+      .iter()
+      .map(|p| glob::Pattern::new(p).map_err(|e| IroncladError::from(e).into()))
+      .collect::<IroncladResult<Vec<_>>>()?;
+    let max_errors_per_module = conf.compiler_options.max_errors_per_module;
+    let warnings_as_errors = conf.compiler_options.warnings_as_errors;
+    Ok(Self {
+      paths: conf.paths,
+      patterns,
+      opts: CompilerOptsImpl::new_from_opts(conf.compiler_options)?,
+      max_errors_per_module,
+      warnings_as_errors,
+      base_dir: base_dir.map(Path::to_path_buf),
+    })
+  }
+
+  /// Check whether the override affects the file. Patterns are matched against the path as given,
+  /// and against the path relative to the directory of the project file, because the input paths
+  /// are absolute. So a project file matches the same files wherever the compiler is started.
+  /// Without a project file the current directory is used.
+  pub fn matches(&self, path: &Path) -> bool {
+    let relative = self
+      .base_dir
+      .clone()
+      .or_else(|| std::env::current_dir().ok())
+      .and_then(|base| path.strip_prefix(base).ok().map(Path::to_path_buf));
+    self.patterns.iter().any(|pattern| {
+      pattern.matches_path(path) || relative.as_ref().is_some_and(|r| pattern.matches_path(r))
+    })
+  }
+
+  /// Apply the override on top of the options
+  pub fn apply_to(&self, opts: &CompilerOptsImpl) -> CompilerOptsImpl {
+    let mut result = opts.overlay(&self.opts);
+    if let Some(max_errors) = self.max_errors_per_module {
+      result.max_errors_per_module = max_errors;
+    }
+    if let Some(warnings_as_errors) = self.warnings_as_errors {
+      result.warnings_as_errors = warnings_as_errors;
+    }
+    result
+  }
+}
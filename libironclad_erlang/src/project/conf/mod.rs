@@ -2,6 +2,7 @@
 use crate::error::ic_error::{IroncladError, IroncladResult};
 use crate::project::conf::serializable_compiler_opts::SerializableCompilerOpts;
 use crate::project::conf::serializable_input_opts::SerializableInputOpts;
+use crate::project::conf::serializable_override::SerializableOverride;
 use core::fmt;
 use core::fmt::Debug;
 use core::option::Option;
@@ -9,9 +10,11 @@ use core::result::Result;
 use serde_derive::Deserialize;
 use std::convert::Into;
 use std::fs;
+use std::path::{Path, PathBuf};
 
 pub mod serializable_compiler_opts;
 pub mod serializable_input_opts;
+pub mod serializable_override;
 
 /// Defines configuration file as it is loaded by TOML and serde
 #[derive(Deserialize)]
@@ -21,6 +24,14 @@ pub struct ProjectConf {
 
   /// Input files and directories (wildcards are allowed)
   pub inputs: Option<SerializableInputOpts>,
+
+  /// Compiler options for groups of files, `[[overrides]]` sections, applied in order
+  pub overrides: Option<Vec<SerializableOverride>>,
+
+  /// Directory of the project file, the `[[overrides]]` paths are relative to it. `None` for a
+  /// configuration which does not come from a file, then the current directory is used.
+  #[serde(skip)]
+  pub project_dir: Option<PathBuf>,
 }
 
 impl ProjectConf {
@@ -30,7 +41,13 @@ impl ProjectConf {
     let config_str = fs::read_to_string(filename).map_err(IroncladError::from)?;
 
     // Parse, and convert toml error into ErlError
-    toml::from_str(&config_str).map_err(|e| IroncladError::from(e).into())
+    let mut conf: Self = toml::from_str(&config_str).map_err(IroncladError::from)?;
+    let dir = Path::new(filename)
+      .parent()
+      .filter(|p| !p.as_os_str().is_empty())
+      .unwrap_or_else(|| Path::new("."));
+    conf.project_dir = Some(fs::canonicalize(dir).map_err(IroncladError::from)?);
+    Ok(conf)
   }
 
   /// Creates project struct from a TOML config as a string
@@ -38,10 +55,27 @@ impl ProjectConf {
     // Parse, and convert toml error into ErlError
     toml::from_str(input).map_err(|e| e.into())
   }
+
+  /// Add preprocessor defines (from the command line) to the project compiler options, in form
+  /// of "NAME" or "NAME=VALUE" or "NAME(ARGS...)=VALUE"
+  pub fn add_defines(&mut self, defines: &[String]) {
+    if defines.is_empty() {
+      return;
+    }
+    let compiler_options = self.compiler_options.get_or_insert_with(Default::default);
+    compiler_options
+      .defines
+      .get_or_insert_with(Vec::new)
+      .extend(defines.iter().cloned());
+  }
 }
 
 impl Debug for ProjectConf {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-    write!(f, "ProjectConf({:?}, {:?})", self.inputs, self.compiler_options)
+    write!(
+      f,
+      "ProjectConf({:?}, {:?}, {:?})",
+      self.inputs, self.compiler_options, self.overrides
+    )
   }
 }
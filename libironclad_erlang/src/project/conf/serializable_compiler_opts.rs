@@ -13,4 +13,14 @@ pub struct SerializableCompilerOpts {
   pub include_paths: Option<Vec<String>>,
   /// Defaults to empty list. Preprocessor defs in form of "NAME" or "NAME=VALUE"
   pub defines: Option<Vec<String>>,
+  /// Features to enable for all modules, like `erlc -enable-feature`, modules can override this
+  pub enable_features: Option<Vec<String>>,
+  /// Features to disable for all modules, like `erlc -disable-feature`
+  pub disable_features: Option<Vec<String>>,
+  /// Try to stop processing a module when this many errors are found
+  pub max_errors_per_module: Option<usize>,
+  /// Report warnings as errors, like `-compile(warnings_as_errors)`
+  pub warnings_as_errors: Option<bool>,
+  /// Warnings to disable, like `-compile(nowarn_<name>)`, for example `["export_all"]`
+  pub nowarn: Option<Vec<String>>,
 }
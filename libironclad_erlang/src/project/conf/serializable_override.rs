@@ -0,0 +1,21 @@
+//! Compiler options overrides for a group of files, as parsed from `[[overrides]]` TOML sections
+use crate::project::conf::serializable_compiler_opts::SerializableCompilerOpts;
+use serde_derive::Deserialize;
+use std::fmt::Debug;
+
+/// Compiler options for the files matching the path patterns. Added to the project compiler
+/// options, like this:
+/// ```toml
+/// [[overrides]]
+/// paths = ["src/legacy/**"]
+/// include_paths = ["src/legacy/include"]
+/// nowarn = ["export_all"]
+/// ```
+#[derive(Deserialize, Debug)]
+pub struct SerializableOverride {
+  /// Glob patterns for the affected files, relative to the directory of the project file
+  pub paths: Vec<String>,
+  /// Options for the affected files, in the same format as `[compiler_options]`
+  #[serde(flatten)]
+  pub compiler_options: SerializableCompilerOpts,
+}
@@ -3,9 +3,11 @@ use crate::project::project_impl::ErlProjectImpl;
 use std::sync::Arc;
 
 pub mod compiler_opts;
+pub mod compiler_override;
 pub mod conf;
 pub mod input_opts;
 pub mod module;
+pub mod project_behaviour;
 pub mod project_impl;
 pub mod project_include;
 pub mod project_inputs;
@@ -1,7 +1,13 @@
 //! Defines an Erlang module ready to be compiled
 
+pub mod module_doc;
+pub mod module_features;
 pub mod module_impl;
+pub mod module_include;
+pub mod module_json;
 pub mod module_parse;
+pub mod module_source_map;
+pub mod module_unused;
 pub mod module_verify;
 pub mod preprocess;
 pub mod scope;
@@ -0,0 +1,476 @@
+//! Documentation model, collected from the `-doc` and `-moduledoc` attributes
+
+use crate::erl_syntax::erl_ast::node_impl::AstNodeType;
+use crate::erl_syntax::erl_ast::AstNode;
+use crate::literal::Literal;
+use crate::project::module::module_impl::ErlModuleImpl;
+use crate::typing::erl_type::ErlType;
+use crate::user_type_def::UserTypeDefinition;
+use libironclad_util::mfarity::MFArity;
+use std::fmt::Write;
+use std::sync::Arc;
+
+/// The item, which a documentation entry belongs to
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DocTarget {
+  /// The module itself, documented by `-moduledoc`
+  Module,
+  /// A function, documented by `-doc` preceding the function
+  Function(MFArity),
+  /// A type, documented by `-doc` preceding `-type` or `-opaque`
+  Type(MFArity),
+  /// A behaviour callback, documented by `-doc` preceding `-callback`
+  Callback(MFArity),
+}
+
+/// One value of a `-doc` or `-moduledoc` attribute
+#[derive(Debug)]
+pub enum DocAttrValue {
+  /// A Markdown string
+  Text(String),
+  /// A `{file, Path}` reference to a Markdown file, relative to the module file
+  File(String),
+  /// `-doc false.` hides the item from the documentation
+  Hidden,
+  /// A map of metadata, like `#{since => "1.0"}`, keys and printed values
+  Metadata(Vec<(String, String)>),
+}
+
+impl DocAttrValue {
+  /// Interpret the expression from a `-doc` attribute, or return `None` if it's not a supported
+  /// documentation value
+  pub fn from_expr(expr: &AstNode) -> Option<Self> {
+    match &expr.content {
+      AstNodeType::Lit { value, .. } => match value.as_ref() {
+        Literal::String(s) | Literal::BinaryString(s) => Some(Self::Text(s.to_string())),
+        Literal::Bool(false) => Some(Self::Hidden),
+        Literal::Atom(a) if a == "false" => Some(Self::Hidden),
+        _ => None,
+      },
+      AstNodeType::Tuple { elements } if elements.len() == 2 && elements[0].is_atom_of("file") => {
+        match &elements[1].content {
+          AstNodeType::Lit { value, .. } => match value.as_ref() {
+            Literal::String(path) => Some(Self::File(path.to_string())),
+            _ => None,
+          },
+          _ => None,
+        }
+      }
+      AstNodeType::MapBuilder { base: None, members } => {
+        let metadata = members
+          .iter()
+          .map(|m| (Self::term_to_string(&m.key), Self::term_to_string(&m.expr)))
+          .collect();
+        Some(Self::Metadata(metadata))
+      }
+      _ => None,
+    }
+  }
+
+  /// Print a metadata term, strings and atoms are printed without quotes
+  fn term_to_string(expr: &AstNode) -> String {
+    match &expr.content {
+      AstNodeType::Lit { value, .. } => match value.as_ref() {
+        Literal::String(s) | Literal::BinaryString(s) => s.to_string(),
+        Literal::Atom(a) => a.clone(),
+        other => format!("{}", other),
+      },
+      AstNodeType::BinaryExpr { elements } if elements.len() == 1 => {
+        Self::term_to_string(&elements[0].value)
+      }
+      _ => format!("{}", expr),
+    }
+  }
+}
+
+/// Documentation for one item, multiple `-doc` attributes before the item are merged together
+#[derive(Debug, Default, Clone)]
+pub struct DocEntry {
+  /// Markdown text
+  pub text: Option<String>,
+  /// Set by `-doc false.`, the item is not documented
+  pub hidden: bool,
+  /// Metadata keys and printed values, in the order of appearance
+  pub metadata: Vec<(String, String)>,
+}
+
+impl DocEntry {
+  /// Merge a value from a `-doc` attribute into the entry. For `{file, Path}` values the file
+  /// contents must be loaded by the caller, and given as `Text`.
+  pub fn apply(&mut self, value: DocAttrValue) {
+    match value {
+      DocAttrValue::Text(text) => self.text = Some(text),
+      DocAttrValue::File(path) => unreachable!("Documentation file {} must be loaded first", path),
+      DocAttrValue::Hidden => self.hidden = true,
+      DocAttrValue::Metadata(metadata) => self.metadata.extend(metadata),
+    }
+  }
+}
+
+/// Documented exported function
+#[derive(Debug)]
+pub struct FunctionDoc {
+  /// Function name and arity
+  pub funarity: MFArity,
+  /// The function `-spec` if present
+  pub spec: Option<ErlType>,
+  /// The documentation if present
+  pub doc: Option<Arc<DocEntry>>,
+}
+
+/// Documented exported type
+#[derive(Debug)]
+pub struct TypeDoc {
+  /// Type name and arity
+  pub name: MFArity,
+  /// The type definition, if known
+  pub definition: Option<Arc<UserTypeDefinition>>,
+  /// The documentation if present
+  pub doc: Option<Arc<DocEntry>>,
+}
+
+/// Documented behaviour callback
+#[derive(Debug)]
+pub struct CallbackDoc {
+  /// Callback name and arity
+  pub funarity: MFArity,
+  /// The callback spec
+  pub spec: ErlType,
+  /// Not required to be exported by the behaviour implementers
+  pub optional: bool,
+  /// The documentation if present
+  pub doc: Option<Arc<DocEntry>>,
+}
+
+/// Documentation for a module: the module doc, and the exported functions, exported types and
+/// callbacks with their specs and docs. Items hidden with `-doc false.` are not included.
+#[derive(Debug)]
+pub struct ModuleDocs {
+  /// Module name
+  pub name: String,
+  /// The `-moduledoc` if present
+  pub doc: Option<Arc<DocEntry>>,
+  /// Exported functions, sorted
+  pub functions: Vec<FunctionDoc>,
+  /// Exported types, sorted
+  pub types: Vec<TypeDoc>,
+  /// Behaviour callbacks, sorted
+  pub callbacks: Vec<CallbackDoc>,
+}
+
+/// Output format for the rendered documentation
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DocFormat {
+  /// Markdown text, `.md`
+  Markdown,
+  /// Static HTML page, `.html`
+  Html,
+}
+
+impl DocFormat {
+  /// File extension for the output files
+  pub fn extension(&self) -> &'static str {
+    match self {
+      DocFormat::Markdown => "md",
+      DocFormat::Html => "html",
+    }
+  }
+}
+
+impl ErlModuleImpl {
+  /// Build the documentation model for this module
+  pub fn documentation(&self) -> ModuleDocs {
+    let scope = &self.root_scope;
+    let doc_for = |target: DocTarget| scope.docs.get(&target);
+    let is_visible = |doc: &Option<Arc<DocEntry>>| !doc.as_ref().map(|d| d.hidden).unwrap_or(false);
+
+    let mut exports: Vec<MFArity> = if self.get_compiler_options().export_all {
+      scope
+        .fn_defs
+        .collection
+        .read()
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect()
+    } else {
+      scope
+        .exports
+        .collection
+        .read()
+        .unwrap()
+        .iter()
+        .cloned()
+        .collect()
+    };
+    exports.sort();
+    let functions = exports
+      .into_iter()
+      .map(|funarity| FunctionDoc {
+        spec: scope.fn_specs.get(&funarity),
+        doc: doc_for(DocTarget::Function(funarity.clone())),
+        funarity,
+      })
+      .filter(|f| is_visible(&f.doc))
+      .collect();
+
+    let mut exported_types: Vec<MFArity> = scope
+      .exported_types
+      .collection
+      .read()
+      .unwrap()
+      .iter()
+      .cloned()
+      .collect();
+    exported_types.sort();
+    let types = exported_types
+      .into_iter()
+      .map(|name| TypeDoc {
+        definition: scope.user_types.get(&name),
+        doc: doc_for(DocTarget::Type(name.clone())),
+        name,
+      })
+      .filter(|t| is_visible(&t.doc))
+      .collect();
+
+    let mut callback_names: Vec<MFArity> = scope
+      .callbacks
+      .collection
+      .read()
+      .unwrap()
+      .keys()
+      .cloned()
+      .collect();
+    callback_names.sort();
+    let callbacks = callback_names
+      .into_iter()
+      .filter_map(|funarity| {
+        Some(CallbackDoc {
+          spec: scope.callbacks.get(&funarity)?,
+          optional: scope.optional_callbacks.contains(&funarity),
+          doc: doc_for(DocTarget::Callback(funarity.clone())),
+          funarity,
+        })
+      })
+      .filter(|c| is_visible(&c.doc))
+      .collect();
+
+    ModuleDocs {
+      name: self.get_name(),
+      doc: doc_for(DocTarget::Module),
+      functions,
+      types,
+      callbacks,
+    }
+  }
+}
+
+impl ModuleDocs {
+  /// Whether the module is hidden with `-moduledoc false.`
+  pub fn is_hidden(&self) -> bool {
+    self.doc.as_ref().map(|d| d.hidden).unwrap_or(false)
+  }
+
+  /// Render the module documentation in the given format
+  pub fn render(&self, format: DocFormat) -> String {
+    match format {
+      DocFormat::Markdown => self.to_markdown(),
+      DocFormat::Html => self.to_html(),
+    }
+  }
+
+  /// Render an index page, linking the documentation pages of the modules
+  pub fn render_index(modules: &[ModuleDocs], format: DocFormat) -> String {
+    let mut out = String::new();
+    match format {
+      DocFormat::Markdown => {
+        out.push_str("# Modules\n\n");
+        for m in modules.iter() {
+          writeln!(out, "* [{name}]({name}.md){}", Self::summary(&m.doc, " - "), name = m.name)
+            .unwrap();
+        }
+      }
+      DocFormat::Html => {
+        out.push_str("<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Modules</title>");
+        out.push_str("</head><body>\n<h1>Modules</h1>\n<ul>\n");
+        for m in modules.iter() {
+          writeln!(
+            out,
+            "<li><a href=\"{name}.html\">{name}</a>{}</li>",
+            html_escape(&Self::summary(&m.doc, " - ")),
+            name = html_escape(&m.name)
+          )
+          .unwrap();
+        }
+        out.push_str("</ul>\n</body></html>\n");
+      }
+    }
+    out
+  }
+
+  /// The first line of the documentation text, prefixed, or an empty string
+  fn summary(doc: &Option<Arc<DocEntry>>, prefix: &str) -> String {
+    doc
+      .as_ref()
+      .and_then(|d| d.text.as_ref())
+      .and_then(|t| t.lines().find(|l| !l.trim().is_empty()))
+      .map(|l| format!("{}{}", prefix, l.trim()))
+      .unwrap_or_default()
+  }
+
+  /// Signature line for a function or a callback: `name(Args) -> Ret` or `name/Arity`
+  fn signature(funarity: &MFArity, spec: &Option<ErlType>) -> String {
+    match spec {
+      Some(spec) => {
+        let mut out = String::new();
+        let clauses = spec.as_fn_type().clauses();
+        for (i, clause) in clauses.iter().enumerate() {
+          if i > 0 {
+            out.push_str(";\n");
+          }
+          write!(out, "{}{}", funarity.name, clause).unwrap();
+        }
+        out
+      }
+      None => format!("{}", funarity),
+    }
+  }
+
+  /// Signature line for a type: `-type name(Vars) :: Definition.`
+  fn type_signature(name: &MFArity, definition: &Option<Arc<UserTypeDefinition>>) -> String {
+    match definition {
+      // The opaque type structure is not documented
+      Some(d) if d.opaque => format!("-opaque {}({}).", d.name, d.vars.join(", ")),
+      Some(d) => format!("{}.", d),
+      None => format!("{}", name),
+    }
+  }
+
+  fn markdown_entry(out: &mut String, title: &str, signature: &str, doc: &Option<Arc<DocEntry>>) {
+    writeln!(out, "### {}\n\n```erlang\n{}\n```\n", title, signature).unwrap();
+    if let Some(d) = doc {
+      for (key, value) in d.metadata.iter() {
+        writeln!(out, "*{}*: {}  ", key, value).unwrap();
+      }
+      if !d.metadata.is_empty() {
+        out.push('\n');
+      }
+      if let Some(text) = &d.text {
+        writeln!(out, "{}\n", text.trim_end()).unwrap();
+      }
+    }
+  }
+
+  /// Render the module documentation as Markdown
+  pub fn to_markdown(&self) -> String {
+    let mut out = format!("# Module {}\n\n", self.name);
+    if let Some(text) = self.doc.as_ref().and_then(|d| d.text.as_ref()) {
+      writeln!(out, "{}\n", text.trim_end()).unwrap();
+    }
+    if !self.types.is_empty() {
+      out.push_str("## Types\n\n");
+      for t in self.types.iter() {
+        let signature = Self::type_signature(&t.name, &t.definition);
+        Self::markdown_entry(&mut out, &format!("{}", t.name), &signature, &t.doc);
+      }
+    }
+    if !self.callbacks.is_empty() {
+      out.push_str("## Callbacks\n\n");
+      for c in self.callbacks.iter() {
+        let title = if c.optional {
+          format!("{} (optional)", c.funarity)
+        } else {
+          format!("{}", c.funarity)
+        };
+        let signature = Self::signature(&c.funarity, &Some(c.spec.clone()));
+        Self::markdown_entry(&mut out, &title, &signature, &c.doc);
+      }
+    }
+    if !self.functions.is_empty() {
+      out.push_str("## Functions\n\n");
+      for f in self.functions.iter() {
+        let signature = Self::signature(&f.funarity, &f.spec);
+        Self::markdown_entry(&mut out, &format!("{}", f.funarity), &signature, &f.doc);
+      }
+    }
+    out
+  }
+
+  fn html_entry(out: &mut String, title: &str, signature: &str, doc: &Option<Arc<DocEntry>>) {
+    writeln!(
+      out,
+      "<h3>{}</h3>\n<pre><code>{}</code></pre>",
+      html_escape(title),
+      html_escape(signature)
+    )
+    .unwrap();
+    if let Some(d) = doc {
+      if !d.metadata.is_empty() {
+        out.push_str("<dl>\n");
+        for (key, value) in d.metadata.iter() {
+          writeln!(out, "<dt>{}</dt><dd>{}</dd>", html_escape(key), html_escape(value)).unwrap();
+        }
+        out.push_str("</dl>\n");
+      }
+      if let Some(text) = &d.text {
+        html_paragraphs(out, text);
+      }
+    }
+  }
+
+  /// Render the module documentation as a static HTML page. The Markdown text is not converted,
+  /// it's split into paragraphs on empty lines.
+  pub fn to_html(&self) -> String {
+    let name = html_escape(&self.name);
+    let mut out = format!(
+      "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{name}</title></head><body>\n\
+      <h1>Module {name}</h1>\n"
+    );
+    if let Some(text) = self.doc.as_ref().and_then(|d| d.text.as_ref()) {
+      html_paragraphs(&mut out, text);
+    }
+    if !self.types.is_empty() {
+      out.push_str("<h2>Types</h2>\n");
+      for t in self.types.iter() {
+        let signature = Self::type_signature(&t.name, &t.definition);
+        Self::html_entry(&mut out, &format!("{}", t.name), &signature, &t.doc);
+      }
+    }
+    if !self.callbacks.is_empty() {
+      out.push_str("<h2>Callbacks</h2>\n");
+      for c in self.callbacks.iter() {
+        let title = if c.optional {
+          format!("{} (optional)", c.funarity)
+        } else {
+          format!("{}", c.funarity)
+        };
+        let signature = Self::signature(&c.funarity, &Some(c.spec.clone()));
+        Self::html_entry(&mut out, &title, &signature, &c.doc);
+      }
+    }
+    if !self.functions.is_empty() {
+      out.push_str("<h2>Functions</h2>\n");
+      for f in self.functions.iter() {
+        let signature = Self::signature(&f.funarity, &f.spec);
+        Self::html_entry(&mut out, &format!("{}", f.funarity), &signature, &f.doc);
+      }
+    }
+    out.push_str("</body></html>\n");
+    out
+  }
+}
+
+/// Write the text as HTML paragraphs, split on empty lines
+fn html_paragraphs(out: &mut String, text: &str) {
+  for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+    writeln!(out, "<p>{}</p>", html_escape(paragraph)).unwrap();
+  }
+}
+
+/// Escape the HTML special characters
+fn html_escape(s: &str) -> String {
+  s.replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
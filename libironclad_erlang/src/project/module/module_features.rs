@@ -0,0 +1,111 @@
+//! Language features, enabled or disabled per module with `-feature(Name, enable | disable).`
+
+use crate::erl_syntax::parsers::token_stream::keyword::Keyword;
+use crate::erl_syntax::parsers::token_stream::token::Token;
+use crate::erl_syntax::parsers::token_stream::token_kind::TokenKind;
+use crate::project::module::module_impl::ErlModuleImpl;
+use std::sync::atomic::Ordering;
+
+/// A language feature which gates some syntax, like `maybe_expr` does since OTP 25
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErlFeature {
+  /// The `maybe ... else ... end` expression, reserves `maybe` and `else` keywords
+  MaybeExpr,
+}
+
+impl ErlFeature {
+  /// All features known to the compiler
+  pub const ALL: [ErlFeature; 1] = [ErlFeature::MaybeExpr];
+
+  /// Feature name as written in `-feature()` and in the project configuration
+  pub fn name(&self) -> &'static str {
+    match self {
+      ErlFeature::MaybeExpr => "maybe_expr",
+    }
+  }
+
+  /// Find a feature by its name
+  pub fn from_name(name: &str) -> Option<Self> {
+    Self::ALL.iter().find(|f| f.name() == name).copied()
+  }
+
+  /// Whether the feature is enabled when neither the configuration nor the module mention it.
+  /// Since OTP 27 `maybe_expr` is enabled by default.
+  pub fn enabled_by_default(&self) -> bool {
+    match self {
+      ErlFeature::MaybeExpr => true,
+    }
+  }
+
+  /// Words which are keywords only while the feature is enabled, and atoms otherwise
+  pub fn keywords(&self) -> &'static [Keyword] {
+    match self {
+      ErlFeature::MaybeExpr => &[Keyword::Maybe, Keyword::Else],
+    }
+  }
+}
+
+impl std::fmt::Display for ErlFeature {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.name())
+  }
+}
+
+impl ErlModuleImpl {
+  /// Set feature states from the compiler options, before the module is preprocessed
+  pub fn apply_config_features(&self) {
+    for (feature, enabled) in self.get_compiler_options().features.iter() {
+      self.features.add(*feature, *enabled)
+    }
+  }
+
+  /// Enable or disable a feature, as `-feature()` does
+  pub fn set_feature(&self, feature: ErlFeature, enabled: bool) {
+    self.features.add(feature, enabled)
+  }
+
+  /// Check whether a feature is currently enabled for this module
+  pub fn is_feature_enabled(&self, feature: ErlFeature) -> bool {
+    self
+      .features
+      .get(&feature)
+      .unwrap_or_else(|| feature.enabled_by_default())
+  }
+
+  /// Called by the preprocessor when code (not a directive or an attribute) is output, after that
+  /// the `-feature` directives are no longer allowed
+  pub(crate) fn mark_function_seen(&self) {
+    self.function_seen.store(true, Ordering::Relaxed)
+  }
+
+  /// Whether code was already output by the preprocessor
+  pub(crate) fn is_function_seen(&self) -> bool {
+    self.function_seen.load(Ordering::Relaxed)
+  }
+
+  /// Keywords reserved by disabled features are replaced with atoms of the same name.
+  /// Returns `None` if no token had to be replaced.
+  pub(crate) fn demote_feature_keywords(&self, tokens: &[Token]) -> Option<Vec<Token>> {
+    let disabled: Vec<Keyword> = ErlFeature::ALL
+      .iter()
+      .filter(|f| !self.is_feature_enabled(**f))
+      .flat_map(|f| f.keywords().iter().copied())
+      .collect();
+    let is_demoted = |t: &Token| matches!(&t.kind, TokenKind::Keyword(kw) if disabled.contains(kw));
+    if !tokens.iter().any(is_demoted) {
+      return None;
+    }
+    let result = tokens
+      .iter()
+      .map(|t| match &t.kind {
+        TokenKind::Keyword(kw) if disabled.contains(kw) => {
+          let mut atom = t.clone();
+          atom.kind = TokenKind::Atom(kw.to_str().to_string());
+          atom
+        }
+        _ => t.clone(),
+      })
+      .collect();
+    Some(result)
+  }
+}
@@ -2,31 +2,42 @@
 
 use crate::erl_syntax::erl_ast::node_impl::AstNodeImpl;
 use crate::erl_syntax::erl_ast::AstNode;
-use crate::erl_syntax::parsers::misc::panicking_tokenizer_error_reporter;
-use crate::erl_syntax::parsers::token_stream::tok_input::{TokenizerInput, TokensResult};
+use crate::erl_syntax::ic_parser_error::IcParserError;
+use crate::erl_syntax::ic_preprocessor_error::IcPreprocessorError;
 use crate::erl_syntax::parsers::token_stream::token::Token;
 use crate::erl_syntax::parsers::token_stream::token_kind::TokenKind;
-use crate::erl_syntax::parsers::token_stream::tokenizer::tokenize_source;
-use crate::error::ic_error::IroncladResult;
+use crate::erl_syntax::parsers::token_stream::tokenizer::tokenize_source_recovering;
+use crate::error::ic_error::{IcSeverity, IroncladResult};
 use crate::error::ic_error_trait::GenericIroncladError;
 use crate::project::compiler_opts::{CompilerOpts, CompilerOptsImpl};
+use crate::project::module::module_doc::DocEntry;
+use crate::project::module::module_features::ErlFeature;
+use crate::project::module::module_include::IncludeEdge;
+use crate::project::module::module_source_map::LineRemap;
 use crate::project::module::scope::root_scope::RootScope;
 use crate::project::ErlProject;
+use crate::source_loc::SourceLoc;
+use crate::user_type_def::UserTypeDefinition;
+use libironclad_util::mfarity::MFArity;
+use libironclad_util::rw_hashmap::RwHashMap;
 use libironclad_util::rw_vec::RwVec;
 use libironclad_util::source_file::{SourceFile, SourceFileImpl};
-use nom::Finish;
 use std::cell::RefCell;
 use std::fmt;
 use std::fmt::Debug;
+use std::ops::Deref;
+use std::path::PathBuf;
 use std::ptr::null;
+use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, RwLock};
 
 /// Erlang Module consists of
 /// - List of forms: attributes, and Erlang functions
 /// - Compiler options used to produce this module
 pub struct ErlModuleImpl {
-  /// Options used to build this module. Possibly just a ref to the main project's options
-  pub compiler_options: CompilerOpts,
+  /// Options used to build this module. Possibly just a ref to the main project's options, until
+  /// a `-compile()` attribute replaces them with a module-specific copy.
+  pub compiler_options: RwLock<CompilerOpts>,
   /// Module name atom, as a string
   pub name: RwLock<String>,
   /// The file we're processing AND the file contents (owned by SourceFile)
@@ -42,8 +53,29 @@ pub struct ErlModuleImpl {
   pub errors: RwVec<GenericIroncladError>,
   /// Warnings which can accumulate but do not block the processing
   pub warnings: RwVec<GenericIroncladError>,
-  // /// Collection of included files to prevent circular imports
-  // pub included_files: RwHashSet<OsString>,
+  /// Files currently being preprocessed, the module file first, the innermost include last.
+  /// Used to resolve includes relative to the including file, and to detect circular includes.
+  pub include_stack: RwVec<PathBuf>,
+  /// Every `-include` and `-include_lib` resolved while preprocessing this module
+  pub include_graph: RwVec<IncludeEdge>,
+  /// Preprocess-only mode (like `erlc -P`): module attributes are kept in the token stream, and
+  /// `-file` attributes mark the include boundaries, so the output can be printed back as source.
+  /// Such token stream is not suitable for parsing.
+  pub preprocess_only: bool,
+  /// Every file tokenized for this module: the module file and the included files
+  pub source_files: RwVec<SourceFile>,
+  /// Line remapping from the `-file` attributes, in the order found
+  pub line_remaps: RwVec<LineRemap>,
+  /// Feature states set by the compiler options and the `-feature` attributes. Features not
+  /// present here have their default state.
+  pub features: RwHashMap<ErlFeature, bool>,
+  /// Set when the preprocessor outputs the first function, `-feature` is not allowed after that
+  pub function_seen: AtomicBool,
+  /// Documentation from the `-doc` attributes, waiting for the function, spec, type or callback
+  /// which follows them, and the location of the first of these attributes
+  pub pending_doc: RwLock<Option<(DocEntry, SourceLoc)>>,
+  /// Location of the first `-compile` attribute which enabled `export_all`
+  pub export_all_location: RwLock<Option<SourceLoc>>,
 }
 
 /// Wraps module into runtime-lockable refcount
@@ -52,14 +84,22 @@ pub type ErlModule = Arc<ErlModuleImpl>;
 impl Default for ErlModuleImpl {
   fn default() -> Self {
     Self {
-      compiler_options: Default::default(),
+      compiler_options: RwLock::new(Default::default()),
       name: RwLock::new(String::default()),
       source_file: Arc::new(SourceFileImpl::default()),
       ast: RefCell::new(AstNodeImpl::new_empty("dummy node for module root".to_string())),
       root_scope: RootScope::default(),
       errors: RwVec::with_capacity(CompilerOptsImpl::MAX_ERRORS_PER_MODULE * 110 / 100),
       warnings: RwVec::default(),
-      // included_files: Default::default(),
+      include_stack: RwVec::default(),
+      include_graph: RwVec::default(),
+      preprocess_only: false,
+      source_files: RwVec::default(),
+      line_remaps: RwVec::default(),
+      features: RwHashMap::default(),
+      function_seen: AtomicBool::new(false),
+      pending_doc: RwLock::new(None),
+      export_all_location: RwLock::new(None),
     }
   }
 }
@@ -79,7 +119,7 @@ impl ErlModuleImpl {
   /// Create a new empty module
   pub fn new(opt: CompilerOpts, source_file: SourceFile) -> ErlModule {
     Self {
-      compiler_options: opt,
+      compiler_options: RwLock::new(opt),
       source_file,
       ..Default::default()
     }
@@ -88,15 +128,62 @@ impl ErlModuleImpl {
 
   /// Given project, module and the source file, break it into tokens, and interpret the
   /// preprocessor directives using module root scope.
+  /// The file is pushed on the module include stack for the duration of the processing. Before the
+  /// module file is processed, the defines and the features from the compiler options are added to
+  /// the module. After the module file is processed, the `-compile()` options are checked.
   pub fn tokenize(
     project: &ErlProject,
     module: &ErlModule,
     src_file: &SourceFile,
+  ) -> IroncladResult<Vec<Token>> {
+    if module.include_stack.is_empty() {
+      module.apply_config_defines();
+      module.apply_config_features();
+    }
+    module.add_source_file(src_file);
+    module.include_stack_push(&src_file.file_name);
+    let result = Self::tokenize_and_preprocess(project, module, src_file);
+    module.include_stack_pop();
+    if module.include_stack.is_empty() {
+      module.check_compile_options();
+    }
+    result
+  }
+
+  /// Same as erlc, apply `warnings_as_errors` to the whole module: the warnings added before the
+  /// option was seen become errors. Warn that `export_all` is used, unless `nowarn_export_all` is
+  /// also given.
+  fn check_compile_options(&self) {
+    let opts = self.get_compiler_options();
+    if opts.warnings_as_errors {
+      for warning in self.warnings.take() {
+        self.add_error(warning);
+      }
+    }
+    if opts.export_all && opts.is_warning_enabled("export_all", None) {
+      let location = self
+        .export_all_location
+        .read()
+        .unwrap()
+        .clone()
+        .unwrap_or(SourceLoc::None);
+      self.add_warning(IcPreprocessorError::new(
+        IcSeverity::Warning,
+        location,
+        "export_all flag enabled - all functions will be exported".to_string(),
+      ))
+    }
+  }
+
+  fn tokenize_and_preprocess(
+    project: &ErlProject,
+    module: &ErlModule,
+    src_file: &SourceFile,
   ) -> IroncladResult<Vec<Token>> {
     //----------------------
     // Stage 1 tokenize the input
     //----------------------
-    let mut tokens = ErlModuleImpl::tokenize_helper(project, src_file.clone(), tokenize_source)?;
+    let mut tokens = ErlModuleImpl::tokenize_helper(module, src_file);
 
     // Inject a mandatory EOL if the stream doesn't end with one
     if !Token::ends_with(&tokens, &[TokenKind::EOL]) {
@@ -115,31 +202,59 @@ impl ErlModuleImpl {
     Ok(tokens_preprocessed)
   }
 
-  /// Generic tokenizer for any Nom entry point
-  pub fn tokenize_helper<T>(
-    _project: &ErlProject,
-    src_file: SourceFile,
-    parse_fn: T,
-  ) -> IroncladResult<Vec<Token>>
-  where
-    T: Fn(TokenizerInput) -> TokensResult<Vec<Token>>,
-  {
-    let input = src_file.text.as_str();
-    let forms = panicking_tokenizer_error_reporter(input, parse_fn(input).finish());
-
-    Ok(forms)
+  /// Break the source file into tokens. A span which can't be tokenized becomes an `Invalid()`
+  /// token, and an error is added to the module for it.
+  pub fn tokenize_helper(module: &ErlModule, src_file: &SourceFile) -> Vec<Token> {
+    let (tokens, errors) = tokenize_source_recovering(src_file.text.as_str());
+    for (invalid, message) in errors.into_iter() {
+      let err = IcParserError::new(IcSeverity::Error, SourceLoc::from_token(&invalid), message);
+      if !module.add_error(err) {
+        break;
+      }
+    }
+    tokens
   }
 
   /// Adds an error to vector of errors. Returns false when error list is full and the calling code
   /// should attempt to stop.
   pub fn add_error(&self, err: GenericIroncladError) -> bool {
     self.errors.push(err);
-    self.errors.len() < self.compiler_options.max_errors_per_module
+    self.errors.len() < self.get_compiler_options().max_errors_per_module
   }
 
-  /// Adds an warning to vector of warnings.
+  /// Adds an warning to vector of warnings. With `warnings_as_errors` option the warning is added
+  /// to the errors instead. The warnings added before the option is seen are moved to the errors
+  /// when the module file is processed.
   pub fn add_warning(&self, err: GenericIroncladError) {
-    self.warnings.push(err);
+    if self.get_compiler_options().warnings_as_errors {
+      self.add_error(err);
+    } else {
+      self.warnings.push(err);
+    }
+  }
+
+  /// Get the compiler options for this module, including the `-compile()` attribute options seen
+  /// so far
+  pub fn get_compiler_options(&self) -> CompilerOpts {
+    self.compiler_options.read().unwrap().clone()
+  }
+
+  /// Change the compiler options for this module only. The options are copied, so the project
+  /// options shared with other modules are not affected.
+  pub fn update_compiler_options<Update: FnOnce(&mut CompilerOptsImpl)>(&self, update: Update) {
+    if let Ok(mut w_opts) = self.compiler_options.write() {
+      let mut opts = w_opts.deref().deref().clone();
+      update(&mut opts);
+      *w_opts = opts.into();
+    } else {
+      panic!("Can't lock module compiler options for update")
+    }
+  }
+
+  /// Check whether the function is exported by `-export()`, or by the `export_all` option
+  pub fn is_exported(&self, funarity: &MFArity) -> bool {
+    self.root_scope.exports.contains(funarity)
+      || (self.get_compiler_options().export_all && self.root_scope.fn_defs.contains(funarity))
   }
 
   /// Update the module name when we learn it from -module() attribute
@@ -163,16 +278,42 @@ impl ErlModuleImpl {
     self.name.read().unwrap().clone()
   }
 
+  /// Find a `-type` or `-opaque` definition for a possibly remote type name. Local types, and types
+  /// qualified with this module name are looked up in this module, other modules are looked up in
+  /// the project. Returns the type name qualified with its defining module, and the definition.
+  /// The lookup is by name and arity only, the type arguments of a parametric type are ignored, and
+  /// the definition is returned with its own type variables.
+  pub fn find_user_type(&self, name: &MFArity) -> Option<(MFArity, Arc<UserTypeDefinition>)> {
+    let self_name = self.get_name();
+    let defined_in = name.module.clone().unwrap_or_else(|| self_name.clone());
+    let key = MFArity::new_local(&name.name, name.arity);
+    let type_def = if defined_in == self_name {
+      self.root_scope.user_types.get(&key)
+    } else {
+      let other_module = self.root_scope.project.modules.get(&defined_in)?;
+      other_module.root_scope.user_types.get(&key)
+    }?;
+    Some((key.clone_with_module(&defined_in), type_def))
+  }
+
   /// Check whether any errors were reported for this module
   pub fn has_errors(&self) -> bool {
     !self.errors.is_empty()
   }
 
+  /// Print an error or a warning, prefixed with `file:line` if its location is known
+  fn print_with_location(&self, err: &GenericIroncladError) {
+    match self.format_location(&err.get_location()) {
+      Some(file_line) => println!("{}: {}", file_line, err),
+      None => println!("{}", err),
+    }
+  }
+
   /// Print errors accumulated for this module
   pub fn print_errors(&self) {
     if let Ok(r_errors) = self.errors.data.read() {
       for err in r_errors.iter() {
-        println!("{}", err);
+        self.print_with_location(err);
       }
     } else {
       panic!("Can't lock module errors collection for printing")
@@ -180,7 +321,7 @@ impl ErlModuleImpl {
 
     if let Ok(r_warnings) = self.warnings.data.read() {
       for wrn in r_warnings.iter() {
-        println!("{}", wrn);
+        self.print_with_location(wrn);
       }
     } else {
       panic!("Can't lock module warnings collection for printing")
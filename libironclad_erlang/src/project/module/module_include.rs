@@ -0,0 +1,79 @@
+//! Tracking of the include chain and the include graph for `ErlModule`
+
+use crate::project::module::module_impl::ErlModuleImpl;
+use std::path::{Path, PathBuf};
+
+/// An edge in the module include graph: file `from` contains an `-include` which resolved to `to`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncludeEdge {
+  /// The file containing the `-include` or `-include_lib` directive
+  pub from: PathBuf,
+  /// The resolved included file
+  pub to: PathBuf,
+}
+
+impl ErlModuleImpl {
+  /// Normalize a path for include chain comparisons. Files which do not exist on disk (like test
+  /// inputs) are kept as is.
+  pub(crate) fn include_key(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+  }
+
+  /// Called when the preprocessor begins processing a file (the module file, or an include)
+  pub(crate) fn include_stack_push(&self, path: &Path) {
+    self.include_stack.push(Self::include_key(path))
+  }
+
+  /// Called when the preprocessor is done with the current file
+  pub(crate) fn include_stack_pop(&self) {
+    self.include_stack.pop();
+  }
+
+  /// Returns the chain of files being currently preprocessed, starting with the module file and
+  /// ending with the innermost included file.
+  pub fn include_chain(&self) -> Vec<PathBuf> {
+    self.include_stack.clone_contents()
+  }
+
+  /// If `path` is already present in the include chain, returns the full chain with `path`
+  /// appended, to be reported as a circular include.
+  pub(crate) fn find_include_cycle(&self, path: &Path) -> Option<Vec<PathBuf>> {
+    let mut chain = self.include_chain();
+    if chain.iter().any(|p| p == path) {
+      chain.push(path.to_path_buf());
+      Some(chain)
+    } else {
+      None
+    }
+  }
+
+  /// Record that `to` was included from the innermost file in the include chain
+  pub(crate) fn add_include_edge(&self, to: &Path) {
+    if let Some(from) = self.include_stack.last() {
+      self
+        .include_graph
+        .push(IncludeEdge { from, to: to.to_path_buf() })
+    }
+  }
+
+  /// Returns all files included by this module directly or indirectly, in order of inclusion and
+  /// without duplicates.
+  pub fn included_files(&self) -> Vec<PathBuf> {
+    let mut result: Vec<PathBuf> = Vec::new();
+    for edge in self.include_graph.clone_contents() {
+      if !result.contains(&edge.to) {
+        result.push(edge.to);
+      }
+    }
+    result
+  }
+
+  /// Format an include chain for the user, as `a.erl -> b.hrl -> c.hrl`
+  pub(crate) fn format_include_chain(chain: &[PathBuf]) -> String {
+    chain
+      .iter()
+      .map(|p| p.to_string_lossy().to_string())
+      .collect::<Vec<String>>()
+      .join(" -> ")
+  }
+}
@@ -0,0 +1,222 @@
+//! Export of a parsed module as JSON, for the external tools which do not link against Ironclad.
+//!
+//! The output is one object, schema version `MODULE_JSON_SCHEMA_VERSION`:
+//! * `schema_version`: integer, incremented when a field is renamed, removed or changes meaning.
+//!   Adding fields does not change the version.
+//! * `module`: the module name; `file`: the source file path.
+//! * `ast`: the module forms. Every node is an object with `type`, the `AstNodeType` variant name,
+//!   `span`, and the variant fields named as in `AstNodeType`. A variant holding one struct, like
+//!   `FnDef`, `Apply`, `BinaryOp` or `Var`, has the struct fields instead, and the clauses, map and
+//!   record members, and binary elements have the fields of their structs, like `guard_expr` of a
+//!   function clause. The catch clause pattern fields `class`, `error` and `stack` are written
+//!   directly in the clause. Literals are `{kind, value}` with the `Literal` variant name and the
+//!   value printed in Erlang syntax, except integers, which are JSON numbers of any size.
+//! * `span`: `{file, line, column, offset}` of the node start, after `-file` remapping of the file
+//!   and the line, or `null` for nodes created by the compiler. The AST does not keep node ends.
+//!   A function definition starts at its first clause.
+//! * `exports`, `exported_types`, `imports`: sorted `name/arity` strings, imports as
+//!   `module:name/arity`.
+//! * `specs`: `{function, spec}` sorted by function, the spec printed in Erlang syntax.
+//! * `types`: `{name, vars, opaque, definition}` sorted by name and arity.
+//! * `records`: `{tag, fields}` sorted by tag, each field is `{name, initializer, type}` with the
+//!   initializer as an AST node or `null`, and the type printed or `null`.
+//! * `attributes`: `{tag, value}` for the attributes which the compiler does not interpret, sorted
+//!   by tag, in the source order for the same tag. The value is an AST node or `null`.
+//! * `defines`: `{name, args, body, defined_at}` sorted by name and arity, with the body printed as
+//!   source text, and `defined_at` as `file:line` or `null` for predefined macros.
+
+use crate::erl_syntax::parsers::token_stream::token_source::format_tokens_as_source;
+use crate::project::module::module_impl::ErlModuleImpl;
+use crate::source_loc::SourceLoc;
+use libironclad_util::json::JsonValue;
+use libironclad_util::mfarity::MFArity;
+use libironclad_util::rw_hashset::RwHashSet;
+use libironclad_util::source_file::SourceFile;
+
+/// Version of the JSON export schema, see the module documentation
+pub const MODULE_JSON_SCHEMA_VERSION: u32 = 1;
+
+/// Sorted `name/arity` strings for a set of functions or types
+fn sorted_mfas_json(set: &RwHashSet<MFArity>) -> JsonValue {
+  let mut items: Vec<String> = if let Ok(r_set) = set.collection.read() {
+    r_set.iter().map(|mfa| mfa.to_string()).collect()
+  } else {
+    panic!("Can't lock a function set for reading")
+  };
+  items.sort();
+  JsonValue::Array(items.into_iter().map(JsonValue::String).collect())
+}
+
+impl ErlModuleImpl {
+  /// Span of a source location: file and line after `-file` remapping, column and offset in the
+  /// physical file, or `null` if the location does not point into `source_files`
+  fn span_json(&self, source_files: &[SourceFile], loc: &SourceLoc) -> JsonValue {
+    let start = match loc.start() {
+      Some(start) => start,
+      None => return JsonValue::Null,
+    };
+    let found = source_files
+      .iter()
+      .find_map(|f| f.position_of(start).map(|pos| (f.file_name.clone(), pos)));
+    match found {
+      Some((physical_file, (offset, line, column))) => {
+        let (file, line) = self.logical_file_line(&physical_file, line);
+        JsonValue::object(vec![
+          ("file", JsonValue::string(file.to_string_lossy())),
+          ("line", JsonValue::number(line)),
+          ("column", JsonValue::number(column)),
+          ("offset", JsonValue::number(offset)),
+        ])
+      }
+      None => JsonValue::Null,
+    }
+  }
+
+  /// Export the module AST, the root scope contents and the preprocessor defines as JSON, see
+  /// the `module_json` module for the schema
+  pub fn to_json(&self) -> JsonValue {
+    let source_files = self.source_files.clone_contents();
+    let span = |loc: &SourceLoc| self.span_json(&source_files, loc);
+    let scope = &self.root_scope;
+
+    let mut specs: Vec<(MFArity, String)> = if let Ok(r_specs) = scope.fn_specs.collection.read() {
+      r_specs
+        .iter()
+        .map(|(mfa, spec)| (mfa.clone(), spec.to_string()))
+        .collect()
+    } else {
+      panic!("Can't lock module specs for reading")
+    };
+    specs.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut types: Vec<(MFArity, JsonValue)> =
+      if let Ok(r_types) = scope.user_types.collection.read() {
+        r_types
+          .iter()
+          .map(|(mfa, def)| {
+            let json = JsonValue::object(vec![
+              ("name", JsonValue::string(mfa)),
+              ("vars", JsonValue::Array(def.vars.iter().map(JsonValue::string).collect())),
+              ("opaque", JsonValue::Bool(def.opaque)),
+              ("definition", JsonValue::string(&def.ty)),
+            ]);
+            (mfa.clone(), json)
+          })
+          .collect()
+      } else {
+        panic!("Can't lock module types for reading")
+      };
+    types.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut records: Vec<(String, JsonValue)> =
+      if let Ok(r_records) = scope.record_defs.collection.read() {
+        r_records
+          .iter()
+          .map(|(tag, def)| {
+            let fields = def
+              .fields
+              .iter()
+              .map(|field| {
+                JsonValue::object(vec![
+                  ("name", JsonValue::string(&field.field_tag)),
+                  (
+                    "initializer",
+                    JsonValue::optional(field.initializer.as_ref(), |n| n.to_json(&span)),
+                  ),
+                  ("type", JsonValue::optional(field.type_ascription.as_ref(), JsonValue::string)),
+                ])
+              })
+              .collect();
+            let json = JsonValue::object(vec![
+              ("tag", JsonValue::string(tag)),
+              ("fields", JsonValue::Array(fields)),
+            ]);
+            (tag.clone(), json)
+          })
+          .collect()
+      } else {
+        panic!("Can't lock module records for reading")
+      };
+    records.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut attributes: Vec<(String, Vec<JsonValue>)> =
+      if let Ok(r_attributes) = scope.attributes.collection.read() {
+        r_attributes
+          .iter()
+          .map(|(tag, attrs)| {
+            let values = attrs
+              .collection
+              .read()
+              .unwrap()
+              .iter()
+              .map(|attr| {
+                JsonValue::object(vec![
+                  ("tag", JsonValue::string(&attr.tag)),
+                  ("value", JsonValue::optional(attr.expr.as_ref(), |n| n.to_json(&span))),
+                ])
+              })
+              .collect();
+            (tag.clone(), values)
+          })
+          .collect()
+      } else {
+        panic!("Can't lock module attributes for reading")
+      };
+    attributes.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut defines: Vec<(MFArity, JsonValue)> =
+      if let Ok(r_defines) = scope.defines.collection.read() {
+        r_defines
+          .iter()
+          .map(|(name_arity, define)| {
+            let json = JsonValue::object(vec![
+              ("name", JsonValue::string(&define.name)),
+              ("args", JsonValue::Array(define.args.iter().map(JsonValue::string).collect())),
+              ("body", JsonValue::string(format_tokens_as_source(&define.tokens).trim_end())),
+              ("defined_at", JsonValue::optional(define.defined_at.as_ref(), JsonValue::string)),
+            ]);
+            (name_arity.clone(), json)
+          })
+          .collect()
+      } else {
+        panic!("Can't lock module defines for reading")
+      };
+    defines.sort_by(|a, b| a.0.cmp(&b.0));
+
+    JsonValue::object(vec![
+      ("schema_version", JsonValue::number(MODULE_JSON_SCHEMA_VERSION)),
+      ("module", JsonValue::string(self.name.read().unwrap())),
+      ("file", JsonValue::string(self.source_file.file_name.to_string_lossy())),
+      ("ast", self.ast.borrow().to_json(&span)),
+      ("exports", sorted_mfas_json(&scope.exports)),
+      ("exported_types", sorted_mfas_json(&scope.exported_types)),
+      ("imports", sorted_mfas_json(&scope.imports)),
+      (
+        "specs",
+        JsonValue::Array(
+          specs
+            .into_iter()
+            .map(|(mfa, spec)| {
+              JsonValue::object(vec![
+                ("function", JsonValue::string(mfa)),
+                ("spec", JsonValue::String(spec)),
+              ])
+            })
+            .collect(),
+        ),
+      ),
+      ("types", JsonValue::Array(types.into_iter().map(|(_, json)| json).collect())),
+      ("records", JsonValue::Array(records.into_iter().map(|(_, json)| json).collect())),
+      (
+        "attributes",
+        JsonValue::Array(
+          attributes
+            .into_iter()
+            .flat_map(|(_, values)| values)
+            .collect(),
+        ),
+      ),
+      ("defines", JsonValue::Array(defines.into_iter().map(|(_, json)| json).collect())),
+    ])
+  }
+}
@@ -1,5 +1,7 @@
 //! Parsing impl for `ErlModule`
 
+use crate::erl_syntax::erl_ast::ast_const_fold::ConstFolder;
+use crate::erl_syntax::erl_ast::ast_folder::fold_node;
 use crate::erl_syntax::erl_ast::node_impl::AstNodeImpl;
 use crate::erl_syntax::erl_ast::AstNode;
 use crate::erl_syntax::parsers::defs::ParserResult;
@@ -8,13 +10,15 @@ use crate::erl_syntax::parsers::parse_expr::parse_expr;
 use crate::erl_syntax::parsers::parse_fn::parse_fndef;
 use crate::erl_syntax::parsers::parse_module;
 use crate::erl_syntax::parsers::parser_input::ParserInput;
-use crate::erl_syntax::parsers::token_stream::token::format_tok_stream;
+use crate::erl_syntax::parsers::token_stream::token::{format_tok_stream, Token};
 use crate::error::ic_error::IroncladResult;
 use crate::project::compiler_opts::CompilerOpts;
 use crate::project::module::module_impl::{ErlModule, ErlModuleImpl};
+use crate::project::module::scope::root_scope::RootScopeImpl;
 use crate::project::ErlProject;
 use libironclad_util::source_file::SourceFile;
 use nom::Finish;
+use std::sync::RwLock;
 
 impl ErlModuleImpl {
   /// Generic parse helper for any Nom entry point.
@@ -32,15 +36,25 @@ impl ErlModuleImpl {
 
     let mut module_impl = ErlModuleImpl {
       source_file: src_file.clone(),
+      root_scope: RootScopeImpl {
+        project: project.clone(),
+        ..RootScopeImpl::default()
+      }
+      .into(),
       ..ErlModuleImpl::default()
     };
     if let Some(o) = compiler_options {
-      module_impl.compiler_options = o;
+      module_impl.compiler_options = RwLock::new(o);
     }
     let module: ErlModule = module_impl.into();
     let tokens = ErlModuleImpl::tokenize(project, &module, &src_file)?;
     // println!("TOKENS {}", format_tok_stream(&tokens, tokens.len()));
 
+    // Errors for the invalid input were added to the module, the parser can't make sense of it
+    if tokens.iter().any(Token::is_invalid) {
+      return Ok(module);
+    }
+
     //----------------------
     // Real parsing begins: tokens to AST
     //----------------------
@@ -75,6 +89,20 @@ impl ErlModuleImpl {
     Ok(module)
   }
 
+  /// Fold the constant expressions in the module AST, see `ConstFolder`. Expressions which are
+  /// guaranteed to fail are reported as warnings.
+  pub fn fold_constants(&self) {
+    let mut folder = ConstFolder::default();
+    let folded = fold_node(&mut folder, &self.ast.borrow());
+    for warning in folder.warnings.into_iter() {
+      self.add_warning(warning);
+    }
+    self.ast.replace(folded.clone());
+
+    // Function definitions in the root scope point to the old AST
+    self.root_scope.update_from_ast(&folded);
+  }
+
   /// Parses code fragment starting with "-module(...)." and containing some function definitions
   /// and the usual module stuff.
   pub fn from_module_source(
@@ -0,0 +1,73 @@
+//! Mapping source locations of `ErlModule` to files and lines, honoring the `-file` attributes
+
+use crate::project::module::module_impl::ErlModuleImpl;
+use crate::source_loc::SourceLoc;
+use libironclad_util::source_file::SourceFile;
+use std::path::{Path, PathBuf};
+
+/// A `-file(File, Line)` attribute found in a source file: the lines which follow it are reported
+/// as lines of `file`, starting with `line`.
+#[derive(Debug, Clone)]
+pub struct LineRemap {
+  /// The file which contains the `-file` attribute
+  pub physical_file: PathBuf,
+  /// The line of the `-file` attribute in the physical file
+  pub after_line: usize,
+  /// The file name to report
+  pub file: PathBuf,
+  /// The line number to report for the line `after_line + 1`
+  pub line: usize,
+}
+
+impl ErlModuleImpl {
+  /// Remember a file which was tokenized for this module, so that source locations pointing into
+  /// its text can be resolved to a file and a line
+  pub(crate) fn add_source_file(&self, source_file: &SourceFile) {
+    let known = self
+      .source_files
+      .clone_contents()
+      .iter()
+      .any(|f| f.text.as_ptr() == source_file.text.as_ptr());
+    if !known {
+      self.source_files.push(source_file.clone())
+    }
+  }
+
+  /// Called by the preprocessor when a `-file` attribute is found
+  pub(crate) fn add_line_remap(&self, remap: LineRemap) {
+    self.line_remaps.push(remap)
+  }
+
+  /// Map a physical file and line to the file and line reported to the user, according to the last
+  /// `-file` attribute which precedes the line in the same physical file.
+  pub fn logical_file_line(&self, physical_file: &Path, line: usize) -> (PathBuf, usize) {
+    let remaps = self.line_remaps.clone_contents();
+    let remap = remaps
+      .iter()
+      .rev()
+      .find(|r| r.physical_file == physical_file && r.after_line < line);
+    match remap {
+      Some(r) => (r.file.clone(), r.line + (line - r.after_line - 1)),
+      None => (physical_file.to_path_buf(), line),
+    }
+  }
+
+  /// Find the file and the line for a source location, after `-file` remapping. Returns `None` for
+  /// locations which do not point into any of the files tokenized for this module.
+  pub fn file_line_of(&self, loc: &SourceLoc) -> Option<(PathBuf, usize)> {
+    let start = loc.start()?;
+    self
+      .source_files
+      .clone_contents()
+      .iter()
+      .find_map(|f| f.line_of(start).map(|line| (f.file_name.clone(), line)))
+      .map(|(file, line)| self.logical_file_line(&file, line))
+  }
+
+  /// Format a source location as `file:line` after `-file` remapping, if the location is known
+  pub fn format_location(&self, loc: &SourceLoc) -> Option<String> {
+    self
+      .file_line_of(loc)
+      .map(|(file, line)| format!("{}:{}", file.to_string_lossy(), line))
+  }
+}
@@ -0,0 +1,362 @@
+//! Warnings for the definitions which are never used, like erlc reports them:
+//! * local functions which are not reachable from the exported functions and `-on_load`;
+//! * records which are never constructed, matched or named in a used type;
+//! * types which are not reachable from the specs, callbacks, record fields, function bodies and
+//!   the exported types;
+//! * macros which are never expanded, or tested with `-ifdef`, `-ifndef` and `defined()`;
+//! * imported functions which are never called.
+//!
+//! Records, types, macros and imports from the included files are not reported. Each warning is
+//! disabled with a `nowarn_` option: `unused_function` and `unused_type` for all or for the listed
+//! functions and types, `unused_record` and `unused_macros` for the whole module. Like in erlc, the
+//! unused imports are only reported with `warn_unused_import`.
+
+use crate::erl_syntax::erl_ast::ast_visitor::{walk_apply, walk_node, walk_optional, Visitor};
+use crate::erl_syntax::erl_ast::node_impl::AstNodeType;
+use crate::erl_syntax::erl_ast::AstNode;
+use crate::erl_syntax::node::erl_apply::ErlApply;
+use crate::erl_syntax::node::erl_callable_target::CallableTarget;
+use crate::erl_syntax::node::erl_record::RecordBuilderMember;
+use crate::error::ic_error::IroncladError;
+use crate::project::compiler_opts::CompilerOptsImpl;
+use crate::project::module::module_impl::ErlModuleImpl;
+use crate::project::module::scope::root_scope::ModuleItem;
+use crate::source_loc::SourceLoc;
+use crate::typing::erl_type::typekind::TypeKind;
+use crate::typing::erl_type::{ErlType, TypeImpl};
+use libironclad_util::mfarity::MFArity;
+use std::collections::{HashMap, HashSet};
+
+/// Local functions, records and types referenced by a piece of code or a type
+#[derive(Default)]
+struct Usage {
+  /// Local calls and `fun f/N` references, without the module
+  calls: HashSet<MFArity>,
+  records: HashSet<String>,
+  /// Local types, without the module
+  types: HashSet<MFArity>,
+}
+
+/// Function name for a call without a module, like `f(X)`
+fn local_call_name(apply: &ErlApply) -> Option<&str> {
+  match &apply.target {
+    CallableTarget::Expr(expr) => CompilerOptsImpl::atom_of(expr),
+    CallableTarget::MFArity(mfa) if mfa.module.is_none() => Some(mfa.name.as_str()),
+    CallableTarget::MFAExpression { module: None, function, .. } => {
+      CompilerOptsImpl::atom_of(function)
+    }
+    _ => None,
+  }
+}
+
+impl Usage {
+  /// Usage in an AST subtree
+  fn of_node(node: &AstNode) -> Self {
+    let mut usage = Usage::default();
+    walk_node(&mut usage, node);
+    usage
+  }
+
+  /// Usage in a type
+  fn of_type(ty: &ErlType) -> Self {
+    let mut usage = Usage::default();
+    usage.add_type(ty);
+    usage
+  }
+
+  fn merge(&mut self, other: Usage) {
+    self.calls.extend(other.calls);
+    self.records.extend(other.records);
+    self.types.extend(other.types);
+  }
+
+  /// Collect the records and the local types named in the type
+  fn add_type(&mut self, ty: &TypeImpl) {
+    match &ty.kind {
+      TypeKind::UserDefinedType { name, args } => {
+        if name.module.is_none() {
+          self.types.insert(name.clone());
+        }
+        self.add_types(args);
+      }
+      TypeKind::Record { tag, fields } => {
+        self.records.insert(tag.clone());
+        for field in fields.iter() {
+          self.add_type(&field.ty);
+        }
+      }
+      TypeKind::RecordRef { tag, pins } => {
+        self.records.insert(tag.clone());
+        for pin in pins.iter() {
+          self.add_type(&pin.ty);
+        }
+      }
+      TypeKind::Tuple { elements } => self.add_types(elements),
+      TypeKind::List { elements, tail, .. } => {
+        self.add_type(elements);
+        if let Some(t) = tail {
+          self.add_type(t);
+        }
+      }
+      TypeKind::StronglyTypedList { elements, tail } => {
+        self.add_types(elements);
+        if let Some(t) = tail {
+          self.add_type(t);
+        }
+      }
+      TypeKind::Map { members } => {
+        for member in members.iter() {
+          self.add_type(&member.key);
+          self.add_type(&member.value);
+        }
+      }
+      TypeKind::Fn(fn_type) => {
+        for clause in fn_type.clauses().iter() {
+          self.add_types(&clause.args);
+          self.add_type(&clause.ret_type);
+        }
+      }
+      TypeKind::Union(union) => self.add_types(&union.types),
+      _ => {}
+    }
+  }
+
+  fn add_types(&mut self, types: &[ErlType]) {
+    for ty in types.iter() {
+      self.add_type(ty);
+    }
+  }
+}
+
+impl Visitor for Usage {
+  fn visit_fn_ref(&mut self, _node: &AstNode, mfa: &MFArity) {
+    if mfa.module.is_none() {
+      self.calls.insert(mfa.clone());
+    }
+  }
+
+  fn visit_type(&mut self, _node: &AstNode, ty: &ErlType) {
+    self.add_type(ty)
+  }
+
+  fn visit_apply(&mut self, _node: &AstNode, apply: &ErlApply) {
+    if let Some(name) = local_call_name(apply) {
+      self
+        .calls
+        .insert(MFArity::new_local(name, apply.args.len()));
+      // `record_info(fields, Tag)`, `record_info(size, Tag)`, `is_record(X, Tag)` and
+      // `is_record(X, Tag, Size)` name a record
+      let names_record = matches!(
+        (name, apply.args.len()),
+        ("record_info", 2) | ("is_record", 2) | ("is_record", 3)
+      );
+      if names_record {
+        if let Some(tag) = CompilerOptsImpl::atom_of(&apply.args[1]) {
+          self.records.insert(tag.to_string());
+        }
+      }
+    }
+    walk_apply(self, apply)
+  }
+
+  fn visit_record_builder(
+    &mut self,
+    _node: &AstNode,
+    base: &Option<AstNode>,
+    tag: &str,
+    members: &[RecordBuilderMember],
+  ) {
+    self.records.insert(tag.to_string());
+    walk_optional(self, base);
+    for member in members.iter() {
+      walk_node(self, &member.expr);
+    }
+  }
+
+  fn visit_record_field(
+    &mut self,
+    _node: &AstNode,
+    base: &Option<AstNode>,
+    tag: &str,
+    _field: &str,
+  ) {
+    self.records.insert(tag.to_string());
+    walk_optional(self, base)
+  }
+}
+
+/// `name(_,_)` for a type with arity 2
+fn format_type_name(name_arity: &MFArity) -> String {
+  format!("{}({})", name_arity.name, vec!["_"; name_arity.arity].join(","))
+}
+
+/// `NAME` for a macro without arguments, `NAME/2` for a macro with 2 arguments
+fn format_macro_name(name_arity: &MFArity) -> String {
+  if name_arity.arity == 0 {
+    name_arity.name.clone()
+  } else {
+    name_arity.to_string()
+  }
+}
+
+impl ErlModuleImpl {
+  /// Report the unused functions, records, types, macros and imports as warnings, see the
+  /// `module_unused` module for the details
+  pub fn verify_unused(&self) {
+    let opts = self.get_compiler_options();
+    let scope = &self.root_scope;
+
+    // Calls made from each function, and everything used anywhere in the module
+    let mut functions: Vec<(MFArity, SourceLoc)> = Vec::new();
+    let mut calls_of: HashMap<MFArity, HashSet<MFArity>> = HashMap::new();
+    let mut used = Usage::default();
+    if let AstNodeType::ModuleForms { forms } = &self.ast.borrow().content {
+      for form in forms.iter() {
+        if let AstNodeType::FnDef(fn_def) = &form.content {
+          let usage = Usage::of_node(form);
+          functions.push((fn_def.funarity.clone(), fn_def.location.clone()));
+          calls_of
+            .entry(fn_def.funarity.clone())
+            .or_default()
+            .extend(usage.calls.iter().cloned());
+          used.merge(usage);
+        }
+      }
+    }
+    for ty in scope
+      .fn_specs
+      .collection
+      .read()
+      .unwrap()
+      .values()
+      .chain(scope.callbacks.collection.read().unwrap().values())
+    {
+      used.merge(Usage::of_type(ty));
+    }
+    // Types and records referenced by each type, they are used only if the type is used
+    let mut types_of: HashMap<MFArity, Usage> = HashMap::new();
+    for (name_arity, type_def) in scope.user_types.collection.read().unwrap().iter() {
+      types_of.insert(name_arity.clone(), Usage::of_type(&type_def.ty));
+    }
+    // A record which refers to itself is not used by that
+    for (tag, record_def) in scope.record_defs.collection.read().unwrap().iter() {
+      let mut usage = Usage::default();
+      for field in record_def.fields.iter() {
+        if let Some(initializer) = &field.initializer {
+          usage.merge(Usage::of_node(initializer));
+        }
+        if let Some(ty) = &field.type_ascription {
+          usage.add_type(ty);
+        }
+      }
+      usage.records.remove(tag);
+      used.merge(usage);
+    }
+
+    // Functions reachable from the exported functions and the `-on_load` function
+    let mut reachable: Vec<MFArity> = functions
+      .iter()
+      .map(|(funarity, _)| funarity)
+      .filter(|funarity| self.is_exported(funarity))
+      .cloned()
+      .collect();
+    if let Some(on_load) = scope.attributes.get(&"on_load".to_string()) {
+      for attr in on_load.collection.read().unwrap().iter() {
+        if let Some(funarity) = attr
+          .expr
+          .as_ref()
+          .and_then(CompilerOptsImpl::function_arity)
+        {
+          reachable.push(funarity);
+        }
+      }
+    }
+    let mut reached: HashSet<MFArity> = reachable.iter().cloned().collect();
+    while let Some(funarity) = reachable.pop() {
+      if let Some(calls) = calls_of.get(&funarity) {
+        for callee in calls.iter() {
+          if calls_of.contains_key(callee) && reached.insert(callee.clone()) {
+            reachable.push(callee.clone());
+          }
+        }
+      }
+    }
+    for (funarity, location) in functions.iter() {
+      if !reached.contains(funarity) && opts.is_warning_enabled("unused_function", Some(funarity)) {
+        self.add_warning(IroncladError::erlang_warning(
+          location.clone(),
+          format!("function {} is unused", funarity),
+        ));
+      }
+    }
+
+    // Types reachable from the specs, callbacks, record fields, function bodies and the exported
+    // types
+    let mut reachable_types: Vec<MFArity> = used
+      .types
+      .iter()
+      .chain(scope.exported_types.collection.read().unwrap().iter())
+      .cloned()
+      .collect();
+    let mut reached_types: HashSet<MFArity> = reachable_types.iter().cloned().collect();
+    while let Some(name_arity) = reachable_types.pop() {
+      if let Some(usage) = types_of.get(&name_arity) {
+        for ty in usage.types.iter() {
+          if reached_types.insert(ty.clone()) {
+            reachable_types.push(ty.clone());
+          }
+        }
+        used.records.extend(usage.records.iter().cloned());
+      }
+    }
+
+    // Records, types, macros and imports defined in the module file
+    let mut own_definitions: Vec<(ModuleItem, SourceLoc)> =
+      if let Ok(r_definitions) = scope.own_definitions.collection.read() {
+        r_definitions
+          .iter()
+          .map(|(k, v)| (k.clone(), v.clone()))
+          .collect()
+      } else {
+        panic!("Can't lock module definitions for reading")
+      };
+    own_definitions.sort_by_key(|(item, _)| match item {
+      ModuleItem::Record(tag) => (0, tag.clone(), 0),
+      ModuleItem::Type(name_arity) => (1, name_arity.name.clone(), name_arity.arity),
+      ModuleItem::Macro(name_arity) => (2, name_arity.name.clone(), name_arity.arity),
+      ModuleItem::Import(mfa) => (3, mfa.to_string(), mfa.arity),
+    });
+    for (item, location) in own_definitions.into_iter() {
+      let message = match &item {
+        ModuleItem::Record(tag)
+          if !used.records.contains(tag) && opts.is_warning_enabled("unused_record", None) =>
+        {
+          format!("record {} is unused", tag)
+        }
+        ModuleItem::Type(name_arity)
+          if !reached_types.contains(name_arity)
+            && opts.is_warning_enabled("unused_type", Some(name_arity)) =>
+        {
+          format!("type {} is unused", format_type_name(name_arity))
+        }
+        ModuleItem::Macro(name_arity)
+          if !scope.used_macros.contains(name_arity)
+            && !scope.tested_macros.contains(&name_arity.name)
+            && opts.is_warning_enabled("unused_macros", None) =>
+        {
+          format!("macro {} is unused", format_macro_name(name_arity))
+        }
+        ModuleItem::Import(mfa)
+          if !used
+            .calls
+            .contains(&MFArity::new_local(&mfa.name, mfa.arity))
+            && opts.is_warning_enabled("unused_import", None) =>
+        {
+          format!("import {} is unused", mfa)
+        }
+        _ => continue,
+      };
+      self.add_warning(IroncladError::erlang_warning(location, message));
+    }
+  }
+}
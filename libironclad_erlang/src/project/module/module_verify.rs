@@ -1,10 +1,23 @@
 //! Checks after parsing, to see that fn specs, exports, etc are not orphaned
 
+use crate::erl_syntax::erl_ast::ast_var_safety::VarSafetyCheck;
+use crate::erl_syntax::erl_ast::ast_visitor::{
+  walk_case_clause, walk_catch_clause, walk_fn_clause, walk_node, Visitor,
+};
 use crate::erl_syntax::erl_ast::node_impl::{AstNodeImpl, AstNodeType};
 use crate::erl_syntax::erl_ast::AstNode;
-use crate::erl_syntax::node::erl_callable_target::CallableTarget;
-use crate::error::ic_error::IroncladResult;
+use crate::erl_syntax::node::erl_case_clause::ErlCaseClause;
+use crate::erl_syntax::node::erl_catch_clause::CatchClause;
+use crate::erl_syntax::node::erl_fn_clause::ErlFnClause;
+use crate::error::ic_error::{IroncladError, IroncladResult};
+use crate::error::ic_error_trait::GenericIroncladError;
 use crate::project::module::module_impl::ErlModuleImpl;
+use crate::source_loc::SourceLoc;
+use crate::typing::check::TypeCheck;
+use crate::typing::erl_type::ErlType;
+use crate::typing::fn_clause_type::FnClauseType;
+use libironclad_util::mfarity::MFArity;
+use std::collections::HashMap;
 
 impl ErlModuleImpl {
   /// Check that specs and exports match the fn defs
@@ -14,170 +27,266 @@ impl ErlModuleImpl {
     Ok(())
   }
 
-  #[allow(clippy::only_used_in_recursion)]
-  fn verify_parsed(&self, ast: &AstNode) -> IroncladResult<()> {
-    match &ast.content {
-      AstNodeType::ModuleForms { forms } => {
-        for f in forms.iter() {
-          self.verify_parsed(f)?;
-        }
-      }
-      AstNodeType::FnDef(fndef) => {
-        for c in fndef.clauses.iter() {
-          for arg in c.args.iter() {
-            AstNodeImpl::verify_expr_is_matchexpr(arg)?;
+  /// Check that every behaviour declared with `-behaviour` is known, every required callback is
+  /// exported, and the specs of the exported callbacks are compatible with the callback specs.
+  /// Like erlc, an unknown behaviour and a missing callback are warnings at the `-behaviour`
+  /// attribute, disabled with `nowarn_undefined_behaviour` and `nowarn_undefined_behaviour_func`.
+  /// An incompatible spec is an error at the `-spec` attribute. Returns `true` if no problems were
+  /// found.
+  pub fn verify_behaviours(&self) -> bool {
+    let mut behaviours: Vec<(String, SourceLoc)> =
+      if let Ok(r_behaviours) = self.root_scope.behaviours.collection.read() {
+        r_behaviours
+          .iter()
+          .map(|(k, v)| (k.clone(), v.clone()))
+          .collect()
+      } else {
+        panic!("Can't lock module behaviours for reading")
+      };
+    behaviours.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut success = true;
+    let opts = self.get_compiler_options();
+    for (behaviour, behaviour_location) in behaviours.iter() {
+      let behaviour_module = match self.root_scope.project.find_behaviour(behaviour) {
+        Some(m) => m,
+        None => {
+          if opts.is_warning_enabled("undefined_behaviour", None) {
+            self.add_warning(IroncladError::behaviour_warning(
+              behaviour_location.clone(),
+              format!("Behaviour {} is not found", behaviour),
+            ));
+            success = false;
           }
-          self.verify_parsed(&c.body)?;
+          continue;
         }
-      }
-      AstNodeType::CommaExpr { elements } => {
-        for e in elements.iter() {
-          self.verify_parsed(e)?;
-        }
-      }
-      AstNodeType::Apply(app) => {
-        match &app.target {
-          CallableTarget::Expr(ex) => {
-            self.verify_parsed(ex)?;
-          }
-          CallableTarget::MFArity(_) => {}
-          CallableTarget::MFAExpression { module, function, .. } => {
-            if let Some(m) = module {
-              self.verify_parsed(m)?;
-            }
-            self.verify_parsed(function)?;
+      };
+      let mut callbacks: Vec<(MFArity, ErlType)> =
+        if let Ok(r_callbacks) = behaviour_module.root_scope.callbacks.collection.read() {
+          r_callbacks
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect()
+        } else {
+          panic!("Can't lock behaviour callbacks for reading")
+        };
+      callbacks.sort_by(|a, b| a.0.cmp(&b.0));
+
+      for (funarity, callback_spec) in callbacks.iter() {
+        if !self.is_exported(funarity) {
+          if !behaviour_module
+            .root_scope
+            .optional_callbacks
+            .contains(funarity)
+            && opts.is_warning_enabled("undefined_behaviour_func", None)
+          {
+            self.add_warning(IroncladError::behaviour_warning(
+              behaviour_location.clone(),
+              format!("Callback {} of behaviour {} is not exported", funarity, behaviour),
+            ));
+            success = false;
           }
+          continue;
         }
-        for a in app.args.iter() {
-          self.verify_parsed(a)?;
-        }
-      }
-      AstNodeType::BinaryOp { binop_expr } => {
-        self.verify_parsed(&binop_expr.left)?;
-        self.verify_parsed(&binop_expr.right)?;
-      }
-      AstNodeType::UnaryOp { unop_expr } => {
-        self.verify_parsed(&unop_expr.expr)?;
-      }
-      AstNodeType::List { elements, tail } => {
-        for e in elements.iter() {
-          self.verify_parsed(e)?;
-        }
-        if let Some(t) = tail {
-          self.verify_parsed(t)?;
-        }
-      }
-      AstNodeType::Tuple { elements } => {
-        for e in elements.iter() {
-          self.verify_parsed(e)?;
-        }
-      }
-      AstNodeType::MapBuilder { base, members, .. } => {
-        if let Some(b) = base {
-          self.verify_parsed(b)?;
-        }
-        for m in members.iter() {
-          self.verify_parsed(&m.key)?;
-          self.verify_parsed(&m.expr)?;
-        }
-      }
-      AstNodeType::RecordBuilder { base, members, .. } => {
-        if let Some(b) = base {
-          self.verify_parsed(b)?;
-        }
-        for m in members.iter() {
-          self.verify_parsed(&m.expr)?;
-        }
-      }
-      AstNodeType::ListComprehension { expr, generators } => {
-        self.verify_parsed(expr)?;
-        for g in generators.iter() {
-          self.verify_parsed(g)?;
-        }
-      }
-      AstNodeType::BinaryComprehension { expr, generators } => {
-        self.verify_parsed(expr)?;
-        for g in generators.iter() {
-          self.verify_parsed(g)?;
+        if let Some(spec) = self.root_scope.fn_specs.get(funarity) {
+          let callback_clauses = callback_spec.as_fn_type().clauses();
+          let compatible = spec
+            .as_fn_type()
+            .clauses()
+            .iter()
+            .all(|c| callback_clauses.iter().any(|cb| c.may_overlap(cb)));
+          if !compatible {
+            let spec_location = self
+              .root_scope
+              .fn_spec_locations
+              .get(funarity)
+              .unwrap_or_else(|| behaviour_location.clone());
+            self.add_error(IroncladError::behaviour(
+              spec_location,
+              format!(
+                "The spec for {} is not compatible with the callback of behaviour {}: {} vs {}",
+                funarity, behaviour, spec, callback_spec
+              ),
+            ));
+            success = false;
+          }
         }
       }
-      AstNodeType::TryCatch { body, of_branches, catch_clauses } => {
-        self.verify_parsed(body)?;
-        if let Some(of_branches_vec) = of_branches {
-          for ofb in of_branches_vec {
-            AstNodeImpl::verify_expr_is_matchexpr(&ofb.pattern)?;
-            self.verify_parsed(&ofb.body)?;
-
-            if let Some(guard) = &ofb.guard {
-              AstNodeImpl::verify_expr_is_matchexpr(guard)?;
+    }
+    success
+  }
+
+  /// Check the variables in every function: unbound and unsafe variables are added to the errors,
+  /// unused and shadowed variables to the warnings. Returns `true` if no errors were found.
+  pub fn verify_variables(&self) -> bool {
+    let mut check = VarSafetyCheck::new(self.get_compiler_options());
+    check.check_module_forms(&self.ast.borrow());
+    for warning in check.warnings {
+      self.add_warning(warning);
+    }
+    let success = check.errors.is_empty();
+    for error in check.errors {
+      self.add_error(error);
+    }
+    success
+  }
+
+  /// Check the patterns which match values of the `-opaque` types from other modules: the
+  /// function clause arguments against the function spec, and the `case` clause patterns, when
+  /// the `case` argument is a function argument variable with a spec type. A function clause must
+  /// not break the opacity for at least one of the spec clauses. Must run after the modules
+  /// defining the types are loaded. Errors are added to the module. Returns `true` if no problems
+  /// were found.
+  pub fn verify_opaque_patterns(&self) -> bool {
+    let mut success = true;
+    if let AstNodeType::ModuleForms { forms } = &self.ast.borrow().content {
+      for form in forms.iter() {
+        if let AstNodeType::FnDef(fn_def) = &form.content {
+          let spec = match self.root_scope.fn_specs.get(&fn_def.funarity) {
+            Some(spec) => spec,
+            None => continue,
+          };
+          for clause in fn_def.clauses.iter() {
+            let errors_per_spec_clause: Vec<Vec<GenericIroncladError>> = spec
+              .as_fn_type()
+              .clauses()
+              .iter()
+              .filter(|spec_clause| spec_clause.arity() == clause.args.len())
+              .map(|spec_clause| self.opaque_pattern_errors(clause, spec_clause))
+              .collect();
+            let errors = if errors_per_spec_clause.iter().any(|e| e.is_empty()) {
+              Vec::new()
+            } else {
+              errors_per_spec_clause
+                .into_iter()
+                .next()
+                .unwrap_or_default()
+            };
+            success = success && errors.is_empty();
+            for error in errors {
+              self.add_error(error);
             }
           }
         }
-        for cc in catch_clauses.iter() {
-          AstNodeImpl::verify_expr_is_matchexpr(&cc.exc_pattern.class)?;
-          AstNodeImpl::verify_expr_is_matchexpr(&cc.exc_pattern.error)?;
-          if let Some(guard) = &cc.when_guard {
-            AstNodeImpl::verify_expr_is_matchexpr(guard)?;
-          }
-          self.verify_parsed(&cc.body)?;
-        }
-      }
-      AstNodeType::ListComprehensionGenerator { left, right } => {
-        self.verify_parsed(left)?;
-        self.verify_parsed(right)?;
-      }
-      AstNodeType::IfStatement { clauses } => {
-        for c in clauses.iter() {
-          self.verify_parsed(&c.cond)?;
-          self.verify_parsed(&c.body)?;
-        }
-        // TODO: Coverage analysis, must end with true or cover all cases?
-      }
-      AstNodeType::BeginEnd { exprs } => {
-        for e in exprs.iter() {
-          self.verify_parsed(e)?;
-        }
-      }
-      AstNodeType::BinaryExpr { elements } => {
-        for e in elements.iter() {
-          self.verify_parsed(&e.value)?;
-        }
       }
-      AstNodeType::RecordField { base, .. } => {
-        if let Some(b) = base {
-          self.verify_parsed(b)?;
-        }
-      }
-      //-----------------------------------
-      AstNodeType::CaseExpr { expr, clauses } => {
-        self.verify_parsed(expr)?;
-        for c in clauses.iter() {
-          self.verify_parsed(&c.pattern)?;
-          if let Some(guard) = &c.guard {
-            AstNodeImpl::verify_expr_is_guard(guard)?;
-          }
-          self.verify_parsed(&c.body)?;
-        }
+    }
+    success
+  }
+
+  /// Opacity violations in a function clause, for one clause of the function spec
+  fn opaque_pattern_errors(
+    &self,
+    clause: &ErlFnClause,
+    spec_clause: &FnClauseType,
+  ) -> Vec<GenericIroncladError> {
+    let mut visitor = OpaquePatternVisitor {
+      module: self,
+      var_types: HashMap::new(),
+      errors: Vec::new(),
+    };
+    for (arg, arg_ty) in clause.args.iter().zip(spec_clause.args.iter()) {
+      if let Err(err) = TypeCheck::check_pattern_opacity(self, arg, arg_ty) {
+        visitor.errors.push(err);
       }
-      AstNodeType::CClause(_, _) => {
-        unreachable!("Node must not occur in the wild: {:?}", &ast.content);
+      if let AstNodeType::Var(var) = &arg.content {
+        visitor.var_types.insert(var.name.clone(), arg_ty.clone());
       }
-      //-----------------------------------
-      // No check necessary for these nodes
-      //-----------------------------------
-      AstNodeType::FnRef { .. }
-      | AstNodeType::Type { .. }
-      | AstNodeType::MFA { .. }
-      | AstNodeType::Lit { .. }
-      | AstNodeType::Var(_) => {}
-      //-----------------------------------
-      _ => unimplemented!("ErlModule::verify_parsed: don't know how to handle {:?}", ast.content),
     }
-    Ok(())
+    if let Some(guard) = &clause.guard_expr {
+      walk_node(&mut visitor, guard);
+    }
+    walk_node(&mut visitor, &clause.body);
+    visitor.errors
   }
 
   /// Check that expression nodes do not contain forbidden node types
   pub fn verify_parsed_integrity(&self) -> IroncladResult<()> {
-    self.verify_parsed(&self.ast.borrow())
+    let mut visitor = VerifyParsedVisitor::default();
+    walk_node(&mut visitor, &self.ast.borrow());
+    match visitor.error {
+      Some(err) => Err(err),
+      None => Ok(()),
+    }
+  }
+}
+
+/// Walks the parsed AST and checks that patterns and guards only contain the allowed expressions.
+/// Stops at the first error.
+#[derive(Default)]
+struct VerifyParsedVisitor {
+  error: Option<GenericIroncladError>,
+}
+
+impl VerifyParsedVisitor {
+  /// Store the error if it is the first one
+  fn check(&mut self, result: IroncladResult<()>) {
+    if let Err(err) = result {
+      if self.error.is_none() {
+        self.error = Some(err);
+      }
+    }
+  }
+}
+
+impl Visitor for VerifyParsedVisitor {
+  fn enter_node(&mut self, _node: &AstNode) -> bool {
+    self.error.is_none()
+  }
+
+  fn visit_fn_clause(&mut self, clause: &ErlFnClause) {
+    for arg in clause.args.iter() {
+      self.check(AstNodeImpl::verify_expr_is_matchexpr(arg));
+    }
+    walk_fn_clause(self, clause)
+  }
+
+  fn visit_case_clause(&mut self, clause: &ErlCaseClause) {
+    if let Some(guard) = &clause.guard {
+      self.check(AstNodeImpl::verify_expr_is_guard(guard));
+    }
+    walk_case_clause(self, clause)
+  }
+
+  fn visit_try_of_clause(&mut self, clause: &ErlCaseClause) {
+    self.check(AstNodeImpl::verify_expr_is_matchexpr(&clause.pattern));
+    if let Some(guard) = &clause.guard {
+      self.check(AstNodeImpl::verify_expr_is_matchexpr(guard));
+    }
+    walk_case_clause(self, clause)
+  }
+
+  fn visit_catch_clause(&mut self, clause: &CatchClause) {
+    self.check(AstNodeImpl::verify_expr_is_matchexpr(&clause.exc_pattern.class));
+    self.check(AstNodeImpl::verify_expr_is_matchexpr(&clause.exc_pattern.error));
+    if let Some(guard) = &clause.when_guard {
+      self.check(AstNodeImpl::verify_expr_is_matchexpr(guard));
+    }
+    walk_catch_clause(self, clause)
+  }
+}
+
+/// Checks the `case` clause patterns, where the `case` argument is a variable with a known type
+struct OpaquePatternVisitor<'a> {
+  module: &'a ErlModuleImpl,
+  /// Function argument variables with the types from the function spec
+  var_types: HashMap<String, ErlType>,
+  errors: Vec<GenericIroncladError>,
+}
+
+impl Visitor for OpaquePatternVisitor<'_> {
+  fn visit_case_expr(&mut self, _node: &AstNode, expr: &AstNode, clauses: &[ErlCaseClause]) {
+    if let AstNodeType::Var(var) = &expr.content {
+      if let Some(var_ty) = self.var_types.get(&var.name) {
+        for clause in clauses.iter() {
+          if let Err(err) = TypeCheck::check_pattern_opacity(self.module, &clause.pattern, var_ty) {
+            self.errors.push(err);
+          }
+        }
+      }
+    }
+    walk_node(self, expr);
+    for clause in clauses.iter() {
+      self.visit_case_clause(clause);
+    }
   }
 }
@@ -1,8 +1,8 @@
 //! Preprocessing support for `ErlModule`
 
+use crate::erl_syntax::erl_ast::node_impl::AstNodeType;
 use crate::erl_syntax::erl_ast::AstNode;
 use crate::erl_syntax::ic_preprocessor_error::IcPreprocessorError;
-use crate::erl_syntax::literal_bool::LiteralBool;
 use crate::erl_syntax::node::erl_record::RecordField;
 use crate::erl_syntax::parsers::token_stream::keyword::Keyword;
 use crate::erl_syntax::parsers::token_stream::token::{format_tok_stream, Token};
@@ -11,12 +11,19 @@ use crate::erl_syntax::preprocessor::pp_define::PreprocessorDefineImpl;
 use crate::erl_syntax::preprocessor::pp_node::pp_type::PreprocessorNodeType;
 use crate::erl_syntax::preprocessor::pp_node::PreprocessorNode;
 use crate::error::ic_error::{IcSeverity, IroncladError, IroncladResult};
+use crate::literal::Literal;
+use crate::project::module::module_doc::{DocAttrValue, DocEntry, DocTarget};
+use crate::project::module::module_features::ErlFeature;
 use crate::project::module::module_impl::{ErlModule, ErlModuleImpl};
+use crate::project::module::module_source_map::LineRemap;
 use crate::project::module::preprocess::pp_macro_substitution::substitute_macro_invocations;
+use crate::project::module::preprocess::pp_tok_stream::TokenStream;
+use crate::project::module::scope::root_scope::ModuleItem;
 use crate::project::ErlProject;
 use crate::record_def::RecordDefinition;
 use crate::source_loc::SourceLoc;
 use crate::typing::erl_type::ErlType;
+use crate::user_type_def::UserTypeDefinition;
 use ::function_name::named;
 use libironclad_util::mfarity::MFArity;
 use libironclad_util::source_file::SourceFile;
@@ -25,6 +32,7 @@ use std::path::{Path, PathBuf};
 use std::slice;
 
 pub mod pp_macro_substitution;
+pub mod pp_output;
 pub mod pp_section;
 pub mod pp_state;
 pub mod pp_tok_stream;
@@ -83,6 +91,62 @@ fn ends_with_dot_eol(line: &[Token]) -> bool {
   Token::ends_with(line, &[TokenKind::Period, TokenKind::EOL])
 }
 
+/// Interpret `-file(File, Line)`: lines after the attribute in the current file are reported as
+/// lines of `file`
+fn on_file(state: &mut PreprocessState, ppnode: &PreprocessorNode, file: &str, line: usize) {
+  let after_line = ppnode
+    .location
+    .start()
+    .and_then(|start| state.source_file.line_of(start))
+    .unwrap_or(0);
+  state.module.add_line_remap(LineRemap {
+    physical_file: state.source_file.file_name.clone(),
+    after_line,
+    file: PathBuf::from(file),
+    line,
+  })
+}
+
+/// Interpret `-feature(Name, enable | disable)`. Features must be set before the first function.
+fn on_feature(state: &mut PreprocessState, ppnode: &PreprocessorNode, name: &str, action: &str) {
+  let enabled = match action {
+    "enable" => Some(true),
+    "disable" => Some(false),
+    _ => None,
+  };
+  let msg = match (ErlFeature::from_name(name), enabled) {
+    (None, _) => format!("Unknown feature '{}' in -feature()", name),
+    (Some(_), None) => {
+      format!("Expected 'enable' or 'disable' in -feature({}, ...), got '{}'", name, action)
+    }
+    (Some(_), Some(_)) if state.module.is_function_seen() => {
+      format!("-feature({}, {}) is not allowed after the first function", name, action)
+    }
+    (Some(feature), Some(enabled)) => {
+      state.module.set_feature(feature, enabled);
+      return;
+    }
+  };
+  if !state.module.add_error(IcPreprocessorError::new(
+    IcSeverity::Error,
+    ppnode.location.clone(),
+    msg,
+  )) {
+    state.too_many_errors = true;
+  }
+}
+
+/// Remember the location of a definition made in the module file, to report it if it is unused
+fn add_own_definition(state: &PreprocessState, item: ModuleItem, ppnode: &PreprocessorNode) {
+  if state.in_module_file() {
+    state
+      .module
+      .root_scope
+      .own_definitions
+      .add(item, ppnode.location.clone());
+  }
+}
+
 fn on_undef(state: &mut PreprocessState, name: &str) {
   state
     .module
@@ -91,9 +155,17 @@ fn on_undef(state: &mut PreprocessState, name: &str) {
     .delete_if(|key, _value| key.name == name);
 }
 
-fn on_define(state: &mut PreprocessState, name: &str, args: &[String], body: &[Token]) {
+fn on_define(
+  state: &mut PreprocessState,
+  ppnode: &PreprocessorNode,
+  name: &str,
+  args: &[String],
+  body: &[Token],
+) {
   let key = MFArity::new_local(name, args.len());
-  let ppdef = PreprocessorDefineImpl::new(name.to_string(), args, body);
+  let defined_at = state.file_line_of(&ppnode.location);
+  let ppdef = PreprocessorDefineImpl::new_at(name.to_string(), args, body, defined_at);
+  add_own_definition(state, ModuleItem::Macro(key.clone()), ppnode);
   state.module.root_scope.defines.add(key, ppdef);
 }
 
@@ -113,23 +185,47 @@ fn on_export_type(state: &mut PreprocessState, type_arities: &[MFArity]) {
   })
 }
 
-fn on_import(state: &mut PreprocessState, module_name: &str, fun_arities: &[MFArity]) {
+fn on_import(
+  state: &mut PreprocessState,
+  ppnode: &PreprocessorNode,
+  module_name: &str,
+  fun_arities: &[MFArity],
+) {
   fun_arities.iter().for_each(|fun_arity| {
-    state
-      .module
-      .root_scope
-      .imports
-      .add(fun_arity.clone_with_module(module_name))
+    let import = fun_arity.clone_with_module(module_name);
+    add_own_definition(state, ModuleItem::Import(import.clone()), ppnode);
+    state.module.root_scope.imports.add(import)
   })
 }
 
-fn on_new_type(state: &mut PreprocessState, name: &str, vars: &[String], ty: ErlType) {
+fn on_new_type(
+  state: &mut PreprocessState,
+  ppnode: &PreprocessorNode,
+  name: &str,
+  vars: &[String],
+  ty: ErlType,
+  opaque: bool,
+) {
   let key = MFArity::new_local(name, vars.len());
-  state.module.root_scope.user_types.add(key, ty)
+  let type_def = UserTypeDefinition {
+    name: name.to_string(),
+    vars: vars.to_vec(),
+    ty,
+    opaque,
+  };
+  attach_pending_doc(&state.module, DocTarget::Type(key.clone()));
+  add_own_definition(state, ModuleItem::Type(key.clone()), ppnode);
+  state.module.root_scope.user_types.add(key, type_def.into())
 }
 
-fn on_new_record(state: &mut PreprocessState, tag: &str, fields: &[RecordField]) {
+fn on_new_record(
+  state: &mut PreprocessState,
+  ppnode: &PreprocessorNode,
+  tag: &str,
+  fields: &[RecordField],
+) {
   let r_def = RecordDefinition { tag: tag.to_string(), fields: fields.into() }.into();
+  add_own_definition(state, ModuleItem::Record(tag.to_string()), ppnode);
   state
     .module
     .root_scope
@@ -137,7 +233,18 @@ fn on_new_record(state: &mut PreprocessState, tag: &str, fields: &[RecordField])
     .add(tag.to_string(), r_def)
 }
 
-fn on_fn_spec(state: &mut PreprocessState, funarity: &MFArity, spec: &ErlType) {
+fn on_fn_spec(
+  state: &mut PreprocessState,
+  ppnode: &PreprocessorNode,
+  funarity: &MFArity,
+  spec: &ErlType,
+) {
+  attach_pending_doc(&state.module, DocTarget::Function(funarity.clone()));
+  state
+    .module
+    .root_scope
+    .fn_spec_locations
+    .add(funarity.clone(), ppnode.location.clone());
   state
     .module
     .root_scope
@@ -145,33 +252,252 @@ fn on_fn_spec(state: &mut PreprocessState, funarity: &MFArity, spec: &ErlType) {
     .add(funarity.clone(), spec.clone())
 }
 
-fn on_attr(state: &mut PreprocessState, tag: &str, term: &Option<AstNode>) {
+fn on_callback(state: &mut PreprocessState, funarity: &MFArity, spec: &ErlType) {
+  attach_pending_doc(&state.module, DocTarget::Callback(funarity.clone()));
+  state
+    .module
+    .root_scope
+    .callbacks
+    .add(funarity.clone(), spec.clone())
+}
+
+fn on_optional_callbacks(state: &mut PreprocessState, fun_arities: &[MFArity]) {
+  fun_arities.iter().for_each(|fun_arity| {
+    state
+      .module
+      .root_scope
+      .optional_callbacks
+      .add(fun_arity.clone())
+  })
+}
+
+fn on_behaviour(state: &mut PreprocessState, ppnode: &PreprocessorNode, name: &str) {
+  state
+    .module
+    .root_scope
+    .behaviours
+    .add(name.to_string(), ppnode.location.clone())
+}
+
+/// Interpret `-doc VALUE.` and `-moduledoc VALUE.`. The module doc is stored immediately, the
+/// item doc waits in the module for the form which follows it: a function, spec, type or callback.
+fn on_doc(
+  state: &mut PreprocessState,
+  ppnode: &PreprocessorNode,
+  module_doc: bool,
+  value: &AstNode,
+) {
+  let doc_value = match DocAttrValue::from_expr(value) {
+    Some(DocAttrValue::File(path)) => load_doc_file(state, ppnode, &path),
+    Some(doc_value) => Some(doc_value),
+    None => {
+      let msg = format!(
+        "Expected a string, false, a metadata map or {{file, Path}} in a -doc attribute, got: {}",
+        value
+      );
+      state.module.add_error(IcPreprocessorError::new(
+        IcSeverity::Error,
+        ppnode.location.clone(),
+        msg,
+      ));
+      None
+    }
+  };
+  let doc_value = match doc_value {
+    Some(v) => v,
+    None => return,
+  };
+  let docs = &state.module.root_scope.docs;
+  if module_doc {
+    let mut entry = docs
+      .get(&DocTarget::Module)
+      .map(|d| (*d).clone())
+      .unwrap_or_default();
+    entry.apply(doc_value);
+    docs.add(DocTarget::Module, entry.into())
+  } else {
+    let mut pending = state.module.pending_doc.write().unwrap();
+    pending
+      .get_or_insert_with(|| (DocEntry::default(), ppnode.location.clone()))
+      .0
+      .apply(doc_value)
+  }
+}
+
+/// Load the `{file, Path}` documentation, the path is relative to the module source file
+fn load_doc_file(
+  state: &mut PreprocessState,
+  ppnode: &PreprocessorNode,
+  path: &str,
+) -> Option<DocAttrValue> {
+  let full_path = match state.source_file.file_name.parent() {
+    Some(dir) => dir.join(path),
+    None => PathBuf::from(path),
+  };
+  let loaded = if full_path.is_file() {
+    state.project.file_cache.get_or_load(&full_path).ok()
+  } else {
+    None
+  };
+  match loaded {
+    Some(doc_file) => Some(DocAttrValue::Text(doc_file.text.to_string())),
+    None => {
+      let msg = format!("Documentation file not found: {}", full_path.to_string_lossy());
+      state.module.add_error(IcPreprocessorError::new(
+        IcSeverity::Error,
+        ppnode.location.clone(),
+        msg,
+      ));
+      None
+    }
+  }
+}
+
+/// Attach the documentation from preceding `-doc` attributes, if any, to the item
+fn attach_pending_doc(module: &ErlModule, target: DocTarget) {
+  if let Some((entry, _location)) = module.pending_doc.write().unwrap().take() {
+    module.root_scope.docs.add(target, entry.into())
+  }
+}
+
+/// Drop the documentation from preceding `-doc` attributes, if any, with a warning: the form
+/// which follows them (or the end of the file) can not be documented
+fn drop_dangling_doc(module: &ErlModule) {
+  let pending = module.pending_doc.write().unwrap().take();
+  if let Some((_entry, location)) = pending {
+    module.add_warning(IcPreprocessorError::new(
+      IcSeverity::Warning,
+      location,
+      "-doc is not followed by a function, spec, type or callback".to_string(),
+    ))
+  }
+}
+
+/// Whether a `-doc` may precede the preprocessor node, without being attached to it. Other module
+/// forms, except the documented ones, leave the `-doc` dangling.
+fn keeps_pending_doc(ppnode: &PreprocessorNodeType) -> bool {
+  match ppnode {
+    PreprocessorNodeType::Doc { .. }
+    | PreprocessorNodeType::NewType { .. }
+    | PreprocessorNodeType::FnSpec { .. }
+    | PreprocessorNodeType::Callback { .. } => true,
+    other => !other.is_module_form(),
+  }
+}
+
+/// Name and arity of the first function defined in a code fragment: the first atom followed by
+/// `(`, and the count of the top level comma-separated arguments till the closing `)`.
+fn first_function_arity(code: &[Token]) -> Option<MFArity> {
+  let mut tokens = code.iter().filter(|t| !t.is_eol());
+  let name = match &tokens.next()?.kind {
+    TokenKind::Atom(name) => name.clone(),
+    _ => return None,
+  };
+  if !matches!(tokens.next()?.kind, TokenKind::ParOpen) {
+    return None;
+  }
+  let mut depth = 0usize;
+  let mut commas = 0usize;
+  let mut has_args = false;
+  for tok in tokens {
+    match tok.kind {
+      TokenKind::ParClose if depth == 0 => {
+        let arity = if has_args { commas + 1 } else { 0 };
+        return Some(MFArity::new_local(&name, arity));
+      }
+      TokenKind::ParOpen
+      | TokenKind::SquareOpen
+      | TokenKind::CurlyOpen
+      | TokenKind::DoubleAngleOpen => depth += 1,
+      TokenKind::ParClose
+      | TokenKind::SquareClose
+      | TokenKind::CurlyClose
+      | TokenKind::DoubleAngleClose => depth = depth.saturating_sub(1),
+      TokenKind::Comma if depth == 0 => commas += 1,
+      _ => {}
+    }
+    has_args = true;
+  }
+  None
+}
+
+fn on_attr(
+  state: &mut PreprocessState,
+  ppnode: &PreprocessorNode,
+  tag: &str,
+  term: &Option<AstNode>,
+) {
+  if tag == "compile" {
+    on_compile(state, ppnode, term)
+  }
   state.module.root_scope.add_attr(tag, term.clone())
 }
 
-#[named]
-fn on_if(state: &mut PreprocessState, ppnode: &PreprocessorNode, cond: &AstNode) {
-  match cond.walk_boolean_litexpr() {
-    LiteralBool::False => state.begin_section(ppnode.clone(), false),
-    LiteralBool::True => state.begin_section(ppnode.clone(), true),
-    LiteralBool::NotABoolean => {
-      let msg =
-        "-if() or elif() condition does not evaluate to a compile-time boolean.".to_string();
+/// Interpret `-compile(Option)` and `-compile([Options])`: the options are added to the compiler
+/// options of this module. Unknown options are reported as warnings.
+fn on_compile(state: &mut PreprocessState, ppnode: &PreprocessorNode, term: &Option<AstNode>) {
+  let options = match term.as_ref().map(|t| &t.content) {
+    Some(AstNodeType::List { elements, tail: None }) => elements.clone(),
+    Some(AstNodeType::Lit { value, .. }) if matches!(value.as_ref(), Literal::Nil) => Vec::new(),
+    Some(_) => vec![term.clone().unwrap()],
+    None => Vec::new(),
+  };
+  let mut problems = Vec::new();
+  let had_export_all = state.module.get_compiler_options().export_all;
+  state.module.update_compiler_options(|opts| {
+    for option in options.iter() {
+      if let Err(msg) = opts.apply_compile_option(option) {
+        problems.push(msg)
+      }
+    }
+  });
+  if !had_export_all && state.module.get_compiler_options().export_all {
+    *state.module.export_all_location.write().unwrap() = Some(ppnode.location.clone());
+  }
+  for msg in problems.into_iter() {
+    state.module.add_warning(IcPreprocessorError::new(
+      IcSeverity::Warning,
+      ppnode.location.clone(),
+      msg,
+    ))
+  }
+}
+
+/// Evaluate an `-if` or `-elif` condition. A non-constant condition is reported as an error and
+/// evaluates to false.
+fn eval_if_condition(state: &mut PreprocessState, cond: &AstNode) -> bool {
+  let module = state.module.clone();
+  let is_defined = |name: &str| {
+    module.root_scope.mark_macro_tested(name);
+    module.root_scope.is_defined(name)
+  };
+  match cond.eval_const_condition(&is_defined) {
+    Ok(value) => value,
+    Err(err) => {
+      let msg = format!("-if() or -elif() condition is not a constant guard expression: {}", err);
       state.module.add_error(IcPreprocessorError::new(
         IcSeverity::Error,
-        SourceLoc::unimplemented(file!(), function_name!()),
+        cond.location.clone(),
         msg,
       ));
+      false
     }
   }
 }
 
+fn on_if(state: &mut PreprocessState, ppnode: &PreprocessorNode, cond: &AstNode) {
+  let value = eval_if_condition(state, cond);
+  state.begin_section(ppnode.clone(), value);
+}
+
 fn on_if_def(state: &mut PreprocessState, ppnode: &PreprocessorNode, macro_name: &str) {
+  state.module.root_scope.mark_macro_tested(macro_name);
   let is_def = state.module.root_scope.is_defined(macro_name);
   state.begin_section(ppnode.clone(), is_def);
 }
 
 fn on_if_not_def(state: &mut PreprocessState, ppnode: &PreprocessorNode, macro_name: &str) {
+  state.module.root_scope.mark_macro_tested(macro_name);
   let is_not_def = !state.module.root_scope.is_defined(macro_name);
   state.begin_section(ppnode.clone(), is_not_def);
 }
@@ -189,7 +515,8 @@ fn on_else(state: &mut PreprocessState) {
       ));
     } else {
       section.else_encountered = true;
-      section.condition = !section.condition;
+      let take_else = section.can_take_branch();
+      section.next_branch(take_else);
     }
   } else {
     let msg =
@@ -217,38 +544,56 @@ fn on_endif(state: &mut PreprocessState) {
   }
 }
 
-/// Pop last section; Invert the condition in it and push back
+/// Switch the last section to the `-elif` branch. The condition is only evaluated if no previous
+/// branch of the section was taken, and the section is not nested in an inactive section.
 #[named]
 fn on_else_if(state: &mut PreprocessState, cond: &AstNode) {
-  if let Some(section) = state.section.pop() {
-    // Open a new -IF section
-    on_if(state, &section.ppnode, cond);
-  } else {
-    let msg = "-elif() encountered without a matching -if, ifdef, -ifndef or -elif.".to_string();
-    state.module.add_error(IcPreprocessorError::new(
-      IcSeverity::Error,
-      SourceLoc::unimplemented(file!(), function_name!()),
-      msg,
-    ));
+  let (else_encountered, can_take_branch) = match state.section.last() {
+    Some(section) => (section.else_encountered, section.can_take_branch()),
+    None => {
+      let msg = "-elif() encountered without a matching -if, ifdef, -ifndef or -elif.".to_string();
+      state.module.add_error(IcPreprocessorError::new(
+        IcSeverity::Error,
+        SourceLoc::unimplemented(file!(), function_name!()),
+        msg,
+      ));
+      return;
+    }
+  };
+
+  if else_encountered {
+    let msg = "-elif() encountered after -else().".to_string();
+    state
+      .module
+      .add_error(IcPreprocessorError::new(IcSeverity::Error, cond.location.clone(), msg));
+    return;
+  }
+
+  let value = can_take_branch && eval_if_condition(state, cond);
+  if let Some(section) = state.section.last_mut() {
+    section.next_branch(value);
   }
 }
 
+/// Load and preprocess an included file, unless it is already being processed higher in the
+/// include chain, in which case a circular include error is reported and nothing is included.
 fn generic_include(
   state: &mut PreprocessState,
-  _ppnode: PreprocessorNode,
+  ppnode: PreprocessorNode,
   found_path: &Path,
 ) -> IroncladResult<Vec<Token>> {
-  // let found_path_oss = found_path.as_os_str().to_os_string();
-
-  // if state.module.included_files.contains(&found_path_oss) {
-  //   // Circular include detected
-  //   let msg = format!(
-  //     "Circular include detected {} from {}",
-  //     found_path_oss.to_string_lossy(),
-  //     state.module.source_file.file_name.to_string_lossy()
-  //   );
-  //   return Err(Box::new(ErlError::preprocessor_error(ppnode.location.clone(), msg)));
-  // }
+  let found_key = ErlModuleImpl::include_key(found_path);
+  state.module.add_include_edge(&found_key);
+
+  if let Some(cycle) = state.module.find_include_cycle(&found_key) {
+    let msg = format!("Circular include detected: {}", ErlModuleImpl::format_include_chain(&cycle));
+    state.module.add_error(IcPreprocessorError::new(
+      IcSeverity::Error,
+      ppnode.location.clone(),
+      msg,
+    ));
+    return Ok(Vec::new());
+  }
 
   let src_file = state
     .project
@@ -256,10 +601,12 @@ fn generic_include(
     .get_or_load(found_path)
     .map_err(IroncladError::from)?;
 
-  // // update included modules collection as to prevent circular includes
-  // state.module.included_files.add(found_path_oss);
-
-  ErlModuleImpl::tokenize(&state.project, &state.module, &src_file)
+  let tokens = ErlModuleImpl::tokenize(&state.project, &state.module, &src_file)?;
+  if state.module.preprocess_only {
+    Ok(state.mark_included_file(&ppnode, found_path, tokens))
+  } else {
+    Ok(tokens)
+  }
 }
 
 /// Handle `-include(Path)` preprocessor directive
@@ -269,38 +616,46 @@ fn on_include(
   ppnode: PreprocessorNode,
 ) -> IroncladResult<Vec<Token>> {
   let literal_path = PathBuf::from(path);
-  let found_path = state
-    .project
-    .find_include(ppnode.location.clone(), &literal_path, None)?;
+  let include_chain = state.module.include_chain();
+  let found_path =
+    state
+      .project
+      .find_include(ppnode.location.clone(), &literal_path, &include_chain)?;
   generic_include(state, ppnode, &found_path)
 }
 
+/// Handle `-include_lib(Path)` preprocessor directive
 fn on_include_lib(
   state: &mut PreprocessState,
   path: &str,
   ppnode: PreprocessorNode,
 ) -> IroncladResult<Vec<Token>> {
   let literal_path = PathBuf::from(path);
-  let found_path = state
-    .project
-    .find_include(ppnode.location.clone(), &literal_path, None)?;
+  let include_chain = state.module.include_chain();
+  let found_path =
+    state
+      .project
+      .find_include(ppnode.location.clone(), &literal_path, &include_chain)?;
   generic_include(state, ppnode, &found_path)
 }
 
 #[named]
 fn preprocess_handle_ppnode(
-  input_tokens: &mut Vec<Token>,
   ppnode: PreprocessorNode,
   state: &mut PreprocessState,
 ) -> IroncladResult<()> {
   let active = state.is_section_condition_true();
+  if active && !keeps_pending_doc(&ppnode.content) {
+    drop_dangling_doc(&state.module);
+  }
 
   match &ppnode.content {
     //------------------
     // Set module name (can be done only once)
     //------------------
     PreprocessorNodeType::ModuleName { name } if active => {
-      ErlModuleImpl::set_name(&state.module, name.as_str())
+      ErlModuleImpl::set_name(&state.module, name.as_str());
+      state.module.define_module_macro();
     }
 
     //------------------
@@ -308,13 +663,11 @@ fn preprocess_handle_ppnode(
     //------------------
     PreprocessorNodeType::Include(path) if active => {
       let included_tokens = on_include(state, path, ppnode.clone())?;
-      state.paste_tokens(input_tokens, included_tokens);
-      state.itr.set_base(input_tokens);
+      state.append_included(included_tokens);
     }
     PreprocessorNodeType::IncludeLib(path) if active => {
       let included_tokens = on_include_lib(state, path, ppnode.clone())?;
-      state.paste_tokens(input_tokens, included_tokens);
-      state.itr.set_base(input_tokens);
+      state.append_included(included_tokens);
     }
     // PreprocessorNodeType::IncludedFile { .. } if active => unimplemented!(),
 
@@ -339,27 +692,49 @@ fn preprocess_handle_ppnode(
     //------------------
     // Populate module scope with stuff
     //------------------
-    PreprocessorNodeType::Attr { tag, term } if active => on_attr(state, tag.as_str(), term),
+    PreprocessorNodeType::Attr { tag, term } if active => {
+      on_attr(state, &ppnode, tag.as_str(), term)
+    }
     PreprocessorNodeType::Export { fun_arities } if active => on_export(state, fun_arities),
     PreprocessorNodeType::ExportType { type_arities } if active => {
       on_export_type(state, type_arities)
     }
     PreprocessorNodeType::Import { module: module_name, fun_arities } if active => {
-      on_import(state, module_name.as_str(), fun_arities)
+      on_import(state, &ppnode, module_name.as_str(), fun_arities)
+    }
+    PreprocessorNodeType::NewType { name, vars, ty, opaque } if active => {
+      on_new_type(state, &ppnode, name.as_str(), vars, ty.clone(), *opaque)
     }
-    PreprocessorNodeType::NewType { name, vars, ty } if active => {
-      on_new_type(state, name.as_str(), vars, ty.clone())
+    PreprocessorNodeType::NewRecord { tag, fields } if active => {
+      on_new_record(state, &ppnode, tag, fields)
+    }
+    PreprocessorNodeType::FnSpec { funarity, spec } if active => {
+      on_fn_spec(state, &ppnode, funarity, spec)
+    }
+    PreprocessorNodeType::Callback { funarity, spec } if active => {
+      on_callback(state, funarity, spec)
+    }
+    PreprocessorNodeType::OptionalCallbacks { fun_arities } if active => {
+      on_optional_callbacks(state, fun_arities)
+    }
+    PreprocessorNodeType::Behaviour { name } if active => on_behaviour(state, &ppnode, name),
+    PreprocessorNodeType::Doc { module_doc, value } if active => {
+      on_doc(state, &ppnode, *module_doc, value)
     }
-    PreprocessorNodeType::NewRecord { tag, fields } if active => on_new_record(state, tag, fields),
-    PreprocessorNodeType::FnSpec { funarity, spec } if active => on_fn_spec(state, funarity, spec),
 
     //------------------
     // Macro define and undefine
     //------------------
     PreprocessorNodeType::Define { name, args, body } if active => {
-      on_define(state, name.as_str(), args, body)
+      on_define(state, &ppnode, name.as_str(), args, body)
     }
     PreprocessorNodeType::Undef(name) if active => on_undef(state, name),
+    PreprocessorNodeType::Feature { name, action } if active => {
+      on_feature(state, &ppnode, name.as_str(), action.as_str())
+    }
+    PreprocessorNodeType::File { file, line } if active => {
+      on_file(state, &ppnode, file.as_str(), *line)
+    }
 
     //------------------
     // Conditionals
@@ -369,6 +744,10 @@ fn preprocess_handle_ppnode(
     PreprocessorNodeType::Ifndef { macro_name } if active => {
       on_if_not_def(state, &ppnode, macro_name)
     }
+    // Conditions nested in an inactive section are not evaluated, but must match their -endif
+    PreprocessorNodeType::If { .. }
+    | PreprocessorNodeType::Ifdef { .. }
+    | PreprocessorNodeType::Ifndef { .. } => state.begin_inactive_section(ppnode.clone()),
 
     // Elseif, Else and Endif do not check the `active` condition, and are always processed
     PreprocessorNodeType::ElseIf { cond } => on_else_if(state, cond),
@@ -392,8 +771,11 @@ fn line_begins_with_preprocessor_or_attr(line: &[Token]) -> bool {
 
 /// Final checks for whether preprocessing was successful:
 /// * Unmatched #if/#endif
+/// * `-doc` at the end of the file
 #[named]
 fn final_state_check(state: &mut PreprocessState) {
+  drop_dangling_doc(&state.module);
+
   // Check for if/ifdef/else without a matching endif
   if let Some(last_sec) = state.section.last() {
     let msg =
@@ -407,6 +789,19 @@ fn final_state_check(state: &mut PreprocessState) {
 }
 
 impl ErlModuleImpl {
+  /// The OTP release which `?OTP_RELEASE` reports, the language level this compiler follows
+  pub const OTP_RELEASE: i64 = 27;
+
+  /// Add defines from the project configuration and the command line to the module scope
+  pub fn apply_config_defines(&self) {
+    for (name_arity, define) in self.get_compiler_options().scope.data.iter() {
+      self
+        .root_scope
+        .defines
+        .add(name_arity.clone(), define.clone());
+    }
+  }
+
   /// Add predefined symbols for current module
   pub fn setup_preprocessor(&self) {
     let add0 = |name: &str, value: &[Token]| {
@@ -416,13 +811,26 @@ impl ErlModuleImpl {
       );
     };
     add0("COMPILER_VSN", &[Token::new_small(42)]);
+    add0("OTP_RELEASE", &[Token::new_small(Self::OTP_RELEASE)]);
+    add0("MACHINE", &[Token::new_atom("BEAM".to_string())]);
     add0(
       "FILE",
       &[Token::new_string(
         self.source_file.file_name.to_string_lossy().to_string(),
       )],
     );
-    add0("MODULE", &[Token::new_atom(self.name.read().unwrap().clone())])
+    if !self.get_name().is_empty() {
+      self.define_module_macro()
+    }
+  }
+
+  /// Define `?MODULE` to the current module name. Like in epp, `?MODULE` is not defined before
+  /// the `-module()` attribute is processed.
+  pub(crate) fn define_module_macro(&self) {
+    self.root_scope.defines.add(
+      MFArity::new_local("MODULE", 0),
+      PreprocessorDefineImpl::new("MODULE".to_string(), &[], &[Token::new_atom(self.get_name())]),
+    );
   }
 
   /// Filter through the tokens array and produce a new token array with preprocessor directives
@@ -432,10 +840,11 @@ impl ErlModuleImpl {
     source_file: &SourceFile,
     project: &ErlProject,
     module: &ErlModule,
-    mut tokens: Vec<Token>,
+    tokens: Vec<Token>,
   ) -> IroncladResult<Vec<Token>> {
     let original_input = source_file.text.as_str();
-    let mut state = PreprocessState::new(project, module, (tokens.as_ptr(), tokens.len()));
+    let mut state =
+      PreprocessState::new(project, module, source_file, (tokens.as_ptr(), tokens.len()));
 
     while let Some(line) = state.itr.next() {
       if state.too_many_errors {
@@ -449,7 +858,16 @@ impl ErlModuleImpl {
 
       if line_begins_with_preprocessor_or_attr(line) {
         let line2 = expand_till_directive_end(line, &mut state);
-        let line3 = substitute_macro_invocations(original_input, line2, &mut state);
+        let line3 = substitute_macro_invocations(line2, &mut state);
+        // Keywords of disabled features become atoms, the `-tag` itself is left as is
+        let line3 = match module.demote_feature_keywords(&line3.as_slice()[2..]) {
+          Some(demoted) => {
+            let mut attr = line3.as_slice()[..2].to_vec();
+            attr.extend(demoted);
+            TokenStream::new_owned(attr)
+          }
+          None => line3,
+        };
         println!("LINE {}", format_tok_stream(line3.as_slice(), line3.as_slice().len()));
 
         let (tail, ppnode) = line3.parse_as_preprocessor(original_input, state.module.clone());
@@ -466,17 +884,36 @@ impl ErlModuleImpl {
             msg,
           ));
         }
-        preprocess_handle_ppnode(&mut tokens, ppnode, &mut state)?;
+        // In preprocess-only mode the module attributes are kept in the output
+        let keep_form = module.preprocess_only
+          && state.is_section_condition_true()
+          && ppnode.content.is_module_form();
+        preprocess_handle_ppnode(ppnode, &mut state)?;
+        if keep_form {
+          state.result.extend(line3.as_slice().iter().cloned())
+        }
       } else if state.is_section_condition_true() {
         // Grow the selection till we hit a start of a preprocessor directive or an attribute
         let line2 = expand_till_directive_start(line, &mut state);
 
         // Substitute macro invocations in the line with their content
-        let line3 = substitute_macro_invocations(original_input, line2, &mut state);
+        let line3 = substitute_macro_invocations(line2, &mut state);
         // println!("{}", format_tok_stream(line3.as_slice(), line3.as_slice().len()));
 
-        // Copy the line contents to result.
-        state.result.extend(line3.as_slice().iter().cloned())
+        // Copy the line contents to result. Keywords of disabled features become atoms.
+        let code = line3.as_slice();
+        if code.iter().any(|t| !t.is_eol()) {
+          module.mark_function_seen();
+        }
+        match first_function_arity(code) {
+          Some(funarity) => attach_pending_doc(module, DocTarget::Function(funarity)),
+          None if code.iter().any(|t| !t.is_eol()) => drop_dangling_doc(module),
+          None => {}
+        }
+        match module.demote_feature_keywords(code) {
+          Some(demoted) => state.result.extend(demoted),
+          None => state.result.extend(code.iter().cloned()),
+        }
       }
     }
 
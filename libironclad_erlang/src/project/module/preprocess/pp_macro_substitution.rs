@@ -1,31 +1,49 @@
 //! Code for processing a line of tokens and pasting macro values instead of macro invocations.
 
 use crate::erl_syntax::ic_preprocessor_error::IcPreprocessorError;
-use crate::erl_syntax::parsers::misc::panicking_parser_error_reporter;
 use crate::erl_syntax::parsers::parser_input::ParserInput;
 use crate::erl_syntax::parsers::token_stream::token::Token;
 use crate::erl_syntax::parsers::token_stream::token_kind::TokenKind;
 use crate::erl_syntax::preprocessor::parsers::parse_pp::parse_macro_invocation_args;
 use crate::erl_syntax::preprocessor::pp_define::{PreprocessorDefine, PreprocessorDefineImpl};
+use crate::erl_syntax::preprocessor::pp_expansion::{MacroExpansion, MacroExpansionImpl};
 use crate::error::ic_error::IcSeverity;
-use crate::exit_codes::erl_fatal_error;
 use crate::project::module::module_impl::ErlModule;
 use crate::project::module::preprocess::pp_state::PreprocessState;
 use crate::project::module::preprocess::pp_tok_stream::TokenStream;
 use crate::source_loc::SourceLoc;
-use ::function_name::named;
 use libironclad_util::mfarity::MFArity;
-use nom::combinator::recognize;
 use nom::Finish;
 
 fn has_any_macro_invocations(line: &[Token]) -> bool {
   line.iter().any(|t| t.is_macro_invocation())
 }
 
+/// Copy a token from the macro body, marking it as produced by `expansion`. Tokens which have no
+/// location (predefined and command line macros) get the location of the `invocation`.
+fn expanded_token(t: &Token, invocation: &Token, expansion: &MacroExpansion) -> Token {
+  let mut result = t.clone();
+  if result.offset.is_null() {
+    result.offset = invocation.offset;
+  }
+  result.expansion = Some(match &t.expansion {
+    Some(inner) => MacroExpansionImpl::nested_in(inner, expansion),
+    None => expansion.clone(),
+  });
+  result
+}
+
 /// For all tokens in `pdef.tokens` paste them into the `output`.
 /// If a token is a `Variable(s)` token, then try look up its name in the macro args list, and if
 /// found, paste the value from `args[]` into the output.
-fn paste_tokens(output: &mut Vec<Token>, pdef: &PreprocessorDefine, args: &[Vec<Token>]) {
+/// Pasted body tokens remember the `expansion`, argument tokens keep their use site location.
+fn paste_tokens(
+  output: &mut Vec<Token>,
+  pdef: &PreprocessorDefine,
+  args: &[Vec<Token>],
+  invocation: &Token,
+  expansion: &MacroExpansion,
+) {
   for t in pdef.tokens.iter() {
     match &t.kind {
       TokenKind::Variable(var) => {
@@ -34,52 +52,124 @@ fn paste_tokens(output: &mut Vec<Token>, pdef: &PreprocessorDefine, args: &[Vec<
           // TODO: Macro invocation inside a macro body
           output.extend(arg.iter().cloned());
         } else {
-          output.push(t.clone());
+          output.push(expanded_token(t, invocation, expansion));
         }
       }
-      _other => output.push(t.clone()),
+      _other => output.push(expanded_token(t, invocation, expansion)),
     }
   }
 }
 
-#[named]
+/// Create the expansion record for an invocation of `pdef` at the `invocation` token
+fn new_expansion(
+  state: &PreprocessState,
+  pdef: &PreprocessorDefine,
+  invocation: &Token,
+) -> MacroExpansion {
+  let invoked_at = state.file_line_of(&SourceLoc::from_token(invocation));
+  MacroExpansionImpl::new(&pdef.name, pdef.args.len(), invoked_at, pdef.defined_at.clone())
+}
+
+/// Build the error message for a macro invocation which does not match any definition, suggesting
+/// other arities if the macro is defined with a different number of arguments.
+fn undefined_macro_message(macro_name: &str, arity: usize, defined_arities: &[usize]) -> String {
+  let mut msg = format!("?{}/{} is not defined", macro_name, arity);
+  if !defined_arities.is_empty() {
+    let suggestions: Vec<String> = defined_arities
+      .iter()
+      .map(|a| format!("?{}/{}", macro_name, a))
+      .collect();
+    msg.push_str(&format!(", did you mean {}?", suggestions.join(" or ")));
+  }
+  msg
+}
+
 #[inline]
 fn lookup_and_paste_macro<'a>(
-  macro_name: &String,
+  macro_name: &str,
   tokens: &'a [Token],
   index: usize,
   output: &mut Vec<Token>,
-  original_input: &str,
   state: &mut PreprocessState<'a>,
 ) -> usize {
-  // Parse once to get the actual arguments grouped by the commas
-  // and parse twice to get the actual span of tokens affected
-  let (args, args_span) =
-    parse_as_invocation_params(original_input, state.module.clone(), &tokens[index + 1..]);
+  // The arguments grouped by the commas, and the count of tokens they take
+  let parsed = parse_as_invocation_params(macro_name, state.module.clone(), &tokens[index + 1..]);
 
-  // Look up the macro definition
-  let key = MFArity::new_local(macro_name, args.len());
+  // Look up the macro definition. Like epp, when no definition takes the given arguments, a macro
+  // without arguments is pasted and the parenthesized tokens after it are left as they are.
+  let defines = &state.module.root_scope.defines;
+  let found = parsed
+    .as_ref()
+    .ok()
+    .and_then(|(args, args_span)| {
+      let key = MFArity::new_local(macro_name, args.len());
+      defines
+        .get(&key)
+        .map(|pdef| (key, pdef, args.clone(), *args_span))
+    })
+    .or_else(|| {
+      let key = MFArity::new_local(macro_name, 0);
+      defines.get(&key).map(|pdef| (key, pdef, Vec::new(), 0))
+    });
 
-  if let Some(pdef) = state.module.root_scope.defines.get(&key) {
+  if let Some((key, pdef, args, args_span)) = found {
+    state.module.root_scope.mark_macro_used(&key);
     // Insert macro body and replace any macro variables with content
-    paste_tokens(output, &pdef, &args);
-  } else {
-    erl_fatal_error(IcPreprocessorError::new(
-      IcSeverity::Fatal,
-      SourceLoc::unimplemented(file!(), function_name!()),
-      format!("Invocation of an undefined macro: {}", macro_name),
-    ));
+    let expansion = new_expansion(state, &pdef, &tokens[index]);
+    paste_tokens(output, &pdef, &args, &tokens[index], &expansion);
+    // Skip input tokens consumed by parsing the arguments
+    return index + args_span + 1;
+  }
+
+  let (msg, skip) = match parsed {
+    Ok((args, args_span)) => {
+      let defined_arities = state.module.root_scope.defined_arities(macro_name);
+      (undefined_macro_message(macro_name, args.len(), &defined_arities), args_span)
+    }
+    // The arguments can't be parsed, skip the rest of the form up to its closing period
+    Err(msg) => {
+      let rest = &tokens[index + 1..];
+      let form_end = rest
+        .iter()
+        .position(|t| matches!(t.kind, TokenKind::Period))
+        .unwrap_or(rest.len());
+      (msg, form_end)
+    }
+  };
+  let location = SourceLoc::from_token(&tokens[index]);
+  if !state
+    .module
+    .add_error(IcPreprocessorError::new(IcSeverity::Error, location, msg))
+  {
+    state.too_many_errors = true;
   }
+  // Continue with a placeholder atom at the invocation location, so that parsing can proceed
+  output.push(Token::new(tokens[index].offset, TokenKind::Atom("undefined".to_string())));
+  index + skip + 1
+}
 
-  // Skip input tokens consumed by parsing the arguments
-  index + args_span + 1
+/// `?FILE` and `?LINE` depend on where they are invoked: the current file (an include file has its
+/// own name) and the line, both remapped by the `-file` attributes.
+fn file_or_line_macro(
+  state: &PreprocessState,
+  macro_name: &str,
+  invocation: &Token,
+) -> PreprocessorDefine {
+  let (file, line) = state
+    .logical_file_line(&SourceLoc::from_token(invocation))
+    .unwrap_or_else(|| (state.source_file.file_name.clone(), 0));
+  let value = if macro_name == "LINE" {
+    Token::new_small(line as i64)
+  } else {
+    Token::new_string(file.to_string_lossy().to_string())
+  };
+  PreprocessorDefineImpl::new(macro_name.to_string(), &[], &[value])
 }
 
 /// Given an input line of tokens, replace macro invocations with their actual body content.
 /// Also substitute the macro variables.
 /// Returns a wrapper struct with either original or substituted tokens.
 pub(crate) fn substitute_macro_invocations<'a>(
-  original_input: &str,
   tokens: &'a [Token],
   state: &mut PreprocessState<'a>,
 ) -> TokenStream<'a> {
@@ -96,13 +186,13 @@ pub(crate) fn substitute_macro_invocations<'a>(
     let t = &tokens[index];
 
     if let TokenKind::MacroInvocation(macro_name) = &t.kind {
-      if macro_name == "LINE" {
-        let pdef = PreprocessorDefineImpl::new("LINE".to_string(), &[], &[Token::new_small(0)]);
-        paste_tokens(&mut output, &pdef, &[]);
+      if macro_name == "LINE" || macro_name == "FILE" {
+        let pdef = file_or_line_macro(state, macro_name, t);
+        let expansion = new_expansion(state, &pdef, t);
+        paste_tokens(&mut output, &pdef, &[], t, &expansion);
         index += 1;
       } else {
-        index =
-          lookup_and_paste_macro(macro_name, tokens, index, &mut output, original_input, state);
+        index = lookup_and_paste_macro(macro_name, tokens, index, &mut output, state);
       }
     } else {
       output.push(t.clone());
@@ -115,25 +205,16 @@ pub(crate) fn substitute_macro_invocations<'a>(
 
 /// Invoke parser producing a list of expressions? separated by commas
 /// Return value: The tokens of arguments, grouped by the separating commas, and the span of the
-///               arguments list (used to skip the length of tokens)
+///               arguments list (used to skip the length of tokens), or an error message if the
+///               arguments can't be parsed
 fn parse_as_invocation_params(
-  original_input: &str,
+  macro_name: &str,
   module: ErlModule,
   tokens: &[Token],
-) -> (Vec<Vec<Token>>, usize) {
-  let parser_input = ParserInput::new_slice(module.clone(), tokens);
-  let (_tail, nodes_as_tokens) = panicking_parser_error_reporter(
-    original_input,
-    parser_input.clone(),
-    parse_macro_invocation_args(parser_input).finish(),
-    false,
-  );
-
-  // Assume this cannot fail, if previous failed it should panic or something
-  let parser_input2 = ParserInput::new_slice(module, tokens);
-  let (_tail2, recognized_span) = recognize(parse_macro_invocation_args)(parser_input2)
-    .finish()
-    .unwrap();
-
-  (nodes_as_tokens, recognized_span.tokens.len())
+) -> Result<(Vec<Vec<Token>>, usize), String> {
+  let parser_input = ParserInput::new_slice(module, tokens);
+  match parse_macro_invocation_args(parser_input).finish() {
+    Ok((tail, nodes_as_tokens)) => Ok((nodes_as_tokens, tokens.len() - tail.tokens.len())),
+    Err(_) => Err(format!("Malformed arguments of the macro invocation ?{}", macro_name)),
+  }
 }
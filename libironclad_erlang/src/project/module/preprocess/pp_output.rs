@@ -0,0 +1,77 @@
+//! Preprocess-only mode: produce the preprocessed module source, like `erlc -P` does
+
+use crate::erl_syntax::parsers::token_stream::token::Token;
+use crate::erl_syntax::parsers::token_stream::token_kind::TokenKind;
+use crate::erl_syntax::parsers::token_stream::token_source::format_tokens_as_source;
+use crate::erl_syntax::preprocessor::pp_node::PreprocessorNode;
+use crate::error::ic_error::IroncladResult;
+use crate::project::compiler_opts::CompilerOpts;
+use crate::project::module::module_impl::{ErlModule, ErlModuleImpl};
+use crate::project::module::preprocess::pp_state::PreprocessState;
+use crate::project::ErlProject;
+use libironclad_util::source_file::SourceFile;
+use std::path::Path;
+use std::ptr::null;
+use std::sync::RwLock;
+
+/// Build tokens for `-file("path", Line).` attribute followed by a newline
+fn file_attr_tokens(path: &Path, line: usize) -> Vec<Token> {
+  vec![
+    Token::new(null(), TokenKind::Minus),
+    Token::new_atom("file".to_string()),
+    Token::new(null(), TokenKind::ParOpen),
+    Token::new_string(path.to_string_lossy().to_string()),
+    Token::new(null(), TokenKind::Comma),
+    Token::new_small(line as i64),
+    Token::new(null(), TokenKind::ParClose),
+    Token::new(null(), TokenKind::Period),
+    Token::new_eol(),
+  ]
+}
+
+impl<'a> PreprocessState<'a> {
+  /// Surround the preprocessed tokens of an included file with `-file` attributes: one pointing
+  /// to the start of the included file, and one pointing back to the line after the include
+  /// directive in the current file.
+  pub(crate) fn mark_included_file(
+    &self,
+    ppnode: &PreprocessorNode,
+    included_path: &Path,
+    tokens: Vec<Token>,
+  ) -> Vec<Token> {
+    let (file, directive_line) = self
+      .logical_file_line(&ppnode.location)
+      .unwrap_or_else(|| (self.source_file.file_name.clone(), 1));
+    let mut result = file_attr_tokens(included_path, 1);
+    result.extend(tokens);
+    result.push(Token::new_eol());
+    result.extend(file_attr_tokens(&file, directive_line + 1));
+    result
+  }
+}
+
+impl ErlModuleImpl {
+  /// Preprocess the module source without parsing it: includes are pasted, conditional sections
+  /// resolved and macros expanded. Module attributes are kept, and `-file` attributes are inserted
+  /// at the include boundaries. Returns the module (to access its errors) and the source text.
+  pub fn preprocess_to_source(
+    project: &ErlProject,
+    source_file: &SourceFile,
+    compiler_options: Option<CompilerOpts>,
+  ) -> IroncladResult<(ErlModule, String)> {
+    let mut module_impl = ErlModuleImpl {
+      source_file: source_file.clone(),
+      preprocess_only: true,
+      ..ErlModuleImpl::default()
+    };
+    if let Some(o) = compiler_options {
+      module_impl.compiler_options = RwLock::new(o);
+    }
+    let module: ErlModule = module_impl.into();
+
+    let mut tokens = file_attr_tokens(&source_file.file_name, 1);
+    tokens.extend(ErlModuleImpl::tokenize(project, &module, source_file)?);
+    let text = format_tokens_as_source(&tokens);
+    Ok((module, text))
+  }
+}
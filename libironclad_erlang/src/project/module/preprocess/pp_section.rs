@@ -11,6 +11,12 @@ pub(crate) struct PreprocessorSection {
   pub(crate) condition: bool,
   /// To prevent double else
   pub(crate) else_encountered: bool,
+  /// Whether the enclosing section was active when this section began. Sections nested in an
+  /// inactive section never become active, but are tracked to match their `-endif`.
+  pub(crate) parent_active: bool,
+  /// Whether one of `-if`, `-elif` or `-else` branches was already taken, so that the following
+  /// `-elif` and `-else` branches are skipped.
+  pub(crate) branch_taken: bool,
 }
 
 impl PreprocessorSection {
@@ -20,6 +26,30 @@ impl PreprocessorSection {
       ppnode,
       condition: is_true,
       else_encountered: false,
+      parent_active: true,
+      branch_taken: is_true,
     }
   }
+
+  /// Create a section nested in an inactive section, none of its branches will be taken
+  pub(crate) fn new_inactive(ppnode: PreprocessorNode) -> Self {
+    PreprocessorSection {
+      ppnode,
+      condition: false,
+      else_encountered: false,
+      parent_active: false,
+      branch_taken: false,
+    }
+  }
+
+  /// Whether an `-elif` condition should be evaluated for this section
+  pub(crate) fn can_take_branch(&self) -> bool {
+    self.parent_active && !self.branch_taken
+  }
+
+  /// Switch to the next `-elif` or `-else` branch with the given condition
+  pub(crate) fn next_branch(&mut self, is_true: bool) {
+    self.condition = is_true;
+    self.branch_taken |= is_true;
+  }
 }
@@ -6,6 +6,9 @@ use crate::erl_syntax::preprocessor::pp_node::PreprocessorNode;
 use crate::project::module::module_impl::ErlModule;
 use crate::project::module::preprocess::pp_section::PreprocessorSection;
 use crate::project::ErlProject;
+use crate::source_loc::SourceLoc;
+use libironclad_util::source_file::SourceFile;
+use std::path::PathBuf;
 
 /// Stores the state of preprocessor directives interpretation.
 pub(crate) struct PreprocessState<'a> {
@@ -15,6 +18,8 @@ pub(crate) struct PreprocessState<'a> {
   pub(crate) too_many_errors: bool,
   /// Module ref with scope
   pub(crate) project: ErlProject,
+  /// The file being preprocessed, the module file or an included file
+  pub(crate) source_file: SourceFile,
   /// Module ref with scope
   pub(crate) module: ErlModule,
   /// Stack of encountered -if/ifdef/ifndef and matching else pairs. Endif pops last stack item.
@@ -28,10 +33,12 @@ impl<'a> PreprocessState<'a> {
   pub fn new(
     project: &ErlProject,
     module: &ErlModule,
+    source_file: &SourceFile,
     input_tokens: (*const Token, usize),
   ) -> Self {
     PreprocessState {
       project: project.clone(),
+      source_file: source_file.clone(),
       module: module.clone(),
       result: Vec::with_capacity(input_tokens.1),
       itr: TokenLinesIter::new(input_tokens),
@@ -40,18 +47,32 @@ impl<'a> PreprocessState<'a> {
     }
   }
 
-  /// Insert tokens vector contents at the current preprocessing state. The vector might be
-  /// reallocated, so also update the `itr` iterator. This is used to include files.
-  pub(crate) fn paste_tokens(&self, paste_into: &mut Vec<Token>, mut tokens: Vec<Token>) {
-    tokens.push(Token::new_eol());
+  /// Find the file and the line for a location in the file being preprocessed, honoring the
+  /// `-file` attributes seen so far
+  pub(crate) fn logical_file_line(&self, loc: &SourceLoc) -> Option<(PathBuf, usize)> {
+    let line = self.source_file.line_of(loc.start()?)?;
+    Some(
+      self
+        .module
+        .logical_file_line(&self.source_file.file_name, line),
+    )
+  }
+
+  /// Whether the file being preprocessed is the module file, and not an included file
+  pub(crate) fn in_module_file(&self) -> bool {
+    self.module.include_stack.len() == 1
+  }
 
-    let paste_pos = self.itr.slice_start + self.itr.slice_len;
-    // println!(
-    //   "Pasting at {} <HERE> {}",
-    //   format_tok_stream(&paste_into[paste_pos - 20..paste_pos], 20),
-    //   format_tok_stream(&paste_into[paste_pos..paste_pos + 20], 20)
-    // );
-    paste_into.splice(paste_pos..paste_pos, tokens.iter().cloned());
+  /// Format a location in the file being preprocessed as `file:line`
+  pub(crate) fn file_line_of(&self, loc: &SourceLoc) -> Option<String> {
+    let (file, line) = self.logical_file_line(loc)?;
+    Some(format!("{}:{}", file.to_string_lossy(), line))
+  }
+
+  /// Append the tokens of an included file, which are already preprocessed, to the output.
+  pub(crate) fn append_included(&mut self, tokens: Vec<Token>) {
+    self.result.extend(tokens);
+    self.result.push(Token::new_eol());
   }
 
   /// Pushes a new section to the stack, when a condition is encountered.
@@ -61,6 +82,12 @@ impl<'a> PreprocessState<'a> {
       .push(PreprocessorSection::new(ppnode, condition));
   }
 
+  /// Pushes a new section to the stack, when a condition is encountered inside an inactive
+  /// section. The condition is not evaluated.
+  pub fn begin_inactive_section(&mut self, ppnode: PreprocessorNode) {
+    self.section.push(PreprocessorSection::new_inactive(ppnode));
+  }
+
   /// Return true if last section `condition` is true, allowing us to paste tokens into the output,
   /// and to interpret further directives in this section.
   pub fn is_section_condition_true(&self) -> bool {
@@ -4,17 +4,33 @@ use crate::erl_syntax::erl_ast::ast_iter::IterableAstNodeT;
 use crate::erl_syntax::erl_ast::node_impl::AstNodeType;
 use crate::erl_syntax::erl_ast::AstNode;
 use crate::erl_syntax::preprocessor::pp_define::PreprocessorDefine;
+use crate::project::module::module_doc::{DocEntry, DocTarget};
 use crate::project::module::scope::mod_attr::ModuleAttributes;
 use crate::project::project_impl::ErlProjectImpl;
 use crate::project::ErlProject;
 use crate::record_def::RecordDefinition;
+use crate::source_loc::SourceLoc;
 use crate::typing::erl_type::ErlType;
+use crate::user_type_def::UserTypeDefinition;
 use libironclad_util::io::file_cache::FileCache;
 use libironclad_util::mfarity::MFArity;
 use libironclad_util::rw_hashmap::RwHashMap;
 use libironclad_util::rw_hashset::RwHashSet;
 use std::sync::Arc;
 
+/// A definition which is reported if it is not used
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ModuleItem {
+  /// `-record(tag, {...})`
+  Record(String),
+  /// `-type name(...)` or `-opaque name(...)`
+  Type(MFArity),
+  /// `-define(NAME(...), ...)`
+  Macro(MFArity),
+  /// A function in `-import(module, [...])`, the key has the module name
+  Import(MFArity),
+}
+
 /// Implements module root scope, stuff available directly from the module root
 #[derive(Debug)]
 pub struct RootScopeImpl {
@@ -26,8 +42,10 @@ pub struct RootScopeImpl {
   pub project: ErlProject,
   /// Contains definitions, added by `-spec` attribute
   pub fn_specs: RwHashMap<MFArity, ErlType>,
-  /// Contains `-type NAME() ...` definitions for new types
-  pub user_types: RwHashMap<MFArity, ErlType>,
+  /// Locations of the `-spec` attributes
+  pub fn_spec_locations: RwHashMap<MFArity, SourceLoc>,
+  /// Contains `-type NAME() ...` and `-opaque NAME() ...` definitions for new types
+  pub user_types: RwHashMap<MFArity, Arc<UserTypeDefinition>>,
   /// Functions can only be found on the module root scope (but technically can be created in the
   /// other internal scopes too)
   pub fn_defs: RwHashMap<MFArity, AstNode>,
@@ -42,6 +60,25 @@ pub struct RootScopeImpl {
   pub exported_types: RwHashSet<MFArity>,
   /// Imported function names keyed by the MFArity
   pub imports: RwHashSet<MFArity>,
+  /// Contains behaviour callback definitions, added by `-callback` attribute
+  pub callbacks: RwHashMap<MFArity, ErlType>,
+  /// Callbacks which the behaviour implementers are not required to export, added by
+  /// `-optional_callbacks` attribute
+  pub optional_callbacks: RwHashSet<MFArity>,
+  /// Behaviours implemented by this module, added by `-behaviour` attribute, with the attribute
+  /// locations
+  pub behaviours: RwHashMap<String, SourceLoc>,
+  /// Documentation from `-moduledoc` and `-doc` attributes, for the module and the items which
+  /// follow the `-doc` attributes
+  pub docs: RwHashMap<DocTarget, Arc<DocEntry>>,
+  /// Records, types, macros and imports defined in the module file, not in the included files,
+  /// with their locations. Only these are reported when unused, because the included definitions
+  /// are shared with other modules.
+  pub own_definitions: RwHashMap<ModuleItem, SourceLoc>,
+  /// Names and arities of the macros which were expanded
+  pub used_macros: RwHashSet<MFArity>,
+  /// Names of the macros tested with `-ifdef`, `-ifndef` and `defined()`, a test uses every arity
+  pub tested_macros: RwHashSet<String>,
 }
 
 /// Alias type for `Arc<>`
@@ -54,6 +91,7 @@ impl Default for RootScopeImpl {
       file_cache: FileCache::default(),
       project: ErlProjectImpl::default().into(),
       fn_specs: RwHashMap::default(),
+      fn_spec_locations: RwHashMap::default(),
       user_types: RwHashMap::default(),
       fn_defs: RwHashMap::default(),
       record_defs: RwHashMap::default(),
@@ -61,6 +99,13 @@ impl Default for RootScopeImpl {
       exports: RwHashSet::default(),
       exported_types: RwHashSet::default(),
       imports: RwHashSet::default(),
+      callbacks: RwHashMap::default(),
+      optional_callbacks: RwHashSet::default(),
+      behaviours: RwHashMap::default(),
+      docs: RwHashMap::default(),
+      own_definitions: RwHashMap::default(),
+      used_macros: RwHashSet::default(),
+      tested_macros: RwHashSet::default(),
     }
   }
 }
@@ -109,6 +154,31 @@ impl RootScopeImpl {
     }
   }
 
+  /// Returns sorted arities of all preprocessor definitions with the given name
+  pub fn defined_arities(&self, name: &str) -> Vec<usize> {
+    if let Ok(r_defines) = self.defines.collection.read() {
+      let mut result: Vec<usize> = r_defines
+        .keys()
+        .filter(|name_arity| name_arity.name == name)
+        .map(|name_arity| name_arity.arity)
+        .collect();
+      result.sort_unstable();
+      result
+    } else {
+      panic!("Can't lock ParserScope.defines for reading")
+    }
+  }
+
+  /// Record that the macro of this arity was expanded, so it is not reported as unused
+  pub fn mark_macro_used(&self, name_arity: &MFArity) {
+    self.used_macros.add(name_arity.clone())
+  }
+
+  /// Record that the macro name was tested, so none of its arities is reported as unused
+  pub fn mark_macro_tested(&self, name: &str) {
+    self.tested_macros.add(name.to_string())
+  }
+
   /// Check if preprocessor definition of any arity exists in the scope
   pub fn is_defined(&self, name: &str) -> bool {
     if let Ok(r_defines) = self.defines.collection.read() {
@@ -59,7 +59,7 @@ impl ScopeImpl {
   }
 
   /// Create a new scope from a variable hashmap
-  pub(crate) fn new(
+  pub fn new(
     name: String,
     parent_scope: Weak<ScopeImpl>,
     variables: HashMap<String, ErlType>,
@@ -0,0 +1,117 @@
+//! Behaviours: finding the callback definitions, and checking the behaviour implementers
+use crate::project::module::module_impl::{ErlModule, ErlModuleImpl};
+use crate::project::project_impl::ErlProjectImpl;
+use libironclad_util::source_file::SourceFileImpl;
+use std::path::PathBuf;
+
+/// Callback definitions for `gen_server`
+const GEN_SERVER_CALLBACKS: &str = "-module(gen_server).
+-callback init(Args :: term()) ->
+  {ok, State :: term()} | {ok, State :: term(), term()} | {stop, Reason :: term()} | ignore.
+-callback handle_call(Request :: term(), From :: {pid(), term()}, State :: term()) ->
+  {reply, Reply :: term(), NewState :: term()}
+  | {reply, Reply :: term(), NewState :: term(), term()}
+  | {noreply, NewState :: term()}
+  | {noreply, NewState :: term(), term()}
+  | {stop, Reason :: term(), Reply :: term(), NewState :: term()}
+  | {stop, Reason :: term(), NewState :: term()}.
+-callback handle_cast(Request :: term(), State :: term()) ->
+  {noreply, NewState :: term()}
+  | {noreply, NewState :: term(), term()}
+  | {stop, Reason :: term(), NewState :: term()}.
+-callback handle_info(Info :: term(), State :: term()) ->
+  {noreply, NewState :: term()}
+  | {noreply, NewState :: term(), term()}
+  | {stop, Reason :: term(), NewState :: term()}.
+-callback handle_continue(Info :: term(), State :: term()) ->
+  {noreply, NewState :: term()}
+  | {noreply, NewState :: term(), term()}
+  | {stop, Reason :: term(), NewState :: term()}.
+-callback terminate(Reason :: term(), State :: term()) -> term().
+-callback code_change(OldVsn :: term(), State :: term(), Extra :: term()) ->
+  {ok, NewState :: term()} | {error, Reason :: term()}.
+-callback format_status(Status :: map()) -> map().
+-callback format_status(Opt :: term(), StatusData :: list()) -> term().
+-optional_callbacks([handle_info/2, handle_continue/2, terminate/2, code_change/3,
+  format_status/1, format_status/2]).
+";
+
+/// Callback definitions for `supervisor`
+const SUPERVISOR_CALLBACKS: &str = "-module(supervisor).
+-callback init(Args :: term()) -> {ok, {SupFlags :: term(), ChildSpecs :: list()}} | ignore.
+";
+
+/// Callback definitions for `gen_statem`
+const GEN_STATEM_CALLBACKS: &str = "-module(gen_statem).
+-callback callback_mode() -> atom() | list().
+-callback init(Args :: term()) ->
+  {ok, State :: term(), Data :: term()}
+  | {ok, State :: term(), Data :: term(), term()}
+  | {stop, Reason :: term()}
+  | ignore.
+-callback handle_event(EventType :: term(), EventContent :: term(), State :: term(),
+  Data :: term()) -> term().
+-callback terminate(Reason :: term(), State :: term(), Data :: term()) -> term().
+-callback code_change(OldVsn :: term(), OldState :: term(), OldData :: term(),
+  Extra :: term()) -> {ok, NewState :: term(), NewData :: term()} | term().
+-callback format_status(Status :: map()) -> map().
+-optional_callbacks([handle_event/4, terminate/3, code_change/4, format_status/1]).
+";
+
+/// Callback definitions for `application`
+const APPLICATION_CALLBACKS: &str = "-module(application).
+-callback start(StartType :: term(), StartArgs :: term()) ->
+  {ok, pid()} | {ok, pid(), State :: term()} | {error, Reason :: term()}.
+-callback stop(State :: term()) -> term().
+-callback prep_stop(State :: term()) -> term().
+-callback config_change(Changed :: list(), New :: list(), Removed :: list()) -> ok.
+-callback start_phase(Phase :: atom(), StartType :: term(), PhaseArgs :: term()) ->
+  ok | {error, Reason :: term()}.
+-optional_callbacks([prep_stop/1, config_change/3, start_phase/3]).
+";
+
+impl ErlProjectImpl {
+  /// Source text with callback definitions for the behaviours shipped with the compiler
+  fn builtin_behaviour_source(name: &str) -> Option<&'static str> {
+    match name {
+      "gen_server" => Some(GEN_SERVER_CALLBACKS),
+      "supervisor" => Some(SUPERVISOR_CALLBACKS),
+      "gen_statem" => Some(GEN_STATEM_CALLBACKS),
+      "application" => Some(APPLICATION_CALLBACKS),
+      _ => None,
+    }
+  }
+
+  /// Find a module with the `-callback` definitions for the behaviour name. Project modules are
+  /// checked first, then the behaviours shipped with the compiler. A built-in behaviour is parsed
+  /// once and kept in the project.
+  pub fn find_behaviour(&self, name: &str) -> Option<ErlModule> {
+    if let Some(module) = self.modules.get(&name.to_string()) {
+      return Some(module);
+    }
+    if let Some(module) = self.builtin_behaviours.get(&name.to_string()) {
+      return Some(module);
+    }
+    let source = Self::builtin_behaviour_source(name)?;
+    let source_file = SourceFileImpl::new(&PathBuf::from(format!("{}.erl", name)), source.into());
+    let project = ErlProjectImpl::default().into();
+    let module = ErlModuleImpl::from_module_source(&project, &source_file, None)
+      .expect("Built-in behaviour callbacks must parse");
+    self
+      .builtin_behaviours
+      .add(name.to_string(), module.clone());
+    Some(module)
+  }
+
+  /// Check every loaded module, which declares `-behaviour` attributes, against the behaviour
+  /// callbacks, see `ErlModuleImpl::verify_behaviours`. Must run after all project modules are
+  /// loaded, because the behaviours can be defined in the project. Errors are added to the modules
+  /// and printed.
+  pub fn verify_behaviours(&self) {
+    for module in self.loaded_modules().iter() {
+      if !module.verify_behaviours() {
+        module.print_errors()
+      }
+    }
+  }
+}
@@ -1,7 +1,9 @@
 //! Erlang project (with inputs defined in the config file)
 
 use crate::error::ic_error::{IroncladError, IroncladResult};
+use crate::error::ic_error_trait::GenericIroncladError;
 use crate::project::compiler_opts::{CompilerOpts, CompilerOptsImpl};
+use crate::project::compiler_override::CompilerOverride;
 use crate::project::conf::ProjectConf;
 use crate::project::input_opts::InputOpts;
 use crate::project::module::module_impl::ErlModule;
@@ -24,26 +26,66 @@ pub struct ErlProjectImpl {
   pub modules: RwHashMap<String, ErlModule>,
   /// Stores files recently loaded from disk
   pub file_cache: FileCache,
+  /// Callback definitions of the behaviours shipped with the compiler, parsed on first use
+  pub builtin_behaviours: RwHashMap<String, ErlModule>,
 }
 
 impl ErlProjectImpl {
-  /// Get a clone of project libironclad options
-  /// TODO: special override options if user specifies extras as a module attribute
+  /// Get a clone of project libironclad options, with the `[[overrides]]` for the file applied.
+  /// The `-compile()` module attributes can add to them later, for that module only, see
+  /// `ErlModuleImpl::update_compiler_options`.
   pub fn get_compiler_options_for(&self, path: &Path) -> CompilerOpts {
+    self
+      .get_compiler_options_per_file(path)
+      .unwrap_or_else(|| self.project_inputs.compiler_opts.clone())
+  }
+
+  /// Get the compiler options for a file affected by `[[overrides]]`, or `None` if no override
+  /// matches the file. The result is cached in `compiler_opts_per_file`.
+  pub fn get_compiler_options_per_file(&self, path: &Path) -> Option<CompilerOpts> {
     // sad reality of generic get having arg of &PathBuf and not &Path
     let pb = PathBuf::from(path);
-
     if let Some(per_file_opts) = self.project_inputs.compiler_opts_per_file.get(&pb) {
-      // If found per-file settings, combine global with per-file
-      self
-        .project_inputs
-        .compiler_opts
-        .overlay(per_file_opts.deref())
-        .into()
-    } else {
-      // If not found per-file settings, just provide a clone of global settings
-      self.project_inputs.compiler_opts.clone()
+      return Some(per_file_opts);
     }
+
+    let mut matching = self.overrides_for(path).peekable();
+    matching.peek()?;
+    let global_opts = self.project_inputs.compiler_opts.deref().clone();
+    let per_file_opts: CompilerOpts = matching
+      .fold(global_opts, |opts, ovr| ovr.apply_to(&opts))
+      .into();
+    self
+      .project_inputs
+      .compiler_opts_per_file
+      .add(pb, per_file_opts.clone());
+    Some(per_file_opts)
+  }
+
+  /// The `[[overrides]]` affecting the file, in the order of application
+  pub fn overrides_for<'a>(&'a self, path: &'a Path) -> impl Iterator<Item = &'a CompilerOverride> {
+    self
+      .project_inputs
+      .overrides
+      .iter()
+      .filter(move |ovr| ovr.matches(path))
+  }
+
+  /// Describe the compiler options for a file and the `[[overrides]]` which affected them
+  pub fn describe_compiler_options_for(&self, path: &Path) -> String {
+    let mut out = format!("Compiler options for {}\n", path.to_string_lossy());
+    let mut affected = false;
+    for (index, ovr) in self.project_inputs.overrides.iter().enumerate() {
+      if ovr.matches(path) {
+        out.push_str(&format!("  override #{} matches: paths = {:?}\n", index + 1, ovr.paths));
+        affected = true;
+      }
+    }
+    if !affected {
+      out.push_str("  no overrides match, project options are used\n");
+    }
+    out.push_str(&format!("{:#?}", self.get_compiler_options_for(path)));
+    out
   }
 
   /// Default file dict capacity
@@ -100,6 +142,27 @@ impl ErlProjectImpl {
     self.modules.add(m_name, module.clone())
   }
 
+  /// All modules loaded so far. The project modules lock is not held while the caller works on
+  /// the modules.
+  pub fn loaded_modules(&self) -> Vec<ErlModule> {
+    if let Ok(r_modules) = self.modules.collection.read() {
+      r_modules.values().cloned().collect()
+    } else {
+      panic!("Can't lock project modules for reading")
+    }
+  }
+
+  /// Check every loaded module for the patterns which break the opacity of the `-opaque` types
+  /// from other modules, see `ErlModuleImpl::verify_opaque_patterns`. Must run after all project
+  /// modules are loaded. Errors are added to the modules and printed.
+  pub fn verify_opaque_patterns(&self) {
+    for module in self.loaded_modules().iter() {
+      if !module.verify_opaque_patterns() {
+        module.print_errors()
+      }
+    }
+  }
+
   /// Retrieve a source file from the file cache, load if necessary
   pub fn get_source_file(&self, path: &Path) -> IroncladResult<SourceFile> {
     self
@@ -109,19 +172,28 @@ impl ErlProjectImpl {
   }
 }
 
-impl From<ProjectConf> for ErlProjectImpl {
-  fn from(conf: ProjectConf) -> Self {
+impl TryFrom<ProjectConf> for ErlProjectImpl {
+  type Error = GenericIroncladError;
+
+  fn try_from(conf: ProjectConf) -> IroncladResult<Self> {
     let inputs = ErlProjectInputs {
-      compiler_opts: CompilerOptsImpl::new_from_maybe_opts(conf.compiler_options).into(),
+      compiler_opts: CompilerOptsImpl::new_from_maybe_opts(conf.compiler_options)?.into(),
       compiler_opts_per_file: Default::default(),
+      overrides: conf
+        .overrides
+        .unwrap_or_default()
+        .into_iter()
+        .map(|o| CompilerOverride::new_from_conf(o, conf.project_dir.as_deref()))
+        .collect::<IroncladResult<Vec<_>>>()?,
       input_opts: InputOpts::from(conf.inputs),
       input_paths: RwVec::default(),
     };
-    Self {
+    Ok(Self {
       project_inputs: inputs,
       modules: RwHashMap::default(),
       file_cache: FileCache::default(),
-    }
+      builtin_behaviours: RwHashMap::default(),
+    })
   }
 }
 
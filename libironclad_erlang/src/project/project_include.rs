@@ -31,40 +31,44 @@ impl ErlProjectImpl {
   /// with that name is included. Otherwise, the specified file is searched for in the following
   /// directories, and in this order:
   ///
+  /// * The directory of the file containing the `-include` (last in `include_chain`)
   /// * The current working directory
-  /// * The directory where the module is being compiled
+  /// * The directory where the module is being compiled (first in `include_chain`)
+  /// * The per-file include paths of the files in `include_chain`, innermost file first
   /// * The directories given by the include option
   pub(crate) fn find_include(
     &self,
     location: SourceLoc,
     find_file: &Path,
-    from_file: Option<PathBuf>,
+    include_chain: &[PathBuf],
   ) -> IroncladResult<PathBuf> {
     if find_file.is_absolute() {
       return Ok(find_file.to_path_buf());
     }
     // TODO: Support $VAR components in paths
 
+    // Try the directory of the file which contains the -include directive
+    if let Some(including_file) = include_chain.last() {
+      if let Some(try_loc) = Self::find_include_in(find_file, &Self::parent_dir(including_file)) {
+        return Ok(try_loc);
+      }
+    }
+
     // Try current working directory
     if let Some(try_loc) = Self::find_include_in(find_file, &[".".to_string()]) {
       return Ok(try_loc);
     }
 
     // Try current compiled file directory
-    if let Some(current_source) = &from_file {
-      let current_source_dir = current_source
-        .parent()
-        .unwrap()
-        .to_string_lossy()
-        .to_string();
-      if let Some(try_loc) = Self::find_include_in(find_file, &[current_source_dir]) {
+    if let Some(module_file) = include_chain.first() {
+      if let Some(try_loc) = Self::find_include_in(find_file, &Self::parent_dir(module_file)) {
         return Ok(try_loc);
       }
     }
 
-    // Try find in local search paths for file
-    if let Some(from_file1) = &from_file {
-      if let Some(opts_per_file) = self.project_inputs.compiler_opts_per_file.get(from_file1) {
+    // Try find in local search paths for the module file and every file which included us
+    for from_file in include_chain.iter().rev() {
+      if let Some(opts_per_file) = self.get_compiler_options_per_file(from_file) {
         if let Some(try_loc) = Self::find_include_in(find_file, &opts_per_file.include_paths) {
           return Ok(try_loc);
         }
@@ -80,4 +84,12 @@ impl ErlProjectImpl {
       IroncladError::file_not_found(location, find_file, "searching for an -include() path")
     }
   }
+
+  /// Directory containing the file, as a one element search list. Empty if the path has no parent.
+  fn parent_dir(file: &Path) -> Vec<String> {
+    file
+      .parent()
+      .map(|dir| vec![dir.to_string_lossy().to_string()])
+      .unwrap_or_default()
+  }
 }
@@ -1,6 +1,7 @@
 //! Project inputs (scanned directories for input files)
 
 use crate::project::compiler_opts::CompilerOpts;
+use crate::project::compiler_override::CompilerOverride;
 use crate::project::input_opts::InputOpts;
 use libironclad_util::rw_hashmap::RwHashMap;
 use libironclad_util::rw_vec::RwVec;
@@ -12,8 +13,11 @@ pub struct ErlProjectInputs {
   /// Input search paths, output paths, flags, ... etc. Shared with all modules which use default
   /// compile options
   pub compiler_opts: CompilerOpts,
-  /// Compiler options but overrides on a per-file basis
+  /// Compiler options for the files affected by `overrides`: the project options with the
+  /// matching overrides applied. Filled when the options for a file are first requested.
   pub compiler_opts_per_file: RwHashMap<PathBuf, CompilerOpts>,
+  /// Compiler options for groups of files, from `[[overrides]]` sections, in order
+  pub overrides: Vec<CompilerOverride>,
   /// Input files and directories (wildcards are allowed)
   pub input_opts: InputOpts,
   /// Prepared paths, scanned from Self::inputs, and with exclusions filtered out
@@ -1,5 +1,7 @@
 //! Source file locations for printing and reporting to the user
 use crate::erl_syntax::parsers::parser_input::ParserInput;
+use crate::erl_syntax::parsers::token_stream::token::Token;
+use crate::erl_syntax::preprocessor::pp_expansion::{MacroExpansion, MacroExpansionImpl};
 use std::fmt::Formatter;
 
 /// Source code span with start and end
@@ -12,6 +14,12 @@ pub enum SourceLoc {
     /// Start of the input
     start: *const u8,
   },
+  /// Offset in the input string, for code pasted from a macro body. Boxed to keep
+  /// `SourceLoc` (and the AST nodes) small.
+  Expanded {
+    /// Start of the input, and the macro invocation which produced the code
+    at: Box<(*const u8, MacroExpansion)>,
+  },
 }
 
 impl SourceLoc {
@@ -22,17 +30,57 @@ impl SourceLoc {
     SourceLoc::None
   }
 
+  /// Location of a token, if the token has one (tokens created by the compiler do not)
+  pub(crate) fn from_token(tok: &Token) -> Self {
+    if tok.offset.is_null() {
+      SourceLoc::None
+    } else {
+      Self::at_token(tok)
+    }
+  }
+
+  /// Location of a token, carrying its macro expansion if the token was pasted from a macro body
+  fn at_token(tok: &Token) -> Self {
+    match &tok.expansion {
+      Some(exp) => SourceLoc::Expanded { at: Box::new((tok.offset, exp.clone())) },
+      None => SourceLoc::Offset { start: tok.offset },
+    }
+  }
+
   /// Create an absolute pointer from an input position. Use this to determine source location later.
+  /// The end of line tokens left before a form are skipped, the location is the first real token.
   pub(crate) fn new(input: &ParserInput) -> Self {
-    Self::Offset { start: input.tokens.iter().next().unwrap().offset }
+    let first = input.tokens.iter().find(|t| !t.is_eol());
+    Self::at_token(first.unwrap_or_else(|| input.tokens.iter().next().unwrap()))
+  }
+
+  /// Pointer to the source text, if known
+  pub fn start(&self) -> Option<*const u8> {
+    match self {
+      SourceLoc::None => None,
+      SourceLoc::Offset { start } => Some(*start),
+      SourceLoc::Expanded { at } => Some(at.0),
+    }
+  }
+
+  /// If the location is inside code pasted from a macro body, returns lines describing the macro
+  /// expansion chain, innermost first, to be printed after the error message. Otherwise empty.
+  pub fn format_expansion(&self) -> String {
+    match self {
+      SourceLoc::Expanded { at } => MacroExpansionImpl::chain(&at.1)
+        .iter()
+        .map(|e| format!("\n    {}", e))
+        .collect(),
+      _ => String::new(),
+    }
   }
 }
 
 impl std::fmt::Display for SourceLoc {
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-    match self {
-      SourceLoc::None => write!(f, "<No info>"),
-      SourceLoc::Offset { start } => write!(f, "SourceLoc[{:x}]", *start as usize),
+    match self.start() {
+      None => write!(f, "<No info>"),
+      Some(start) => write!(f, "SourceLoc[{:x}]", start as usize),
     }
   }
 }
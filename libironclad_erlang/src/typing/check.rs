@@ -1,11 +1,18 @@
 //! Checks whether a type matches synthesized type for AST
 
+use crate::erl_syntax::erl_ast::node_impl::AstNodeType;
 use crate::erl_syntax::erl_ast::AstNode;
+use crate::erl_syntax::erl_op::ErlBinaryOp;
 use crate::error::ic_error::IroncladResult;
-use crate::project::module::module_impl::ErlModule;
+use crate::project::module::module_impl::{ErlModule, ErlModuleImpl};
 use crate::project::module::scope::scope_impl::Scope;
+use crate::typing::erl_type::typekind::TypeKind;
 use crate::typing::erl_type::TypeImpl;
 use crate::typing::type_error::TypeError;
+use crate::user_type_def::UserTypeDefinition;
+use libironclad_util::mfarity::MFArity;
+use std::collections::HashSet;
+use std::sync::Arc;
 
 /// Contains type checking code
 pub struct TypeCheck {}
@@ -22,12 +29,59 @@ impl TypeCheck {
   ) -> IroncladResult<bool> {
     let synthesized_ty = ast.synthesize(module, scope)?;
 
+    // Opaque types from the other modules are only compatible with themselves
+    let self_name = module.get_name();
+    let expected_opaque = Self::foreign_opaque_type(module, expected_ty, &self_name);
+    let synthesized_opaque = Self::foreign_opaque_type(module, &synthesized_ty, &self_name);
+    match (expected_opaque, synthesized_opaque) {
+      (Some(expected_name), Some(synth_name)) if expected_name == synth_name => return Ok(true),
+      (Some(_), None) if synthesized_ty.is_any() => return Ok(true),
+      (Some(expected_name), _) => {
+        return Err(TypeError::new_opacity_violation(
+          Some(ast.location.clone()),
+          expected_name,
+          format!(
+            "The term of type {} is passed where the opaque type {} is expected, this breaks \
+            the opacity of the term",
+            synthesized_ty, expected_ty
+          ),
+        ))
+      }
+      (None, Some(synth_name))
+        if Self::may_hold_opaque(
+          module,
+          expected_ty,
+          &synth_name,
+          &self_name,
+          &mut HashSet::new(),
+        ) =>
+      {
+        return Ok(true)
+      }
+      (None, Some(synth_name)) => {
+        return Err(TypeError::new_opacity_violation(
+          Some(ast.location.clone()),
+          synth_name,
+          format!(
+            "The term of opaque type {} is matched against its structure {}, this breaks the \
+            opacity of the term",
+            synthesized_ty, expected_ty
+          ),
+        ))
+      }
+      (None, None) => {}
+    }
+
+    // Types defined in this module, and transparent types from other modules, are checked by their
+    // definition. The type arguments are not substituted into the definition.
+    let expected_ty = Self::expand_alias(module, expected_ty, &self_name);
+
     println!(
       "Checking AST type vs expected type\n\tAst: {}\n\tSynth type: {}\n\tExpected: {}",
       ast, synthesized_ty, expected_ty
     );
 
-    if !synthesized_ty.is_subtype_of(expected_ty) {
+    if !synthesized_ty.is_subtype_of(&expected_ty) {
       // let type_err = TypeError::ExpectedType {
       //   expected_type: format!("{}", expected_ty),
       //   actual_type: format!("{}", synthesized_ty),
@@ -42,6 +96,193 @@ impl TypeCheck {
       Ok(true)
     }
   }
+
+  /// Checks that the pattern does not look into the structure of an opaque type from another
+  /// module. A variable, or a match `A = B` of such patterns, may bind an opaque term, any other
+  /// pattern for an opaque type is an opacity violation. Tuple and list patterns are checked
+  /// element-wise against the tuple and list types, and transparent user types by their
+  /// definitions. Other types, including unions, are not looked into.
+  pub fn check_pattern_opacity(
+    module: &ErlModuleImpl,
+    pattern: &AstNode,
+    expected_ty: &TypeImpl,
+  ) -> IroncladResult<()> {
+    Self::check_pattern_opacity_in(
+      module,
+      pattern,
+      expected_ty,
+      &module.get_name(),
+      &mut HashSet::new(),
+    )
+  }
+
+  /// Check the pattern opacity for a type written in the module `context`, the unqualified type
+  /// names in the type are resolved there. `expanding` contains the user types being expanded,
+  /// to stop on the type alias cycles.
+  fn check_pattern_opacity_in(
+    module: &ErlModuleImpl,
+    pattern: &AstNode,
+    expected_ty: &TypeImpl,
+    context: &str,
+    expanding: &mut HashSet<MFArity>,
+  ) -> IroncladResult<()> {
+    if let AstNodeType::BinaryOp { binop_expr } = &pattern.content {
+      if binop_expr.operator == ErlBinaryOp::Match {
+        Self::check_pattern_opacity_in(module, &binop_expr.left, expected_ty, context, expanding)?;
+        return Self::check_pattern_opacity_in(
+          module,
+          &binop_expr.right,
+          expected_ty,
+          context,
+          expanding,
+        );
+      }
+    }
+    if pattern.is_var() {
+      return Ok(());
+    }
+    if let Some(opaque_name) = Self::foreign_opaque_type(module, expected_ty, context) {
+      return Err(TypeError::new_opacity_violation(
+        Some(pattern.location.clone()),
+        opaque_name,
+        format!(
+          "The term of opaque type {} is matched against the pattern {}, this breaks the \
+          opacity of the term",
+          expected_ty, pattern
+        ),
+      ));
+    }
+    match (&pattern.content, &expected_ty.kind) {
+      (AstNodeType::Tuple { elements }, TypeKind::Tuple { elements: element_types })
+        if elements.len() == element_types.len() =>
+      {
+        for (element, element_ty) in elements.iter().zip(element_types.iter()) {
+          Self::check_pattern_opacity_in(module, element, element_ty, context, expanding)?;
+        }
+        Ok(())
+      }
+      (AstNodeType::List { elements, tail }, TypeKind::List { elements: element_ty, .. }) => {
+        for element in elements.iter() {
+          Self::check_pattern_opacity_in(module, element, element_ty, context, expanding)?;
+        }
+        match tail {
+          Some(t) => Self::check_pattern_opacity_in(module, t, expected_ty, context, expanding),
+          None => Ok(()),
+        }
+      }
+      (
+        AstNodeType::List { elements, tail: None },
+        TypeKind::StronglyTypedList { elements: element_types, .. },
+      ) if elements.len() == element_types.len() => {
+        for (element, element_ty) in elements.iter().zip(element_types.iter()) {
+          Self::check_pattern_opacity_in(module, element, element_ty, context, expanding)?;
+        }
+        Ok(())
+      }
+      (_, TypeKind::UserDefinedType { name, .. }) => {
+        match Self::find_user_type_in(module, name, context) {
+          Some((qualified_name, type_def)) if expanding.insert(qualified_name.clone()) => {
+            let defined_in = qualified_name.module.clone().unwrap_or_default();
+            let result =
+              Self::check_pattern_opacity_in(module, pattern, &type_def.ty, &defined_in, expanding);
+            expanding.remove(&qualified_name);
+            result
+          }
+          _ => Ok(()),
+        }
+      }
+      _ => Ok(()),
+    }
+  }
+
+  /// Find a user type definition, an unqualified name is looked up in the module `context` where
+  /// the type was written
+  fn find_user_type_in(
+    module: &ErlModuleImpl,
+    name: &MFArity,
+    context: &str,
+  ) -> Option<(MFArity, Arc<UserTypeDefinition>)> {
+    match name.module {
+      Some(_) => module.find_user_type(name),
+      None => module.find_user_type(&name.clone_with_module(context)),
+    }
+  }
+
+  /// Replace a transparent user type with its definition, repeatedly, while the definition is
+  /// another transparent user type. The names in each definition are resolved in the module which
+  /// defines it. Opaque types from other modules and unknown types are not expanded.
+  fn expand_alias(module: &ErlModuleImpl, ty: &TypeImpl, context: &str) -> Arc<TypeImpl> {
+    let mut current = Arc::new(ty.clone());
+    let mut context = context.to_string();
+    let mut expanded = HashSet::new();
+    while let TypeKind::UserDefinedType { name, .. } = &current.kind {
+      if Self::foreign_opaque_type(module, &current, &context).is_some() {
+        break;
+      }
+      match Self::find_user_type_in(module, name, &context) {
+        Some((qualified_name, type_def)) if expanded.insert(qualified_name.clone()) => {
+          context = qualified_name.module.unwrap_or_default();
+          current = type_def.ty.clone();
+        }
+        _ => break,
+      }
+    }
+    current
+  }
+
+  /// Whether a term of the foreign opaque type `opaque_name` may be passed where `ty` is expected,
+  /// written in the module `context`. This is so for `any()`, the same opaque type, a union with a
+  /// member which may hold it, and an unknown type. A transparent user type is checked by its
+  /// definition. Structural types, and other opaque types, can not hold an opaque term.
+  fn may_hold_opaque(
+    module: &ErlModuleImpl,
+    ty: &TypeImpl,
+    opaque_name: &MFArity,
+    context: &str,
+    expanding: &mut HashSet<MFArity>,
+  ) -> bool {
+    match &ty.kind {
+      TypeKind::Any => true,
+      TypeKind::Union(members) => members
+        .types
+        .iter()
+        .any(|t| Self::may_hold_opaque(module, t, opaque_name, context, expanding)),
+      TypeKind::UserDefinedType { name, .. } => {
+        match Self::find_user_type_in(module, name, context) {
+          Some((qualified_name, _)) if &qualified_name == opaque_name => true,
+          Some((qualified_name, type_def)) => {
+            if type_def.opaque && qualified_name.module.as_deref() != Some(&module.get_name()) {
+              return false;
+            }
+            if !expanding.insert(qualified_name.clone()) {
+              return false;
+            }
+            let defined_in = qualified_name.module.clone().unwrap_or_default();
+            let result =
+              Self::may_hold_opaque(module, &type_def.ty, opaque_name, &defined_in, expanding);
+            expanding.remove(&qualified_name);
+            result
+          }
+          None => true,
+        }
+      }
+      _ => false,
+    }
+  }
+
+  /// If the type refers to an `-opaque` type defined in another module, return the type name,
+  /// qualified with the defining module name. The type is written in the module `context`. Inside
+  /// its defining module an opaque type is transparent. The type arguments are not compared: two
+  /// uses of a parametric opaque type with different arguments are the same opaque type here.
+  fn foreign_opaque_type(module: &ErlModuleImpl, ty: &TypeImpl, context: &str) -> Option<MFArity> {
+    if let TypeKind::UserDefinedType { name, .. } = &ty.kind {
+      let (qualified_name, type_def) = Self::find_user_type_in(module, name, context)?;
+      if type_def.opaque && qualified_name.module != Some(module.get_name()) {
+        return Some(qualified_name);
+      }
+    }
+    None
+  }
 }
 
 // /// For function definition, check every clause if any return type is matching
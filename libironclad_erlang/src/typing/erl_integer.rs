@@ -64,8 +64,40 @@ impl ErlInteger {
   /// Flip sign on self contents
   pub fn negate(&self) -> Self {
     match self {
-      Small(i) => Small(-*i),
-      Big(big) => Big(-big.clone()),
+      Small(i) => match i.checked_neg() {
+        Some(negated) => Small(negated),
+        None => Self::from_bigint(-BigInt::from(*i)),
+      },
+      Big(big) => Self::from_bigint(-big.clone()),
+    }
+  }
+
+  /// Convert to a `BigInt` for arithmetic
+  pub(crate) fn to_bigint(&self) -> BigInt {
+    match self {
+      Small(small) => BigInt::from(*small),
+      Big(big) => big.clone(),
+    }
+  }
+
+  /// Wrap a `BigInt` result, using the small representation if the value fits in 64 bits
+  pub(crate) fn from_bigint(big: BigInt) -> Self {
+    match big.to_i64() {
+      Some(small) => Small(small),
+      None => Big(big),
+    }
+  }
+
+  /// Convert to a float, returns `None` if the value is too large for a float
+  pub(crate) fn as_f64(&self) -> Option<f64> {
+    let result = match self {
+      Small(small) => *small as f64,
+      Big(big) => big.to_f64()?,
+    };
+    if result.is_finite() {
+      Some(result)
+    } else {
+      None
     }
   }
 }
@@ -149,7 +149,6 @@ impl TypeImpl {
   }
 
   /// True if type is any()
-  #[allow(dead_code)]
   pub(crate) fn is_any(&self) -> bool {
     matches!(&self.kind, TypeKind::Any)
   }
@@ -56,7 +56,17 @@ impl std::fmt::Display for TypeImpl {
         write!(f, "}}")
       }
       TypeKind::AnyBinary => write!(f, "binary()"),
-      TypeKind::Binary { .. } => unimplemented!("Display type for binary"),
+      TypeKind::Binary { head, tail } => {
+        write!(f, "<<")?;
+        if let Some(h) = head {
+          write!(f, "_:{}", h.0)?;
+        }
+        if let Some(t) = tail {
+          let sep = if head.is_some() { ", " } else { "" };
+          write!(f, "{}_:_*{}", sep, t.0)?;
+        }
+        write!(f, ">>")
+      }
       TypeKind::AnyFn => write!(f, "function()"),
       TypeKind::Fn(fntype) => {
         write!(f, "fun ").unwrap();
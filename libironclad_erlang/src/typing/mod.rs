@@ -10,5 +10,7 @@ pub mod record_field_type;
 pub mod subtyping;
 pub mod type_error;
 pub mod type_narrow;
+pub mod type_overlap;
 pub mod type_synth;
+pub mod type_synth_comprehension;
 pub mod type_union;
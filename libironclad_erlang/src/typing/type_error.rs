@@ -34,6 +34,11 @@ pub enum TypeErrorKind {
   BadArguments,
   /// Something wrong with type specs
   TypeSpecError,
+  /// A term of an opaque type was constructed, matched or inspected outside of its defining module
+  OpacityViolation {
+    /// The opaque type, qualified with its defining module
+    opaque_type: MFArity,
+  },
 }
 
 /// Wraps a message with error kind together
@@ -132,6 +137,20 @@ impl TypeError {
     })
   }
 
+  /// Create a new `opacity violation` error.
+  pub fn new_opacity_violation(
+    location: Option<SourceLoc>,
+    opaque_type: MFArity,
+    message: String,
+  ) -> GenericIroncladError {
+    Box::new(Self {
+      severity: IcSeverity::Error,
+      kind: TypeErrorKind::OpacityViolation { opaque_type },
+      location,
+      message,
+    })
+  }
+
   /// Create a new `different type expected` error.
   pub fn new_type_error(
     location: Option<SourceLoc>,
@@ -166,6 +185,9 @@ impl Display for TypeError {
       TypeErrorKind::BadArguments => write!(f, "Bad arguments: {}", self.message),
       TypeErrorKind::FunctionNotFound { mfa } => write!(f, "Function not found: {}", mfa),
       TypeErrorKind::TypeSpecError => write!(f, "Type spec error: {}", self.message),
+      TypeErrorKind::OpacityViolation { opaque_type } => {
+        write!(f, "Opacity violation for {}: {}", opaque_type, self.message)
+      }
     }
   }
 }
@@ -0,0 +1,98 @@
+//! Checks whether two types can share a value. This is weaker than the subtype check, and is used
+//! where two independently written types are compared, like a behaviour callback spec and the spec
+//! of the function implementing it.
+
+use crate::literal::Literal;
+use crate::typing::erl_type::typekind::TypeKind;
+use crate::typing::erl_type::TypeImpl;
+use crate::typing::fn_clause_type::FnClauseType;
+
+/// Coarse category of Erlang values, types from different categories never share a value
+#[derive(Debug, Eq, PartialEq)]
+enum ValueCategory {
+  Atom,
+  Number,
+  List,
+  Tuple,
+  Map,
+  Binary,
+  Fun,
+  Pid,
+  Reference,
+  Port,
+}
+
+impl TypeImpl {
+  /// Check whether some value could belong to both types. Types which can't be compared here, like
+  /// `any()`, user-defined types and record references, are assumed to overlap with everything.
+  pub fn may_overlap(&self, other: &TypeImpl) -> bool {
+    match (&self.kind, &other.kind) {
+      (TypeKind::Union(u), _) => u.types.iter().any(|t| t.may_overlap(other)),
+      (_, TypeKind::Union(u)) => u.types.iter().any(|t| self.may_overlap(t)),
+      (TypeKind::Tuple { elements: a }, TypeKind::Tuple { elements: b }) => {
+        a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| x.may_overlap(y))
+      }
+      (TypeKind::Singleton { val: a }, TypeKind::Singleton { val: b })
+        if matches!(a.as_ref(), Literal::Atom(_)) && matches!(b.as_ref(), Literal::Atom(_)) =>
+      {
+        a == b
+      }
+      _ => match (self.value_category(), other.value_category()) {
+        (Some(a), Some(b)) => a == b,
+        _ => true,
+      },
+    }
+  }
+
+  /// Category of values for the type, or `None` if the type is not limited to one category
+  fn value_category(&self) -> Option<ValueCategory> {
+    match &self.kind {
+      TypeKind::Atom | TypeKind::Boolean => Some(ValueCategory::Atom),
+      TypeKind::Number | TypeKind::Float | TypeKind::Integer | TypeKind::IntegerRange { .. } => {
+        Some(ValueCategory::Number)
+      }
+      TypeKind::AnyTuple | TypeKind::Tuple { .. } | TypeKind::Record { .. } => {
+        Some(ValueCategory::Tuple)
+      }
+      TypeKind::AnyList
+      | TypeKind::List { .. }
+      | TypeKind::StronglyTypedList { .. }
+      | TypeKind::Nil => Some(ValueCategory::List),
+      TypeKind::AnyMap | TypeKind::Map { .. } => Some(ValueCategory::Map),
+      TypeKind::AnyBinary | TypeKind::Binary { .. } => Some(ValueCategory::Binary),
+      TypeKind::AnyFn | TypeKind::Fn(_) | TypeKind::FnRef { .. } | TypeKind::Lambda => {
+        Some(ValueCategory::Fun)
+      }
+      TypeKind::Pid => Some(ValueCategory::Pid),
+      TypeKind::Reference => Some(ValueCategory::Reference),
+      TypeKind::Port => Some(ValueCategory::Port),
+      TypeKind::Singleton { val } => match val.as_ref() {
+        Literal::Atom(_) | Literal::Bool(_) => Some(ValueCategory::Atom),
+        Literal::Integer(_)
+        | Literal::Float(_)
+        | Literal::Character(_)
+        | Literal::EscapedCharacter { .. } => Some(ValueCategory::Number),
+        Literal::List { .. } | Literal::Nil | Literal::String(_) => Some(ValueCategory::List),
+        Literal::Tuple(_) => Some(ValueCategory::Tuple),
+        Literal::Map(_) => Some(ValueCategory::Map),
+        Literal::EmptyBinary | Literal::BinaryString(_) | Literal::Binary(_) => {
+          Some(ValueCategory::Binary)
+        }
+      },
+      _ => None,
+    }
+  }
+}
+
+impl FnClauseType {
+  /// Check whether the two clauses can accept same arguments and return same values
+  pub(crate) fn may_overlap(&self, other: &FnClauseType) -> bool {
+    self.arity() == other.arity()
+      && self
+        .args
+        .iter()
+        .zip(other.args.iter())
+        .all(|(a, b)| a.may_overlap(b))
+      && self.ret_type.may_overlap(&other.ret_type)
+  }
+}
@@ -1,7 +1,8 @@
 //! Synthesize a type from AST node
 
 use crate::erl_syntax::erl_ast::node_impl::AstNodeType::{
-  Apply, BinaryOp, FnDef, FnRef, List, Lit, Tuple, Var,
+  Apply, BinaryComprehension, BinaryOp, FnDef, FnRef, FnRefDynamic, List, ListComprehension, Lit,
+  MapComprehension, Tuple, Var,
 };
 use crate::erl_syntax::erl_ast::node_impl::{AstNodeImpl, AstNodeType};
 use crate::erl_syntax::erl_ast::AstNode;
@@ -21,6 +22,10 @@ impl AstNodeImpl {
         unreachable!("Should not be synthesizing type from AST node: Empty({})", comment)
       }
       FnDef(fndef) => fndef.synthesize_function_type(module, scope),
+      // Remote functions are not known here, the reference is the type
+      FnRef { mfa, .. } if mfa.module.is_some() => {
+        Ok(TypeImpl::new_unnamed(TypeKind::FnRef { fun: mfa.clone() }))
+      }
       FnRef { mfa, .. } => match module.root_scope.fn_defs.get(mfa) {
         None => ErlError::local_function_not_found(
           self.location.clone(),
@@ -29,6 +34,7 @@ impl AstNodeImpl {
         ),
         Some(fndef) => Ok(fndef.as_fn_def().synthesize_function_type(module, scope)?),
       },
+      FnRefDynamic { .. } => Ok(TypeImpl::new_unnamed(TypeKind::AnyFn)),
       Apply(apply) => apply.synthesize_application_type(self.location.clone(), module, scope),
       Var(v) => match scope.variables.get(&v.name) {
         None => {
@@ -43,6 +49,15 @@ impl AstNodeImpl {
       }
       List { elements, tail, .. } => Self::synthesize_list_type(module, scope, elements, tail),
       Tuple { elements, .. } => Self::synthesize_tuple_type(module, scope, elements),
+      ListComprehension { expr, generators } => {
+        Self::synthesize_list_comprehension_type(module, scope, expr, generators)
+      }
+      BinaryComprehension { generators, .. } => {
+        Self::synthesize_binary_comprehension_type(module, scope, generators)
+      }
+      MapComprehension { key, value, generators } => {
+        Self::synthesize_map_comprehension_type(module, scope, key, value, generators)
+      }
       other => unimplemented!("Don't know how to synthesize type from {:?}", other),
     }
   }
@@ -0,0 +1,296 @@
+//! Synthesize types for list, binary and map comprehensions, and check their generators
+
+use crate::erl_syntax::erl_ast::node_impl::{AstNodeImpl, AstNodeType};
+use crate::erl_syntax::erl_ast::AstNode;
+use crate::erl_syntax::node::erl_binary_element::{
+  BinaryElement, TypeSpecifier, ValueType, ValueWidth,
+};
+use crate::error::ic_error::IroncladResult;
+use crate::project::module::module_impl::ErlModule;
+use crate::project::module::scope::scope_impl::{Scope, ScopeImpl};
+use crate::typing::erl_type::map_type::MapMemberType;
+use crate::typing::erl_type::typekind::TypeKind;
+use crate::typing::erl_type::{ErlType, TypeImpl};
+use crate::typing::type_error::TypeError;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+impl AstNodeImpl {
+  /// Having a list comprehension `[E || ...]` AST node, synthesize a list of the output
+  /// expression type
+  pub(crate) fn synthesize_list_comprehension_type(
+    module: &ErlModule,
+    scope: &Scope,
+    expr: &AstNode,
+    generators: &[AstNode],
+  ) -> IroncladResult<ErlType> {
+    let comp_scope = Self::comprehension_scope(module, scope, generators)?;
+    let element_t = expr.synthesize(module, &comp_scope)?;
+    Ok(TypeImpl::new_unnamed(TypeKind::list_of(element_t, false)))
+  }
+
+  /// Having a binary comprehension `<<E || ...>>` AST node, check the generators and produce a
+  /// binary type
+  pub(crate) fn synthesize_binary_comprehension_type(
+    module: &ErlModule,
+    scope: &Scope,
+    generators: &[AstNode],
+  ) -> IroncladResult<ErlType> {
+    Self::comprehension_scope(module, scope, generators)?;
+    Ok(TypeImpl::any_binary())
+  }
+
+  /// Having a map comprehension `#{K => V || ...}` AST node, synthesize a map type from the key
+  /// and value expression types
+  pub(crate) fn synthesize_map_comprehension_type(
+    module: &ErlModule,
+    scope: &Scope,
+    key: &AstNode,
+    value: &AstNode,
+    generators: &[AstNode],
+  ) -> IroncladResult<ErlType> {
+    let comp_scope = Self::comprehension_scope(module, scope, generators)?;
+    let member = MapMemberType {
+      key: key.synthesize(module, &comp_scope)?,
+      value: value.synthesize(module, &comp_scope)?,
+    };
+    Ok(TypeImpl::new_unnamed(TypeKind::new_map(vec![member])))
+  }
+
+  /// Create a scope for the output expression of a comprehension, where the variables bound by
+  /// the generator patterns are visible. The strict generators are checked here.
+  fn comprehension_scope(
+    module: &ErlModule,
+    scope: &Scope,
+    generators: &[AstNode],
+  ) -> IroncladResult<Scope> {
+    let mut variables = scope.variables.collection.read().unwrap().clone();
+    for g in generators.iter() {
+      Self::extract_generator_variables(g, &mut variables)?;
+    }
+    let comp_scope = ScopeImpl::new(scope.name.clone(), Arc::downgrade(scope), variables);
+
+    for g in generators.iter() {
+      Self::check_strict_generator(module, &comp_scope, g)?;
+    }
+    Ok(comp_scope)
+  }
+
+  /// Add the variables bound by a generator pattern. Filter expressions bind nothing.
+  fn extract_generator_variables(
+    generator: &AstNode,
+    variables: &mut HashMap<String, ErlType>,
+  ) -> IroncladResult<()> {
+    match &generator.content {
+      AstNodeType::ListComprehensionGenerator { left, map_value, .. } => {
+        Self::extract_variables(left, variables)?;
+        if let Some(v) = map_value {
+          Self::extract_variables(v, variables)?;
+        }
+        Ok(())
+      }
+      AstNodeType::ZipGenerator { generators } => {
+        for g in generators.iter() {
+          Self::extract_generator_variables(g, variables)?;
+        }
+        Ok(())
+      }
+      _ => Ok(()),
+    }
+  }
+
+  /// A strict generator crashes on an element which does not match its pattern. An error is
+  /// reported when an element type is not a subtype of the pattern type. Only `any()` elements
+  /// are not checked, they only must be able to match the pattern.
+  fn check_strict_generator(
+    module: &ErlModule,
+    scope: &Scope,
+    generator: &AstNode,
+  ) -> IroncladResult<()> {
+    match &generator.content {
+      AstNodeType::ZipGenerator { generators } => {
+        for g in generators.iter() {
+          Self::check_strict_generator(module, scope, g)?;
+        }
+        Ok(())
+      }
+      AstNodeType::ListComprehensionGenerator { left, right, kind, .. }
+        if kind.is_strict() && kind.is_binary() =>
+      {
+        let input_t = right.synthesize(module, scope)?;
+        Self::check_strict_binary_generator(generator, left, &input_t)
+      }
+      AstNodeType::ListComprehensionGenerator { left, map_value, right, kind }
+        if kind.is_strict() =>
+      {
+        let input_t = right.synthesize(module, scope)?;
+        // Map generator elements are compared as `{Key, Value}` pairs
+        let pattern_t = match map_value {
+          None => Self::generator_pattern_type(module, scope, left)?,
+          Some(v) => TypeImpl::new_unnamed(TypeKind::new_tuple(&[
+            Self::generator_pattern_type(module, scope, left)?,
+            Self::generator_pattern_type(module, scope, v)?,
+          ])),
+        };
+        for element_t in Self::generator_element_types(&input_t, map_value.is_some()) {
+          if !Self::strict_pattern_covers(&element_t, &pattern_t) {
+            let msg = format!(
+              "Strict generator pattern {} does not match all elements of {}",
+              pattern_t, input_t
+            );
+            return Err(TypeError::new_type_error(
+              Some(generator.location.clone()),
+              pattern_t.to_string(),
+              element_t.to_string(),
+              msg,
+            ));
+          }
+        }
+        Ok(())
+      }
+      _ => Ok(()),
+    }
+  }
+
+  /// Whether every value of the element type matches the pattern type. A union is covered when
+  /// all its members are covered, `any()` is covered when the pattern can match at all.
+  fn strict_pattern_covers(element_t: &ErlType, pattern_t: &ErlType) -> bool {
+    match &element_t.kind {
+      TypeKind::Any => element_t.may_overlap(pattern_t),
+      TypeKind::Union(members) => members
+        .types
+        .iter()
+        .all(|t| Self::strict_pattern_covers(t, pattern_t)),
+      _ => element_t.is_subtype_of(pattern_t),
+    }
+  }
+
+  /// A strict binary generator `<<...>> <:= Bin` crashes when its pattern does not match the next
+  /// bits of the input, including the bits left at the end. An error is reported when the input
+  /// is not a binary, when a segment of the pattern is a literal (the input bits are not known),
+  /// or when the input size is not always a multiple of the pattern size.
+  fn check_strict_binary_generator(
+    generator: &AstNode,
+    pattern: &AstNode,
+    input_t: &ErlType,
+  ) -> IroncladResult<()> {
+    if input_t.is_any() {
+      return Ok(());
+    }
+    let fail = |msg: String| {
+      Err(TypeError::new_type_error(
+        Some(generator.location.clone()),
+        "binary()".to_string(),
+        input_t.to_string(),
+        msg,
+      ))
+    };
+    if !input_t.may_overlap(&TypeImpl::any_binary()) {
+      return fail(format!("Strict binary generator input {} is not a binary", input_t));
+    }
+    let elements = match &pattern.content {
+      AstNodeType::BinaryExpr { elements } => elements,
+      _ => return Ok(()),
+    };
+    if let Some(lit) = elements
+      .iter()
+      .find(|el| matches!(el.value.content, AstNodeType::Lit { .. }))
+    {
+      return fail(format!(
+        "Strict binary generator pattern {} does not match all values of the segment {}",
+        pattern, lit
+      ));
+    }
+    // The input size is `head + k * unit` bits, each step consumes `pattern_bits`
+    let (head, unit) = match &input_t.kind {
+      TypeKind::AnyBinary => (0, 8),
+      TypeKind::Binary { head, tail } => {
+        (head.as_ref().map(|h| h.0).unwrap_or(0), tail.as_ref().map(|t| t.0).unwrap_or(0))
+      }
+      _ => return Ok(()),
+    };
+    match Self::binary_pattern_bits(elements) {
+      Some(pattern_bits)
+        if pattern_bits > 0 && (head % pattern_bits != 0 || unit % pattern_bits != 0) =>
+      {
+        fail(format!(
+          "Strict binary generator pattern {} of {} bits does not match the end of {}",
+          pattern, pattern_bits, input_t
+        ))
+      }
+      _ => Ok(()),
+    }
+  }
+
+  /// Size in bits of a binary pattern, if all its segments have a known size
+  fn binary_pattern_bits(elements: &[BinaryElement]) -> Option<usize> {
+    elements.iter().try_fold(0usize, |total, el| {
+      let mut value_type = None;
+      let mut unit = None;
+      for spec in el.type_specs.iter() {
+        match spec {
+          TypeSpecifier::Type(t) => value_type = Some(t),
+          TypeSpecifier::Unit(u) => unit = Some(*u),
+          _ => {}
+        }
+      }
+      let bits = match (&el.width, value_type) {
+        (ValueWidth::Literal(w), Some(ValueType::Bytes)) => w * unit.unwrap_or(8),
+        (ValueWidth::Literal(w), _) => w * unit.unwrap_or(1),
+        (ValueWidth::DefaultWidth, None | Some(ValueType::Integer)) => 8,
+        (ValueWidth::DefaultWidth, Some(ValueType::Float)) => 64,
+        _ => return None,
+      };
+      Some(total + bits)
+    })
+  }
+
+  /// The type of the values a generator pattern can match. Maps, records and binaries, and the
+  /// forms which are not compared in detail, produce the widest type of their kind.
+  fn generator_pattern_type(
+    module: &ErlModule,
+    scope: &Scope,
+    pattern: &AstNode,
+  ) -> IroncladResult<ErlType> {
+    match &pattern.content {
+      AstNodeType::Lit { .. } => pattern.synthesize(module, scope),
+      AstNodeType::Var(v) => Ok(scope.variables.get(&v.name).unwrap_or_else(TypeImpl::any)),
+      AstNodeType::Tuple { elements } => {
+        let elements: IroncladResult<Vec<ErlType>> = elements
+          .iter()
+          .map(|el| Self::generator_pattern_type(module, scope, el))
+          .collect();
+        Ok(TypeImpl::new_unnamed(TypeKind::Tuple { elements: elements? }))
+      }
+      AstNodeType::List { elements, tail } => {
+        let elements: IroncladResult<Vec<ErlType>> = elements
+          .iter()
+          .map(|el| Self::generator_pattern_type(module, scope, el))
+          .collect();
+        let tail = match tail {
+          None => None,
+          Some(t) => Some(Self::generator_pattern_type(module, scope, t)?),
+        };
+        Ok(TypeImpl::new_unnamed(TypeKind::StronglyTypedList { elements: elements?, tail }))
+      }
+      AstNodeType::MapBuilder { .. } => Ok(TypeImpl::new_unnamed(TypeKind::AnyMap)),
+      AstNodeType::RecordBuilder { .. } => Ok(TypeImpl::any_tuple()),
+      AstNodeType::BinaryExpr { .. } => Ok(TypeImpl::any_binary()),
+      _ => Ok(TypeImpl::any()),
+    }
+  }
+
+  /// The element types of a generator input, when they are known. For a map generator these
+  /// are `{Key, Value}` tuples.
+  fn generator_element_types(input_t: &ErlType, is_map_generator: bool) -> Vec<ErlType> {
+    match (&input_t.kind, is_map_generator) {
+      (TypeKind::List { elements, .. }, false) => vec![elements.clone()],
+      (TypeKind::StronglyTypedList { elements, .. }, false) => elements.clone(),
+      (TypeKind::Map { members }, true) => members
+        .iter()
+        .map(|m| TypeImpl::new_unnamed(TypeKind::new_tuple(&[m.key.clone(), m.value.clone()])))
+        .collect(),
+      _ => Vec::new(),
+    }
+  }
+}
@@ -0,0 +1,27 @@
+//! User type definition container
+
+use crate::typing::erl_type::ErlType;
+use libironclad_util::pretty::Pretty;
+use std::fmt::Formatter;
+
+/// Describes a type defined in a module with `-type` or `-opaque` attribute
+#[derive(Debug)]
+pub struct UserTypeDefinition {
+  /// The type name
+  pub name: String,
+  /// Type parameter variables
+  pub vars: Vec<String>,
+  /// The type definition
+  pub ty: ErlType,
+  /// Created with `-opaque`: transparent inside the defining module, and abstract in the other
+  /// modules, which are not allowed to see its structure.
+  pub opaque: bool,
+}
+
+impl std::fmt::Display for UserTypeDefinition {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "-{} {}", if self.opaque { "opaque" } else { "type" }, self.name)?;
+    Pretty::display_paren_list(self.vars.iter(), f)?;
+    write!(f, " :: {}", self.ty)
+  }
+}
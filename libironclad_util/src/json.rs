@@ -0,0 +1,128 @@
+//! Minimal JSON value for exporting data to external tools. Object keys keep the insertion order,
+//! so the output is stable and can be compared as text.
+
+use std::fmt::{Display, Formatter, Write};
+
+/// A JSON value
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+  /// `null`
+  Null,
+  /// `true` or `false`
+  Bool(bool),
+  /// A number, stored as its JSON text, so that big integers are not truncated
+  Number(String),
+  /// A string, escaped when printed
+  String(String),
+  /// An array of values
+  Array(Vec<JsonValue>),
+  /// An object, keys in the insertion order
+  Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+  /// Create a string value
+  pub fn string(s: impl Display) -> Self {
+    JsonValue::String(s.to_string())
+  }
+
+  /// Create a number value from anything which prints as a JSON number, like an integer
+  pub fn number(n: impl Display) -> Self {
+    JsonValue::Number(n.to_string())
+  }
+
+  /// Create an object from key-value pairs
+  pub fn object<Key: Into<String>>(pairs: Vec<(Key, JsonValue)>) -> Self {
+    JsonValue::Object(pairs.into_iter().map(|(k, v)| (k.into(), v)).collect())
+  }
+
+  /// Convert an optional value, `None` becomes `null`
+  pub fn optional<T>(value: Option<T>, convert: impl FnOnce(T) -> JsonValue) -> Self {
+    value.map(convert).unwrap_or(JsonValue::Null)
+  }
+
+  /// Print with 2-space indentation, one array element or object member per line
+  pub fn to_pretty_string(&self) -> String {
+    let mut out = String::new();
+    self.write_pretty(&mut out, 0).unwrap();
+    out.push('\n');
+    out
+  }
+
+  fn write_pretty(&self, out: &mut String, indent: usize) -> std::fmt::Result {
+    let pad = |n: usize| "  ".repeat(n);
+    match self {
+      JsonValue::Array(items) if !items.is_empty() => {
+        out.push_str("[\n");
+        for (i, item) in items.iter().enumerate() {
+          out.push_str(&pad(indent + 1));
+          item.write_pretty(out, indent + 1)?;
+          out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+        }
+        write!(out, "{}]", pad(indent))
+      }
+      JsonValue::Object(members) if !members.is_empty() => {
+        out.push_str("{\n");
+        for (i, (key, value)) in members.iter().enumerate() {
+          write!(out, "{}", pad(indent + 1))?;
+          write_json_string(out, key)?;
+          out.push_str(": ");
+          value.write_pretty(out, indent + 1)?;
+          out.push_str(if i + 1 < members.len() { ",\n" } else { "\n" });
+        }
+        write!(out, "{}}}", pad(indent))
+      }
+      other => write!(out, "{}", other),
+    }
+  }
+}
+
+/// Write a string in quotes, escaping the quotes, the backslash and the control characters
+fn write_json_string<W: Write>(out: &mut W, s: &str) -> std::fmt::Result {
+  out.write_char('"')?;
+  for c in s.chars() {
+    match c {
+      '"' => out.write_str("\\\"")?,
+      '\\' => out.write_str("\\\\")?,
+      '\n' => out.write_str("\\n")?,
+      '\r' => out.write_str("\\r")?,
+      '\t' => out.write_str("\\t")?,
+      c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+      c => out.write_char(c)?,
+    }
+  }
+  out.write_char('"')
+}
+
+/// Compact form, without whitespace
+impl Display for JsonValue {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      JsonValue::Null => write!(f, "null"),
+      JsonValue::Bool(b) => write!(f, "{}", b),
+      JsonValue::Number(n) => write!(f, "{}", n),
+      JsonValue::String(s) => write_json_string(f, s),
+      JsonValue::Array(items) => {
+        write!(f, "[")?;
+        for (i, item) in items.iter().enumerate() {
+          if i > 0 {
+            write!(f, ",")?;
+          }
+          write!(f, "{}", item)?;
+        }
+        write!(f, "]")
+      }
+      JsonValue::Object(members) => {
+        write!(f, "{{")?;
+        for (i, (key, value)) in members.iter().enumerate() {
+          if i > 0 {
+            write!(f, ",")?;
+          }
+          write_json_string(f, key)?;
+          write!(f, ":{}", value)?;
+        }
+        write!(f, "}}")
+      }
+    }
+  }
+}
@@ -1,4 +1,5 @@
 pub mod io;
+pub mod json;
 pub mod mfarity;
 pub mod pretty;
 pub mod rw_btreemap;
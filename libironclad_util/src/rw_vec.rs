@@ -36,6 +36,15 @@ impl<ValType: Clone> RwVec<ValType> {
       panic!("Can't lock RwVec for cloning")
     }
   }
+
+  /// Return a clone of the last item, if the vector is not empty
+  pub fn last(&self) -> Option<ValType> {
+    if let Ok(r_vec) = self.data.read() {
+      r_vec.last().cloned()
+    } else {
+      panic!("Can't lock RwVec for reading")
+    }
+  }
 }
 
 impl<ValType> RwVec<ValType> {
@@ -80,6 +89,24 @@ impl<ValType> RwVec<ValType> {
     }
   }
 
+  /// Remove and return the last item of the vector
+  pub fn pop(&self) -> Option<ValType> {
+    if let Ok(mut w_data) = self.data.write() {
+      w_data.pop()
+    } else {
+      panic!("Can't lock RwVec to pop")
+    }
+  }
+
+  /// Remove and return all items
+  pub fn take(&self) -> Vec<ValType> {
+    if let Ok(mut w_data) = self.data.write() {
+      std::mem::take(&mut *w_data)
+    } else {
+      panic!("Can't lock RwVec to take the values")
+    }
+  }
+
   /// Replace the contents
   pub fn replace<Iter>(&self, itr: Iter)
   where
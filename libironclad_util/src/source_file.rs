@@ -33,4 +33,36 @@ impl SourceFileImpl {
     }
     .into()
   }
+
+  /// For a pointer into `text` returns the byte offset, 1-based line and 1-based column (in
+  /// characters), or `None` if the pointer is outside of this file's text.
+  pub fn position_of(&self, ptr: *const u8) -> Option<(usize, usize, usize)> {
+    let start = self.text.as_ptr() as usize;
+    let pos = (ptr as usize).checked_sub(start)?;
+    if pos > self.text.len() {
+      return None;
+    }
+    let before = self.text.get(..pos)?;
+    let line_start = before.rfind('\n').map(|p| p + 1).unwrap_or(0);
+    let line = before.matches('\n').count() + 1;
+    let column = before[line_start..].chars().count() + 1;
+    Some((pos, line, column))
+  }
+
+  /// For a pointer into `text` returns the 1-based line number, or `None` if the pointer is
+  /// outside of this file's text (for example a token which came from a macro body).
+  pub fn line_of(&self, ptr: *const u8) -> Option<usize> {
+    let start = self.text.as_ptr() as usize;
+    let pos = (ptr as usize).checked_sub(start)?;
+    if pos > self.text.len() {
+      return None;
+    }
+    Some(
+      self.text.as_bytes()[..pos]
+        .iter()
+        .filter(|&&b| b == b'\n')
+        .count()
+        + 1,
+    )
+  }
 }